@@ -1,11 +1,10 @@
 use super::{BuildContext, SharedBuildCommand};
 use crate::{error::*, types::ProjectConfig};
 use android_manifest::AndroidManifest;
-use android_tools::java_tools::Key;
 use clap::{ArgAction, Parser};
 use crossbundle_tools::{
     commands::{android::*, combine_folders},
-    error::CommandExt,
+    error::{AndroidError, CommandExt},
     types::*,
 };
 use std::path::{Path, PathBuf};
@@ -32,15 +31,23 @@ pub struct AndroidBuildCommand {
     /// Path to export Gradle project. By default exports to `target/android/` folder.
     #[clap(long)]
     pub export_path: Option<PathBuf>,
-    /// Path to the signing key.
-    #[clap(long, requires_all = &["sign_key_pass", "sign_key_alias"])]
+    /// Path to the signing key. Overrides `package.metadata.android.signing`.
+    #[clap(long, requires = "sign_key_alias")]
     pub sign_key_path: Option<PathBuf>,
-    /// Signing key password.
+    /// Signing key password. Deprecated, the password is exposed in process listings and
+    /// logs. Use `--sign-key-pass-from` instead.
+    #[clap(long, conflicts_with = "sign_key_pass_from")]
+    pub sign_key_pass: Option<Secret>,
+    /// Reads the signing key password from `env:NAME`, `file:PATH` or `stdin`.
     #[clap(long)]
-    pub sign_key_pass: Option<String>,
+    pub sign_key_pass_from: Option<SecretSource>,
     /// Signing key alias.
     #[clap(long)]
     pub sign_key_alias: Option<String>,
+    /// Build one APK per target, each with an ABI-derived version code. Only supported
+    /// by the `native-apk` strategy.
+    #[clap(long)]
+    pub split_per_abi: bool,
     /// Print the immutable build plan without creating files or running commands.
     #[clap(long)]
     pub dry_run: bool,
@@ -74,13 +81,14 @@ impl AndroidBuildCommand {
         operation: crossbundle_tools::toolchain::PlanOperation,
         attach_logger: bool,
     ) -> crossbundle_tools::toolchain::BuildPlan {
-        let profile = self.shared.profile();
+        let profile = &context.profile;
         let targets = Self::android_build_targets(context, profile, &self.target)
             .iter()
             .map(|target| target.rust_triple().to_owned())
             .collect();
         let strategy = match self.strategy {
             AndroidStrategy::GradleApk => crossbundle_tools::toolchain::PlanStrategy::GradleApk,
+            AndroidStrategy::GradleAab => crossbundle_tools::toolchain::PlanStrategy::GradleAab,
             AndroidStrategy::NativeApk => crossbundle_tools::toolchain::PlanStrategy::NativeApk,
             AndroidStrategy::NativeAab => crossbundle_tools::toolchain::PlanStrategy::NativeAab,
         };
@@ -93,6 +101,9 @@ impl AndroidBuildCommand {
                 attach_logger,
                 library_only: self.lib.is_some(),
                 runtime: context.project_config.android.runtime,
+                split_per_abi: self.split_per_abi || context.project_config.android.split_per_abi,
+                plugin_binaries: context.project_config.android.plugins.has_binaries(),
+                variant: context.project_config.variant().cloned(),
             },
             &crossbundle_tools::toolchain::Environment::discover(),
         )
@@ -105,7 +116,13 @@ impl AndroidBuildCommand {
                 serde_json::to_string_pretty(plan).map_err(Error::DoctorReport)?
             );
         } else {
-            println!("Android {:?} plan ({:?})", plan.operation, plan.strategy);
+            match &plan.variant {
+                Some(variant) => println!(
+                    "Android {:?} plan ({:?}, variant {})",
+                    plan.operation, plan.strategy, variant.name
+                ),
+                None => println!("Android {:?} plan ({:?})", plan.operation, plan.strategy),
+            }
             for (index, step) in plan.steps.iter().enumerate() {
                 println!("{}. {}: {}", index + 1, step.id, step.action);
             }
@@ -158,7 +175,9 @@ impl AndroidBuildCommand {
         let (assets, resources) =
             Self::prepare_assets_and_resources(&context.project_config, &android_build_dir)?;
         config.status_message("Reading", "AndroidManifest.xml")?;
-        let manifest = Self::get_android_manifest(context, AndroidStrategy::GradleApk)?;
+        let (manifest, merge_report) =
+            Self::get_android_manifest(context, AndroidStrategy::GradleApk, &context.profile)?;
+        save_manifest_merge_report(&android_build_dir, &merge_report)?;
         let manifest_package = manifest
             .package
             .as_deref()
@@ -221,7 +240,7 @@ impl AndroidBuildCommand {
         export_path: Option<PathBuf>,
         ndk: &AndroidNdk,
     ) -> Result<()> {
-        let profile = self.shared.profile();
+        let profile = &context.profile;
         let example = self.shared.example.as_ref();
         let (_, target_dir, package_name) = Self::needed_project_dirs(example, context)?;
         config.status_message("Starting lib build process", &package_name)?;
@@ -233,7 +252,8 @@ impl AndroidBuildCommand {
         };
 
         config.status_message("Reading", "AndroidManifest.xml")?;
-        let manifest = Self::get_android_manifest(context, AndroidStrategy::NativeApk)?;
+        let (manifest, _) =
+            Self::get_android_manifest(context, AndroidStrategy::NativeApk, profile)?;
 
         config.status_message("Compiling", "lib")?;
         let min_sdk_version = Self::min_sdk_version(&manifest);
@@ -244,6 +264,7 @@ impl AndroidBuildCommand {
             ndk,
             profile,
             min_sdk_version,
+            Self::page_size(context, &manifest),
             &target_dir,
             config,
         )?;
@@ -255,7 +276,7 @@ impl AndroidBuildCommand {
                 &output_file_name,
             )?;
             let abi = build_target.android_abi();
-            let out_dir = android_build_dir.join("libs").join(profile).join(abi);
+            let out_dir = gradle_jni_libs_dir(&android_build_dir, profile, abi);
             if !out_dir.exists() {
                 std::fs::create_dir_all(&out_dir)?;
             }
@@ -265,27 +286,78 @@ impl AndroidBuildCommand {
     }
 
     /// Specifies project path and target directory needed to build application.
+    ///
+    /// The returned package name names the `target/android/` directory and the artifacts,
+    /// and ends with the name of the selected variant, like `game-staging`.
     pub fn needed_project_dirs(
         example: Option<&String>,
         context: &BuildContext,
     ) -> Result<(PathBuf, PathBuf, String)> {
         let project_path: PathBuf = context.project_path.clone();
         let target_dir: PathBuf = context.target_dir.clone();
-        let package_name = if let Some(example) = example {
+        let mut package_name = if let Some(example) = example {
             example.clone()
         } else {
             context.project.package.name.clone()
         };
+        if let Some(variant) = context.project_config.variant() {
+            package_name = format!("{package_name}-{}", variant.name);
+        }
         Ok((project_path, target_dir, package_name))
     }
 
-    /// Find keystore for signing application or create it.
-    pub fn find_keystore(
-        sign_key_path: Option<PathBuf>,
-        sign_key_pass: Option<String>,
-        sign_key_alias: Option<String>,
-    ) -> Result<Key> {
-        gen_key(sign_key_path, sign_key_pass, sign_key_alias).map_err(Into::into)
+    /// Resolves the signer of native builds. Command line options take precedence over
+    /// `package.metadata.android.signer` and `package.metadata.android.signing`, which are
+    /// only used for release builds. Otherwise the debug keystore is used and generated if
+    /// needed.
+    pub fn signer(&self, config: &CliContext, context: &BuildContext) -> Result<AndroidSigner> {
+        if let Some(keystore) = &self.sign_key_path {
+            let store_password = match (&self.sign_key_pass, &self.sign_key_pass_from) {
+                (Some(password), _) => {
+                    config.shell().warn(
+                        "`--sign-key-pass` exposes the password in process listings and logs, \
+                         use `--sign-key-pass-from` instead",
+                    )?;
+                    password.clone()
+                }
+                (None, Some(source)) => source.read()?,
+                (None, None) => {
+                    return Err(crossbundle_tools::error::Error::from(
+                        AndroidError::InvalidSigningConfig(
+                            "`--sign-key-path` requires `--sign-key-pass-from`".to_owned(),
+                        ),
+                    )
+                    .into());
+                }
+            };
+            return Ok(AndroidSigner::Keystore(KeystoreCredentials {
+                keystore: keystore.clone(),
+                key_alias: self.sign_key_alias.clone().unwrap_or_default(),
+                store_password,
+                key_password: None,
+            }));
+        }
+        let android = &context.project_config.android;
+        if context.profile.is_release() {
+            match (&android.signer, &android.signing) {
+                (Some(_), Some(_)) => {
+                    return Err(crossbundle_tools::error::Error::from(
+                        AndroidError::InvalidSigningConfig(
+                            "`signer` and `signing` can't be used together".to_owned(),
+                        ),
+                    )
+                    .into());
+                }
+                (Some(signer), None) => return Ok(AndroidSigner::External(signer.clone())),
+                (None, Some(signing)) => {
+                    config.status_message("Reading", "release signing key")?;
+                    return Ok(KeystoreCredentials::from_config(signing)?.into());
+                }
+                (None, None) => {}
+            }
+        }
+        config.status_message("Generating", "debug signing key")?;
+        Ok(KeystoreCredentials::from(gen_key(None, None, None)?).into())
     }
 
     /// Compiling libs for architecture and write out it in vector.
@@ -294,8 +366,9 @@ impl AndroidBuildCommand {
         context: &BuildContext,
         build_targets: Vec<AndroidTarget>,
         ndk: &AndroidNdk,
-        profile: Profile,
+        profile: &Profile,
         min_sdk_version: u32,
+        page_size: AndroidPageSize,
         target_dir: &Path,
         config: &CliContext,
     ) -> Result<Vec<(PathBuf, AndroidTarget)>> {
@@ -311,10 +384,11 @@ impl AndroidBuildCommand {
                 &context.project.package,
                 &cargo_library_name,
                 profile,
-                &self.shared.features,
+                &context.features,
                 self.shared.all_features,
                 self.shared.no_default_features,
                 min_sdk_version,
+                page_size,
                 target_dir,
             )?;
             libs.push((compiled_lib, build_target));
@@ -351,53 +425,85 @@ impl AndroidBuildCommand {
             .unwrap()
     }
 
+    /// Page size native libraries are linked, zip-aligned and checked for. See
+    /// [`link_page_size`].
+    pub fn page_size(
+        context: &BuildContext,
+        android_manifest: &AndroidManifest,
+    ) -> AndroidPageSize {
+        link_page_size(
+            Self::min_sdk_version(android_manifest),
+            context.project_config.android.page_size,
+        )
+    }
+
     /// Get android build targets from cargo manifest
     pub fn android_build_targets(
         context: &BuildContext,
-        profile: Profile,
+        profile: &Profile,
         build_targets: &[AndroidTarget],
     ) -> Vec<AndroidTarget> {
         if !build_targets.is_empty() {
             return build_targets.into();
         };
-        if profile == Profile::Debug
-            && !context
-                .project_config
-                .android
-                .debug_build_targets
-                .is_empty()
-        {
-            return context.project_config.android.debug_build_targets.clone();
-        };
-        if profile == Profile::Release
-            && !context
-                .project_config
-                .android
-                .release_build_targets
-                .is_empty()
-        {
-            return context.project_config.android.release_build_targets.clone();
+        let targets = context
+            .project_config
+            .android
+            .profile_build_targets(profile);
+        if !targets.is_empty() {
+            return targets.into();
         };
         vec![AndroidTarget::Aarch64]
     }
 
-    /// Get android manifest from the path in cargo manifest or generate it with the given
-    /// configuration
+    /// Merges the Android manifests of the project and its plugins, and completes the
+    /// result with the given configuration.
+    ///
+    /// Sources from the highest to the lowest priority: `android.profiles.<profile>`
+    /// overlays, the inline `android.manifest`, `android.manifest_path` and the manifest
+    /// fragments of discovered Crossbow plugins.
     pub fn get_android_manifest(
         context: &BuildContext,
         strategy: AndroidStrategy,
-    ) -> Result<AndroidManifest> {
-        let mut manifest =
-            if let Some(manifest_path) = &context.project_config.android.manifest_path {
-                read_android_manifest_with_variables(
-                    manifest_path,
-                    context.project_config.build_variables(),
-                )?
-            } else if let Some(manifest) = &context.project_config.android.manifest {
-                manifest.clone()
-            } else {
-                AndroidManifest::default()
-            };
+        profile: &Profile,
+    ) -> Result<(AndroidManifest, ManifestMergeReport)> {
+        let android = &context.project_config.android;
+        let variables = context.project_config.build_variables();
+        let mut sources = Vec::new();
+        if let Some(overlay) = android.profiles.get(profile.name()) {
+            if let Some(manifest) = &overlay.manifest {
+                sources.push(ManifestSource::overriding(
+                    format!("android.profiles.{profile}.manifest"),
+                    manifest,
+                )?);
+            }
+            if let Some(path) = &overlay.manifest_path {
+                sources.push(ManifestSource::from_path(
+                    path.display().to_string(),
+                    path,
+                    variables,
+                )?);
+            }
+        }
+        if let Some(manifest) = &android.manifest {
+            sources.push(ManifestSource::overriding("android.manifest", manifest)?);
+        }
+        if let Some(path) = &android.manifest_path {
+            sources.push(ManifestSource::from_path(
+                path.display().to_string(),
+                path,
+                variables,
+            )?);
+        }
+        for plugin in &android.discovered_plugins {
+            if let Some(fragment) = plugin.manifest_fragment() {
+                sources.push(ManifestSource::fragment(
+                    format!("plugin {}", plugin.package),
+                    &fragment,
+                )?);
+            }
+        }
+        let (mut manifest, report) = merge_android_manifests(&sources)?;
         let library_name = context
             .project
             .library_target()
@@ -410,6 +516,7 @@ impl AndroidBuildCommand {
             strategy,
             context.project_config.android.runtime,
             context.project_config.android_uses_crossbow_bridge(),
+            context.project_config.splash.is_some(),
         );
         context
             .project_config
@@ -424,33 +531,49 @@ impl AndroidBuildCommand {
                 None,
             ));
         }
-        Ok(manifest)
+        Ok((manifest, report))
     }
 
     /// Prepare assets and resources for the application.
     ///
-    /// Also, this function will generate mipmap icon resources if specified in the
+    /// Also, this function will generate mipmap icon resources, or adaptive icon
+    /// resources for layered icons, and splash screen resources if specified in the
     /// Project configuration.
     pub fn prepare_assets_and_resources(
         config: &ProjectConfig,
         out_dir: &Path,
     ) -> Result<(Option<PathBuf>, Option<PathBuf>)> {
         let res = config.android_resources();
-        let gen_resources = if res.is_empty() && config.icon.is_none() {
+        let gen_resources = if res.is_empty() && config.icon.is_none() && config.splash.is_none() {
             None
         } else {
             let path = out_dir.join("gen_resources");
             std::fs::remove_dir_all(&path).ok();
             combine_folders(res, &path)?;
 
-            if let Some(icon) = &config.icon {
-                ImageGeneration {
+            match &config.icon {
+                Some(IconConfig::Path(icon)) => ImageGeneration {
                     icon_path: icon.to_owned(),
                     out_icon_name: "ic_launcher.png".to_owned(),
                     output_path: path.clone(),
                     force: true,
                 }
-                .gen_mipmap_res_from_icon()?;
+                .gen_mipmap_res_from_icon()?,
+                Some(IconConfig::Layers(layers)) => AdaptiveIconGeneration {
+                    foreground: layers.foreground.clone(),
+                    background: layers.background.clone(),
+                    monochrome: layers.monochrome.clone(),
+                    output_path: path.clone(),
+                }
+                .gen_adaptive_icon_res()?,
+                None => {}
+            }
+            if let Some(splash) = &config.splash {
+                SplashScreenGeneration {
+                    splash: splash.clone(),
+                    output_path: path.clone(),
+                }
+                .gen_splash_res()?;
             }
             Some(path)
         };
@@ -476,8 +599,8 @@ pub(crate) struct AndroidBuildExecutor<'a> {
     ndk: AndroidNdk,
     pub(crate) gradle_executable: Option<&'a Path>,
     java: Option<&'a Path>,
-    jarsigner: Option<&'a Path>,
     bundletool: Option<&'a Path>,
+    signer: Option<AndroidSigner>,
     pub(crate) artifact: Option<AndroidBuildArtifact>,
 }
 
@@ -485,14 +608,16 @@ pub(crate) enum AndroidBuildArtifact {
     NativeApk {
         manifest: AndroidManifest,
         sdk: AndroidSdk,
-        path: PathBuf,
+        /// Built APKs. Per-ABI APKs are paired with their target.
+        apks: Vec<(Option<AndroidTarget>, PathBuf)>,
     },
-    NativeAab {
+    /// App bundle built by the `native-aab` or `gradle-aab` strategy.
+    Aab {
         manifest: AndroidManifest,
         sdk: AndroidSdk,
         path: PathBuf,
         package: String,
-        key: Key,
+        signer: AndroidSigner,
         apks: Option<PathBuf>,
     },
     Gradle {
@@ -523,8 +648,8 @@ impl<'a> AndroidBuildExecutor<'a> {
             )?,
             gradle_executable: plan.toolchain.gradle.as_deref(),
             java: plan.toolchain.java.as_deref(),
-            jarsigner: plan.toolchain.jarsigner.as_deref(),
             bundletool: plan.toolchain.bundletool.as_deref(),
+            signer: None,
             artifact: None,
         })
     }
@@ -537,12 +662,22 @@ impl<'a> AndroidBuildExecutor<'a> {
         Ok(command)
     }
 
+    /// Resolves the signer once, so passwords from stdin are read only once per build.
+    fn signer(&mut self) -> Result<AndroidSigner> {
+        if let Some(signer) = &self.signer {
+            return Ok(signer.clone());
+        }
+        let signer = self.command.signer(self.config, self.context)?;
+        self.signer = Some(signer.clone());
+        Ok(signer)
+    }
+
     pub(crate) fn try_run_build_step(
         &mut self,
-        kind: crossbundle_tools::toolchain::PlanStepKind,
+        step: &crossbundle_tools::toolchain::PlanStep,
     ) -> Result<bool> {
         use crossbundle_tools::toolchain::PlanStepKind;
-        self.artifact = match kind {
+        self.artifact = match step.kind {
             PlanStepKind::BuildRustLibrary => {
                 let name = self.command.lib.as_deref().unwrap_or("crossbow_android");
                 self.command
@@ -550,31 +685,49 @@ impl<'a> AndroidBuildExecutor<'a> {
                 return Ok(true);
             }
             PlanStepKind::BuildNativeApk => {
-                let (manifest, sdk, path) =
-                    self.command
-                        .execute_apk(self.config, self.context, &self.sdk, &self.ndk)?;
+                let target = step
+                    .target
+                    .as_deref()
+                    .map(str::parse::<AndroidTarget>)
+                    .transpose()
+                    .map_err(crossbundle_tools::error::Error::from)?;
+                let signer = self.signer()?;
+                let (manifest, sdk, path) = self.command.execute_apk(
+                    self.config,
+                    self.context,
+                    &self.sdk,
+                    &self.ndk,
+                    target,
+                    &signer,
+                )?;
+                let mut apks = match self.artifact.take() {
+                    Some(AndroidBuildArtifact::NativeApk { apks, .. }) if target.is_some() => apks,
+                    _ => Vec::new(),
+                };
+                apks.push((target, path));
                 Some(AndroidBuildArtifact::NativeApk {
                     manifest,
                     sdk,
-                    path,
+                    apks,
                 })
             }
             PlanStepKind::BuildNativeAab => {
-                let (manifest, sdk, path, package, key) = self.command.execute_aab(
+                let signer = self.signer()?;
+                let (manifest, sdk, path, package) = self.command.execute_aab(
                     self.config,
                     self.context,
                     &self.sdk,
                     &self.ndk,
                     required_path(self.java, "Java")?,
-                    required_path(self.jarsigner, "jarsigner")?,
                     required_path(self.bundletool, "bundletool")?,
+                    &signer,
                 )?;
-                Some(AndroidBuildArtifact::NativeAab {
+                Some(AndroidBuildArtifact::Aab {
                     manifest,
                     sdk,
                     path,
                     package,
-                    key,
+                    signer,
                     apks: None,
                 })
             }
@@ -611,6 +764,55 @@ impl<'a> AndroidBuildExecutor<'a> {
                 gradle.output_err(true)?;
                 return Ok(true);
             }
+            PlanStepKind::BuildGradleBundle => {
+                let signer = self.signer()?;
+                let Some(AndroidBuildArtifact::Gradle {
+                    manifest,
+                    sdk,
+                    project,
+                }) = self.artifact.take()
+                else {
+                    return Err(anyhow::anyhow!("Gradle project was not prepared").into());
+                };
+                let profile = &self.context.profile;
+                self.config
+                    .status_message("Building Gradle app bundle", gradle_bundle_task(profile))?;
+                let mut gradle = std::process::Command::new(required_path(
+                    self.gradle_executable,
+                    "Gradle executable",
+                )?);
+                gradle
+                    .env("ANDROID_SDK_ROOT", sdk.sdk_path())
+                    .arg(gradle_bundle_task(profile))
+                    .arg("-p")
+                    .arg(dunce::simplified(&project));
+                // Gradle signs with the keystore. An external signer gets the unsigned
+                // bundle afterwards, like in the `native-aab` strategy.
+                if let AndroidSigner::Keystore(credentials) = &signer {
+                    gradle.envs(gradle_signing_env(credentials)?);
+                }
+                gradle.output_err(true)?;
+                let path = gradle_bundle_path(&project, profile)?;
+                if let AndroidSigner::External(_) = &signer {
+                    self.config
+                        .status_message("Signing", "AAB with the external signer")?;
+                    signer.sign_aab(&path)?;
+                }
+                self.config
+                    .status_message("Gradle app bundle generated", path.to_string_lossy())?;
+                let (_, _, package) = AndroidBuildCommand::needed_project_dirs(
+                    self.command.shared.example.as_ref(),
+                    self.context,
+                )?;
+                Some(AndroidBuildArtifact::Aab {
+                    manifest,
+                    sdk,
+                    path,
+                    package,
+                    signer,
+                    apks: None,
+                })
+            }
             _ => return Ok(false),
         };
         Ok(true)
@@ -621,7 +823,7 @@ impl crossbundle_tools::toolchain::Runner for AndroidBuildExecutor<'_> {
     type Error = Error;
 
     fn run_step(&mut self, step: &crossbundle_tools::toolchain::PlanStep) -> Result<()> {
-        self.try_run_build_step(step.kind)?
+        self.try_run_build_step(step)?
             .then_some(())
             .ok_or_else(|| anyhow::anyhow!("unexpected {:?} step in build plan", step.kind).into())
     }
@@ -1,12 +1,10 @@
-use super::{AndroidBuildCommand, BuildContext};
+use super::{AndroidBuildCommand, BuildContext, validate_cargo_library_target};
 use crate::error::*;
-use android_tools::java_tools::Key;
 use crossbundle_tools::{
-    commands::android::*,
-    error::CommandExt,
+    commands::{CargoProject, android::*},
     types::{
-        AndroidNdk, AndroidSdk, AndroidStrategy, AndroidTarget, CliContext,
-        android_manifest::AndroidManifest,
+        AndroidNdk, AndroidPageSize, AndroidSdk, AndroidStrategy, AndroidTarget, AssetPack,
+        CliContext, FeatureModule, IntoRustTriple, android_manifest::AndroidManifest,
     },
 };
 use std::path::{Path, PathBuf};
@@ -19,6 +17,13 @@ struct PreparedNativeBuild {
     assets: Option<PathBuf>,
     resources: Option<PathBuf>,
     compiled_libs: Vec<(PathBuf, AndroidTarget)>,
+    /// Page size the libraries are linked, zip-aligned and checked for.
+    page_size: AndroidPageSize,
+    /// Directory with unstripped copies of packaged libraries. Only set for release
+    /// builds, which package stripped libraries.
+    debug_symbols: Option<PathBuf>,
+    /// Whether the prebuilt Crossbow bridge DEX is packaged.
+    crossbow_bridge: bool,
 }
 
 impl AndroidBuildCommand {
@@ -29,32 +34,55 @@ impl AndroidBuildCommand {
         ndk: &AndroidNdk,
         kind: &str,
         strategy: AndroidStrategy,
+        split_target: Option<AndroidTarget>,
     ) -> Result<PreparedNativeBuild> {
-        let profile = self.shared.profile();
+        let profile = &context.profile;
         let (_, target_dir, package_name) =
             Self::needed_project_dirs(self.shared.example.as_ref(), context)?;
         config.status_message(format!("Starting {kind} build process"), &package_name)?;
+        let crossbow_bridge = context.project_config.android_uses_crossbow_bridge();
+        if crossbow_bridge {
+            // Fail before compiling if this crossbundle build has no bridge to package.
+            crossbow_bridge_dex()?;
+        }
 
         let android_build_dir = target_dir.join("android").join(&package_name);
-        let native_build_dir = android_build_dir.join("native").join(kind);
+        let mut native_build_dir = android_build_dir.join("native").join(kind);
         std::fs::create_dir_all(android_build_dir.join("outputs"))?;
 
         config.status_message("Reading", "AndroidManifest.xml")?;
-        let manifest = Self::get_android_manifest(context, strategy)?;
+        let (mut manifest, merge_report) = Self::get_android_manifest(context, strategy, profile)?;
+        if let Some(target) = split_target {
+            native_build_dir = native_build_dir.join(target.android_abi());
+            manifest.version_code =
+                Some(target.split_version_code(manifest.version_code.unwrap_or(1))?);
+        }
         config.status_message("Generating", "AndroidManifest.xml")?;
         save_android_manifest(&native_build_dir, &manifest)?;
+        save_manifest_merge_report(&native_build_dir, &merge_report)?;
+        let debug_symbols = profile
+            .is_release()
+            .then(|| native_build_dir.join("native-debug-symbols"));
+        if let Some(debug_symbols) = &debug_symbols {
+            std::fs::remove_dir_all(debug_symbols).ok();
+        }
         config.status("Preparing resources and assets")?;
         let (assets, resources) =
             Self::prepare_assets_and_resources(&context.project_config, &android_build_dir)?;
 
         config.status_message("Compiling", "lib")?;
-        let targets = Self::android_build_targets(context, profile, &self.target);
+        let targets = match split_target {
+            Some(target) => vec![target],
+            None => Self::android_build_targets(context, profile, &self.target),
+        };
+        let page_size = Self::page_size(context, &manifest);
         let compiled_libs = self.build_target(
             context,
             targets,
             ndk,
             profile,
             Self::min_sdk_version(&manifest),
+            page_size,
             &target_dir,
             config,
         )?;
@@ -67,19 +95,37 @@ impl AndroidBuildCommand {
             assets,
             resources,
             compiled_libs,
+            page_size,
+            debug_symbols,
+            crossbow_bridge,
         })
     }
 
-    /// Builds APK with aapt tool and signs it with apksigner.
+    /// Builds APK with aapt tool and signs it with APK Signature Schemes v1, v2 and v3.
+    ///
+    /// With a `split_target` the APK only contains libraries of that target, is named
+    /// after its ABI and gets the ABI-derived version code.
     pub fn execute_apk(
         &self,
         config: &CliContext,
         context: &BuildContext,
         sdk: &AndroidSdk,
         ndk: &AndroidNdk,
+        split_target: Option<AndroidTarget>,
+        signer: &AndroidSigner,
     ) -> Result<(AndroidManifest, AndroidSdk, PathBuf)> {
-        let build =
-            self.prepare_native_build(config, context, ndk, "apk", AndroidStrategy::NativeApk)?;
+        let build = self.prepare_native_build(
+            config,
+            context,
+            ndk,
+            "apk",
+            AndroidStrategy::NativeApk,
+            split_target,
+        )?;
+        let apk_name = match split_target {
+            Some(target) => format!("{}-{}", build.package_name, target.android_abi()),
+            None => build.package_name.clone(),
+        };
 
         config.status_message("Generating", "unaligned APK file")?;
         let unaligned_apk_path = gen_unaligned_apk(
@@ -101,35 +147,47 @@ impl AndroidBuildCommand {
                 &unaligned_apk_path,
                 &compiled_lib,
                 build_target,
-                self.shared.profile(),
+                &context.profile,
                 Self::min_sdk_version(&build.manifest),
+                build.page_size,
                 &build.android_build_dir,
                 &context.target_dir,
+                build.debug_symbols.as_deref(),
             )?;
         }
 
+        if build.crossbow_bridge {
+            config.status("Adding Crossbow bridge into APK file")?;
+            add_bridge_dex_into_apk(sdk, &unaligned_apk_path)?;
+        }
+
         config.status("Aligning APK file")?;
         let aligned_apk_path = align_apk(
-            sdk,
             &unaligned_apk_path,
-            &build.package_name,
+            &apk_name,
             &build.android_build_dir.join("outputs"),
-        )?;
-
-        config.status_message("Generating", "debug signing key")?;
-        let key = Self::find_keystore(
-            self.sign_key_path.clone(),
-            self.sign_key_pass.clone(),
-            self.sign_key_alias.clone(),
+            build.page_size,
         )?;
 
         config.status("Signing APK file")?;
-        sign_apk(sdk, &aligned_apk_path, key)?;
+        sign_apk(&aligned_apk_path, signer)?;
+        if let Some(debug_symbols) = &build.debug_symbols {
+            let symbols_name = match split_target {
+                Some(target) => format!("native-debug-symbols-{}.zip", target.android_abi()),
+                None => "native-debug-symbols.zip".to_owned(),
+            };
+            config.status_message("Generating", &symbols_name)?;
+            gen_native_debug_symbols_zip(
+                debug_symbols,
+                &build.android_build_dir.join("outputs").join(symbols_name),
+            )?;
+        }
         config.status("Build finished successfully")?;
         Ok((build.manifest, sdk.clone(), aligned_apk_path))
     }
 
-    /// Builds AAB with aapt2 tool and signs it with jarsigner.
+    /// Builds AAB with aapt2 tool and signs it with a JAR signature.
+    #[allow(clippy::too_many_arguments)]
     pub fn execute_aab(
         &self,
         config: &CliContext,
@@ -137,11 +195,33 @@ impl AndroidBuildCommand {
         sdk: &AndroidSdk,
         ndk: &AndroidNdk,
         java: &Path,
-        jarsigner: &Path,
         bundletool: &Path,
-    ) -> Result<(AndroidManifest, AndroidSdk, PathBuf, String, Key)> {
-        let build =
-            self.prepare_native_build(config, context, ndk, "aab", AndroidStrategy::NativeAab)?;
+        signer: &AndroidSigner,
+    ) -> Result<(AndroidManifest, AndroidSdk, PathBuf, String)> {
+        let asset_packs = &context.project_config.android.asset_packs;
+        let feature_modules = &context.project_config.android.feature_modules;
+        AssetPack::validate(asset_packs)?;
+        FeatureModule::validate(feature_modules, asset_packs)?;
+        let mut build = self.prepare_native_build(
+            config,
+            context,
+            ndk,
+            "aab",
+            AndroidStrategy::NativeAab,
+            None,
+        )?;
+        if !feature_modules.is_empty() {
+            let resources = match build.resources.take() {
+                Some(resources) => resources,
+                None => {
+                    let resources = build.android_build_dir.join("gen_resources");
+                    std::fs::remove_dir_all(&resources).ok();
+                    resources
+                }
+            };
+            gen_feature_module_titles(&resources, feature_modules)?;
+            build.resources = Some(resources);
+        }
 
         config.status_message("Generating", "proto format APK file")?;
         let compiled_res = if let Some(resources) = &build.resources {
@@ -172,6 +252,16 @@ impl AndroidBuildCommand {
             .auto_add_overlay(true)
             .run()?;
 
+        let binary_base_apk = if feature_modules.is_empty() {
+            None
+        } else {
+            Some(gen_binary_base_apk(
+                sdk,
+                &apk_path,
+                &build.native_build_dir.join("base_binary.apk"),
+            )?)
+        };
+
         config.status("Extracting apk files")?;
         let extracted_apk_path = extract_archive(
             &apk_path,
@@ -179,19 +269,31 @@ impl AndroidBuildCommand {
         )?;
 
         config.status("Adding libs")?;
+        let build_targets: Vec<_> = build
+            .compiled_libs
+            .iter()
+            .map(|(_, build_target)| *build_target)
+            .collect();
         for (compiled_lib, build_target) in build.compiled_libs {
             add_libs_into_aapt2(
                 ndk,
                 &compiled_lib,
                 build_target,
-                self.shared.profile(),
+                &context.profile,
                 Self::min_sdk_version(&build.manifest),
+                build.page_size,
                 &extracted_apk_path,
                 &context.target_dir,
                 &build.package_name,
+                build.debug_symbols.as_deref(),
             )?;
         }
 
+        if build.crossbow_bridge {
+            config.status("Adding Crossbow bridge")?;
+            add_bridge_dex_into_aab_module(&extracted_apk_path)?;
+        }
+
         config.status("Generating ZIP module from extracted files")?;
         let module = gen_zip_modules(
             &build.native_build_dir,
@@ -205,36 +307,72 @@ impl AndroidBuildCommand {
             }
         }
 
+        let mut modules = vec![module];
+        let target_sdk_version = Self::target_sdk_version(&build.manifest, sdk)?;
+        let manifest_package = build
+            .manifest
+            .package
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("Android manifest package is missing"))?;
+        for pack in asset_packs {
+            config.status_message("Generating asset pack", &pack.name)?;
+            modules.push(gen_asset_pack_module(
+                sdk,
+                &build.native_build_dir,
+                manifest_package,
+                pack,
+                target_sdk_version,
+            )?);
+        }
+
+        if let Some(binary_base_apk) = &binary_base_apk {
+            for (index, module) in feature_modules.iter().enumerate() {
+                config.status_message("Generating feature module", &module.name)?;
+                let extracted_module_path = gen_feature_module(
+                    sdk,
+                    &build.native_build_dir,
+                    manifest_package,
+                    module,
+                    index,
+                    binary_base_apk,
+                    target_sdk_version,
+                )?;
+                self.add_feature_module_libs(
+                    config,
+                    context,
+                    ndk,
+                    module,
+                    &build_targets,
+                    Self::min_sdk_version(&build.manifest),
+                    build.page_size,
+                    &extracted_module_path,
+                    &build.package_name,
+                    build.debug_symbols.as_deref(),
+                )?;
+                modules.push(gen_zip_modules(
+                    &build.native_build_dir.join("feature_modules"),
+                    &module.name,
+                    &extracted_module_path,
+                )?);
+            }
+        }
+
         config.status("Generating aab from modules")?;
+        let metadata_files = match &build.debug_symbols {
+            Some(debug_symbols) => debug_symbols_metadata(debug_symbols)?,
+            None => Vec::new(),
+        };
         let aab_path = gen_aab_from_modules_with_toolchain(
             &build.package_name,
-            &[module],
+            &modules,
+            &metadata_files,
             &build.android_build_dir.join("outputs"),
             java,
             bundletool,
         )?;
 
-        config.status_message("Generating", "debug signing key")?;
-        let key = Self::find_keystore(
-            self.sign_key_path.clone(),
-            self.sign_key_pass.clone(),
-            self.sign_key_alias.clone(),
-        )?;
-        config.status_message("Signing", "debug signing key")?;
-        let mut command = std::process::Command::new(jarsigner);
-        command
-            .arg("-keystore")
-            .arg(&key.key_path)
-            .arg("-storepass")
-            .arg(&key.key_pass)
-            .arg("-verbose")
-            .arg("-sigalg")
-            .arg("SHA256withRSA")
-            .arg("-digestalg")
-            .arg("SHA-256")
-            .arg(&aab_path)
-            .arg(&key.key_alias);
-        command.output_err(true)?;
+        config.status("Signing AAB file")?;
+        signer.sign_aab(&aab_path)?;
 
         let output = build
             .android_build_dir
@@ -242,6 +380,62 @@ impl AndroidBuildCommand {
             .join(format!("{}_signed.aab", build.package_name));
         std::fs::rename(aab_path, &output)?;
         config.status("Build finished successfully")?;
-        Ok((build.manifest, sdk.clone(), output, build.package_name, key))
+        Ok((build.manifest, sdk.clone(), output, build.package_name))
+    }
+
+    /// Compiles the library of a feature module for every build target and adds it with
+    /// its dependencies into the extracted module.
+    #[allow(clippy::too_many_arguments)]
+    fn add_feature_module_libs(
+        &self,
+        config: &CliContext,
+        context: &BuildContext,
+        ndk: &AndroidNdk,
+        module: &FeatureModule,
+        build_targets: &[AndroidTarget],
+        min_sdk_version: u32,
+        page_size: AndroidPageSize,
+        extracted_module_path: &Path,
+        package_name: &str,
+        debug_symbols: Option<&Path>,
+    ) -> Result<()> {
+        let project = CargoProject::load_package(&module.path.join("Cargo.toml"))?;
+        let library = project.library_target();
+        let library_name = validate_cargo_library_target(
+            None,
+            library.map(|target| (target.name.as_str(), target.is_cdylib())),
+        )?;
+        for build_target in build_targets {
+            config.status_message(
+                format!("Compiling {} for architecture", module.name),
+                build_target.rust_triple(),
+            )?;
+            let compiled_lib = standard_cargo_compile(
+                ndk,
+                *build_target,
+                &project.package,
+                &library_name,
+                &context.profile,
+                &module.features,
+                false,
+                false,
+                min_sdk_version,
+                page_size,
+                &context.target_dir,
+            )?;
+            add_libs_into_aapt2(
+                ndk,
+                &compiled_lib,
+                *build_target,
+                &context.profile,
+                min_sdk_version,
+                page_size,
+                extracted_module_path,
+                &context.target_dir,
+                package_name,
+                debug_symbols,
+            )?;
+        }
+        Ok(())
     }
 }
@@ -4,6 +4,7 @@ use apple_bundle::prelude::InfoPlist;
 use clap::{ArgAction, Parser};
 use crossbundle_tools::{
     commands::{CargoBuild, apple, combine_folders},
+    error::AppleError,
     types::*,
 };
 use std::path::{Path, PathBuf};
@@ -29,6 +30,9 @@ pub struct IosBuildCommand {
     /// Certificate name or SHA-1 hash used to sign the application.
     #[clap(long, requires_all = ["profile_path", "team_id"])]
     pub signing_identity: Option<String>,
+    /// Print the merged Info.plist to stdout before building.
+    #[clap(long)]
+    pub print_merged_plist: bool,
 }
 
 impl IosBuildCommand {
@@ -43,12 +47,18 @@ impl IosBuildCommand {
         config: &CliContext,
         context: &BuildContext,
     ) -> Result<(InfoPlist, Vec<(IosTarget, PathBuf)>)> {
-        let profile = self.shared.profile();
+        let profile = &context.profile;
         let target = context
             .project
             .executable_target(self.bin.as_deref(), self.shared.example.as_deref())?;
         let package_name = target.name().to_owned();
-        let properties = Self::gen_info_plist(context, &package_name)?;
+        let properties = Self::gen_info_plist(config, context, &package_name)?;
+        if self.print_merged_plist {
+            let mut xml = Vec::new();
+            apple_bundle::plist::to_writer_xml(&mut xml, &properties)
+                .map_err(|error| crossbundle_tools::error::Error::from(AppleError::from(error)))?;
+            println!("{}", String::from_utf8_lossy(&xml));
+        }
         config.status_message("Starting build process", &package_name)?;
         config.status("Compiling app")?;
         let build_targets = Self::ios_build_targets(context, profile, &self.target);
@@ -75,7 +85,7 @@ impl IosBuildCommand {
         target: &CargoTargetSelection,
         build_target: IosTarget,
         properties: &InfoPlist,
-        profile: Profile,
+        profile: &Profile,
         name: &str,
     ) -> Result<PathBuf> {
         let rust_triple = build_target.rust_triple();
@@ -87,7 +97,7 @@ impl IosBuildCommand {
                 target_triple: rust_triple,
                 target_dir: &context.target_dir,
                 profile,
-                features: &self.shared.features,
+                features: &context.features,
                 all_features: self.shared.all_features,
                 no_default_features: self.shared.no_default_features,
             },
@@ -98,11 +108,11 @@ impl IosBuildCommand {
         )?;
 
         config.status("Generating app folder")?;
-        let apple_target_dir = &context
-            .target_dir
-            .join("apple")
-            .join(rust_triple)
-            .join(profile);
+        let mut apple_target_dir = context.target_dir.join("apple");
+        if let Some(variant) = context.project_config.variant() {
+            apple_target_dir.push(&variant.name);
+        }
+        let apple_target_dir = &apple_target_dir.join(rust_triple).join(profile);
 
         config.status("Preparing resources and assets")?;
         let (assets, resources) =
@@ -150,6 +160,12 @@ impl IosBuildCommand {
                 Some(&xcent_path),
             )?;
             config.status("Code signing process finished")?;
+        } else if let Some(signer) = &context.project_config.apple.signer
+            && profile.is_release()
+            && !build_target.is_simulator()
+        {
+            config.status("Signing the bundle with the external signer")?;
+            apple::codesign_externally(&app_path, signer)?;
         }
 
         config.status("Generating ipa file")?;
@@ -161,52 +177,62 @@ impl IosBuildCommand {
     /// Get iOS build targets from Cargo metadata.
     pub fn ios_build_targets(
         context: &BuildContext,
-        profile: Profile,
+        profile: &Profile,
         build_targets: &[IosTarget],
     ) -> Vec<IosTarget> {
         if !build_targets.is_empty() {
             return build_targets.into();
         }
-        if profile == Profile::Debug && !context.project_config.apple.debug_build_targets.is_empty()
-        {
-            return context.project_config.apple.debug_build_targets.clone();
-        }
-        if profile == Profile::Release
-            && !context
-                .project_config
-                .apple
-                .release_build_targets
-                .is_empty()
-        {
-            return context.project_config.apple.release_build_targets.clone();
+        let targets = context.project_config.apple.profile_build_targets(profile);
+        if !targets.is_empty() {
+            return targets.into();
         }
         vec![IosTarget::host_simulator()]
     }
 
-    /// Get info plist from the path in cargo manifest or generate it with the given
-    /// configuration
-    pub fn gen_info_plist(context: &BuildContext, package_name: &str) -> Result<InfoPlist> {
-        Ok(apple::resolve_info_plist(
+    /// Merges the Info.plist sources of the project and reports conflicting keys as
+    /// warnings.
+    pub fn gen_info_plist(
+        config: &CliContext,
+        context: &BuildContext,
+        package_name: &str,
+    ) -> Result<InfoPlist> {
+        let merged = apple::merge_project_info_plist(
             &context.project_config,
             package_name,
             context.project_config.apple.info_plist_path.as_deref(),
-        )?)
+        )?;
+        for conflict in &merged.conflicts {
+            config.shell().warn(conflict)?;
+        }
+        Ok(merged.info_plist)
     }
 
     /// Prepare assets and resources for the application.
+    ///
+    /// Also generates the app icons and launch screen images, and their asset catalog sets
+    /// for Xcode in `out_dir`, if an icon or splash screen is specified in the Project
+    /// configuration.
     pub fn prepare_assets_and_resources(
         config: &ProjectConfig,
         out_dir: &Path,
     ) -> Result<(Option<PathBuf>, Option<PathBuf>)> {
         let res = config.apple_resources();
-        let gen_resources = if res.is_empty() && config.icon.is_none() {
+        let gen_resources = if res.is_empty() && config.icon.is_none() && config.splash.is_none() {
             None
         } else {
             let path = out_dir.join("gen_resources");
             std::fs::remove_dir_all(&path).ok();
             combine_folders(res, &path)?;
 
-            // TODO: Generate icons
+            if let Some(icon) = &config.icon {
+                apple::gen_ios_app_icons(icon, &path)?;
+                apple::gen_app_icon_set(icon, out_dir)?;
+            }
+            if let Some(splash) = &config.splash {
+                apple::gen_launch_screen_images(splash, &path)?;
+                apple::gen_launch_screen_assets(splash, out_dir)?;
+            }
             Some(path)
         };
 
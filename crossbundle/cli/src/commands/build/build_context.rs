@@ -2,7 +2,7 @@ use super::SharedBuildCommand;
 use crate::{error::*, types::*};
 use crossbundle_tools::{
     commands::*,
-    types::{CliContext, parse_project_config},
+    types::{CliContext, Profile, parse_project_config},
 };
 use std::path::PathBuf;
 
@@ -13,10 +13,14 @@ pub struct BuildContext {
     // Configurations
     pub project: CargoProject,
     pub project_config: ProjectConfig,
+    /// Requested Cargo features and the features of the selected variant.
+    pub features: Vec<String>,
+    /// Cargo profile selected with `--release` or `--profile`.
+    pub profile: Profile,
 }
 
 impl BuildContext {
-    /// Create new instance of build context
+    /// Create new instance of build context. Applies the variant selected in `command`.
     pub fn new(context: &CliContext, command: &SharedBuildCommand) -> Result<Self> {
         info!("Reading Cargo metadata");
         let loaded = LoadedProject::load_with_features(
@@ -25,8 +29,29 @@ impl BuildContext {
             command.all_features,
             command.no_default_features,
         )?;
+        let parsed = parse_project_config(loaded.cargo.package.metadata.clone())
+            .and_then(|metadata| metadata.with_variant(command.variant.as_deref()))
+            .map_err(Error::InvalidMetadata)?;
+        let mut features = command.features.clone();
+        let variant_features = parsed
+            .variant()
+            .map(|variant| variant.features.as_slice())
+            .unwrap_or_default();
+        let loaded = if variant_features.is_empty() {
+            loaded
+        } else {
+            // Variant features may enable dependencies, such as plugins.
+            features.extend_from_slice(variant_features);
+            LoadedProject::load_with_features(
+                context.current_dir(),
+                &features,
+                command.all_features,
+                command.no_default_features,
+            )?
+        };
         let project_path = loaded.root;
         let project = loaded.cargo;
+        let profile = command.profile(&project.workspace_manifest_path)?;
         let target_dir = match &command.target_dir {
             Some(path) if path.is_relative() => {
                 std::path::absolute(context.current_dir().join(path))?
@@ -34,15 +59,21 @@ impl BuildContext {
             Some(path) => path.clone(),
             None => project.target_directory.clone(),
         };
-        let mut project_config = parse_project_config(project.package.metadata.clone())
-            .and_then(|metadata| metadata.resolve())
-            .map_err(Error::InvalidMetadata)?;
+        let mut project_config = parsed.resolve().map_err(Error::InvalidMetadata)?;
         project_config.resolve_paths(&project_path);
+        #[cfg(feature = "android")]
+        project_config.add_android_plugins(project.android_plugins()?);
+        #[cfg(feature = "apple")]
+        {
+            project_config.apple.discovered_plugins = project.apple_plugins()?;
+        }
         Ok(Self {
             project_path,
             target_dir,
             project_config,
             project,
+            features,
+            profile,
         })
     }
 }
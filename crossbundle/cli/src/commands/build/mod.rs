@@ -14,7 +14,7 @@ use apple::IosBuildCommand;
 use crate::error::Result;
 use clap::Parser;
 use crossbundle_tools::types::{CliContext, Profile};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Parser, Clone, Debug)]
 pub enum BuildCommand {
@@ -57,18 +57,27 @@ pub struct SharedBuildCommand {
     #[clap(long)]
     pub no_default_features: bool,
     /// Build optimized artifact with the `release` profile
-    #[clap(long)]
+    #[clap(long, conflicts_with = "profile")]
     pub release: bool,
+    /// Build with the given Cargo profile, like `release-lto`. Custom profiles are read
+    /// from `[profile.<name>]` of the workspace manifest
+    #[clap(long, value_name = "NAME")]
+    pub profile: Option<String>,
     /// Directory for generated artifact and intermediate files
     #[clap(long)]
     pub target_dir: Option<PathBuf>,
+    /// Apply the overrides of a variant declared in `package.metadata.crossbow.variants`
+    #[clap(long)]
+    pub variant: Option<String>,
 }
 
 impl SharedBuildCommand {
-    pub fn profile(&self) -> Profile {
-        match self.release {
-            true => Profile::Release,
-            false => Profile::Debug,
-        }
+    /// Resolves the selected profile against the workspace manifest.
+    pub fn profile(&self, workspace_manifest: &Path) -> Result<Profile> {
+        Ok(match (&self.profile, self.release) {
+            (Some(name), _) => Profile::resolve(name, workspace_manifest)?,
+            (None, true) => Profile::Release,
+            (None, false) => Profile::Debug,
+        })
     }
 }
@@ -0,0 +1,93 @@
+use crate::error::{Error, Result};
+use clap::Parser;
+use crossbundle_tools::commands::inspect::{ArchiveFile, InspectReport, inspect_artifact};
+use crossbundle_tools::types::CliContext;
+use std::path::PathBuf;
+
+#[derive(Parser, Clone, Debug)]
+pub struct InspectCommand {
+    /// Path to an .apk, .aab, .apks or .ipa file
+    pub path: PathBuf,
+    /// Emit a stable, versioned JSON report to stdout
+    #[clap(long)]
+    pub json: bool,
+}
+
+impl InspectCommand {
+    pub fn run(&self, config: &CliContext) -> Result<()> {
+        let path = if self.path.is_absolute() {
+            self.path.clone()
+        } else {
+            config.current_dir().join(&self.path)
+        };
+        let report = inspect_artifact(&path)?;
+        if self.json {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&report).map_err(Error::InspectReport)?
+            );
+        } else {
+            print_report(&report);
+        }
+        Ok(())
+    }
+}
+
+fn print_report(report: &InspectReport) {
+    eprintln!(
+        "Crossbundle inspect ({:?}, {} bytes): {}",
+        report.kind,
+        report.size,
+        report.path.display()
+    );
+    let fields = [
+        ("Identifier", &report.identifier),
+        ("Version name", &report.version_name),
+        ("Version code", &report.version_code),
+        ("Min SDK", &report.min_sdk_version),
+        ("Target SDK", &report.target_sdk_version),
+        ("Minimum OS", &report.minimum_os_version),
+    ];
+    for (name, value) in fields {
+        if let Some(value) = value {
+            eprintln!("{name}: {value}");
+        }
+    }
+    if !report.modules.is_empty() {
+        eprintln!("Modules: {}", report.modules.join(", "));
+    }
+    print_files("APKs", &report.apks);
+    eprintln!("ABIs: {}", report.abis.join(", "));
+    print_files("Native libraries", &report.native_libraries);
+    print_files("Assets", &report.assets);
+    match &report.signing.error {
+        Some(error) => eprintln!("Signing: {error}"),
+        None => eprintln!("Signing: {}", report.signing.schemes.join(", ")),
+    }
+    for certificate in &report.signing.certificates {
+        eprintln!("    Subject: {}", certificate.subject);
+        eprintln!(
+            "    Valid: {} to {}",
+            certificate.not_before, certificate.not_after
+        );
+        eprintln!("    SHA-1: {}", certificate.sha1);
+        eprintln!("    SHA-256: {}", certificate.sha256);
+    }
+    if let Some(manifest) = &report.manifest {
+        eprintln!("Manifest:");
+        eprintln!("{}", manifest.trim_end());
+    }
+}
+
+fn print_files(title: &str, files: &[ArchiveFile]) {
+    if files.is_empty() {
+        return;
+    }
+    eprintln!("{title} ({}):", files.len());
+    for file in files {
+        eprintln!(
+            "    {} ({} bytes, {} compressed)",
+            file.path, file.size, file.compressed_size
+        );
+    }
+}
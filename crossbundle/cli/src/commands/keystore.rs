@@ -0,0 +1,248 @@
+use crate::error::{Error, Result};
+use clap::Parser;
+use crossbundle_tools::{
+    commands::android::{
+        CertificateFormat, DEFAULT_KEY_VALIDITY, KeyReport, KeystoreCredentials, KeystoreOptions,
+        SigningKeyAlgorithm, export_certificate, gen_keystore, inspect_keystore, load_certificates,
+    },
+    types::{CliContext, KeystoreType, Secret, SecretSource},
+};
+use std::path::PathBuf;
+
+#[derive(Parser, Clone, Debug)]
+pub enum KeystoreCommand {
+    /// Generates a keystore with a self-signed key, e.g. an upload key for Google Play
+    Generate(GenerateKeystoreCommand),
+    /// Prints the certificate of a key and warns about expiring or weak keys
+    Inspect(InspectKeystoreCommand),
+    /// Prints SHA-1 and SHA-256 certificate fingerprints of a key, as registered in Google
+    /// APIs and Play App Signing
+    Fingerprint(FingerprintKeystoreCommand),
+    /// Writes the certificate of a key to a file, e.g. to register an upload key
+    ExportCert(ExportCertKeystoreCommand),
+}
+
+impl KeystoreCommand {
+    pub fn handle_command(&self, config: &CliContext) -> Result<()> {
+        match self {
+            Self::Generate(cmd) => cmd.run(config),
+            Self::Inspect(cmd) => cmd.run(config),
+            Self::Fingerprint(cmd) => cmd.run(config),
+            Self::ExportCert(cmd) => cmd.run(config),
+        }
+    }
+}
+
+#[derive(Parser, Clone, Debug)]
+pub struct KeystoreArgs {
+    /// Path to the keystore
+    #[clap(long, short)]
+    pub keystore: PathBuf,
+    /// Alias of the key in the keystore
+    #[clap(long, short)]
+    pub alias: String,
+    /// Keystore password. Exposed in process listings and logs, prefer `--store-pass-from`
+    #[clap(
+        long,
+        conflicts_with = "store_pass_from",
+        required_unless_present = "store_pass_from"
+    )]
+    pub store_pass: Option<Secret>,
+    /// Reads the keystore password from `env:NAME`, `file:PATH` or `stdin`
+    #[clap(long)]
+    pub store_pass_from: Option<SecretSource>,
+    /// Key password. By default the keystore password is used
+    #[clap(long, conflicts_with = "key_pass_from")]
+    pub key_pass: Option<Secret>,
+    /// Reads the key password from `env:NAME`, `file:PATH` or `stdin`. With `stdin` for
+    /// both passwords, the keystore password is read first
+    #[clap(long)]
+    pub key_pass_from: Option<SecretSource>,
+}
+
+impl KeystoreArgs {
+    /// Reads the passwords and resolves the keystore path.
+    fn credentials(&self, config: &CliContext) -> Result<KeystoreCredentials> {
+        let keystore = if self.keystore.is_absolute() {
+            self.keystore.clone()
+        } else {
+            config.current_dir().join(&self.keystore)
+        };
+        let store_password = match (&self.store_pass, &self.store_pass_from) {
+            (Some(password), _) => password.clone(),
+            (None, Some(source)) => source.read()?,
+            (None, None) => unreachable!("clap requires a keystore password"),
+        };
+        let key_password = match (&self.key_pass, &self.key_pass_from) {
+            (Some(password), _) => Some(password.clone()),
+            (None, Some(source)) => Some(source.read()?),
+            (None, None) => None,
+        };
+        Ok(KeystoreCredentials {
+            keystore,
+            key_alias: self.alias.clone(),
+            store_password,
+            key_password,
+        })
+    }
+}
+
+fn report(credentials: &KeystoreCredentials) -> Result<KeyReport> {
+    Ok(inspect_keystore(
+        &credentials.keystore,
+        credentials.store_password.expose(),
+        &credentials.key_alias,
+        credentials.key_password.as_ref().map(Secret::expose),
+    )?)
+}
+
+#[derive(Parser, Clone, Debug)]
+pub struct GenerateKeystoreCommand {
+    #[clap(flatten)]
+    pub keystore: KeystoreArgs,
+    /// Distinguished name of the certificate, e.g. "CN=Name,O=Company,C=US"
+    #[clap(long)]
+    pub dname: String,
+    /// Key algorithm: `rsa` or `ec`. Native signing supports only RSA keys
+    #[clap(long, default_value = "rsa")]
+    pub keyalg: SigningKeyAlgorithm,
+    /// Key size in bits. By default: 2048 for RSA and 256 for EC keys
+    #[clap(long)]
+    pub keysize: Option<u32>,
+    /// Validity of the certificate in days
+    #[clap(long, default_value_t = DEFAULT_KEY_VALIDITY)]
+    pub validity: u32,
+    /// Keystore type: `pkcs12` or `jks`. Only JKS keystores support a separate key
+    /// password
+    #[clap(long, default_value = "pkcs12")]
+    pub store_type: KeystoreType,
+}
+
+impl GenerateKeystoreCommand {
+    pub fn run(&self, config: &CliContext) -> Result<()> {
+        let credentials = self.keystore.credentials(config)?;
+        gen_keystore(&KeystoreOptions {
+            path: credentials.keystore.clone(),
+            alias: credentials.key_alias.clone(),
+            store_pass: credentials.store_password.clone(),
+            key_pass: credentials.key_password.clone(),
+            dname: self.dname.clone(),
+            algorithm: self.keyalg,
+            key_size: self.keysize,
+            validity: self.validity,
+            store_type: self.store_type,
+        })?;
+        config.status_message("Generated keystore", credentials.keystore.display())?;
+        print_report(&report(&credentials)?);
+        Ok(())
+    }
+}
+
+#[derive(Parser, Clone, Debug)]
+pub struct InspectKeystoreCommand {
+    #[clap(flatten)]
+    pub keystore: KeystoreArgs,
+    /// Emit the report as JSON to stdout
+    #[clap(long)]
+    pub json: bool,
+}
+
+impl InspectKeystoreCommand {
+    pub fn run(&self, config: &CliContext) -> Result<()> {
+        let report = report(&self.keystore.credentials(config)?)?;
+        if self.json {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&report).map_err(Error::KeystoreReport)?
+            );
+        } else {
+            print_report(&report);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Parser, Clone, Debug)]
+pub struct FingerprintKeystoreCommand {
+    #[clap(flatten)]
+    pub keystore: KeystoreArgs,
+    /// Emit the fingerprints as JSON to stdout
+    #[clap(long)]
+    pub json: bool,
+}
+
+impl FingerprintKeystoreCommand {
+    pub fn run(&self, config: &CliContext) -> Result<()> {
+        let certificate = report(&self.keystore.credentials(config)?)?.certificate;
+        if self.json {
+            let fingerprints = serde_json::json!({
+                "sha1": certificate.sha1,
+                "sha256": certificate.sha256,
+            });
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&fingerprints).map_err(Error::KeystoreReport)?
+            );
+        } else {
+            println!("SHA-1: {}", certificate.sha1);
+            println!("SHA-256: {}", certificate.sha256);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Parser, Clone, Debug)]
+pub struct ExportCertKeystoreCommand {
+    #[clap(flatten)]
+    pub keystore: KeystoreArgs,
+    /// Path of the exported certificate
+    #[clap(long, short)]
+    pub output: PathBuf,
+    /// Certificate encoding: `pem` or `der`
+    #[clap(long, default_value = "pem")]
+    pub format: CertificateFormat,
+}
+
+impl ExportCertKeystoreCommand {
+    pub fn run(&self, config: &CliContext) -> Result<()> {
+        let credentials = self.keystore.credentials(config)?;
+        let certificates = load_certificates(
+            &credentials.keystore,
+            credentials.store_password.expose(),
+            &credentials.key_alias,
+            credentials.key_password.as_ref().map(Secret::expose),
+        )?;
+        let output = if self.output.is_absolute() {
+            self.output.clone()
+        } else {
+            config.current_dir().join(&self.output)
+        };
+        if let Some(parent) = output.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&output, export_certificate(&certificates[0], self.format)?)?;
+        config.status_message("Exported certificate", output.display())?;
+        Ok(())
+    }
+}
+
+fn print_report(report: &KeyReport) {
+    eprintln!("Key `{}` in {}", report.alias, report.keystore.display());
+    match report.key_size {
+        Some(key_size) => eprintln!("Key: {} {key_size} bits", report.key_algorithm),
+        None => eprintln!("Key: {}", report.key_algorithm),
+    }
+    eprintln!("Signature algorithm: {}", report.signature_algorithm);
+    eprintln!("Subject: {}", report.certificate.subject);
+    eprintln!("Issuer: {}", report.certificate.issuer);
+    eprintln!("Serial number: {}", report.certificate.serial_number);
+    eprintln!(
+        "Valid: {} to {}",
+        report.certificate.not_before, report.certificate.not_after
+    );
+    eprintln!("SHA-1: {}", report.certificate.sha1);
+    eprintln!("SHA-256: {}", report.certificate.sha256);
+    for warning in &report.warnings {
+        eprintln!("warning: {warning}");
+    }
+}
@@ -1,8 +1,13 @@
 pub mod build;
 #[cfg(any(feature = "android", feature = "apple"))]
 pub mod doctor;
+#[cfg(any(feature = "android", feature = "apple"))]
+pub mod inspect;
 pub mod install;
+#[cfg(feature = "android")]
+pub mod keystore;
 pub mod new;
+pub mod publish;
 pub mod run;
 pub mod update;
 
@@ -18,10 +23,20 @@ pub enum Commands {
     /// Checks host tools and, optionally, an explicit project without changing anything
     #[cfg(any(feature = "android", feature = "apple"))]
     Doctor(doctor::DoctorCommand),
+    /// Prints the manifest, native libraries, assets and signatures of a built artifact
+    #[cfg(any(feature = "android", feature = "apple"))]
+    Inspect(inspect::InspectCommand),
+    /// Generates and inspects signing keystores, and prints fingerprints of their keys
+    #[cfg(feature = "android")]
+    #[clap(subcommand)]
+    Keystore(keystore::KeystoreCommand),
     /// Executes `build` command and then deploy and launches the application on the
     /// device/emulator
     #[clap(subcommand)]
     Run(run::RunCommand),
+    /// Uploads a built release to an app store
+    #[clap(subcommand)]
+    Publish(publish::PublishCommand),
     /// Creates a new Cargo package in the given directory. Project will be ready to build
     /// with `crossbundle`
     New(new::NewCommand),
@@ -40,7 +55,12 @@ impl Commands {
             Commands::Build(cmd) => cmd.handle_command(config),
             #[cfg(any(feature = "android", feature = "apple"))]
             Commands::Doctor(cmd) => cmd.run(config),
+            #[cfg(any(feature = "android", feature = "apple"))]
+            Commands::Inspect(cmd) => cmd.run(config),
+            #[cfg(feature = "android")]
+            Commands::Keystore(cmd) => cmd.handle_command(config),
             Commands::Run(cmd) => cmd.handle_command(config),
+            Commands::Publish(cmd) => cmd.handle_command(config),
             Commands::New(cmd) => cmd.handle_command(config),
             Commands::Install(cmd) => cmd.handle_command(config),
             Commands::Update(cmd) => cmd.handle_command(config),
@@ -52,6 +72,10 @@ impl Commands {
             Commands::Update(_) => false,
             #[cfg(any(feature = "android", feature = "apple"))]
             Commands::Doctor(_) => false,
+            #[cfg(any(feature = "android", feature = "apple"))]
+            Commands::Inspect(_) => false,
+            #[cfg(feature = "android")]
+            Commands::Keystore(_) => false,
             #[cfg(feature = "android")]
             Commands::Build(build::BuildCommand::Android(command)) => !command.dry_run,
             #[cfg(feature = "android")]
@@ -0,0 +1,143 @@
+use super::{
+    DEFAULT_API_URL, GooglePlayClient, PlayRelease, PlayTrack, ReleaseNotes, ServiceAccount,
+    release_aab_candidates,
+};
+use crate::{
+    commands::build::{BuildContext, SharedBuildCommand, android::AndroidBuildCommand},
+    error::{Error, Result},
+};
+use clap::{ArgAction, Parser};
+use crossbundle_tools::{
+    commands::{android::DEFAULT_DEBUG_DNAME, inspect::inspect_artifact},
+    types::CliContext,
+};
+use std::path::PathBuf;
+
+#[derive(Parser, Clone, Debug)]
+pub struct AndroidPublishCommand {
+    /// Track to release the bundle on: `internal`, `alpha`, `beta` or `production`
+    #[clap(long)]
+    pub track: PlayTrack,
+    /// Service account JSON key with access to the app in Play Console
+    #[clap(long)]
+    pub service_account: PathBuf,
+    /// Signed AAB to upload. Defaults to the newest release AAB built with the
+    /// `native-aab` or Gradle strategy
+    #[clap(long)]
+    pub aab: Option<PathBuf>,
+    /// Publish the AAB of the specified example
+    #[clap(long)]
+    pub example: Option<String>,
+    /// Directory the AAB was built in
+    #[clap(long)]
+    pub target_dir: Option<PathBuf>,
+    /// Publish the AAB built with a variant declared in `package.metadata.crossbow.variants`
+    #[clap(long)]
+    pub variant: Option<String>,
+    /// Package name of the app. Defaults to the package name in the AAB
+    #[clap(long)]
+    pub package_name: Option<String>,
+    /// Name of the release in Play Console. Defaults to the version name
+    #[clap(long)]
+    pub release_name: Option<String>,
+    /// Release notes as `<language>=<text>`, e.g. `en-US=Bug fixes`. May be specified
+    /// once per language
+    #[clap(long, action = ArgAction::Append)]
+    pub release_notes: Vec<ReleaseNotes>,
+    /// Percentage of users to roll the release out to. Releases below 100 are staged
+    /// rollouts
+    #[clap(long, default_value_t = 100.0)]
+    pub rollout: f64,
+    /// Base URL of the Android Publisher API
+    #[clap(long, default_value = DEFAULT_API_URL)]
+    pub api_url: String,
+}
+
+impl AndroidPublishCommand {
+    pub fn run(&self, config: &CliContext) -> Result<()> {
+        if !(self.rollout > 0.0 && self.rollout <= 100.0) {
+            return Err(Error::GooglePlay(format!(
+                "rollout must be above 0 and at most 100, got {}",
+                self.rollout
+            )));
+        }
+        let aab_path = self.aab_path(config)?;
+        let report = inspect_artifact(&aab_path)?;
+        if report.signing.schemes.is_empty() {
+            return Err(Error::GooglePlay(format!(
+                "{} isn't signed: {}",
+                aab_path.display(),
+                report.signing.error.unwrap_or_default()
+            )));
+        }
+        if report
+            .signing
+            .certificates
+            .iter()
+            .any(|certificate| certificate.subject == DEFAULT_DEBUG_DNAME)
+        {
+            return Err(Error::GooglePlay(format!(
+                "{} is signed with the debug key, build it with `--release` and a release key",
+                aab_path.display()
+            )));
+        }
+        let package_name = self
+            .package_name
+            .clone()
+            .or(report.identifier)
+            .ok_or_else(|| {
+                Error::GooglePlay("package name not found, pass `--package-name`".to_owned())
+            })?;
+
+        let service_account =
+            ServiceAccount::from_file(&config.current_dir().join(&self.service_account))?;
+        config.status_message("Authenticating as", &service_account.client_email)?;
+        let client =
+            GooglePlayClient::authenticate(&service_account, &self.api_url, &package_name)?;
+        config.status_message("Uploading", aab_path.display())?;
+        let release = PlayRelease {
+            name: self.release_name.clone(),
+            release_notes: self.release_notes.clone(),
+            user_fraction: (self.rollout < 100.0).then_some(self.rollout / 100.0),
+        };
+        let version_code = client.publish(&aab_path, self.track, &release)?;
+        config.status_message(
+            "Published",
+            format!(
+                "{package_name} version code {version_code} to the {} track",
+                self.track.as_str()
+            ),
+        )?;
+        Ok(())
+    }
+
+    fn aab_path(&self, config: &CliContext) -> Result<PathBuf> {
+        if let Some(aab) = &self.aab {
+            return Ok(config.current_dir().join(aab));
+        }
+        let shared = SharedBuildCommand {
+            example: self.example.clone(),
+            target_dir: self.target_dir.clone(),
+            variant: self.variant.clone(),
+            ..Default::default()
+        };
+        let context = BuildContext::new(config, &shared)?;
+        let (_, target_dir, package_name) =
+            AndroidBuildCommand::needed_project_dirs(self.example.as_ref(), &context)?;
+        let android_build_dir = target_dir.join("android").join(&package_name);
+        release_aab_candidates(&android_build_dir, &package_name)
+            .into_iter()
+            .max_by_key(|path| {
+                path.metadata()
+                    .and_then(|metadata| metadata.modified())
+                    .ok()
+            })
+            .ok_or_else(|| {
+                Error::GooglePlay(format!(
+                    "no release AAB found in {}, build one with `crossbundle build android \
+                     --release -s native-aab` or pass `--aab`",
+                    android_build_dir.display()
+                ))
+            })
+    }
+}
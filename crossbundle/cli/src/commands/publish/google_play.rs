@@ -0,0 +1,589 @@
+use crate::error::{Error, Result};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use rsa::{
+    RsaPrivateKey,
+    pkcs1v15::SigningKey,
+    pkcs8::DecodePrivateKey,
+    sha2::Sha256,
+    signature::{SignatureEncoding, Signer},
+};
+use serde_json::{Value, json};
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use ureq::{Agent, Body, http::Response};
+
+/// Base URL of the Android Publisher API.
+pub const DEFAULT_API_URL: &str = "https://androidpublisher.googleapis.com";
+const ANDROID_PUBLISHER_SCOPE: &str = "https://www.googleapis.com/auth/androidpublisher";
+const JWT_BEARER_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:jwt-bearer";
+
+/// Google Play release track.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayTrack {
+    Internal,
+    Alpha,
+    Beta,
+    Production,
+}
+
+impl PlayTrack {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Internal => "internal",
+            Self::Alpha => "alpha",
+            Self::Beta => "beta",
+            Self::Production => "production",
+        }
+    }
+}
+
+impl std::str::FromStr for PlayTrack {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "internal" => Ok(Self::Internal),
+            "alpha" => Ok(Self::Alpha),
+            "beta" => Ok(Self::Beta),
+            "production" => Ok(Self::Production),
+            _ => Err(format!(
+                "unknown track `{s}`, use `internal`, `alpha`, `beta` or `production`"
+            )),
+        }
+    }
+}
+
+/// Release notes in one language, parsed from `<language>=<text>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReleaseNotes {
+    /// BCP-47 language tag, e.g. `en-US`.
+    pub language: String,
+    pub text: String,
+}
+
+impl std::str::FromStr for ReleaseNotes {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((language, text)) if !language.is_empty() && !text.is_empty() => Ok(Self {
+                language: language.to_owned(),
+                text: text.to_owned(),
+            }),
+            _ => Err(format!(
+                "`{s}` are not release notes, use `<language>=<text>`, e.g. `en-US=Bug fixes`"
+            )),
+        }
+    }
+}
+
+/// Release created on a track for the uploaded bundle.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PlayRelease {
+    /// Name shown in Play Console. Defaults to the version name of the bundle.
+    pub name: Option<String>,
+    pub release_notes: Vec<ReleaseNotes>,
+    /// Fraction of users that get a staged rollout, between 0 and 1. The release is
+    /// rolled out to everyone if not set.
+    pub user_fraction: Option<f64>,
+}
+
+impl PlayRelease {
+    fn to_json(&self, version_code: i64) -> Value {
+        let mut release = json!({
+            "versionCodes": [version_code.to_string()],
+            "status": if self.user_fraction.is_some() { "inProgress" } else { "completed" },
+        });
+        if let Some(name) = &self.name {
+            release["name"] = json!(name);
+        }
+        if let Some(user_fraction) = self.user_fraction {
+            release["userFraction"] = json!(user_fraction);
+        }
+        if !self.release_notes.is_empty() {
+            release["releaseNotes"] = self
+                .release_notes
+                .iter()
+                .map(|notes| json!({ "language": notes.language, "text": notes.text }))
+                .collect();
+        }
+        release
+    }
+}
+
+/// Google Cloud service account key, as downloaded in JSON from the Cloud Console.
+pub struct ServiceAccount {
+    pub client_email: String,
+    pub token_uri: String,
+    private_key: RsaPrivateKey,
+}
+
+impl ServiceAccount {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let invalid = |reason: String| Error::InvalidServiceAccount(path.to_owned(), reason);
+        let json = std::fs::read_to_string(path).map_err(|error| invalid(error.to_string()))?;
+        Self::from_json(&json).map_err(invalid)
+    }
+
+    pub fn from_json(json: &str) -> std::result::Result<Self, String> {
+        let value: Value = serde_json::from_str(json).map_err(|error| error.to_string())?;
+        let field = |name: &str| {
+            value[name]
+                .as_str()
+                .map(str::to_owned)
+                .ok_or_else(|| format!("`{name}` is missing"))
+        };
+        if value["type"].as_str() != Some("service_account") {
+            return Err("`type` must be `service_account`".to_owned());
+        }
+        let private_key = RsaPrivateKey::from_pkcs8_pem(&field("private_key")?)
+            .map_err(|error| format!("invalid `private_key`: {error}"))?;
+        Ok(Self {
+            client_email: field("client_email")?,
+            token_uri: field("token_uri")?,
+            private_key,
+        })
+    }
+
+    /// Signed JWT that is exchanged for an access token.
+    fn assertion(&self, issued_at: u64) -> String {
+        let header = json!({ "alg": "RS256", "typ": "JWT" });
+        let claims = json!({
+            "iss": self.client_email,
+            "scope": ANDROID_PUBLISHER_SCOPE,
+            "aud": self.token_uri,
+            "iat": issued_at,
+            "exp": issued_at + 3600,
+        });
+        let signing_input = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(header.to_string()),
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        );
+        let signature = SigningKey::<Sha256>::new(self.private_key.clone())
+            .sign(signing_input.as_bytes())
+            .to_vec();
+        format!("{signing_input}.{}", URL_SAFE_NO_PAD.encode(signature))
+    }
+}
+
+impl std::fmt::Debug for ServiceAccount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServiceAccount")
+            .field("client_email", &self.client_email)
+            .field("token_uri", &self.token_uri)
+            .field("private_key", &"<redacted>")
+            .finish()
+    }
+}
+
+/// Client of the edits API of the Android Publisher API for one application.
+pub struct GooglePlayClient {
+    agent: Agent,
+    api_url: String,
+    package_name: String,
+    access_token: String,
+}
+
+impl GooglePlayClient {
+    /// Exchanges a JWT signed with the service account key for an access token.
+    pub fn authenticate(
+        account: &ServiceAccount,
+        api_url: &str,
+        package_name: &str,
+    ) -> Result<Self> {
+        let agent: Agent = Agent::config_builder()
+            .http_status_as_error(false)
+            .build()
+            .into();
+        let issued_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let response = agent.post(&account.token_uri).send_form([
+            ("grant_type", JWT_BEARER_GRANT_TYPE),
+            ("assertion", &account.assertion(issued_at)),
+        ]);
+        let token = read_response("Requesting an access token", response)?;
+        let access_token = token["access_token"]
+            .as_str()
+            .ok_or_else(|| Error::GooglePlay("token response has no `access_token`".to_owned()))?
+            .to_owned();
+        Ok(Self {
+            agent,
+            api_url: api_url.trim_end_matches('/').to_owned(),
+            package_name: package_name.to_owned(),
+            access_token,
+        })
+    }
+
+    /// Uploads the bundle and releases it on the track in a new edit. The edit is
+    /// deleted if any step fails, so nothing is left half published. Returns the version
+    /// code of the uploaded bundle.
+    pub fn publish(&self, aab_path: &Path, track: PlayTrack, release: &PlayRelease) -> Result<i64> {
+        let edit_id = self.create_edit()?;
+        let result = self
+            .upload_bundle(&edit_id, aab_path)
+            .and_then(|version_code| {
+                self.update_track(&edit_id, track, &release.to_json(version_code))?;
+                self.commit_edit(&edit_id)?;
+                Ok(version_code)
+            });
+        if result.is_err() {
+            // The original error is more useful than a failure to clean up.
+            self.delete_edit(&edit_id).ok();
+        }
+        result
+    }
+
+    pub fn create_edit(&self) -> Result<String> {
+        let response = self
+            .agent
+            .post(self.edits_url())
+            .header("Authorization", self.authorization())
+            .header("Content-Type", "application/json")
+            .send("{}");
+        let edit = read_response("Creating an edit", response)?;
+        edit["id"]
+            .as_str()
+            .map(str::to_owned)
+            .ok_or_else(|| Error::GooglePlay("edit response has no `id`".to_owned()))
+    }
+
+    /// Uploads the AAB and returns its version code.
+    pub fn upload_bundle(&self, edit_id: &str, aab_path: &Path) -> Result<i64> {
+        let url = format!(
+            "{}/upload/androidpublisher/v3/applications/{}/edits/{edit_id}/bundles?uploadType=media",
+            self.api_url, self.package_name
+        );
+        let response = self
+            .agent
+            .post(url)
+            .header("Authorization", self.authorization())
+            .header("Content-Type", "application/octet-stream")
+            .send(std::fs::read(aab_path)?);
+        let bundle = read_response("Uploading the bundle", response)?;
+        bundle["versionCode"]
+            .as_i64()
+            .ok_or_else(|| Error::GooglePlay("bundle response has no `versionCode`".to_owned()))
+    }
+
+    pub fn update_track(&self, edit_id: &str, track: PlayTrack, release: &Value) -> Result<()> {
+        let body = json!({ "track": track.as_str(), "releases": [release] });
+        let response = self
+            .agent
+            .put(format!(
+                "{}/{edit_id}/tracks/{}",
+                self.edits_url(),
+                track.as_str()
+            ))
+            .header("Authorization", self.authorization())
+            .header("Content-Type", "application/json")
+            .send(body.to_string());
+        read_response("Updating the track", response)?;
+        Ok(())
+    }
+
+    pub fn commit_edit(&self, edit_id: &str) -> Result<()> {
+        let response = self
+            .agent
+            .post(format!("{}/{edit_id}:commit", self.edits_url()))
+            .header("Authorization", self.authorization())
+            .send_empty();
+        read_response("Committing the edit", response)?;
+        Ok(())
+    }
+
+    pub fn delete_edit(&self, edit_id: &str) -> Result<()> {
+        let response = self
+            .agent
+            .delete(format!("{}/{edit_id}", self.edits_url()))
+            .header("Authorization", self.authorization())
+            .call();
+        read_response("Deleting the edit", response)?;
+        Ok(())
+    }
+
+    fn edits_url(&self) -> String {
+        format!(
+            "{}/androidpublisher/v3/applications/{}/edits",
+            self.api_url, self.package_name
+        )
+    }
+
+    fn authorization(&self) -> String {
+        format!("Bearer {}", self.access_token)
+    }
+}
+
+/// Reads a JSON response, turning error statuses into errors with the API message.
+fn read_response(
+    action: &str,
+    response: std::result::Result<Response<Body>, ureq::Error>,
+) -> Result<Value> {
+    let failed = |reason: String| Error::GooglePlay(format!("{action}: {reason}"));
+    let mut response = response.map_err(|error| failed(error.to_string()))?;
+    let status = response.status();
+    let body = response
+        .body_mut()
+        .read_to_string()
+        .map_err(|error| failed(error.to_string()))?;
+    let value = if body.trim().is_empty() {
+        Value::Null
+    } else {
+        serde_json::from_str(&body).unwrap_or(Value::String(body))
+    };
+    if !status.is_success() {
+        let message = value["error"]["message"]
+            .as_str()
+            .or_else(|| value["error_description"].as_str())
+            .or_else(|| value.as_str())
+            .unwrap_or_default();
+        return Err(failed(format!("{status} {message}").trim_end().to_owned()));
+    }
+    Ok(value)
+}
+
+/// Default locations of release AABs: the `native-aab` output and Gradle release bundles.
+pub fn release_aab_candidates(android_build_dir: &Path, package_name: &str) -> Vec<PathBuf> {
+    let mut candidates = vec![
+        android_build_dir
+            .join("outputs")
+            .join(format!("{package_name}_signed.aab")),
+    ];
+    let gradle_bundles = android_build_dir
+        .join("gradle")
+        .join("build")
+        .join("outputs")
+        .join("bundle")
+        .join("release");
+    if let Ok(entries) = std::fs::read_dir(gradle_bundles) {
+        candidates.extend(
+            entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|extension| extension == "aab")),
+        );
+    }
+    candidates.retain(|path| path.is_file());
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        sync::{Arc, Mutex},
+    };
+
+    #[derive(Debug)]
+    struct Request {
+        method: String,
+        path: String,
+        authorization: Option<String>,
+        body: Vec<u8>,
+    }
+
+    type Requests = Arc<Mutex<Vec<Request>>>;
+
+    /// Local stand-in for the OAuth and Android Publisher endpoints.
+    fn mock_server(respond: fn(&Request) -> (u16, String)) -> (String, Requests) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Requests::default();
+        let recorded = requests.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { break };
+                let recorded = recorded.clone();
+                std::thread::spawn(move || {
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    let mut line = String::new();
+                    while reader.read_line(&mut line).unwrap_or(0) > 0 {
+                        let mut parts = line.split_whitespace();
+                        let method = parts.next().unwrap_or_default().to_owned();
+                        let path = parts.next().unwrap_or_default().to_owned();
+                        let (mut length, mut authorization) = (0, None);
+                        loop {
+                            let mut header = String::new();
+                            reader.read_line(&mut header).unwrap();
+                            let Some((name, value)) = header.trim_end().split_once(": ") else {
+                                break;
+                            };
+                            match name.to_ascii_lowercase().as_str() {
+                                "content-length" => length = value.parse().unwrap(),
+                                "authorization" => authorization = Some(value.to_owned()),
+                                _ => {}
+                            }
+                        }
+                        let mut body = vec![0; length];
+                        reader.read_exact(&mut body).unwrap();
+                        let request = Request {
+                            method,
+                            path,
+                            authorization,
+                            body,
+                        };
+                        let (status, response) = respond(&request);
+                        recorded.lock().unwrap().push(request);
+                        write!(
+                            stream,
+                            "HTTP/1.1 {status} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{response}",
+                            response.len()
+                        )
+                        .unwrap();
+                        line.clear();
+                    }
+                });
+            }
+        });
+        (url, requests)
+    }
+
+    fn respond(request: &Request) -> (u16, String) {
+        let edits = "/androidpublisher/v3/applications/com.example.game/edits";
+        match (request.method.as_str(), request.path.as_str()) {
+            ("POST", "/token") => (
+                200,
+                r#"{"access_token":"token-1","expires_in":3600}"#.into(),
+            ),
+            ("POST", path) if path == edits => (200, r#"{"id":"edit-1"}"#.into()),
+            ("POST", path) if path.starts_with("/upload/") => (200, r#"{"versionCode":42}"#.into()),
+            ("PUT", path) if path.ends_with("/tracks/internal") => (
+                403,
+                r#"{"error":{"code":403,"message":"Track is locked."}}"#.into(),
+            ),
+            ("PUT", _) | ("POST", _) | ("DELETE", _) => (200, "{}".into()),
+            _ => (404, String::new()),
+        }
+    }
+
+    fn service_account(token_uri: &str) -> ServiceAccount {
+        let private_key = std::fs::read_to_string(
+            Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("../tools/tests/fixtures/signing/debug-key.pem"),
+        )
+        .unwrap();
+        let json = json!({
+            "type": "service_account",
+            "client_email": "publisher@example.iam.gserviceaccount.com",
+            "private_key": private_key,
+            "token_uri": token_uri,
+        });
+        ServiceAccount::from_json(&json.to_string()).unwrap()
+    }
+
+    fn publish(track: PlayTrack, release: &PlayRelease) -> (Result<i64>, Vec<Request>) {
+        let (url, requests) = mock_server(respond);
+        let temp_dir = tempfile::tempdir().unwrap();
+        let aab = temp_dir.path().join("game.aab");
+        std::fs::write(&aab, b"signed-aab").unwrap();
+        let account = service_account(&format!("{url}/token"));
+        let result = GooglePlayClient::authenticate(&account, &url, "com.example.game")
+            .and_then(|client| client.publish(&aab, track, release));
+        let requests = std::mem::take(&mut *requests.lock().unwrap());
+        (result, requests)
+    }
+
+    #[test]
+    fn publishes_bundles_in_an_edit() {
+        let release = PlayRelease {
+            name: Some("1.2.0".to_owned()),
+            release_notes: vec!["en-US=Bug fixes".parse().unwrap()],
+            user_fraction: Some(0.1),
+        };
+        let (result, requests) = publish(PlayTrack::Beta, &release);
+        assert_eq!(result.unwrap(), 42);
+
+        let calls = requests
+            .iter()
+            .map(|request| format!("{} {}", request.method, request.path))
+            .collect::<Vec<_>>();
+        let edits = "/androidpublisher/v3/applications/com.example.game/edits";
+        assert_eq!(
+            calls,
+            [
+                "POST /token".to_owned(),
+                format!("POST {edits}"),
+                format!("POST /upload{edits}/edit-1/bundles?uploadType=media"),
+                format!("PUT {edits}/edit-1/tracks/beta"),
+                format!("POST {edits}/edit-1:commit"),
+            ]
+        );
+
+        let form = String::from_utf8(requests[0].body.clone()).unwrap();
+        let assertion = form
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("assertion="))
+            .unwrap();
+        let claims: Value = serde_json::from_slice(
+            &URL_SAFE_NO_PAD
+                .decode(assertion.split('.').nth(1).unwrap())
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(claims["iss"], "publisher@example.iam.gserviceaccount.com");
+        assert_eq!(claims["scope"], ANDROID_PUBLISHER_SCOPE);
+        assert!(
+            requests[1..]
+                .iter()
+                .all(|request| request.authorization.as_deref() == Some("Bearer token-1"))
+        );
+        assert_eq!(requests[2].body, b"signed-aab");
+
+        let track: Value = serde_json::from_slice(&requests[3].body).unwrap();
+        assert_eq!(
+            track,
+            json!({
+                "track": "beta",
+                "releases": [{
+                    "name": "1.2.0",
+                    "versionCodes": ["42"],
+                    "status": "inProgress",
+                    "userFraction": 0.1,
+                    "releaseNotes": [{ "language": "en-US", "text": "Bug fixes" }],
+                }],
+            })
+        );
+    }
+
+    #[test]
+    fn deletes_the_edit_when_a_step_fails() {
+        let (result, requests) = publish(PlayTrack::Internal, &PlayRelease::default());
+        let error = result.unwrap_err().to_string();
+        assert!(
+            error.contains("403") && error.contains("Track is locked."),
+            "{error}"
+        );
+        let last = requests.last().unwrap();
+        assert_eq!(last.method, "DELETE");
+        assert!(last.path.ends_with("/edits/edit-1"));
+        assert!(
+            requests
+                .iter()
+                .all(|request| !request.path.ends_with(":commit"))
+        );
+    }
+
+    #[test]
+    fn parses_tracks_and_release_notes() {
+        assert_eq!("alpha".parse::<PlayTrack>().unwrap(), PlayTrack::Alpha);
+        assert!("nightly".parse::<PlayTrack>().is_err());
+        assert_eq!(
+            "de-DE=Fehlerbehebungen = Stabilität"
+                .parse::<ReleaseNotes>()
+                .unwrap(),
+            ReleaseNotes {
+                language: "de-DE".to_owned(),
+                text: "Fehlerbehebungen = Stabilität".to_owned(),
+            }
+        );
+        assert!("Bug fixes".parse::<ReleaseNotes>().is_err());
+        assert!(ServiceAccount::from_json(r#"{"type":"authorized_user"}"#).is_err());
+        let account = service_account("https://oauth2.googleapis.com/token");
+        assert!(!format!("{account:?}").contains("PRIVATE KEY"));
+    }
+}
@@ -0,0 +1,28 @@
+#[cfg(feature = "android")]
+mod android;
+#[cfg(feature = "android")]
+mod google_play;
+
+#[cfg(feature = "android")]
+pub use google_play::*;
+
+use crate::error::Result;
+use clap::Parser;
+use crossbundle_tools::types::CliContext;
+
+#[derive(Parser, Clone, Debug)]
+pub enum PublishCommand {
+    /// Uploads a signed release AAB to Google Play and releases it on a track
+    #[cfg(feature = "android")]
+    Android(android::AndroidPublishCommand),
+}
+
+impl PublishCommand {
+    pub fn handle_command(&self, _context: &CliContext) -> Result<()> {
+        #[cfg(feature = "android")]
+        match &self {
+            Self::Android(cmd) => cmd.run(_context)?,
+        }
+        Ok(())
+    }
+}
@@ -55,23 +55,36 @@ impl crossbundle_tools::toolchain::Runner for AndroidRunPlanRunner<'_> {
 
     fn run_step(&mut self, step: &crossbundle_tools::toolchain::PlanStep) -> Result<()> {
         use crossbundle_tools::toolchain::PlanStepKind;
-        if self.build.try_run_build_step(step.kind)? {
+        if self.build.try_run_build_step(step)? {
             return Ok(());
         }
         match step.kind {
             PlanStepKind::GenerateApksArchive => {
                 let mut command = self.build.bundletool_command()?;
-                let Some(AndroidBuildArtifact::NativeAab {
+                let Some(AndroidBuildArtifact::Aab {
                     path,
                     package,
-                    key,
+                    signer,
                     apks,
                     ..
                 }) = self.build.artifact.as_mut()
                 else {
                     return Err(anyhow::anyhow!("AAB artifact was not built").into());
                 };
+                // APKs for the connected device are signed locally, an external signer
+                // only signs the published AAB.
+                let key = match signer {
+                    AndroidSigner::Keystore(key) => key.clone(),
+                    AndroidSigner::External(_) => {
+                        self.build
+                            .config
+                            .status_message("Generating", "debug signing key for APKs")?;
+                        gen_key(None, None, None)?.into()
+                    }
+                };
                 self.build.config.status("Generating apks")?;
+                let store_password = SecretFile::new(&key.store_password)?;
+                let key_password = SecretFile::new(key.key_password())?;
                 let output = path
                     .parent()
                     .ok_or_else(|| anyhow::anyhow!("AAB output path has no parent directory"))?
@@ -84,11 +97,13 @@ impl crossbundle_tools::toolchain::Runner for AndroidRunPlanRunner<'_> {
                     .arg(&output)
                     .arg("--overwrite")
                     .arg("--ks")
-                    .arg(&key.key_path)
+                    .arg(&key.keystore)
                     .arg("--ks-pass")
-                    .arg(format!("pass:{}", key.key_pass))
+                    .arg(store_password.password_arg())
                     .arg("--ks-key-alias")
-                    .arg(&key.key_alias);
+                    .arg(&key.key_alias)
+                    .arg("--key-pass")
+                    .arg(key_password.password_arg());
                 command.output_err(true)?;
                 *apks = Some(output);
             }
@@ -98,11 +113,27 @@ impl crossbundle_tools::toolchain::Runner for AndroidRunPlanRunner<'_> {
                 .as_ref()
                 .ok_or_else(|| anyhow::anyhow!("installable artifact was not built"))?
             {
-                AndroidBuildArtifact::NativeApk { sdk, path, .. } => {
+                AndroidBuildArtifact::NativeApk { sdk, apks, .. } => {
+                    let path = match apks.as_slice() {
+                        [(None, path)] => path,
+                        apks => {
+                            let abi = detect_abi(sdk)?;
+                            &apks
+                                .iter()
+                                .find(|(target, _)| *target == Some(abi))
+                                .ok_or_else(|| {
+                                    anyhow::anyhow!(
+                                        "no APK was built for the device ABI `{}`",
+                                        abi.android_abi()
+                                    )
+                                })?
+                                .1
+                        }
+                    };
                     self.build.config.status("Installing APK file")?;
                     install_apk(sdk, path)?;
                 }
-                AndroidBuildArtifact::NativeAab { apks, .. } => {
+                AndroidBuildArtifact::Aab { apks, .. } => {
                     self.build.config.status("Installing APKs file")?;
                     let apks = apks
                         .as_ref()
@@ -133,7 +164,7 @@ impl crossbundle_tools::toolchain::Runner for AndroidRunPlanRunner<'_> {
                     .ok_or_else(|| anyhow::anyhow!("launchable artifact was not built"))?
                 {
                     AndroidBuildArtifact::NativeApk { manifest, sdk, .. }
-                    | AndroidBuildArtifact::NativeAab { manifest, sdk, .. } => {
+                    | AndroidBuildArtifact::Aab { manifest, sdk, .. } => {
                         start_manifest_activity(sdk, manifest)?;
                     }
                     AndroidBuildArtifact::Gradle { manifest, sdk, .. } => {
@@ -151,7 +182,7 @@ impl crossbundle_tools::toolchain::Runner for AndroidRunPlanRunner<'_> {
                     .ok_or_else(|| anyhow::anyhow!("artifact was not built"))?
                 {
                     AndroidBuildArtifact::NativeApk { sdk, .. }
-                    | AndroidBuildArtifact::NativeAab { sdk, .. }
+                    | AndroidBuildArtifact::Aab { sdk, .. }
                     | AndroidBuildArtifact::Gradle { sdk, .. } => sdk,
                 };
                 attach_logger_only_app(sdk)?;
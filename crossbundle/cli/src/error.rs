@@ -12,6 +12,10 @@ pub enum Error {
     DoctorFailed,
     /// Failed to serialize the doctor report: {0}
     DoctorReport(serde_json::Error),
+    /// Failed to serialize the inspect report: {0}
+    InspectReport(serde_json::Error),
+    /// Failed to serialize the keystore report: {0}
+    KeystoreReport(serde_json::Error),
     /// Platform '{0}' was not compiled into this crossbundle build
     #[cfg(any(feature = "android", feature = "apple"))]
     DoctorPlatformDisabled(crossbundle_tools::toolchain::DoctorPlatform),
@@ -34,6 +38,10 @@ pub enum Error {
     PathNotFound(std::path::PathBuf),
     /// Home dir not found
     HomeDirNotFound,
+    /// Invalid Google Play service account {0:?}: {1}
+    InvalidServiceAccount(std::path::PathBuf, String),
+    /// Google Play publishing failed: {0}
+    GooglePlay(String),
     /// Failed to download jar file: {0:?}
     DownloadFailed(Box<ureq::Error>),
     /// Failed to create jar file in specified path `{path}` cause of `{cause}`
@@ -51,7 +59,7 @@ pub enum Error {
 impl Error {
     pub fn exit_code(&self) -> i32 {
         match self {
-            Self::DoctorReport(_) => 2,
+            Self::DoctorReport(_) | Self::InspectReport(_) | Self::KeystoreReport(_) => 2,
             #[cfg(any(feature = "android", feature = "apple"))]
             Self::DoctorPlatformDisabled(_) => 2,
             _ => 1,
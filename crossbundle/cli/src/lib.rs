@@ -165,6 +165,220 @@ mod tests {
         assert!(command.json && command.strict);
     }
 
+    #[cfg(any(feature = "android", feature = "apple"))]
+    #[test]
+    fn parses_inspect_path_and_json() {
+        let opts =
+            Opts::try_parse_from(["crossbundle", "inspect", "target/game.apk", "--json"]).unwrap();
+        let commands::Commands::Inspect(command) = opts.cmd else {
+            panic!("expected inspect")
+        };
+        assert_eq!(command.path, std::path::PathBuf::from("target/game.apk"));
+        assert!(command.json);
+        assert!(Opts::try_parse_from(["crossbundle", "inspect"]).is_err());
+    }
+
+    #[cfg(feature = "android")]
+    #[test]
+    fn parses_signing_password_sources() {
+        use crossbundle_tools::types::SecretSource;
+
+        let opts = Opts::try_parse_from([
+            "crossbundle",
+            "build",
+            "android",
+            "--sign-key-path",
+            "upload.p12",
+            "--sign-key-alias",
+            "upload",
+            "--sign-key-pass-from",
+            "env:UPLOAD_STORE_PASSWORD",
+        ])
+        .unwrap();
+        let commands::Commands::Build(commands::build::BuildCommand::Android(command)) = opts.cmd
+        else {
+            panic!("expected android build")
+        };
+        assert_eq!(
+            command.sign_key_pass_from,
+            Some(SecretSource::Env("UPLOAD_STORE_PASSWORD".to_owned()))
+        );
+        for args in [
+            &["--sign-key-path", "upload.p12"][..],
+            &["--sign-key-pass-from", "pass:android"][..],
+            &[
+                "--sign-key-pass",
+                "android",
+                "--sign-key-pass-from",
+                "stdin",
+            ][..],
+        ] {
+            let mut command = vec!["crossbundle", "build", "android"];
+            command.extend(args);
+            assert!(Opts::try_parse_from(command).is_err());
+        }
+
+        let opts = Opts::try_parse_from([
+            "crossbundle",
+            "build",
+            "android",
+            "--sign-key-path",
+            "upload.p12",
+            "--sign-key-alias",
+            "upload",
+            "--sign-key-pass",
+            "plain-password",
+        ])
+        .unwrap();
+        assert!(!format!("{:?}", opts.cmd).contains("plain-password"));
+    }
+
+    #[cfg(feature = "android")]
+    #[test]
+    fn parses_keystore_generate_options() {
+        use crossbundle_tools::{commands::android::SigningKeyAlgorithm, types::KeystoreType};
+
+        let opts = Opts::try_parse_from([
+            "crossbundle",
+            "keystore",
+            "generate",
+            "-k",
+            "upload.jks",
+            "-a",
+            "upload",
+            "--store-pass",
+            "store-pass",
+            "--key-pass",
+            "key-pass",
+            "--dname",
+            "CN=Crossbow,O=Crossbow,C=US",
+            "--keyalg",
+            "ec",
+            "--validity",
+            "9000",
+            "--store-type",
+            "jks",
+        ])
+        .unwrap();
+        let commands::Commands::Keystore(commands::keystore::KeystoreCommand::Generate(command)) =
+            opts.cmd
+        else {
+            panic!("expected keystore generate")
+        };
+        assert_eq!(command.keystore.alias, "upload");
+        assert_eq!(
+            command.keystore.key_pass.as_ref().map(|pass| pass.expose()),
+            Some("key-pass")
+        );
+        assert_eq!(command.keyalg, SigningKeyAlgorithm::Ec);
+        assert_eq!(command.keysize, None);
+        assert_eq!(command.validity, 9000);
+        assert_eq!(command.store_type, KeystoreType::Jks);
+        assert!(
+            Opts::try_parse_from([
+                "crossbundle",
+                "keystore",
+                "generate",
+                "-k",
+                "upload.p12",
+                "-a",
+                "upload",
+                "--store-pass",
+                "store-pass",
+            ])
+            .is_err()
+        );
+    }
+
+    #[cfg(feature = "android")]
+    #[test]
+    fn parses_keystore_export_cert_format() {
+        use crossbundle_tools::commands::android::CertificateFormat;
+
+        let args = |format: &'static str| {
+            let mut args = vec![
+                "crossbundle",
+                "keystore",
+                "export-cert",
+                "-k",
+                "upload.p12",
+                "-a",
+                "upload",
+                "--store-pass",
+                "store-pass",
+                "-o",
+                "upload.pem",
+            ];
+            if !format.is_empty() {
+                args.extend(["--format", format]);
+            }
+            args
+        };
+        for (format, expected) in [
+            ("", CertificateFormat::Pem),
+            ("der", CertificateFormat::Der),
+        ] {
+            let opts = Opts::try_parse_from(args(format)).unwrap();
+            let commands::Commands::Keystore(commands::keystore::KeystoreCommand::ExportCert(
+                command,
+            )) = opts.cmd
+            else {
+                panic!("expected keystore export-cert")
+            };
+            assert_eq!(command.format, expected);
+        }
+        assert!(Opts::try_parse_from(args("crt")).is_err());
+    }
+
+    #[cfg(feature = "android")]
+    #[test]
+    fn parses_publish_android_options() {
+        use commands::publish::PlayTrack;
+
+        let opts = Opts::try_parse_from([
+            "crossbundle",
+            "publish",
+            "android",
+            "--track",
+            "beta",
+            "--service-account",
+            "play.json",
+            "--release-notes",
+            "en-US=Bug fixes",
+            "--release-notes",
+            "de-DE=Fehlerbehebungen",
+            "--rollout",
+            "20",
+            "--variant",
+            "free",
+        ])
+        .unwrap();
+        let commands::Commands::Publish(commands::publish::PublishCommand::Android(command)) =
+            opts.cmd
+        else {
+            panic!("expected android publish command")
+        };
+        assert_eq!(command.track, PlayTrack::Beta);
+        assert_eq!(command.release_notes.len(), 2);
+        assert_eq!(command.rollout, 20.0);
+        assert_eq!(command.variant.as_deref(), Some("free"));
+        assert_eq!(command.api_url, commands::publish::DEFAULT_API_URL);
+        for track in ["nightly", ""] {
+            assert!(
+                Opts::try_parse_from([
+                    "crossbundle",
+                    "publish",
+                    "android",
+                    "--track",
+                    track,
+                    "--service-account",
+                    "play.json",
+                ])
+                .is_err()
+            );
+        }
+    }
+
     #[cfg(any(feature = "android", feature = "apple"))]
     #[test]
     fn parses_repeated_and_delimited_doctor_platforms() {
@@ -267,4 +481,55 @@ mod tests {
         };
         assert!(command.dry_run && command.json);
     }
+
+    #[cfg(feature = "android")]
+    #[test]
+    fn parses_variant_for_android_run_dry_run() {
+        let opts = Opts::try_parse_from([
+            "crossbundle",
+            "run",
+            "android",
+            "--variant",
+            "staging",
+            "--dry-run",
+        ])
+        .unwrap();
+        let commands::Commands::Run(commands::run::RunCommand::Android(command)) = opts.cmd else {
+            panic!("expected Android run")
+        };
+        assert_eq!(
+            command.build_command.shared.variant.as_deref(),
+            Some("staging")
+        );
+        assert!(command.build_command.dry_run);
+    }
+
+    #[test]
+    fn parses_custom_profile_for_android_build() {
+        let opts = Opts::try_parse_from([
+            "crossbundle",
+            "build",
+            "android",
+            "--profile",
+            "release-lto",
+        ])
+        .unwrap();
+        let commands::Commands::Build(commands::build::BuildCommand::Android(command)) = opts.cmd
+        else {
+            panic!("expected Android build")
+        };
+        assert_eq!(command.shared.profile.as_deref(), Some("release-lto"));
+
+        assert!(
+            Opts::try_parse_from([
+                "crossbundle",
+                "build",
+                "android",
+                "--release",
+                "--profile",
+                "release-lto",
+            ])
+            .is_err()
+        );
+    }
 }
@@ -3,7 +3,9 @@
 use crossbundle_lib::commands::build::{BuildContext, android::AndroidBuildCommand};
 use crossbundle_tools::{
     commands::gen_minimal_project,
-    types::{AndroidStrategy, AndroidTarget, CliContext, Shell, android_manifest::from_str},
+    types::{
+        AndroidStrategy, AndroidTarget, CliContext, Profile, Shell, android_manifest::from_str,
+    },
 };
 
 #[test]
@@ -34,8 +36,12 @@ fn test_cargo_metadata() {
         .status_message("Reading metadata", package_name)
         .unwrap();
 
-    let android_manifest =
-        AndroidBuildCommand::get_android_manifest(&context, AndroidStrategy::GradleApk).unwrap();
+    let (android_manifest, _) = AndroidBuildCommand::get_android_manifest(
+        &context,
+        AndroidStrategy::GradleApk,
+        &Profile::Debug,
+    )
+    .unwrap();
 
     let expected_manifest = r#"<?xml version="1.0" encoding="utf-8"?>
 <manifest xmlns:android="http://schemas.android.com/apk/res/android" package="com.crossbow.example" android:versionCode="1" android:versionName="0.1.0">
@@ -34,17 +34,17 @@ fn test_execute_aab() {
     let sdk = AndroidSdk::from_env().unwrap();
     let ndk = AndroidNdk::from_env(sdk.sdk_path()).unwrap();
     let java = std::path::PathBuf::from("java");
-    let jarsigner = std::path::PathBuf::from("jarsigner");
     let bundletool = std::path::PathBuf::from(std::env::var_os("BUNDLETOOL_PATH").unwrap());
-    let (_, _, generated_aab_path, _, _) = AndroidBuildCommand::execute_aab(
+    let signer = android_build_command.signer(&config, &context).unwrap();
+    let (_, _, generated_aab_path, _) = AndroidBuildCommand::execute_aab(
         &android_build_command,
         &config,
         &context,
         &sdk,
         &ndk,
         &java,
-        &jarsigner,
         &bundletool,
+        &signer,
     )
     .unwrap();
     let expected_path = target_dir
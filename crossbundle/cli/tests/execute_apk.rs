@@ -33,9 +33,17 @@ fn test_execute_apk() {
 
     let sdk = AndroidSdk::from_env().unwrap();
     let ndk = AndroidNdk::from_env(sdk.sdk_path()).unwrap();
-    let (_, _, generated_apk_path) =
-        AndroidBuildCommand::execute_apk(&android_build_command, &config, &context, &sdk, &ndk)
-            .unwrap();
+    let signer = android_build_command.signer(&config, &context).unwrap();
+    let (_, _, generated_apk_path) = AndroidBuildCommand::execute_apk(
+        &android_build_command,
+        &config,
+        &context,
+        &sdk,
+        &ndk,
+        None,
+        &signer,
+    )
+    .unwrap();
     let expected_path = target_dir
         .join("android")
         .join("example")
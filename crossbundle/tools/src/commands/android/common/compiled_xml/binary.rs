@@ -0,0 +1,425 @@
+use super::{XmlAttribute, XmlElement, framework_attribute_name};
+use crate::error::{AndroidError, Result};
+
+const RES_STRING_POOL_TYPE: u16 = 0x0001;
+const RES_XML_TYPE: u16 = 0x0003;
+const RES_XML_START_NAMESPACE_TYPE: u16 = 0x0100;
+const RES_XML_END_NAMESPACE_TYPE: u16 = 0x0101;
+const RES_XML_START_ELEMENT_TYPE: u16 = 0x0102;
+const RES_XML_END_ELEMENT_TYPE: u16 = 0x0103;
+const RES_XML_RESOURCE_MAP_TYPE: u16 = 0x0180;
+const UTF8_FLAG: u32 = 1 << 8;
+const NO_INDEX: u32 = u32::MAX;
+
+/// Decodes Android binary XML, the format of `AndroidManifest.xml` and XML resources
+/// inside APKs.
+pub fn decode_binary_xml(data: &[u8]) -> Result<XmlElement> {
+    parse(data).map_err(|error| AndroidError::InvalidCompiledXml(error).into())
+}
+
+fn parse(data: &[u8]) -> std::result::Result<XmlElement, String> {
+    let (chunk_type, header_size, size) = chunk_header(data, 0)?;
+    if chunk_type != RES_XML_TYPE {
+        return Err(format!("unexpected document chunk type {chunk_type:#x}"));
+    }
+    let end = size.min(data.len());
+    let mut strings = Vec::new();
+    let mut resource_ids = Vec::new();
+    let mut pending_namespaces = Vec::new();
+    let mut stack: Vec<XmlElement> = Vec::new();
+    let mut root = None;
+    let mut offset = header_size;
+    while offset + 8 <= end {
+        let (chunk_type, header_size, size) = chunk_header(data, offset)?;
+        if size < 8 || offset + size > end {
+            return Err(format!("chunk at {offset} has invalid size {size}"));
+        }
+        let chunk = &data[offset..offset + size];
+        let body = header_size.max(16);
+        match chunk_type {
+            RES_STRING_POOL_TYPE => strings = string_pool(chunk)?,
+            RES_XML_RESOURCE_MAP_TYPE => {
+                resource_ids = chunk[header_size.min(size)..]
+                    .chunks_exact(4)
+                    .map(|id| u32::from_le_bytes([id[0], id[1], id[2], id[3]]))
+                    .collect();
+            }
+            RES_XML_START_NAMESPACE_TYPE => {
+                let prefix = string(&strings, u32_at(chunk, body)?)?;
+                let uri = string(&strings, u32_at(chunk, body + 4)?)?;
+                pending_namespaces.push((prefix, uri));
+            }
+            RES_XML_END_NAMESPACE_TYPE => {}
+            RES_XML_START_ELEMENT_TYPE => {
+                let name = string(&strings, u32_at(chunk, body + 4)?)?;
+                let attribute_start = u16_at(chunk, body + 8)? as usize;
+                let attribute_size = u16_at(chunk, body + 10)? as usize;
+                let attribute_count = u16_at(chunk, body + 12)? as usize;
+                let mut attributes = Vec::with_capacity(attribute_count);
+                for index in 0..attribute_count {
+                    let attribute = body + attribute_start + index * attribute_size;
+                    attributes.push(self::attribute(&strings, &resource_ids, chunk, attribute)?);
+                }
+                stack.push(XmlElement {
+                    name,
+                    namespaces: std::mem::take(&mut pending_namespaces),
+                    attributes,
+                    children: Vec::new(),
+                });
+            }
+            RES_XML_END_ELEMENT_TYPE => {
+                let element = stack.pop().ok_or("unbalanced end element")?;
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => root = Some(element),
+                }
+            }
+            // CDATA and unknown chunks don't matter for manifests.
+            _ => {}
+        }
+        offset += size;
+    }
+    if !stack.is_empty() {
+        return Err("document ended inside an element".to_owned());
+    }
+    root.ok_or_else(|| "document has no root element".to_owned())
+}
+
+fn attribute(
+    strings: &[String],
+    resource_ids: &[u32],
+    chunk: &[u8],
+    offset: usize,
+) -> std::result::Result<XmlAttribute, String> {
+    let namespace = u32_at(chunk, offset)?;
+    let name_index = u32_at(chunk, offset + 4)?;
+    let raw_value = u32_at(chunk, offset + 8)?;
+    let data_type = *chunk.get(offset + 15).ok_or("truncated attribute")?;
+    let data = u32_at(chunk, offset + 16)?;
+
+    let mut name = string(strings, name_index)?;
+    if name.is_empty()
+        && let Some(id) = resource_ids.get(name_index as usize)
+    {
+        name = framework_attribute_name(*id)
+            .map(ToOwned::to_owned)
+            .unwrap_or_else(|| format!("{id:#010x}"));
+    }
+    let value = if raw_value != NO_INDEX {
+        string(strings, raw_value)?
+    } else {
+        typed_value(strings, data_type, data)?
+    };
+    Ok(XmlAttribute {
+        namespace: (namespace != NO_INDEX)
+            .then(|| string(strings, namespace))
+            .transpose()?,
+        name,
+        value,
+    })
+}
+
+/// Formats a `Res_value` the way `aapt2 dump xmltree` does.
+fn typed_value(
+    strings: &[String],
+    data_type: u8,
+    data: u32,
+) -> std::result::Result<String, String> {
+    Ok(match data_type {
+        0x00 => String::new(),
+        0x01 | 0x07 => format!("@{data:#010x}"),
+        0x02 => format!("?{data:#010x}"),
+        0x03 => string(strings, data)?,
+        0x04 => f32::from_bits(data).to_string(),
+        0x10 => (data as i32).to_string(),
+        0x11 => format!("{data:#x}"),
+        0x12 => (data != 0).to_string(),
+        0x1c..=0x1f => format!("#{data:08x}"),
+        _ => format!("(type {data_type:#04x}){data:#x}"),
+    })
+}
+
+fn string_pool(chunk: &[u8]) -> std::result::Result<Vec<String>, String> {
+    let header_size = u16_at(chunk, 2)? as usize;
+    let count = u32_at(chunk, 8)? as usize;
+    let utf8 = u32_at(chunk, 16)? & UTF8_FLAG != 0;
+    let strings_start = u32_at(chunk, 20)? as usize;
+    let mut strings = Vec::with_capacity(count.min(chunk.len() / 4));
+    for index in 0..count {
+        let offset = strings_start.saturating_add(u32_at(chunk, header_size + index * 4)? as usize);
+        strings.push(if utf8 {
+            utf8_string(chunk, offset)?
+        } else {
+            utf16_string(chunk, offset)?
+        });
+    }
+    Ok(strings)
+}
+
+fn utf8_string(chunk: &[u8], offset: usize) -> std::result::Result<String, String> {
+    let byte = |offset: usize| chunk.get(offset).copied().ok_or("truncated string");
+    // The UTF-16 length comes first and is only needed to skip it.
+    let mut offset = offset + if byte(offset)? & 0x80 != 0 { 2 } else { 1 };
+    let mut length = byte(offset)? as usize;
+    offset += 1;
+    if length & 0x80 != 0 {
+        length = ((length & 0x7f) << 8) | byte(offset)? as usize;
+        offset += 1;
+    }
+    let bytes = chunk
+        .get(offset..offset + length)
+        .ok_or("truncated string")?;
+    Ok(String::from_utf8_lossy(bytes).into_owned())
+}
+
+fn utf16_string(chunk: &[u8], offset: usize) -> std::result::Result<String, String> {
+    let mut length = u16_at(chunk, offset)? as usize;
+    let mut offset = offset + 2;
+    if length & 0x8000 != 0 {
+        length = ((length & 0x7fff) << 16) | u16_at(chunk, offset)? as usize;
+        offset += 2;
+    }
+    let units = (0..length)
+        .map(|index| u16_at(chunk, offset + index * 2))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(String::from_utf16_lossy(&units))
+}
+
+fn string(strings: &[String], index: u32) -> std::result::Result<String, String> {
+    strings
+        .get(index as usize)
+        .cloned()
+        .ok_or_else(|| format!("string index {index} is out of bounds"))
+}
+
+fn chunk_header(data: &[u8], offset: usize) -> std::result::Result<(u16, usize, usize), String> {
+    Ok((
+        u16_at(data, offset)?,
+        u16_at(data, offset + 2)? as usize,
+        u32_at(data, offset + 4)? as usize,
+    ))
+}
+
+fn u16_at(data: &[u8], offset: usize) -> std::result::Result<u16, String> {
+    data.get(offset..offset.saturating_add(2))
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        .ok_or_else(|| format!("unexpected end of data at {offset}"))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> std::result::Result<u32, String> {
+    data.get(offset..offset.saturating_add(4))
+        .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or_else(|| format!("unexpected end of data at {offset}"))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::{super::ANDROID_NAMESPACE, *};
+
+    /// Typed attribute value used by [`encode_binary_xml`].
+    pub(crate) enum Value<'a> {
+        String(&'a str),
+        Int(u32),
+        Reference(u32),
+    }
+
+    /// Element used by [`encode_binary_xml`]. Attributes with a resource ID are
+    /// written in the `android:` namespace.
+    pub(crate) struct Element<'a> {
+        pub name: &'a str,
+        pub attributes: Vec<(&'a str, Option<u32>, Value<'a>)>,
+        pub children: Vec<Element<'a>>,
+    }
+
+    /// Encodes a document with an UTF-16 string pool, like aapt2 does for manifests.
+    pub(crate) fn encode_binary_xml(root: &Element) -> Vec<u8> {
+        // Attribute names with resource IDs must come first to match the resource map.
+        let mut strings: Vec<String> = Vec::new();
+        let mut resource_ids = Vec::new();
+        fn collect_ids(element: &Element, strings: &mut Vec<String>, ids: &mut Vec<u32>) {
+            for (name, id, _) in &element.attributes {
+                if let Some(id) = id
+                    && !strings.iter().any(|string| string == name)
+                {
+                    strings.push(name.to_string());
+                    ids.push(*id);
+                }
+            }
+            for child in &element.children {
+                collect_ids(child, strings, ids);
+            }
+        }
+        collect_ids(root, &mut strings, &mut resource_ids);
+        let mut index = |value: &str| match strings.iter().position(|string| string == value) {
+            Some(index) => index as u32,
+            None => {
+                strings.push(value.to_owned());
+                strings.len() as u32 - 1
+            }
+        };
+        let prefix = index("android");
+        let uri = index(ANDROID_NAMESPACE);
+
+        let mut elements = Vec::new();
+        fn write_element(
+            element: &Element,
+            uri: u32,
+            index: &mut dyn FnMut(&str) -> u32,
+            output: &mut Vec<u8>,
+        ) {
+            let mut attributes = Vec::new();
+            for (name, id, value) in &element.attributes {
+                let namespace = if id.is_some() { uri } else { NO_INDEX };
+                let (raw, data_type, data) = match value {
+                    Value::String(value) => {
+                        let string = index(value);
+                        (string, 0x03u8, string)
+                    }
+                    Value::Int(value) => (NO_INDEX, 0x10, *value),
+                    Value::Reference(value) => (NO_INDEX, 0x01, *value),
+                };
+                for field in [namespace, index(name), raw] {
+                    attributes.extend(field.to_le_bytes());
+                }
+                attributes.extend(8u16.to_le_bytes());
+                attributes.extend([0, data_type]);
+                attributes.extend(data.to_le_bytes());
+            }
+            let name = index(element.name);
+            let mut body = Vec::new();
+            body.extend(NO_INDEX.to_le_bytes());
+            body.extend(name.to_le_bytes());
+            body.extend(20u16.to_le_bytes());
+            body.extend(20u16.to_le_bytes());
+            body.extend((element.attributes.len() as u16).to_le_bytes());
+            body.extend([0; 6]);
+            body.extend(attributes);
+            write_node(output, RES_XML_START_ELEMENT_TYPE, &body);
+            for child in &element.children {
+                write_element(child, uri, index, output);
+            }
+            let mut end = Vec::new();
+            end.extend(NO_INDEX.to_le_bytes());
+            end.extend(name.to_le_bytes());
+            write_node(output, RES_XML_END_ELEMENT_TYPE, &end);
+        }
+        let mut namespace = Vec::new();
+        namespace.extend(prefix.to_le_bytes());
+        namespace.extend(uri.to_le_bytes());
+        write_node(&mut elements, RES_XML_START_NAMESPACE_TYPE, &namespace);
+        write_element(root, uri, &mut index, &mut elements);
+        write_node(&mut elements, RES_XML_END_NAMESPACE_TYPE, &namespace);
+
+        let mut offsets = Vec::new();
+        let mut string_data = Vec::new();
+        for string in &strings {
+            offsets.extend((string_data.len() as u32).to_le_bytes());
+            let units: Vec<u16> = string.encode_utf16().collect();
+            string_data.extend((units.len() as u16).to_le_bytes());
+            units
+                .iter()
+                .for_each(|unit| string_data.extend(unit.to_le_bytes()));
+            string_data.extend([0, 0]);
+        }
+        string_data.resize(string_data.len().next_multiple_of(4), 0);
+        let mut pool = Vec::new();
+        pool.extend((strings.len() as u32).to_le_bytes());
+        pool.extend(0u32.to_le_bytes());
+        pool.extend(0u32.to_le_bytes());
+        pool.extend((28 + offsets.len() as u32).to_le_bytes());
+        pool.extend(0u32.to_le_bytes());
+        pool.extend(offsets);
+        pool.extend(string_data);
+
+        let mut chunks = Vec::new();
+        write_chunk(&mut chunks, RES_STRING_POOL_TYPE, 28, &pool);
+        let map: Vec<u8> = resource_ids
+            .iter()
+            .flat_map(|id| id.to_le_bytes())
+            .collect();
+        write_chunk(&mut chunks, RES_XML_RESOURCE_MAP_TYPE, 8, &map);
+        chunks.extend(elements);
+        let mut document = Vec::new();
+        write_chunk(&mut document, RES_XML_TYPE, 8, &chunks);
+        document
+    }
+
+    fn write_chunk(output: &mut Vec<u8>, chunk_type: u16, header_size: u16, body: &[u8]) {
+        output.extend(chunk_type.to_le_bytes());
+        output.extend(header_size.to_le_bytes());
+        output.extend((body.len() as u32 + 8).to_le_bytes());
+        output.extend(body);
+    }
+
+    fn write_node(output: &mut Vec<u8>, chunk_type: u16, body: &[u8]) {
+        let mut node = vec![0; 8];
+        node[4..].copy_from_slice(&NO_INDEX.to_le_bytes());
+        node.extend(body);
+        write_chunk(output, chunk_type, 16, &node);
+    }
+
+    pub(crate) fn test_manifest() -> Element<'static> {
+        Element {
+            name: "manifest",
+            attributes: vec![
+                ("versionCode", Some(0x0101021b), Value::Int(7)),
+                ("versionName", Some(0x0101021c), Value::String("1.2.0")),
+                ("package", None, Value::String("com.crossbow.test")),
+            ],
+            children: vec![
+                Element {
+                    name: "uses-sdk",
+                    attributes: vec![
+                        ("minSdkVersion", Some(0x0101020c), Value::Int(23)),
+                        ("targetSdkVersion", Some(0x01010270), Value::Int(36)),
+                    ],
+                    children: Vec::new(),
+                },
+                Element {
+                    name: "application",
+                    attributes: vec![("icon", Some(0x01010002), Value::Reference(0x7f010000))],
+                    children: Vec::new(),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn decodes_binary_manifest() {
+        let manifest = decode_binary_xml(&encode_binary_xml(&test_manifest())).unwrap();
+        assert_eq!(manifest.name, "manifest");
+        assert_eq!(
+            manifest.namespaces,
+            vec![("android".to_owned(), ANDROID_NAMESPACE.to_owned())]
+        );
+        assert_eq!(
+            manifest.attribute(None, "package"),
+            Some("com.crossbow.test")
+        );
+        assert_eq!(manifest.android_attribute("versionCode"), Some("7"));
+        assert_eq!(manifest.android_attribute("versionName"), Some("1.2.0"));
+        let uses_sdk = manifest.child("uses-sdk").unwrap();
+        assert_eq!(uses_sdk.android_attribute("minSdkVersion"), Some("23"));
+        assert_eq!(uses_sdk.android_attribute("targetSdkVersion"), Some("36"));
+        let application = manifest.child("application").unwrap();
+        assert_eq!(application.android_attribute("icon"), Some("@0x7f010000"));
+    }
+
+    #[test]
+    fn names_stripped_attributes_by_resource_id() {
+        let mut manifest = test_manifest();
+        manifest.children[0].attributes[0].0 = "";
+        let manifest = decode_binary_xml(&encode_binary_xml(&manifest)).unwrap();
+        let uses_sdk = manifest.child("uses-sdk").unwrap();
+        assert_eq!(uses_sdk.android_attribute("minSdkVersion"), Some("23"));
+    }
+
+    #[test]
+    fn rejects_truncated_documents() {
+        let document = encode_binary_xml(&test_manifest());
+        for length in [0, 7, 40, document.len() - 12] {
+            assert!(decode_binary_xml(&document[..length]).is_err());
+        }
+        assert!(decode_binary_xml(b"<manifest />").is_err());
+    }
+}
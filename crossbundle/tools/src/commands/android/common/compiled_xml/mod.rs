@@ -0,0 +1,244 @@
+//! XML element tree shared by the manifest tools, with decoders for compiled Android XML
+//! documents: binary XML used inside APKs and aapt2's protobuf XML used inside AAB modules.
+
+mod binary;
+mod proto;
+
+pub use binary::*;
+pub use proto::*;
+
+use crate::error::Result;
+
+#[cfg(test)]
+pub(crate) use binary::tests::{encode_binary_xml, test_manifest};
+#[cfg(test)]
+pub(crate) use proto::tests::encode_proto_xml;
+
+/// Android namespace URI that the `android:` prefix is bound to.
+pub const ANDROID_NAMESPACE: &str = "http://schemas.android.com/apk/res/android";
+
+/// Element of a decoded XML document.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct XmlElement {
+    pub name: String,
+    /// Namespaces declared on this element as `(prefix, uri)` pairs.
+    pub namespaces: Vec<(String, String)>,
+    pub attributes: Vec<XmlAttribute>,
+    pub children: Vec<XmlElement>,
+}
+
+/// Attribute of a decoded XML element. Compiled values are formatted the way
+/// `aapt2 dump xmltree` would print them, e.g. `@0x7f010000` for references.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct XmlAttribute {
+    pub namespace: Option<String>,
+    pub name: String,
+    pub value: String,
+}
+
+impl XmlElement {
+    /// Returns the value of an attribute with given namespace URI and name.
+    pub fn attribute(&self, namespace: Option<&str>, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|attribute| attribute.namespace.as_deref() == namespace && attribute.name == name)
+            .map(|attribute| attribute.value.as_str())
+    }
+
+    /// Returns the value of an attribute in the `android:` namespace.
+    pub fn android_attribute(&self, name: &str) -> Option<&str> {
+        self.attribute(Some(ANDROID_NAMESPACE), name)
+    }
+
+    /// Returns the first direct child with given name.
+    pub fn child(&self, name: &str) -> Option<&XmlElement> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    /// Parses a text XML document, like a manifest serialized by `android_manifest`.
+    pub fn parse(xml: &[u8]) -> Result<Self> {
+        use xml::reader::{EventReader, XmlEvent};
+
+        let mut stack: Vec<(XmlElement, xml::namespace::Namespace)> = Vec::new();
+        for event in EventReader::new(xml) {
+            match event.map_err(|error| anyhow::anyhow!("invalid Android XML: {error}"))? {
+                XmlEvent::StartElement {
+                    name,
+                    attributes,
+                    namespace,
+                } => {
+                    let namespaces = namespace
+                        .iter()
+                        .filter(|(prefix, uri)| {
+                            !matches!(*prefix, "" | "xml" | "xmlns")
+                                && stack
+                                    .last()
+                                    .is_none_or(|(_, parent)| parent.get(prefix) != Some(*uri))
+                        })
+                        .map(|(prefix, uri)| (prefix.to_owned(), uri.to_owned()))
+                        .collect();
+                    let element = XmlElement {
+                        name: name.borrow().to_repr(),
+                        namespaces,
+                        attributes: attributes
+                            .into_iter()
+                            .map(|attribute| XmlAttribute {
+                                namespace: attribute.name.namespace,
+                                name: attribute.name.local_name,
+                                value: attribute.value,
+                            })
+                            .collect(),
+                        children: Vec::new(),
+                    };
+                    stack.push((element, namespace));
+                }
+                XmlEvent::EndElement { .. } => {
+                    let (element, _) = stack.pop().expect("xml reader balances elements");
+                    match stack.last_mut() {
+                        Some((parent, _)) => parent.children.push(element),
+                        None => return Ok(element),
+                    }
+                }
+                _ => {}
+            }
+        }
+        Err(anyhow::anyhow!("Android XML has no root element").into())
+    }
+
+    /// Serializes the element and its children into an indented XML document.
+    pub fn to_xml_string(&self) -> String {
+        let mut output = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+        self.write_xml(&mut output, &mut Vec::new(), 0);
+        output
+    }
+
+    fn write_xml(&self, output: &mut String, scope: &mut Vec<(String, String)>, depth: usize) {
+        let scope_len = scope.len();
+        scope.extend(self.namespaces.iter().cloned());
+        let indent = "    ".repeat(depth);
+        output.push_str(&format!("{indent}<{}", self.name));
+        for (prefix, uri) in &self.namespaces {
+            output.push_str(&format!(" xmlns:{prefix}=\"{}\"", escape_xml(uri)));
+        }
+        for attribute in &self.attributes {
+            let prefix = attribute.namespace.as_ref().map(|namespace| {
+                scope
+                    .iter()
+                    .rev()
+                    .find(|(_, uri)| uri == namespace)
+                    .map(|(prefix, _)| prefix.clone())
+                    .unwrap_or_else(|| namespace.clone())
+            });
+            match prefix {
+                Some(prefix) => output.push_str(&format!(
+                    "\n{indent}    {prefix}:{}=\"{}\"",
+                    attribute.name,
+                    escape_xml(&attribute.value)
+                )),
+                None => output.push_str(&format!(
+                    "\n{indent}    {}=\"{}\"",
+                    attribute.name,
+                    escape_xml(&attribute.value)
+                )),
+            }
+        }
+        if self.children.is_empty() {
+            output.push_str(" />\n");
+        } else {
+            output.push_str(">\n");
+            for child in &self.children {
+                child.write_xml(output, scope, depth + 1);
+            }
+            output.push_str(&format!("{indent}</{}>\n", self.name));
+        }
+        scope.truncate(scope_len);
+    }
+}
+
+fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for character in value.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\n' => escaped.push_str("&#10;"),
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+/// Name of a framework attribute by its resource ID. Used for attributes whose names were
+/// stripped from the string pool by resource shrinkers.
+fn framework_attribute_name(id: u32) -> Option<&'static str> {
+    Some(match id {
+        0x01010000 => "theme",
+        0x01010001 => "label",
+        0x01010002 => "icon",
+        0x01010003 => "name",
+        0x0101000c => "hasCode",
+        0x0101000f => "debuggable",
+        0x01010010 => "exported",
+        0x0101001e => "screenOrientation",
+        0x0101001f => "configChanges",
+        0x01010024 => "value",
+        0x0101020c => "minSdkVersion",
+        0x0101021b => "versionCode",
+        0x0101021c => "versionName",
+        0x01010270 => "targetSdkVersion",
+        0x01010271 => "maxSdkVersion",
+        0x01010280 => "allowBackup",
+        0x01010281 => "glEsVersion",
+        0x0101028e => "required",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_namespaced_attributes() {
+        let manifest = XmlElement {
+            name: "manifest".to_owned(),
+            namespaces: vec![("android".to_owned(), ANDROID_NAMESPACE.to_owned())],
+            attributes: vec![
+                XmlAttribute {
+                    namespace: None,
+                    name: "package".to_owned(),
+                    value: "com.crossbow.test".to_owned(),
+                },
+                XmlAttribute {
+                    namespace: Some(ANDROID_NAMESPACE.to_owned()),
+                    name: "versionName".to_owned(),
+                    value: "1.0 \"beta\" & more".to_owned(),
+                },
+            ],
+            children: vec![XmlElement {
+                name: "application".to_owned(),
+                ..Default::default()
+            }],
+        };
+        assert_eq!(
+            manifest.to_xml_string(),
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+             <manifest xmlns:android=\"http://schemas.android.com/apk/res/android\"\n    \
+             package=\"com.crossbow.test\"\n    \
+             android:versionName=\"1.0 &quot;beta&quot; &amp; more\">\n    \
+             <application />\n\
+             </manifest>\n"
+        );
+        assert_eq!(
+            manifest.android_attribute("versionName"),
+            Some("1.0 \"beta\" & more")
+        );
+        assert!(manifest.child("application").is_some());
+        assert_eq!(
+            XmlElement::parse(manifest.to_xml_string().as_bytes()).unwrap(),
+            manifest
+        );
+    }
+}
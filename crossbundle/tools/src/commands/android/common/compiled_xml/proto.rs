@@ -0,0 +1,316 @@
+use super::{XmlAttribute, XmlElement, framework_attribute_name};
+use crate::error::{AndroidError, Result};
+
+/// Decodes aapt2's protobuf XML (`aapt.pb.XmlNode`), the format of `AndroidManifest.xml`
+/// and XML resources inside AAB modules.
+pub fn decode_proto_xml(data: &[u8]) -> Result<XmlElement> {
+    let element = fields(data)
+        .and_then(|fields| {
+            fields
+                .into_iter()
+                .find_map(|(number, value)| match (number, value) {
+                    (1, Field::Bytes(element)) => Some(element),
+                    _ => None,
+                })
+                .ok_or_else(|| "document has no root element".to_owned())
+        })
+        .and_then(element);
+    element.map_err(|error| AndroidError::InvalidCompiledXml(error).into())
+}
+
+/// Decodes `aapt.pb.XmlElement`.
+fn element(data: &[u8]) -> std::result::Result<XmlElement, String> {
+    let mut element = XmlElement::default();
+    for (number, value) in fields(data)? {
+        match (number, value) {
+            (1, Field::Bytes(namespace)) => {
+                let namespace = fields(namespace)?;
+                element
+                    .namespaces
+                    .push((string_field(&namespace, 1)?, string_field(&namespace, 2)?));
+            }
+            (3, Field::Bytes(name)) => element.name = utf8(name)?,
+            (4, Field::Bytes(attribute)) => element.attributes.push(self::attribute(attribute)?),
+            (5, Field::Bytes(node)) => {
+                // Text nodes don't matter for manifests.
+                for (number, value) in fields(node)? {
+                    if let (1, Field::Bytes(child)) = (number, value) {
+                        element.children.push(self::element(child)?);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(element)
+}
+
+/// Decodes `aapt.pb.XmlAttribute`.
+fn attribute(data: &[u8]) -> std::result::Result<XmlAttribute, String> {
+    let fields = fields(data)?;
+    let namespace = string_field(&fields, 1)?;
+    let mut name = string_field(&fields, 2)?;
+    let resource_id = fields.iter().find_map(|(number, value)| match value {
+        Field::Varint(id) if *number == 5 => Some(*id as u32),
+        _ => None,
+    });
+    if name.is_empty()
+        && let Some(id) = resource_id
+    {
+        name = framework_attribute_name(id)
+            .map(ToOwned::to_owned)
+            .unwrap_or_else(|| format!("{id:#010x}"));
+    }
+    let mut value = string_field(&fields, 3)?;
+    if value.is_empty()
+        && let Some(item) = bytes_field(&fields, 6)
+    {
+        value = compiled_item(item)?;
+    }
+    Ok(XmlAttribute {
+        namespace: (!namespace.is_empty()).then_some(namespace),
+        name,
+        value,
+    })
+}
+
+/// Formats `aapt.pb.Item`, used when an attribute has no raw string value.
+fn compiled_item(data: &[u8]) -> std::result::Result<String, String> {
+    let item = fields(data)?;
+    if let Some(reference) = bytes_field(&item, 1) {
+        let reference = fields(reference)?;
+        let name = string_field(&reference, 3)?;
+        if !name.is_empty() {
+            return Ok(format!("@{name}"));
+        }
+        let id = varint_field(&reference, 2).unwrap_or_default();
+        return Ok(format!("@{id:#010x}"));
+    }
+    for number in [2, 3] {
+        if let Some(string) = bytes_field(&item, number) {
+            return string_field(&fields(string)?, 1);
+        }
+    }
+    if let Some(primitive) = bytes_field(&item, 7) {
+        let primitive = fields(primitive)?;
+        if let Some((number, value)) = primitive.into_iter().next() {
+            return Ok(match (number, value) {
+                (3, Field::Fixed32(value)) => f32::from_bits(value).to_string(),
+                (6, Field::Varint(value)) => (value as i32).to_string(),
+                (7, Field::Varint(value)) => format!("{:#x}", value as u32),
+                (8, Field::Varint(value)) => (value != 0).to_string(),
+                (9..=12, Field::Varint(value)) => format!("#{:08x}", value as u32),
+                _ => String::new(),
+            });
+        }
+    }
+    Ok(String::new())
+}
+
+enum Field<'a> {
+    Varint(u64),
+    Fixed32(u32),
+    Fixed64,
+    Bytes(&'a [u8]),
+}
+
+/// Splits a protobuf message into its fields.
+fn fields(mut data: &[u8]) -> std::result::Result<Vec<(u64, Field<'_>)>, String> {
+    let mut fields = Vec::new();
+    while !data.is_empty() {
+        let key = varint(&mut data)?;
+        let value = match key & 0x7 {
+            0 => Field::Varint(varint(&mut data)?),
+            1 => {
+                take(&mut data, 8)?;
+                Field::Fixed64
+            }
+            2 => {
+                let length = usize::try_from(varint(&mut data)?).map_err(|e| e.to_string())?;
+                Field::Bytes(take(&mut data, length)?)
+            }
+            5 => Field::Fixed32(u32::from_le_bytes(
+                take(&mut data, 4)?.try_into().unwrap_or_default(),
+            )),
+            wire_type => return Err(format!("unsupported wire type {wire_type}")),
+        };
+        fields.push((key >> 3, value));
+    }
+    Ok(fields)
+}
+
+fn varint(data: &mut &[u8]) -> std::result::Result<u64, String> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = data.split_first().ok_or("truncated varint")?;
+        *data = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err("varint is too long".to_owned())
+}
+
+fn take<'a>(data: &mut &'a [u8], length: usize) -> std::result::Result<&'a [u8], String> {
+    if data.len() < length {
+        return Err("truncated field".to_owned());
+    }
+    let (value, rest) = data.split_at(length);
+    *data = rest;
+    Ok(value)
+}
+
+fn bytes_field<'a>(fields: &[(u64, Field<'a>)], number: u64) -> Option<&'a [u8]> {
+    fields.iter().find_map(|(field, value)| match value {
+        Field::Bytes(bytes) if *field == number => Some(*bytes),
+        _ => None,
+    })
+}
+
+fn varint_field(fields: &[(u64, Field)], number: u64) -> Option<u64> {
+    fields.iter().find_map(|(field, value)| match value {
+        Field::Varint(value) if *field == number => Some(*value),
+        _ => None,
+    })
+}
+
+fn string_field(fields: &[(u64, Field)], number: u64) -> std::result::Result<String, String> {
+    bytes_field(fields, number)
+        .map(utf8)
+        .transpose()
+        .map(Option::unwrap_or_default)
+}
+
+fn utf8(data: &[u8]) -> std::result::Result<String, String> {
+    String::from_utf8(data.to_vec()).map_err(|error| error.to_string())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::{super::ANDROID_NAMESPACE, *};
+
+    fn write_varint(output: &mut Vec<u8>, mut value: u64) {
+        while value >= 0x80 {
+            output.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        output.push(value as u8);
+    }
+
+    fn write_bytes(output: &mut Vec<u8>, number: u64, bytes: &[u8]) {
+        write_varint(output, number << 3 | 2);
+        write_varint(output, bytes.len() as u64);
+        output.extend(bytes);
+    }
+
+    /// Encodes an `aapt.pb.XmlNode`. Attributes with a resource ID are written in the
+    /// `android:` namespace, integers and references only as compiled items.
+    pub(crate) fn encode_proto_xml(root: &super::super::binary::tests::Element) -> Vec<u8> {
+        use super::super::binary::tests::{Element, Value};
+        fn encode_element(element: &Element, root: bool) -> Vec<u8> {
+            let mut output = Vec::new();
+            if root {
+                let mut namespace = Vec::new();
+                write_bytes(&mut namespace, 1, b"android");
+                write_bytes(&mut namespace, 2, ANDROID_NAMESPACE.as_bytes());
+                write_bytes(&mut output, 1, &namespace);
+            }
+            write_bytes(&mut output, 3, element.name.as_bytes());
+            for (name, id, value) in &element.attributes {
+                let mut attribute = Vec::new();
+                if let Some(id) = id {
+                    write_bytes(&mut attribute, 1, ANDROID_NAMESPACE.as_bytes());
+                    write_bytes(&mut attribute, 2, name.as_bytes());
+                    write_varint(&mut attribute, 5 << 3);
+                    write_varint(&mut attribute, u64::from(*id));
+                } else {
+                    write_bytes(&mut attribute, 2, name.as_bytes());
+                }
+                let mut item = Vec::new();
+                match value {
+                    Value::String(value) => {
+                        write_bytes(&mut attribute, 3, value.as_bytes());
+                        let mut string = Vec::new();
+                        write_bytes(&mut string, 1, value.as_bytes());
+                        write_bytes(&mut item, 2, &string);
+                    }
+                    Value::Int(value) => {
+                        let mut primitive = Vec::new();
+                        write_varint(&mut primitive, 6 << 3);
+                        write_varint(&mut primitive, u64::from(*value));
+                        write_bytes(&mut item, 7, &primitive);
+                    }
+                    Value::Reference(value) => {
+                        let mut reference = Vec::new();
+                        write_varint(&mut reference, 2 << 3);
+                        write_varint(&mut reference, u64::from(*value));
+                        write_bytes(&mut item, 1, &reference);
+                    }
+                }
+                write_bytes(&mut attribute, 6, &item);
+                write_bytes(&mut output, 4, &attribute);
+            }
+            for child in &element.children {
+                let mut node = Vec::new();
+                write_bytes(&mut node, 1, &encode_element(child, false));
+                write_bytes(&mut output, 5, &node);
+            }
+            output
+        }
+        let mut node = Vec::new();
+        write_bytes(&mut node, 1, &encode_element(root, true));
+        node
+    }
+
+    #[test]
+    fn decodes_manifest_compiled_by_aapt2() {
+        // Base module manifest of an AAB built with `aapt2 link --proto-format`.
+        let data = std::fs::read(
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/fixtures/compiled_xml/proto_AndroidManifest.xml"),
+        )
+        .unwrap();
+        let manifest = decode_proto_xml(&data).unwrap();
+
+        assert_eq!(manifest.name, "manifest");
+        assert_eq!(
+            manifest.attribute(None, "package"),
+            Some("com.rust.minimal_unsigned_aab")
+        );
+        assert_eq!(manifest.android_attribute("versionCode"), Some("1"));
+        let uses_sdk = manifest.child("uses-sdk").unwrap();
+        assert_eq!(uses_sdk.android_attribute("minSdkVersion"), Some("9"));
+        assert_eq!(uses_sdk.android_attribute("targetSdkVersion"), Some("30"));
+    }
+
+    #[test]
+    fn decodes_proto_manifest() {
+        let manifest = super::super::binary::tests::test_manifest();
+        let manifest = decode_proto_xml(&encode_proto_xml(&manifest)).unwrap();
+        assert_eq!(manifest.name, "manifest");
+        assert_eq!(
+            manifest.namespaces,
+            vec![("android".to_owned(), ANDROID_NAMESPACE.to_owned())]
+        );
+        assert_eq!(
+            manifest.attribute(None, "package"),
+            Some("com.crossbow.test")
+        );
+        assert_eq!(manifest.android_attribute("versionCode"), Some("7"));
+        assert_eq!(manifest.android_attribute("versionName"), Some("1.2.0"));
+        let uses_sdk = manifest.child("uses-sdk").unwrap();
+        assert_eq!(uses_sdk.android_attribute("minSdkVersion"), Some("23"));
+        assert_eq!(uses_sdk.android_attribute("targetSdkVersion"), Some("36"));
+        let application = manifest.child("application").unwrap();
+        assert_eq!(application.android_attribute("icon"), Some("@0x7f010000"));
+    }
+
+    #[test]
+    fn rejects_malformed_messages() {
+        let document = encode_proto_xml(&super::super::binary::tests::test_manifest());
+        assert!(decode_proto_xml(&document[..document.len() - 3]).is_err());
+        assert!(decode_proto_xml(&[]).is_err());
+        assert!(decode_proto_xml(&[0x0f]).is_err());
+    }
+}
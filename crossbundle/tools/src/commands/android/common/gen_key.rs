@@ -1,13 +1,190 @@
-use android_tools::java_tools::{Key, KeyAlgorithm, Keytool};
+use crate::{
+    commands::android::SecretFile,
+    error::{AndroidError, CommandExt, Result},
+    types::{KeystoreType, Secret},
+};
+use android_tools::java_tools::{Key, keytool};
 use std::path::PathBuf;
 
-/// Generates keystore with default configuration. You can manage configuration with
-/// options
+/// Distinguished name of the debug keystore generated when no signing key is given.
+pub const DEFAULT_DEBUG_DNAME: &str = "CN=Android Debug,O=Android,C=US";
+
+/// Validity of generated keys in days. Google Play requires upload keys to stay valid
+/// until at least 22 October 2033.
+pub const DEFAULT_KEY_VALIDITY: u32 = 10000;
+
+/// Algorithm of a generated key pair.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SigningKeyAlgorithm {
+    /// RSA key. The only algorithm supported by the built-in APK and AAB signers.
+    #[default]
+    Rsa,
+    /// Elliptic curve key. Can be used with `apksigner`, but not with the built-in signers.
+    Ec,
+}
+
+impl SigningKeyAlgorithm {
+    /// Key size used by `keytool` defaults for the algorithm.
+    pub fn default_key_size(self) -> u32 {
+        match self {
+            Self::Rsa => 2048,
+            Self::Ec => 256,
+        }
+    }
+}
+
+impl std::str::FromStr for SigningKeyAlgorithm {
+    type Err = AndroidError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "rsa" => Ok(Self::Rsa),
+            "ec" => Ok(Self::Ec),
+            _ => Err(AndroidError::InvalidKeystoreOptions(format!(
+                "unsupported key algorithm `{s}`, use `rsa` or `ec`"
+            ))),
+        }
+    }
+}
+
+/// Options of a keystore generated with [`gen_keystore`].
+#[derive(Debug, Clone)]
+pub struct KeystoreOptions {
+    pub path: PathBuf,
+    pub alias: String,
+    pub store_pass: Secret,
+    /// Password of the key. Defaults to the store password.
+    pub key_pass: Option<Secret>,
+    /// Distinguished name of the self-signed certificate, e.g. `CN=Name,O=Company,C=US`.
+    pub dname: String,
+    pub algorithm: SigningKeyAlgorithm,
+    /// Key size in bits. Defaults to [`SigningKeyAlgorithm::default_key_size`].
+    pub key_size: Option<u32>,
+    /// Validity of the certificate in days.
+    pub validity: u32,
+    pub store_type: KeystoreType,
+}
+
+impl KeystoreOptions {
+    /// Options of the debug keystore generated for the given key.
+    pub fn debug(key: &Key) -> Self {
+        Self {
+            path: key.key_path.clone(),
+            alias: key.key_alias.clone(),
+            store_pass: Secret::new(key.key_pass.clone()),
+            key_pass: None,
+            dname: DEFAULT_DEBUG_DNAME.to_owned(),
+            algorithm: SigningKeyAlgorithm::Rsa,
+            key_size: None,
+            validity: DEFAULT_KEY_VALIDITY,
+            store_type: KeystoreType::Pkcs12,
+        }
+    }
+
+    /// Checks options before `keytool` is invoked.
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |reason: &str| AndroidError::InvalidKeystoreOptions(reason.to_owned());
+        if self.path.exists() {
+            return Err(AndroidError::KeystoreAlreadyExists(self.path.clone()).into());
+        }
+        if self.alias.is_empty() {
+            return Err(invalid("key alias can't be empty").into());
+        }
+        if self.store_pass.expose().len() < 6
+            || self
+                .key_pass
+                .as_ref()
+                .is_some_and(|pass| pass.expose().len() < 6)
+        {
+            return Err(invalid("passwords must be at least 6 characters long").into());
+        }
+        if self.store_type == KeystoreType::Pkcs12
+            && self
+                .key_pass
+                .as_ref()
+                .is_some_and(|pass| pass != &self.store_pass)
+        {
+            return Err(invalid(
+                "PKCS#12 keystores don't support a separate key password, use the `jks` store type",
+            )
+            .into());
+        }
+        if self.algorithm == SigningKeyAlgorithm::Ec
+            && !matches!(self.key_size, None | Some(256 | 384 | 521))
+        {
+            return Err(invalid("EC keys must be 256, 384 or 521 bits long").into());
+        }
+        if !self.dname.contains('=') {
+            return Err(
+                invalid("distinguished name must look like `CN=Name,O=Company,C=US`").into(),
+            );
+        }
+        if self.validity == 0 {
+            return Err(invalid("validity must be at least one day").into());
+        }
+        Ok(())
+    }
+}
+
+/// Generates a keystore with a self-signed key pair with `keytool`. Passwords are passed
+/// to `keytool` in files that only the current user can read.
+pub fn gen_keystore(options: &KeystoreOptions) -> Result<()> {
+    options.validate()?;
+    if let Some(parent) = options.path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)?;
+    }
+    let store_pass = SecretFile::new(&options.store_pass)?;
+    let key_pass = SecretFile::new(options.key_pass.as_ref().unwrap_or(&options.store_pass))?;
+    let key_size = options
+        .key_size
+        .unwrap_or_else(|| options.algorithm.default_key_size());
+    let mut keytool = keytool()?;
+    keytool
+        .arg("-genkeypair")
+        .arg("-v")
+        .arg("-keystore")
+        .arg(&options.path)
+        .arg("-storetype")
+        .arg(match options.store_type {
+            KeystoreType::Pkcs12 => "PKCS12",
+            KeystoreType::Jks => "JKS",
+        })
+        .arg("-storepass:file")
+        .arg(store_pass.path())
+        .arg("-keypass:file")
+        .arg(key_pass.path())
+        .arg("-alias")
+        .arg(&options.alias)
+        .arg("-dname")
+        .arg(&options.dname)
+        .arg("-validity")
+        .arg(options.validity.to_string());
+    match options.algorithm {
+        SigningKeyAlgorithm::Rsa => keytool
+            .arg("-keyalg")
+            .arg("RSA")
+            .arg("-keysize")
+            .arg(key_size.to_string()),
+        SigningKeyAlgorithm::Ec => keytool
+            .arg("-keyalg")
+            .arg("EC")
+            .arg("-groupname")
+            .arg(format!("secp{key_size}r1")),
+    };
+    keytool.output_err(true)?;
+    Ok(())
+}
+
+/// Returns the signing key to use, generating a debug keystore with
+/// [`DEFAULT_DEBUG_DNAME`] if no key is given and the default one doesn't exist yet.
+/// Use [`gen_keystore`] to generate release keys.
 pub fn gen_key(
     sign_key_path: Option<PathBuf>,
     sign_key_pass: Option<String>,
     sign_key_alias: Option<String>,
-) -> crate::error::Result<Key> {
+) -> Result<Key> {
     let key = match (sign_key_path, sign_key_pass, sign_key_alias) {
         (Some(key_path), Some(key_pass), Some(key_alias)) => Key {
             key_path,
@@ -21,20 +198,78 @@ pub fn gen_key(
         }
         (None, _, _) => Key::new_default()?,
     };
-    if key.key_path.exists() {
-        return Ok(key);
-    }
-    Keytool::new()
-        .genkeypair(true)
-        .v(true)
-        .keystore(&key.key_path)
-        .alias(&key.key_alias)
-        .keypass(&key.key_pass)
-        .storepass(&key.key_pass)
-        .dname(&["CN=Android Debug,O=Android,C=US".to_owned()])
-        .keyalg(KeyAlgorithm::RSA)
-        .keysize(2048)
-        .validity(10000)
-        .run()?
-        .ok_or_else(|| anyhow::anyhow!("keytool did not return the generated signing key").into())
+    if !key.key_path.exists() {
+        gen_keystore(&KeystoreOptions::debug(&key))?;
+    }
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+
+    fn options(path: PathBuf) -> KeystoreOptions {
+        KeystoreOptions {
+            path,
+            alias: "upload".to_owned(),
+            store_pass: Secret::new("store-pass"),
+            key_pass: None,
+            dname: "CN=Crossbow Upload,O=Crossbow,C=US".to_owned(),
+            algorithm: SigningKeyAlgorithm::Rsa,
+            key_size: None,
+            validity: DEFAULT_KEY_VALIDITY,
+            store_type: KeystoreType::Pkcs12,
+        }
+    }
+
+    #[test]
+    fn parses_algorithms_and_store_types() {
+        assert_eq!(
+            "EC".parse::<SigningKeyAlgorithm>().unwrap(),
+            SigningKeyAlgorithm::Ec
+        );
+        assert_eq!("jks".parse::<KeystoreType>().unwrap(), KeystoreType::Jks);
+        assert!("dsa".parse::<SigningKeyAlgorithm>().is_err());
+        assert!("jceks".parse::<KeystoreType>().is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_keystore_options() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("upload.p12");
+        assert!(options(path.clone()).validate().is_ok());
+
+        let existing = options(temp_dir.path().to_owned());
+        assert!(matches!(
+            existing.validate(),
+            Err(Error::Android(AndroidError::KeystoreAlreadyExists(_)))
+        ));
+        let mut short_pass = options(path.clone());
+        short_pass.store_pass = Secret::new("12345");
+        let mut separate_pass = options(path.clone());
+        separate_pass.key_pass = Some(Secret::new("key-pass"));
+        let mut dname = options(path.clone());
+        dname.dname = "Crossbow".to_owned();
+        let mut ec_size = options(path.clone());
+        ec_size.algorithm = SigningKeyAlgorithm::Ec;
+        ec_size.key_size = Some(2048);
+        for options in [short_pass, separate_pass.clone(), dname, ec_size] {
+            assert!(matches!(
+                options.validate(),
+                Err(Error::Android(AndroidError::InvalidKeystoreOptions(_)))
+            ));
+        }
+        separate_pass.store_type = KeystoreType::Jks;
+        assert!(separate_pass.validate().is_ok());
+    }
+
+    #[test]
+    fn debug_output_redacts_passwords() {
+        let mut options = options(PathBuf::from("upload.jks"));
+        options.key_pass = Some(Secret::new("key-pass"));
+        let output = format!("{options:?}");
+        assert!(!output.contains("store-pass") && !output.contains("key-pass"));
+        assert!(output.contains("<redacted>"));
+    }
 }
@@ -1,7 +1,10 @@
-use crate::error::*;
-use image::{DynamicImage, GenericImageView, ImageFormat, Rgba};
+use crate::{commands::IconImage, error::*, types::IconBackground};
+use image::{DynamicImage, ImageFormat, Rgba, RgbaImage, imageops};
 use serde::{Deserialize, Serialize};
-use std::{fs::File, path::PathBuf};
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+};
 
 #[derive(Debug, Default, Clone, Deserialize, Serialize)]
 pub struct ImageGeneration {
@@ -16,16 +19,20 @@ pub struct ImageGeneration {
 }
 
 impl ImageGeneration {
-    /// Generate mipmap resources from the icon. Width and height of the icon must be
-    /// equal.
+    /// Generate mipmap resources from the icon. Width and height of a raster icon must
+    /// be equal, SVG icons are rasterised for every density.
     pub fn gen_mipmap_res_from_icon(&self) -> Result<()> {
-        let image = image::open(&self.icon_path)?;
-        let (width, height) = image.dimensions();
-        if width != height || height % 2 != 0 {
+        let image = IconImage::open(&self.icon_path)?;
+        if let IconImage::Raster(raster) = &image
+            && raster.height() % 2 != 0
+        {
             return Err(Error::WidthAndHeightDifSizes);
         }
         for (name, size) in get_icon_sizes() {
-            let scaled = image.thumbnail(size, size);
+            let scaled = match &image {
+                IconImage::Raster(raster) => raster.thumbnail(size, size),
+                IconImage::Svg(_) => image.render(size).into(),
+            };
             let img = Self::round_image(&scaled, size);
             // TODO: Add shadow. See this: https://github.com/romannurik/AndroidAssetStudio
             self.write_image(&name, img)?;
@@ -64,8 +71,144 @@ impl ImageGeneration {
     }
 }
 
+/// Generates the resources of an adaptive launcher icon named `ic_launcher`.
+///
+/// Writes `mipmap-anydpi-v26/ic_launcher.xml` and `ic_launcher_round.xml` that reference
+/// the layers, the layer images for every [`MipmapDpi`], and legacy square and round
+/// `ic_launcher` images composed from the layers for Android 7.1 and older. A monochrome
+/// layer is used by themed icons on Android 13 and newer.
+#[derive(Debug, Clone)]
+pub struct AdaptiveIconGeneration {
+    pub foreground: PathBuf,
+    pub background: IconBackground,
+    pub monochrome: Option<PathBuf>,
+    /// Output path to Android resources.
+    pub output_path: PathBuf,
+}
+
+impl AdaptiveIconGeneration {
+    pub fn gen_adaptive_icon_res(&self) -> Result<()> {
+        let foreground = IconImage::open(&self.foreground)?;
+        let background = match &self.background {
+            IconBackground::Color(color) => {
+                let rgba = IconBackground::rgba(color)
+                    .ok_or_else(|| anyhow::anyhow!("invalid icon background color `{color}`"))?;
+                let values = self.output_path.join("values");
+                std::fs::create_dir_all(&values)?;
+                std::fs::write(
+                    values.join("ic_launcher_background.xml"),
+                    format!(
+                        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<resources>\n    <color name=\"ic_launcher_background\">{color}</color>\n</resources>\n"
+                    ),
+                )?;
+                Layer::Color(Rgba(rgba))
+            }
+            IconBackground::Image(path) => Layer::Image(IconImage::open(path)?),
+        };
+        let monochrome = self
+            .monochrome
+            .as_deref()
+            .map(IconImage::open)
+            .transpose()?;
+
+        for (dpi, legacy_size) in get_icon_sizes() {
+            let dir = self.output_path.join(format!("mipmap-{dpi}"));
+            std::fs::create_dir_all(&dir)?;
+            // Layers are 108 dp, legacy icons 48 dp.
+            let layer_size = legacy_size * 108 / 48;
+            let foreground = DynamicImage::from(foreground.render(layer_size));
+            write_png(&foreground, &dir.join("ic_launcher_foreground.png"))?;
+            let mut composed = match &background {
+                Layer::Color(color) => RgbaImage::from_pixel(layer_size, layer_size, *color),
+                Layer::Image(image) => {
+                    let image = image.render(layer_size);
+                    write_png(
+                        &image.clone().into(),
+                        &dir.join("ic_launcher_background.png"),
+                    )?;
+                    image
+                }
+            };
+            if let Some(monochrome) = &monochrome {
+                let monochrome = DynamicImage::from(monochrome.render(layer_size));
+                write_png(&monochrome, &dir.join("ic_launcher_monochrome.png"))?;
+            }
+            imageops::overlay(&mut composed, &foreground.to_rgba8(), 0, 0);
+            // Launchers show the centered 72 dp of the layers.
+            let viewport = layer_size * 72 / 108;
+            let offset = (layer_size - viewport) / 2;
+            let legacy = DynamicImage::from(
+                imageops::crop_imm(&composed, offset, offset, viewport, viewport).to_image(),
+            )
+            .resize_exact(legacy_size, legacy_size, imageops::FilterType::Lanczos3);
+            let corner = legacy_size as f64 / 6.0;
+            write_png(
+                &mask(&legacy, |x, y, size| {
+                    rounded_square_contains(x, y, size, corner)
+                }),
+                &dir.join("ic_launcher.png"),
+            )?;
+            write_png(
+                &mask(&legacy, |x, y, size| {
+                    let radius = size / 2.0;
+                    (x - radius).hypot(y - radius) <= radius
+                }),
+                &dir.join("ic_launcher_round.png"),
+            )?;
+        }
+
+        let background = match &background {
+            Layer::Color(_) => "@color/ic_launcher_background",
+            Layer::Image(_) => "@mipmap/ic_launcher_background",
+        };
+        let monochrome = if monochrome.is_some() {
+            "    <monochrome android:drawable=\"@mipmap/ic_launcher_monochrome\" />\n"
+        } else {
+            ""
+        };
+        let xml = format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<adaptive-icon xmlns:android=\"http://schemas.android.com/apk/res/android\">\n    <background android:drawable=\"{background}\" />\n    <foreground android:drawable=\"@mipmap/ic_launcher_foreground\" />\n{monochrome}</adaptive-icon>\n"
+        );
+        let anydpi = self.output_path.join("mipmap-anydpi-v26");
+        std::fs::create_dir_all(&anydpi)?;
+        std::fs::write(anydpi.join("ic_launcher.xml"), &xml)?;
+        std::fs::write(anydpi.join("ic_launcher_round.xml"), &xml)?;
+        Ok(())
+    }
+}
+
+enum Layer {
+    Color(Rgba<u8>),
+    Image(IconImage),
+}
+
+fn write_png(image: &DynamicImage, path: &Path) -> Result<()> {
+    let mut output = File::create(path)?;
+    image.write_to(&mut output, ImageFormat::Png)?;
+    Ok(())
+}
+
+/// Makes pixels outside of the shape transparent. The shape gets pixel centers and the
+/// image size.
+fn mask(image: &DynamicImage, contains: impl Fn(f64, f64, f64) -> bool) -> DynamicImage {
+    let mut image = image.to_rgba8();
+    let size = image.width() as f64;
+    for (x, y, pixel) in image.enumerate_pixels_mut() {
+        if !contains(x as f64 + 0.5, y as f64 + 0.5, size) {
+            *pixel = Rgba([0, 0, 0, 0]);
+        }
+    }
+    image.into()
+}
+
+fn rounded_square_contains(x: f64, y: f64, size: f64, corner: f64) -> bool {
+    let dx = (corner - x).max(x - (size - corner)).max(0.0);
+    let dy = (corner - y).max(y - (size - corner)).max(0.0);
+    dx.hypot(dy) <= corner
+}
+
 /// Scale image down according to scale ratio.
-fn get_icon_sizes() -> Vec<(String, u32)> {
+pub(crate) fn get_icon_sizes() -> Vec<(String, u32)> {
     vec![
         (MipmapDpi::Xxxhdpi.to_string(), 192),
         (MipmapDpi::Xxhdpi.to_string(), 144),
@@ -101,6 +244,7 @@ impl std::fmt::Display for MipmapDpi {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use image::GenericImageView;
     use std::env::current_dir;
 
     #[test]
@@ -131,4 +275,71 @@ mod tests {
                 .exists()
         )
     }
+
+    #[test]
+    fn generates_mipmaps_from_svg_icon() {
+        let dir = tempfile::tempdir().unwrap();
+        let icon_path = dir.path().join("icon.svg");
+        std::fs::write(
+            &icon_path,
+            r##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 3 2"><rect width="3" height="2" fill="#00FF00"/></svg>"##,
+        )
+        .unwrap();
+        ImageGeneration {
+            icon_path,
+            out_icon_name: "ic_launcher.png".to_owned(),
+            output_path: dir.path().join("res"),
+            force: false,
+        }
+        .gen_mipmap_res_from_icon()
+        .unwrap();
+        for (dpi, size) in get_icon_sizes() {
+            let icon =
+                image::open(dir.path().join(format!("res/mipmap-{dpi}/ic_launcher.png"))).unwrap();
+            assert_eq!(icon.dimensions(), (size, size));
+            assert_eq!(icon.get_pixel(size / 2, size / 2), Rgba([0, 255, 0, 255]));
+        }
+    }
+
+    #[test]
+    fn generates_adaptive_icon_with_legacy_fallbacks() {
+        let dir = tempfile::tempdir().unwrap();
+        let foreground = dir.path().join("foreground.png");
+        let monochrome = dir.path().join("monochrome.png");
+        DynamicImage::from(RgbaImage::from_pixel(216, 216, Rgba([0, 0, 255, 255])))
+            .save(&foreground)
+            .unwrap();
+        DynamicImage::from(RgbaImage::from_pixel(216, 216, Rgba([0, 0, 0, 255])))
+            .save(&monochrome)
+            .unwrap();
+        let res = dir.path().join("res");
+        AdaptiveIconGeneration {
+            foreground,
+            background: IconBackground::Color("#FF0000".into()),
+            monochrome: Some(monochrome),
+            output_path: res.clone(),
+        }
+        .gen_adaptive_icon_res()
+        .unwrap();
+
+        let xml = std::fs::read_to_string(res.join("mipmap-anydpi-v26/ic_launcher.xml")).unwrap();
+        assert!(xml.contains("@color/ic_launcher_background"));
+        assert!(xml.contains("<monochrome android:drawable=\"@mipmap/ic_launcher_monochrome\" />"));
+        assert!(res.join("mipmap-anydpi-v26/ic_launcher_round.xml").exists());
+        let colors =
+            std::fs::read_to_string(res.join("values/ic_launcher_background.xml")).unwrap();
+        assert!(colors.contains(">#FF0000</color>"));
+        for (dpi, size) in get_icon_sizes() {
+            let dir = res.join(format!("mipmap-{dpi}"));
+            let foreground = image::open(dir.join("ic_launcher_foreground.png")).unwrap();
+            assert_eq!(foreground.dimensions(), (size * 108 / 48, size * 108 / 48));
+            assert!(dir.join("ic_launcher_monochrome.png").exists());
+            let round = image::open(dir.join("ic_launcher_round.png")).unwrap();
+            assert_eq!(round.dimensions(), (size, size));
+            assert_eq!(round.get_pixel(0, 0)[3], 0);
+            assert_eq!(round.get_pixel(size / 2, size / 2), Rgba([0, 0, 255, 255]));
+            let legacy = image::open(dir.join("ic_launcher.png")).unwrap();
+            assert_eq!(legacy.get_pixel(size / 2, 1)[3], 255);
+        }
+    }
 }
@@ -0,0 +1,158 @@
+use super::get_icon_sizes;
+use crate::{commands::render_centered_image, error::*, types::SplashConfig};
+use image::{DynamicImage, ImageFormat};
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+/// Theme of the launch activity when a splash screen is configured.
+pub const SPLASH_THEME: &str = "Theme.Crossbow.Splash";
+
+/// Generates the resources of the [`SPLASH_THEME`] theme.
+///
+/// Android 12 and newer show `windowSplashScreenBackground` with the centered
+/// `windowSplashScreenAnimatedIcon`. Older versions show the `splash_background`
+/// drawable, the same color and image, as the window background. Dark mode uses the
+/// `night` resources.
+#[derive(Debug, Clone)]
+pub struct SplashScreenGeneration {
+    pub splash: SplashConfig,
+    /// Output path to Android resources.
+    pub output_path: PathBuf,
+}
+
+impl SplashScreenGeneration {
+    pub fn gen_splash_res(&self) -> Result<()> {
+        let splash = &self.splash;
+        self.write("values/splash.xml", &legacy_values(&splash.background))?;
+        if splash.dark.is_some() {
+            self.write("values-night/splash.xml", &colors(splash.dark_background()))?;
+        }
+        self.write("values-v31/splash.xml", &values_v31(splash.image.is_some()))?;
+
+        let mut layers = "    <item android:drawable=\"@color/splash_background\" />\n".to_owned();
+        if let Some(image) = &splash.image {
+            self.write_images("drawable", image)?;
+            if let Some(dark) = splash.dark_image().filter(|dark| dark != image) {
+                self.write_images("drawable-night", dark)?;
+            }
+            layers.push_str(
+                "    <item>\n        <bitmap android:gravity=\"center\" android:src=\"@drawable/splash_image\" />\n    </item>\n",
+            );
+        }
+        self.write(
+            "drawable/splash_background.xml",
+            &format!(
+                "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<layer-list xmlns:android=\"http://schemas.android.com/apk/res/android\">\n{layers}</layer-list>\n"
+            ),
+        )
+    }
+
+    /// Writes `splash_image.png` for every density. The image covers 288x288 dp and its
+    /// content is centered in 192x192 dp, the area Android 12 shows without cropping.
+    fn write_images(&self, directory: &str, image: &Path) -> Result<()> {
+        for (dpi, legacy_size) in get_icon_sizes() {
+            let size = legacy_size * 288 / 48;
+            let content = legacy_size * 192 / 48;
+            let mut canvas = image::RgbaImage::new(size, size);
+            let offset = ((size - content) / 2).into();
+            image::imageops::overlay(
+                &mut canvas,
+                &render_centered_image(image, content)?,
+                offset,
+                offset,
+            );
+            let dir = self.output_path.join(format!("{directory}-{dpi}"));
+            std::fs::create_dir_all(&dir)?;
+            let mut output = File::create(dir.join("splash_image.png"))?;
+            DynamicImage::from(canvas).write_to(&mut output, ImageFormat::Png)?;
+        }
+        Ok(())
+    }
+
+    fn write(&self, path: &str, content: &str) -> Result<()> {
+        let path = self.output_path.join(path);
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+}
+
+const FULLSCREEN_THEME: &str = "android:Theme.DeviceDefault.NoActionBar.Fullscreen";
+
+fn colors(background: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<resources>\n    <color name=\"splash_background\">{background}</color>\n</resources>\n"
+    )
+}
+
+fn legacy_values(background: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<resources>\n    <color name=\"splash_background\">{background}</color>\n    <style name=\"{SPLASH_THEME}\" parent=\"{FULLSCREEN_THEME}\">\n        <item name=\"android:windowBackground\">@drawable/splash_background</item>\n    </style>\n</resources>\n"
+    )
+}
+
+fn values_v31(image: bool) -> String {
+    let icon = if image {
+        "        <item name=\"android:windowSplashScreenAnimatedIcon\">@drawable/splash_image</item>\n"
+    } else {
+        ""
+    };
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<resources>\n    <style name=\"{SPLASH_THEME}\" parent=\"{FULLSCREEN_THEME}\">\n        <item name=\"android:windowBackground\">@color/splash_background</item>\n        <item name=\"android:windowSplashScreenBackground\">@color/splash_background</item>\n{icon}    </style>\n</resources>\n"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GenericImageView, Rgba, RgbaImage};
+
+    #[test]
+    fn generates_splash_theme_and_night_resources() {
+        let dir = tempfile::tempdir().unwrap();
+        let logo = dir.path().join("logo.png");
+        let dark_logo = dir.path().join("logo-dark.png");
+        RgbaImage::from_pixel(96, 48, Rgba([255, 0, 0, 255]))
+            .save(&logo)
+            .unwrap();
+        RgbaImage::from_pixel(48, 48, Rgba([255, 255, 255, 255]))
+            .save(&dark_logo)
+            .unwrap();
+        let splash: SplashConfig = serde_json::from_value(serde_json::json!({
+            "background": "#1B1B1F",
+            "image": logo,
+            "dark": { "background": "#000000", "image": dark_logo }
+        }))
+        .unwrap();
+        let res = dir.path().join("res");
+        SplashScreenGeneration {
+            splash,
+            output_path: res.clone(),
+        }
+        .gen_splash_res()
+        .unwrap();
+
+        let values = std::fs::read_to_string(res.join("values/splash.xml")).unwrap();
+        assert!(values.contains("<color name=\"splash_background\">#1B1B1F</color>"));
+        assert!(values.contains("@drawable/splash_background"));
+        let night = std::fs::read_to_string(res.join("values-night/splash.xml")).unwrap();
+        assert!(night.contains(">#000000</color>"));
+        let v31 = std::fs::read_to_string(res.join("values-v31/splash.xml")).unwrap();
+        assert!(v31.contains(
+            "<item name=\"android:windowSplashScreenAnimatedIcon\">@drawable/splash_image</item>"
+        ));
+        let layers = std::fs::read_to_string(res.join("drawable/splash_background.xml")).unwrap();
+        assert!(layers.contains("@drawable/splash_image"));
+
+        let image = image::open(res.join("drawable-mdpi/splash_image.png")).unwrap();
+        assert_eq!(image.dimensions(), (288, 288));
+        assert_eq!(image.get_pixel(0, 0)[3], 0);
+        assert_eq!(image.get_pixel(144, 144), Rgba([255, 0, 0, 255]));
+        // The wide image is centered vertically in the 192 dp content area.
+        assert_eq!(image.get_pixel(144, 60)[3], 0);
+        let dark = image::open(res.join("drawable-night-xxxhdpi/splash_image.png")).unwrap();
+        assert_eq!(dark.dimensions(), (1152, 1152));
+    }
+}
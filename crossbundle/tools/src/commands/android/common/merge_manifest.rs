@@ -0,0 +1,687 @@
+use super::{XmlElement, interpolate_xml};
+use crate::{
+    error::{AndroidError, Result},
+    types::BuildVariables,
+};
+use android_manifest::AndroidManifest;
+use serde::Serialize;
+use std::{fmt, fs::File, io::BufReader, path::Path};
+use xml::name::OwnedName;
+
+const ANDROID_NS: &str = "http://schemas.android.com/apk/res/android";
+const TOOLS_NS: &str = "http://schemas.android.com/tools";
+const DIST_NS: &str = "http://schemas.android.com/apk/distribution";
+
+/// Elements that appear at most once in their parent and are merged regardless of
+/// attributes.
+const SINGLETONS: &[&str] = &[
+    "manifest",
+    "application",
+    "uses-sdk",
+    "queries",
+    "supports-screens",
+    "compatible-screens",
+];
+
+/// One input of [`merge_android_manifests`].
+pub struct ManifestSource {
+    name: String,
+    root: Element,
+    overriding: bool,
+}
+
+impl ManifestSource {
+    /// Reads an `AndroidManifest.xml` file, expanding build variables like
+    /// [`read_android_manifest_with_variables`](super::read_android_manifest_with_variables).
+    /// XML sources may use the `tools:node`, `tools:replace` and `tools:remove` directives.
+    pub fn from_path(
+        name: impl Into<String>,
+        path: &Path,
+        variables: &BuildVariables,
+    ) -> Result<Self> {
+        let file = File::open(path).map_err(|_| {
+            AndroidError::FailedToFindAndroidManifest(path.to_string_lossy().to_string())
+        })?;
+        let xml = interpolate_xml(BufReader::new(file), variables)?;
+        Self::from_xml(name, &xml)
+    }
+
+    /// Parses an XML manifest.
+    pub fn from_xml(name: impl Into<String>, xml: &[u8]) -> Result<Self> {
+        Ok(Self {
+            name: name.into(),
+            root: Element::parse(xml)?,
+            overriding: false,
+        })
+    }
+
+    /// Manifest configured in `Cargo.toml`. TOML can't carry `tools:replace`, so its values
+    /// replace the ones of lower-priority sources.
+    pub fn overriding(name: impl Into<String>, manifest: &AndroidManifest) -> Result<Self> {
+        let xml = android_manifest::to_string(manifest).map_err(AndroidError::from)?;
+        Ok(Self {
+            overriding: true,
+            ..Self::from_xml(name, xml.as_bytes())?
+        })
+    }
+
+    /// Manifest fragment, e.g. of a plugin. Conflicting values are errors unless a
+    /// higher-priority XML source resolves them with `tools:replace`.
+    pub fn fragment(name: impl Into<String>, manifest: &AndroidManifest) -> Result<Self> {
+        let xml = android_manifest::to_string(manifest).map_err(AndroidError::from)?;
+        Self::from_xml(name, xml.as_bytes())
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// What the merger did with an element or attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ManifestMergeAction {
+    /// The element was taken from the source.
+    Added,
+    /// Attributes or children of the source were merged into an existing element.
+    Merged,
+    /// A value of the source was replaced by a higher-priority source.
+    Replaced,
+    /// The element or attribute of the source was removed by a `tools:` directive.
+    Removed,
+}
+
+impl fmt::Display for ManifestMergeAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Added => "ADDED",
+            Self::Merged => "MERGED",
+            Self::Replaced => "REPLACED",
+            Self::Removed => "REMOVED",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ManifestMergeRecord {
+    pub action: ManifestMergeAction,
+    /// Path of the element, e.g. `manifest/application/activity[.MainActivity]`.
+    pub element: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attribute: Option<String>,
+    /// Name of the source the element or attribute came from.
+    pub source: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Explains where every element of a merged manifest came from.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ManifestMergeReport {
+    pub records: Vec<ManifestMergeRecord>,
+}
+
+impl ManifestMergeReport {
+    fn record(
+        &mut self,
+        action: ManifestMergeAction,
+        element: &str,
+        attribute: Option<String>,
+        source: &str,
+        reason: Option<String>,
+    ) {
+        self.records.push(ManifestMergeRecord {
+            action,
+            element: element.to_owned(),
+            attribute,
+            source: source.to_owned(),
+            reason,
+        });
+    }
+}
+
+impl fmt::Display for ManifestMergeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for record in &self.records {
+            write!(f, "{} {}", record.action, record.element)?;
+            if let Some(attribute) = &record.attribute {
+                write!(f, "@{attribute}")?;
+            }
+            write!(f, " from {}", record.source)?;
+            if let Some(reason) = &record.reason {
+                write!(f, " ({reason})")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Merges manifests given from the highest to the lowest priority, like the Android
+/// Gradle Plugin's manifest merger.
+///
+/// Elements are matched by tag and `android:name`, singletons like `<application>` by
+/// tag only, and unnamed elements like `<intent-filter>` by their content. Attributes
+/// missing in a higher-priority element are taken from lower ones. Different values are
+/// a conflict unless the higher-priority element lists the attribute in `tools:replace`,
+/// or comes from an [overriding](ManifestSource::overriding) source. `tools:remove`
+/// drops attributes and `tools:node="remove"` or `"replace"` drop whole elements of
+/// lower-priority sources.
+pub fn merge_android_manifests(
+    sources: &[ManifestSource],
+) -> Result<(AndroidManifest, ManifestMergeReport)> {
+    let mut report = ManifestMergeReport::default();
+    let Some((first, rest)) = sources.split_first() else {
+        return Ok((AndroidManifest::default(), report));
+    };
+    let mut merged = first.root.clone();
+    merged.set_origin(0);
+    merged.record_added(&element_path("", &merged), &first.name, &mut report);
+    for (index, source) in rest.iter().enumerate() {
+        let mut lower = source.root.clone();
+        lower.set_origin(index + 1);
+        let path = element_path("", &merged);
+        merge_element(&mut merged, &lower, &path, sources, &mut report)?;
+    }
+    merged.strip_directives();
+    let manifest = android_manifest::from_str(&merged.to_xml()).map_err(AndroidError::from)?;
+    Ok((manifest, report))
+}
+
+fn merge_element(
+    higher: &mut Element,
+    lower: &Element,
+    path: &str,
+    sources: &[ManifestSource],
+    report: &mut ManifestMergeReport,
+) -> Result<()> {
+    let source = &sources[lower.origin].name;
+    let replace = higher.directive_names("replace");
+    let remove = higher.directive_names("remove");
+    for attribute in &lower.attributes {
+        if is_directive(&attribute.name) {
+            higher.merge_directive(attribute);
+            continue;
+        }
+        let name = qualified_name(&attribute.name);
+        if remove.contains(&name) {
+            report.record(
+                ManifestMergeAction::Removed,
+                path,
+                Some(name),
+                source,
+                Some("tools:remove".into()),
+            );
+            continue;
+        }
+        match higher
+            .attributes
+            .iter()
+            .find(|existing| same_name(&existing.name, &attribute.name))
+        {
+            None => higher.attributes.push(attribute.clone()),
+            Some(existing) if existing.value == attribute.value => {}
+            Some(existing) => {
+                let winner = &sources[existing.origin];
+                if !winner.overriding && !replace.contains(&name) {
+                    return Err(AndroidError::ManifestMergeConflict(format!(
+                        "{path}@{name} is {:?} in {} and {:?} in {source}; add tools:replace=\"{name}\" to the element in {} to keep its value",
+                        existing.value, winner.name, attribute.value, winner.name
+                    ))
+                    .into());
+                }
+                report.record(
+                    ManifestMergeAction::Replaced,
+                    path,
+                    Some(name),
+                    source,
+                    Some(format!("value of {} is kept", winner.name)),
+                );
+            }
+        }
+    }
+    report.record(ManifestMergeAction::Merged, path, None, source, None);
+
+    for child in &lower.children {
+        let child_path = element_path(path, child);
+        let key = child.key();
+        match higher
+            .children
+            .iter_mut()
+            .find(|existing| existing.key() == key)
+        {
+            Some(existing) => match existing.directive("node") {
+                Some(node @ ("remove" | "replace")) => {
+                    let action = if node == "remove" {
+                        ManifestMergeAction::Removed
+                    } else {
+                        ManifestMergeAction::Replaced
+                    };
+                    report.record(
+                        action,
+                        &child_path,
+                        None,
+                        source,
+                        Some(format!(
+                            "tools:node=\"{node}\" in {}",
+                            sources[existing.origin].name
+                        )),
+                    );
+                }
+                _ => merge_element(existing, child, &child_path, sources, report)?,
+            },
+            None => {
+                child.record_added(&child_path, source, report);
+                higher.children.push(child.clone());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn element_path(parent: &str, element: &Element) -> String {
+    let tag = qualified_name(&element.name);
+    let tag = match element.android_name() {
+        Some(name) => format!("{tag}[{name}]"),
+        None => tag,
+    };
+    if parent.is_empty() {
+        tag
+    } else {
+        format!("{parent}/{tag}")
+    }
+}
+
+fn is_directive(name: &OwnedName) -> bool {
+    name.namespace.as_deref() == Some(TOOLS_NS)
+        && matches!(name.local_name.as_str(), "node" | "replace" | "remove")
+}
+
+fn same_name(left: &OwnedName, right: &OwnedName) -> bool {
+    left.namespace == right.namespace && left.local_name == right.local_name
+}
+
+/// Name with the conventional prefix of its namespace, as used by `tools:replace`.
+fn qualified_name(name: &OwnedName) -> String {
+    match prefix_for(name) {
+        Some(prefix) => format!("{prefix}:{}", name.local_name),
+        None => name.local_name.clone(),
+    }
+}
+
+fn prefix_for(name: &OwnedName) -> Option<String> {
+    match name.namespace.as_deref() {
+        Some(ANDROID_NS) => Some("android".into()),
+        Some(TOOLS_NS) => Some("tools".into()),
+        Some(DIST_NS) => Some("dist".into()),
+        Some(_) => name.prefix.clone(),
+        None => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Attribute {
+    name: OwnedName,
+    value: String,
+    origin: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Element {
+    name: OwnedName,
+    attributes: Vec<Attribute>,
+    children: Vec<Element>,
+    origin: usize,
+}
+
+#[derive(Debug, PartialEq)]
+enum ElementKey {
+    Singleton(String),
+    Named(String, String),
+    Content(String),
+}
+
+impl Element {
+    fn parse(xml: &[u8]) -> Result<Self> {
+        Ok(Self::from_xml_element(
+            XmlElement::parse(xml)?,
+            &mut Vec::new(),
+        ))
+    }
+
+    /// Converts a parsed element, resolving prefixes against the `(prefix, uri)` namespace
+    /// declarations in scope.
+    fn from_xml_element(element: XmlElement, scope: &mut Vec<(String, String)>) -> Self {
+        let scope_len = scope.len();
+        scope.extend(element.namespaces);
+        let (prefix, local_name) = match element.name.split_once(':') {
+            Some((prefix, local_name)) => (Some(prefix.to_owned()), local_name.to_owned()),
+            None => (None, element.name),
+        };
+        let namespace = prefix.as_ref().and_then(|prefix| {
+            scope
+                .iter()
+                .rev()
+                .find(|(declared, _)| declared == prefix)
+                .map(|(_, uri)| uri.clone())
+        });
+        let attributes = element
+            .attributes
+            .into_iter()
+            .map(|attribute| Attribute {
+                name: OwnedName {
+                    prefix: attribute.namespace.as_ref().and_then(|namespace| {
+                        scope
+                            .iter()
+                            .rev()
+                            .find(|(_, uri)| uri == namespace)
+                            .map(|(prefix, _)| prefix.clone())
+                    }),
+                    namespace: attribute.namespace,
+                    local_name: attribute.name,
+                },
+                value: attribute.value,
+                origin: 0,
+            })
+            .collect();
+        let children = element
+            .children
+            .into_iter()
+            .map(|child| Self::from_xml_element(child, scope))
+            .collect();
+        scope.truncate(scope_len);
+        Self {
+            name: OwnedName {
+                local_name,
+                namespace,
+                prefix,
+            },
+            attributes,
+            children,
+            origin: 0,
+        }
+    }
+
+    fn set_origin(&mut self, origin: usize) {
+        self.origin = origin;
+        for attribute in &mut self.attributes {
+            attribute.origin = origin;
+        }
+        for child in &mut self.children {
+            child.set_origin(origin);
+        }
+    }
+
+    fn record_added(&self, path: &str, source: &str, report: &mut ManifestMergeReport) {
+        report.record(ManifestMergeAction::Added, path, None, source, None);
+        for child in &self.children {
+            child.record_added(&element_path(path, child), source, report);
+        }
+    }
+
+    fn attribute(&self, namespace: &str, local_name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|attribute| {
+                attribute.name.namespace.as_deref() == Some(namespace)
+                    && attribute.name.local_name == local_name
+            })
+            .map(|attribute| attribute.value.as_str())
+    }
+
+    fn android_name(&self) -> Option<&str> {
+        self.attribute(ANDROID_NS, "name")
+    }
+
+    fn directive(&self, name: &str) -> Option<&str> {
+        self.attribute(TOOLS_NS, name)
+    }
+
+    fn directive_names(&self, name: &str) -> Vec<String> {
+        self.directive(name)
+            .map(|names| {
+                names
+                    .split(',')
+                    .map(|name| name.trim().to_owned())
+                    .filter(|name| !name.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Keeps `tools:` directives of lower-priority sources so they still apply to the
+    /// sources merged after them.
+    fn merge_directive(&mut self, directive: &Attribute) {
+        match self
+            .attributes
+            .iter_mut()
+            .find(|existing| same_name(&existing.name, &directive.name))
+        {
+            Some(existing) if directive.name.local_name != "node" => {
+                for name in directive.value.split(',').map(str::trim) {
+                    if !existing.value.split(',').any(|value| value.trim() == name) {
+                        existing.value = format!("{},{name}", existing.value);
+                    }
+                }
+            }
+            Some(_) => {}
+            None => self.attributes.push(directive.clone()),
+        }
+    }
+
+    fn key(&self) -> ElementKey {
+        let tag = qualified_name(&self.name);
+        if SINGLETONS.contains(&tag.as_str()) {
+            return ElementKey::Singleton(tag);
+        }
+        match self.android_name() {
+            Some(name) => ElementKey::Named(tag, name.to_owned()),
+            None => ElementKey::Content(self.content()),
+        }
+    }
+
+    /// Canonical form of the element without `tools:` directives, used to match unnamed
+    /// elements.
+    fn content(&self) -> String {
+        let mut attributes = self
+            .attributes
+            .iter()
+            .filter(|attribute| !is_directive(&attribute.name))
+            .map(|attribute| format!("{}={:?}", qualified_name(&attribute.name), attribute.value))
+            .collect::<Vec<_>>();
+        attributes.sort();
+        let children = self
+            .children
+            .iter()
+            .map(Element::content)
+            .collect::<Vec<_>>();
+        format!(
+            "<{} {}>{}</>",
+            qualified_name(&self.name),
+            attributes.join(" "),
+            children.join("")
+        )
+    }
+
+    /// Drops `tools:node="remove"` markers and all merge directives from the result.
+    fn strip_directives(&mut self) {
+        self.attributes
+            .retain(|attribute| !is_directive(&attribute.name));
+        self.children
+            .retain(|child| child.directive("node") != Some("remove"));
+        for child in &mut self.children {
+            child.strip_directives();
+        }
+    }
+
+    fn to_xml(&self) -> String {
+        let mut namespaces = Vec::new();
+        self.collect_namespaces(&mut namespaces);
+        let declarations = namespaces
+            .iter()
+            .map(|(prefix, uri)| format!(" xmlns:{prefix}=\"{}\"", escape(uri)))
+            .collect::<String>();
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+        self.write_xml(&mut xml, &declarations);
+        xml
+    }
+
+    fn collect_namespaces(&self, namespaces: &mut Vec<(String, String)>) {
+        let names = std::iter::once(&self.name)
+            .chain(self.attributes.iter().map(|attribute| &attribute.name));
+        for name in names {
+            if let (Some(prefix), Some(uri)) = (prefix_for(name), &name.namespace)
+                && !namespaces.iter().any(|(existing, _)| *existing == prefix)
+            {
+                namespaces.push((prefix, uri.clone()));
+            }
+        }
+        for child in &self.children {
+            child.collect_namespaces(namespaces);
+        }
+    }
+
+    fn write_xml(&self, xml: &mut String, declarations: &str) {
+        let tag = qualified_name(&self.name);
+        xml.push('<');
+        xml.push_str(&tag);
+        xml.push_str(declarations);
+        for attribute in &self.attributes {
+            xml.push_str(&format!(
+                " {}=\"{}\"",
+                qualified_name(&attribute.name),
+                escape(&attribute.value)
+            ));
+        }
+        if self.children.is_empty() {
+            xml.push_str("/>");
+            return;
+        }
+        xml.push('>');
+        for child in &self.children {
+            child.write_xml(xml, "");
+        }
+        xml.push_str(&format!("</{tag}>"));
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = r#"<manifest xmlns:android="http://schemas.android.com/apk/res/android"
+        xmlns:tools="http://schemas.android.com/tools" package="com.example.game">
+        <application android:label="Game" tools:replace="android:label">
+            <activity android:name=".MainActivity" android:exported="true">
+                <intent-filter>
+                    <action android:name="android.intent.action.MAIN" />
+                    <category android:name="android.intent.category.LAUNCHER" />
+                </intent-filter>
+            </activity>
+            <meta-data android:name="com.example.tracking" tools:node="remove" />
+        </application>
+        <uses-permission android:name="android.permission.READ_CONTACTS"
+            tools:remove="android:maxSdkVersion" />
+    </manifest>"#;
+
+    fn fragment(name: &str, manifest: serde_json::Value) -> ManifestSource {
+        ManifestSource::fragment(name, &serde_json::from_value(manifest).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn merges_sources_by_priority_with_tools_directives() {
+        let release = ManifestSource::overriding(
+            "release overlay",
+            &serde_json::from_value(serde_json::json!({
+                "application": { "debuggable": false }
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+        let base = ManifestSource::from_xml("manifest_path", BASE.as_bytes()).unwrap();
+        let plugin = fragment(
+            "plugin play-billing",
+            serde_json::json!({
+                "application": {
+                    "label": "Billing",
+                    "meta_data": [
+                        { "name": "com.example.tracking", "value": "on" },
+                        { "name": "com.crossbow.plugin.v1.Billing", "value": "billing" }
+                    ],
+                    "activity": [{
+                        "name": ".MainActivity",
+                        "intent_filter": [{
+                            "action": [{ "name": "android.intent.action.VIEW" }]
+                        }]
+                    }]
+                },
+                "uses_permission": [
+                    { "name": "android.permission.READ_CONTACTS", "max_sdk_version": 28 },
+                    { "name": "com.android.vending.BILLING" }
+                ]
+            }),
+        );
+
+        let (manifest, report) = merge_android_manifests(&[release, base, plugin]).unwrap();
+
+        assert_eq!(manifest.package.as_deref(), Some("com.example.game"));
+        let application = &manifest.application;
+        assert_eq!(
+            application.label.as_ref().unwrap().to_string(),
+            "Game".to_string()
+        );
+        assert_eq!(application.meta_data.len(), 1);
+        assert_eq!(
+            application.meta_data[0].name.as_deref(),
+            Some("com.crossbow.plugin.v1.Billing")
+        );
+        assert_eq!(application.activity.len(), 1);
+        assert_eq!(application.activity[0].intent_filter.len(), 2);
+        assert_eq!(manifest.uses_permission.len(), 2);
+        assert_eq!(manifest.uses_permission[0].max_sdk_version, None);
+
+        let text = report.to_string();
+        assert!(text.contains("ADDED manifest/application from release overlay"));
+        assert!(text.contains("MERGED manifest/application from manifest_path"));
+        assert!(text.contains(
+            "REPLACED manifest/application@android:label from plugin play-billing (value of manifest_path is kept)"
+        ));
+        assert!(text.contains(
+            "REMOVED manifest/application/meta-data[com.example.tracking] from plugin play-billing (tools:node=\"remove\" in manifest_path)"
+        ));
+        assert!(text.contains(
+            "ADDED manifest/uses-permission[com.android.vending.BILLING] from plugin play-billing"
+        ));
+    }
+
+    #[test]
+    fn conflicting_fragments_fail_with_remediation() {
+        let first = fragment(
+            "plugin admob",
+            serde_json::json!({ "application": { "meta_data": [
+                { "name": "com.google.android.gms.ads.APPLICATION_ID", "value": "first" }
+            ] } }),
+        );
+        let second = fragment(
+            "plugin ads",
+            serde_json::json!({ "application": { "meta_data": [
+                { "name": "com.google.android.gms.ads.APPLICATION_ID", "value": "second" }
+            ] } }),
+        );
+
+        let error = merge_android_manifests(&[first, second])
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("android:value"));
+        assert!(error.contains("plugin admob"));
+        assert!(error.contains("add tools:replace"));
+    }
+}
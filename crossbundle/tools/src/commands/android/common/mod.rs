@@ -1,23 +1,39 @@
 mod attach_logger;
+mod compiled_xml;
 mod detect_abi;
 mod extract_archive;
 mod gen_key;
 mod gen_mipmap_res;
+mod gen_splash_res;
 mod helper_functions;
+mod merge_manifest;
+mod read_elf;
 mod read_manifest;
 mod rust_compile;
 mod save_manifest;
+mod signing;
 mod start_app;
 mod write_zip;
+mod zip_align;
+mod zip_layout;
 
 pub use attach_logger::*;
+pub use compiled_xml::*;
 pub use detect_abi::*;
 pub use extract_archive::*;
 pub use gen_key::*;
 pub use gen_mipmap_res::*;
+pub use gen_splash_res::*;
 pub use helper_functions::*;
+pub use merge_manifest::*;
+pub use read_elf::*;
 pub use read_manifest::*;
 pub use rust_compile::*;
 pub use save_manifest::*;
+pub use signing::*;
 pub use start_app::*;
 pub use write_zip::*;
+pub use zip_align::*;
+
+#[cfg(test)]
+pub(crate) use read_elf::tests::elf_library;
@@ -0,0 +1,423 @@
+use crate::{
+    error::{AndroidError, Result},
+    types::AndroidPageSize,
+};
+use std::path::{Path, PathBuf};
+
+const ELF_MAGIC: &[u8; 4] = b"\x7fELF";
+const ELF_CLASS_32: u8 = 1;
+const ELF_CLASS_64: u8 = 2;
+const ELF_DATA_LITTLE_ENDIAN: u8 = 1;
+/// Program header type of a loadable segment.
+pub const PT_LOAD: u32 = 1;
+/// Program header type of the dynamic linking information segment.
+pub const PT_DYNAMIC: u32 = 2;
+const DT_NULL: u64 = 0;
+const DT_NEEDED: u64 = 1;
+const DT_STRTAB: u64 = 5;
+
+/// Word size of an ELF file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElfClass {
+    Elf32,
+    Elf64,
+}
+
+/// Segment described by an ELF program header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ElfSegment {
+    pub kind: u32,
+    pub offset: u64,
+    pub virtual_address: u64,
+    pub file_size: u64,
+    pub memory_size: u64,
+    pub align: u64,
+}
+
+/// Headers of a little-endian ELF shared library, as used by every Android ABI.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElfFile {
+    pub class: ElfClass,
+    pub machine: u16,
+    pub segments: Vec<ElfSegment>,
+    /// Names of shared libraries from `DT_NEEDED` entries, in the order of the dynamic
+    /// section.
+    pub needed: Vec<String>,
+}
+
+impl ElfFile {
+    /// Reads ELF headers from the file at given path.
+    pub fn read(path: &Path) -> Result<Self> {
+        let data = std::fs::read(path)?;
+        Self::parse(&data)
+            .map_err(|reason| AndroidError::InvalidElf(path.to_owned(), reason).into())
+    }
+
+    /// Parses ELF headers. Returns the reason if data isn't a supported ELF file.
+    pub fn parse(data: &[u8]) -> std::result::Result<Self, String> {
+        if data.len() < 16 || &data[..4] != ELF_MAGIC {
+            return Err("not an ELF file".to_owned());
+        }
+        if data[5] != ELF_DATA_LITTLE_ENDIAN {
+            return Err("big-endian ELF files are not supported".to_owned());
+        }
+        let class = match data[4] {
+            ELF_CLASS_32 => ElfClass::Elf32,
+            ELF_CLASS_64 => ElfClass::Elf64,
+            class => return Err(format!("unknown ELF class {class}")),
+        };
+        let reader = Reader { data, class };
+        let machine = reader.u16(18)?;
+        let (program_headers, entry_size, count) = match class {
+            ElfClass::Elf32 => (reader.u32(28)? as u64, reader.u16(42)?, reader.u16(44)?),
+            ElfClass::Elf64 => (reader.u64(32)?, reader.u16(54)?, reader.u16(56)?),
+        };
+        let segments = (0..count as u64)
+            .map(|index| {
+                reader.segment(to_usize(
+                    program_headers.saturating_add(index * entry_size as u64),
+                )?)
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let needed = match segments.iter().find(|segment| segment.kind == PT_DYNAMIC) {
+            Some(dynamic) => reader.needed(dynamic, &segments)?,
+            None => Vec::new(),
+        };
+        Ok(Self {
+            class,
+            machine,
+            segments,
+            needed,
+        })
+    }
+
+    /// Segments that are mapped into memory when the library is loaded.
+    pub fn load_segments(&self) -> impl Iterator<Item = &ElfSegment> {
+        self.segments
+            .iter()
+            .filter(|segment| segment.kind == PT_LOAD)
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    class: ElfClass,
+}
+
+impl Reader<'_> {
+    fn bytes<const N: usize>(&self, offset: usize) -> std::result::Result<[u8; N], String> {
+        self.data
+            .get(offset..offset.saturating_add(N))
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| format!("unexpected end of file at offset {offset}"))
+    }
+
+    fn u16(&self, offset: usize) -> std::result::Result<u16, String> {
+        self.bytes(offset).map(u16::from_le_bytes)
+    }
+
+    fn u32(&self, offset: usize) -> std::result::Result<u32, String> {
+        self.bytes(offset).map(u32::from_le_bytes)
+    }
+
+    fn u64(&self, offset: usize) -> std::result::Result<u64, String> {
+        self.bytes(offset).map(u64::from_le_bytes)
+    }
+
+    /// Reads a word, which is 4 bytes long in ELF32 and 8 bytes long in ELF64.
+    fn word(&self, offset: usize) -> std::result::Result<u64, String> {
+        match self.class {
+            ElfClass::Elf32 => self.u32(offset).map(Into::into),
+            ElfClass::Elf64 => self.u64(offset),
+        }
+    }
+
+    /// Reads `DT_NEEDED` entries of the dynamic section. Their values are offsets into
+    /// the string table, which is referenced by its virtual address.
+    fn needed(
+        &self,
+        dynamic: &ElfSegment,
+        segments: &[ElfSegment],
+    ) -> std::result::Result<Vec<String>, String> {
+        let word_size = match self.class {
+            ElfClass::Elf32 => 4,
+            ElfClass::Elf64 => 8,
+        };
+        let mut string_table = None;
+        let mut name_offsets = Vec::new();
+        for index in 0..dynamic.file_size / (word_size * 2) {
+            let offset = to_usize(dynamic.offset.saturating_add(index * word_size * 2))?;
+            let tag = self.word(offset)?;
+            let value = self.word(offset + word_size as usize)?;
+            match tag {
+                DT_NULL => break,
+                DT_NEEDED => name_offsets.push(value),
+                DT_STRTAB => string_table = Some(value),
+                _ => (),
+            }
+        }
+        if name_offsets.is_empty() {
+            return Ok(Vec::new());
+        }
+        let address = string_table.ok_or("dynamic section has no string table")?;
+        let string_table = segments
+            .iter()
+            .filter(|segment| segment.kind == PT_LOAD)
+            .find(|segment| {
+                (segment.virtual_address..segment.virtual_address.saturating_add(segment.file_size))
+                    .contains(&address)
+            })
+            .map(|segment| address - segment.virtual_address + segment.offset)
+            .ok_or("string table is not mapped by any LOAD segment")?;
+        name_offsets
+            .into_iter()
+            .map(|offset| self.string(string_table.saturating_add(offset)))
+            .collect()
+    }
+
+    fn string(&self, offset: u64) -> std::result::Result<String, String> {
+        let bytes = self
+            .data
+            .get(to_usize(offset)?..)
+            .ok_or_else(|| format!("string offset {offset} is out of range"))?;
+        let end = bytes
+            .iter()
+            .position(|byte| *byte == 0)
+            .ok_or_else(|| format!("string at offset {offset} is not terminated"))?;
+        String::from_utf8(bytes[..end].to_vec())
+            .map_err(|_| format!("string at offset {offset} is not valid UTF-8"))
+    }
+
+    fn segment(&self, offset: usize) -> std::result::Result<ElfSegment, String> {
+        let kind = self.u32(offset)?;
+        Ok(match self.class {
+            ElfClass::Elf32 => ElfSegment {
+                kind,
+                offset: self.word(offset + 4)?,
+                virtual_address: self.word(offset + 8)?,
+                file_size: self.word(offset + 16)?,
+                memory_size: self.word(offset + 20)?,
+                align: self.word(offset + 28)?,
+            },
+            ElfClass::Elf64 => ElfSegment {
+                kind,
+                offset: self.word(offset + 8)?,
+                virtual_address: self.word(offset + 16)?,
+                file_size: self.word(offset + 32)?,
+                memory_size: self.word(offset + 40)?,
+                align: self.word(offset + 48)?,
+            },
+        })
+    }
+}
+
+fn to_usize(offset: u64) -> std::result::Result<usize, String> {
+    usize::try_from(offset).map_err(|_| format!("offset {offset} is out of range"))
+}
+
+/// Native library with a `LOAD` segment that can't be mapped on devices with given
+/// page size.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnalignedLibrary {
+    pub path: PathBuf,
+    /// Smallest alignment of the library `LOAD` segments.
+    pub align: u64,
+}
+
+/// Returns libraries with `LOAD` segments aligned to less than `page_size`.
+///
+/// Only 64-bit libraries are inspected, as 16 KB page size devices don't run 32-bit
+/// code.
+pub fn unaligned_libraries<P: AsRef<Path>>(
+    libraries: &[P],
+    page_size: AndroidPageSize,
+) -> Result<Vec<UnalignedLibrary>> {
+    let mut unaligned = Vec::new();
+    for path in libraries {
+        let path = path.as_ref();
+        let elf = ElfFile::read(path)?;
+        if elf.class != ElfClass::Elf64 {
+            continue;
+        }
+        if let Some(align) = elf
+            .load_segments()
+            .map(|segment| segment.align)
+            .filter(|align| *align < page_size.bytes())
+            .min()
+        {
+            unaligned.push(UnalignedLibrary {
+                path: path.to_owned(),
+                align,
+            });
+        }
+    }
+    Ok(unaligned)
+}
+
+/// Fails with the list of offending libraries if any of them can't be loaded on
+/// devices with given page size.
+pub fn check_elf_page_alignment<P: AsRef<Path>>(
+    libraries: &[P],
+    page_size: AndroidPageSize,
+) -> Result<()> {
+    let unaligned = unaligned_libraries(libraries, page_size)?;
+    if unaligned.is_empty() {
+        return Ok(());
+    }
+    let libraries = unaligned
+        .iter()
+        .map(|library| format!("{} (aligned to {})", library.path.display(), library.align))
+        .collect::<Vec<_>>()
+        .join(", ");
+    Err(AndroidError::UnalignedNativeLibraries {
+        page_size: page_size.bytes(),
+        libraries,
+    }
+    .into())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::error::Error;
+
+    /// Builds a minimal shared library header with given `LOAD` segment alignments.
+    pub(crate) fn elf_library(class: ElfClass, load_alignments: &[u64]) -> Vec<u8> {
+        let (header_size, entry_size) = match class {
+            ElfClass::Elf32 => (52, 32),
+            ElfClass::Elf64 => (64, 56),
+        };
+        let mut data = vec![0; header_size];
+        data[..4].copy_from_slice(ELF_MAGIC);
+        data[4] = match class {
+            ElfClass::Elf32 => ELF_CLASS_32,
+            ElfClass::Elf64 => ELF_CLASS_64,
+        };
+        data[5] = ELF_DATA_LITTLE_ENDIAN;
+        data[6] = 1;
+        data[16..18].copy_from_slice(&3u16.to_le_bytes());
+        let count = load_alignments.len() as u16 + 1;
+        match class {
+            ElfClass::Elf32 => {
+                data[18..20].copy_from_slice(&40u16.to_le_bytes());
+                data[28..32].copy_from_slice(&(header_size as u32).to_le_bytes());
+                data[42..44].copy_from_slice(&(entry_size as u16).to_le_bytes());
+                data[44..46].copy_from_slice(&count.to_le_bytes());
+            }
+            ElfClass::Elf64 => {
+                data[18..20].copy_from_slice(&183u16.to_le_bytes());
+                data[32..40].copy_from_slice(&(header_size as u64).to_le_bytes());
+                data[54..56].copy_from_slice(&(entry_size as u16).to_le_bytes());
+                data[56..58].copy_from_slice(&count.to_le_bytes());
+            }
+        }
+        // A non-loadable PT_PHDR segment, which must be ignored.
+        let segments = std::iter::once((6, 8)).chain(load_alignments.iter().map(|a| (PT_LOAD, *a)));
+        for (index, (kind, align)) in segments.enumerate() {
+            let mut entry = vec![0; entry_size];
+            entry[..4].copy_from_slice(&kind.to_le_bytes());
+            let address = (index as u64) * 0x10000;
+            match class {
+                ElfClass::Elf32 => {
+                    entry[4..8].copy_from_slice(&(address as u32).to_le_bytes());
+                    entry[8..12].copy_from_slice(&(address as u32).to_le_bytes());
+                    entry[28..32].copy_from_slice(&(align as u32).to_le_bytes());
+                }
+                ElfClass::Elf64 => {
+                    entry[8..16].copy_from_slice(&address.to_le_bytes());
+                    entry[16..24].copy_from_slice(&address.to_le_bytes());
+                    entry[48..56].copy_from_slice(&align.to_le_bytes());
+                }
+            }
+            data.extend(entry);
+        }
+        data
+    }
+
+    #[test]
+    fn reads_load_segments_of_both_classes() {
+        for (class, machine) in [(ElfClass::Elf32, 40), (ElfClass::Elf64, 183)] {
+            let elf = ElfFile::parse(&elf_library(class, &[16384, 4096])).unwrap();
+            assert_eq!(elf.class, class);
+            assert_eq!(elf.machine, machine);
+            assert_eq!(elf.segments.len(), 3);
+            let alignments: Vec<_> = elf.load_segments().map(|s| s.align).collect();
+            assert_eq!(alignments, [16384, 4096]);
+            assert_eq!(elf.segments[2].virtual_address, 0x20000);
+        }
+    }
+
+    fn fixture(abi: &str, name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/elf")
+            .join(abi)
+            .join(name)
+    }
+
+    #[test]
+    fn reads_needed_libraries_of_both_classes() {
+        for (abi, class, machine) in [("x86", ElfClass::Elf32, 3), ("x86_64", ElfClass::Elf64, 62)]
+        {
+            let elf = ElfFile::read(&fixture(abi, "libmain.so")).unwrap();
+            assert_eq!(elf.class, class);
+            assert_eq!(elf.machine, machine);
+            assert_eq!(elf.needed, ["libdep.so", "liblog.so", "libc++_shared.so"]);
+            let elf = ElfFile::read(&fixture(abi, "leaf/libleaf.so")).unwrap();
+            assert!(elf.needed.is_empty());
+            assert!(elf.load_segments().all(|segment| segment.align == 16384));
+        }
+        let synthetic = ElfFile::parse(&elf_library(ElfClass::Elf64, &[4096])).unwrap();
+        assert!(synthetic.needed.is_empty());
+    }
+
+    #[test]
+    fn rejects_malformed_files() {
+        assert!(ElfFile::parse(b"not an elf").is_err());
+        let mut truncated = elf_library(ElfClass::Elf64, &[16384]);
+        truncated.truncate(100);
+        assert!(ElfFile::parse(&truncated).is_err());
+    }
+
+    #[test]
+    fn reports_every_unaligned_library() {
+        let dir = tempfile::tempdir().unwrap();
+        let library = |name: &str, class: ElfClass, alignments: &[u64]| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, elf_library(class, alignments)).unwrap();
+            path
+        };
+        let libraries = [
+            library("libmain.so", ElfClass::Elf64, &[16384, 16384]),
+            library("libthirdparty.so", ElfClass::Elf64, &[16384, 4096]),
+            library("libold.so", ElfClass::Elf64, &[4096]),
+            library("libarmv7.so", ElfClass::Elf32, &[4096]),
+        ];
+        let unaligned = unaligned_libraries(&libraries, AndroidPageSize::Page16K).unwrap();
+        assert_eq!(
+            unaligned,
+            [
+                UnalignedLibrary {
+                    path: libraries[1].clone(),
+                    align: 4096
+                },
+                UnalignedLibrary {
+                    path: libraries[2].clone(),
+                    align: 4096
+                },
+            ]
+        );
+        check_elf_page_alignment(&libraries, AndroidPageSize::Page4K).unwrap();
+        match check_elf_page_alignment(&libraries, AndroidPageSize::Page16K) {
+            Err(Error::Android(AndroidError::UnalignedNativeLibraries {
+                page_size,
+                libraries,
+            })) => {
+                assert_eq!(page_size, 16384);
+                assert!(libraries.contains("libthirdparty.so (aligned to 4096)"));
+                assert!(libraries.contains("libold.so"));
+                assert!(!libraries.contains("libmain.so"));
+            }
+            result => panic!("unexpected result: {result:?}"),
+        }
+    }
+}
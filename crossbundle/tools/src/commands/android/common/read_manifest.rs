@@ -23,7 +23,10 @@ pub fn read_android_manifest_with_variables(
     Ok(android_manifest::from_reader(xml.as_slice()).map_err(AndroidError::from)?)
 }
 
-fn interpolate_xml(reader: impl std::io::Read, variables: &BuildVariables) -> Result<Vec<u8>> {
+pub(crate) fn interpolate_xml(
+    reader: impl std::io::Read,
+    variables: &BuildVariables,
+) -> Result<Vec<u8>> {
     let mut output = Vec::new();
     let mut writer = xml::EmitterConfig::new()
         .perform_indent(false)
@@ -6,11 +6,13 @@ pub fn cmake_env(
     build_target: crate::types::AndroidTarget,
     ndk: &AndroidNdk,
     min_sdk_version: u32,
+    page_size: AndroidPageSize,
     build_target_dir: &std::path::Path,
 ) -> Result<Vec<(String, std::ffi::OsString)>> {
     // Return path to toolchain cmake file
     let cmake_toolchain_path = write_cmake_toolchain(
         min_sdk_version,
+        page_size,
         ndk.ndk_path(),
         build_target_dir,
         build_target,
@@ -54,6 +56,7 @@ pub fn make_path(ndk_path: &std::path::Path) -> std::path::PathBuf {
 /// target appropriately Returns the path to the generated toolchain file
 pub fn write_cmake_toolchain(
     min_sdk_version: u32,
+    page_size: AndroidPageSize,
     ndk_path: &std::path::Path,
     build_target_dir: &std::path::Path,
     build_target: crate::types::AndroidTarget,
@@ -64,6 +67,7 @@ pub fn write_cmake_toolchain(
         toolchain_file,
         r#"set(ANDROID_PLATFORM android-{min_sdk_version})
         set(ANDROID_ABI {abi})
+        set(ANDROID_SUPPORT_FLEXIBLE_PAGE_SIZES {flexible_page_sizes})
         string(REPLACE "--target={build_target}" "" CMAKE_C_FLAGS "${{CMAKE_C_FLAGS}}")
         string(REPLACE "--target={build_target}" "" CMAKE_CXX_FLAGS "${{CMAKE_CXX_FLAGS}}")
         unset(CMAKE_C_COMPILER CACHE)
@@ -73,6 +77,10 @@ pub fn write_cmake_toolchain(
         ndk_path = dunce::simplified(ndk_path).to_string_lossy(),
         build_target = build_target.rust_triple(),
         abi = build_target.android_abi(),
+        flexible_page_sizes = match page_size {
+            AndroidPageSize::Page4K => "OFF",
+            AndroidPageSize::Page16K => "ON",
+        },
     )?;
     Ok(toolchain_path)
 }
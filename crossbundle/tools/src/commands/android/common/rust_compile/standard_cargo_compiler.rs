@@ -7,17 +7,22 @@ use crate::{
 use std::path::{Path, PathBuf};
 
 /// Build an Android shared library through Cargo's public command-line interface.
+///
+/// The library is linked with `max-page-size` of `page_size`, the result of
+/// [`link_page_size`], so its `LOAD` segments can be mapped on devices with that page size
+/// regardless of the NDK default for `min_sdk_version`.
 #[allow(clippy::too_many_arguments)]
 pub fn standard_cargo_compile(
     ndk: &AndroidNdk,
     build_target: AndroidTarget,
     package: &CargoPackage,
     library_target_name: &str,
-    profile: Profile,
+    profile: &Profile,
     features: &[String],
     all_features: bool,
     no_default_features: bool,
     min_sdk_version: u32,
+    page_size: AndroidPageSize,
     target_dir: &Path,
 ) -> Result<PathBuf> {
     let triple = build_target.rust_triple();
@@ -30,7 +35,7 @@ pub fn standard_cargo_compile(
     );
     let build_dir = target_dir.join(triple).join(profile);
     std::fs::create_dir_all(&build_dir)?;
-    let cmake = cmake_env(build_target, ndk, min_sdk_version, &build_dir)?;
+    let cmake = cmake_env(build_target, ndk, min_sdk_version, page_size, &build_dir)?;
     let target = CargoTargetSelection::Lib(library_target_name.to_owned());
     let artifact = CargoBuild {
         package,
@@ -42,7 +47,7 @@ pub fn standard_cargo_compile(
         all_features,
         no_default_features,
     }
-    .run(|cargo| {
+    .run_rustc(&page_size_link_args(page_size), |cargo| {
         cargo
             .env(format!("CC_{triple}"), &clang)
             .env(format!("CFLAGS_{triple}"), &clang_target)
@@ -81,6 +86,29 @@ pub fn standard_cargo_compile(
     }
 }
 
+/// Android 15, the first version that supports devices with 16 KB pages.
+const ANDROID_16K_PAGES_MIN_SDK: u32 = 35;
+
+/// Page size libraries are linked for. Google Play requires apps targeting Android 15 to
+/// support 16 KB pages, so apps that require Android 15 or newer are always linked for
+/// 16 KB pages. Libraries are aligned and checked for the same page size.
+pub fn link_page_size(min_sdk_version: u32, page_size: AndroidPageSize) -> AndroidPageSize {
+    match min_sdk_version >= ANDROID_16K_PAGES_MIN_SDK {
+        true => AndroidPageSize::Page16K,
+        false => page_size,
+    }
+}
+
+/// `rustc` arguments passing the `max-page-size` linker flag. They are given to
+/// `cargo rustc`, so they only apply to the library and are added to `RUSTFLAGS` and
+/// the `rustflags` of Cargo configuration.
+fn page_size_link_args(page_size: AndroidPageSize) -> Vec<String> {
+    vec![
+        "-C".to_owned(),
+        format!("link-arg=-Wl,-z,max-page-size={}", page_size.bytes()),
+    ]
+}
+
 fn cargo_env_target_cfg(key: &str, target: &str) -> String {
     format!(
         "CARGO_TARGET_{}_{}",
@@ -88,3 +116,32 @@ fn cargo_env_target_cfg(key: &str, target: &str) -> String {
         key
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_with_max_page_size_of_the_min_sdk() {
+        assert_eq!(
+            link_page_size(23, AndroidPageSize::Page16K),
+            AndroidPageSize::Page16K
+        );
+        assert_eq!(
+            link_page_size(23, AndroidPageSize::Page4K),
+            AndroidPageSize::Page4K
+        );
+        assert_eq!(
+            link_page_size(35, AndroidPageSize::Page4K),
+            AndroidPageSize::Page16K
+        );
+        assert_eq!(
+            page_size_link_args(AndroidPageSize::Page16K),
+            ["-C", "link-arg=-Wl,-z,max-page-size=16384"]
+        );
+        assert_eq!(
+            page_size_link_args(AndroidPageSize::Page4K),
+            ["-C", "link-arg=-Wl,-z,max-page-size=4096"]
+        );
+    }
+}
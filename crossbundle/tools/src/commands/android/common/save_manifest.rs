@@ -1,3 +1,4 @@
+use super::ManifestMergeReport;
 use crate::error::{AndroidError, Result};
 use android_manifest::AndroidManifest;
 use std::{
@@ -15,6 +16,14 @@ pub fn save_android_manifest(out_dir: &Path, manifest: &AndroidManifest) -> Resu
     Ok(manifest_path)
 }
 
+/// Saves the report of a manifest merge as `manifest-merger-report.txt`.
+pub fn save_manifest_merge_report(out_dir: &Path, report: &ManifestMergeReport) -> Result<PathBuf> {
+    create_dir_all(out_dir)?;
+    let report_path = out_dir.join("manifest-merger-report.txt");
+    std::fs::write(&report_path, report.to_string())?;
+    Ok(report_path)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
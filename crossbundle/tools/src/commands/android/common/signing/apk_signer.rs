@@ -0,0 +1,277 @@
+use super::{
+    ApkSignatureScheme, SigningKey,
+    jar_signer::{jar_sign_bytes, verify_jar_signature},
+    signing_block::{
+        APK_SIGNATURE_SCHEME_V2_BLOCK_ID, APK_SIGNATURE_SCHEME_V3_BLOCK_ID, add_signing_block,
+        verify_signing_block,
+    },
+};
+use crate::error::{AndroidError, Result};
+use std::path::Path;
+use x509_cert::Certificate;
+
+/// Signature schemes that APKs are signed with by default.
+pub const DEFAULT_APK_SIGNATURE_SCHEMES: [ApkSignatureScheme; 3] = [
+    ApkSignatureScheme::V1,
+    ApkSignatureScheme::V2,
+    ApkSignatureScheme::V3,
+];
+
+/// Verified signatures of an APK.
+#[derive(Debug, Clone)]
+pub struct ApkSignatures {
+    /// Schemes with a valid signature.
+    pub schemes: Vec<ApkSignatureScheme>,
+    /// Signer certificate shared by all schemes.
+    pub certificate: Certificate,
+}
+
+/// Signs APK in place with given signature schemes.
+///
+/// JAR signature is added first, so that v2 and v3 signatures cover it.
+pub fn sign_apk_with_key(
+    apk_path: &Path,
+    key: &SigningKey,
+    schemes: &[ApkSignatureScheme],
+) -> Result<()> {
+    let mut data = std::fs::read(apk_path)?;
+    if schemes.contains(&ApkSignatureScheme::V1) {
+        let apk_signed_schemes = schemes
+            .iter()
+            .filter(|scheme| **scheme != ApkSignatureScheme::V1)
+            .map(ApkSignatureScheme::id)
+            .collect::<Vec<_>>();
+        data = jar_sign_bytes(&data, key, &apk_signed_schemes)?;
+    }
+    if schemes
+        .iter()
+        .any(|scheme| *scheme != ApkSignatureScheme::V1)
+    {
+        data = add_signing_block(&data, key, schemes)?;
+    }
+    std::fs::write(apk_path, data)?;
+    Ok(())
+}
+
+/// Verifies every signature present in the APK.
+///
+/// Fails if the APK is unsigned, if any signature doesn't match the APK contents, if
+/// schemes are signed by different certificates or if a signature listed in
+/// `X-Android-APK-Signed` has been stripped.
+pub fn verify_apk(apk_path: &Path) -> Result<ApkSignatures> {
+    verify_apk_bytes(&std::fs::read(apk_path)?)
+}
+
+pub(crate) fn verify_apk_bytes(data: &[u8]) -> Result<ApkSignatures> {
+    let failed = |reason: String| AndroidError::ApkVerificationFailed(reason);
+    let mut schemes = Vec::new();
+    let mut certificates = Vec::new();
+    // Signing block digests cover the whole file, so check them before JAR entries.
+    for (scheme, block_id) in [
+        (ApkSignatureScheme::V3, APK_SIGNATURE_SCHEME_V3_BLOCK_ID),
+        (ApkSignatureScheme::V2, APK_SIGNATURE_SCHEME_V2_BLOCK_ID),
+    ] {
+        if let Some(certificate) = verify_signing_block(data, block_id)? {
+            schemes.push(scheme);
+            certificates.push(certificate);
+        }
+    }
+    let jar_signature = verify_jar_signature(data)?;
+    if let Some(signature) = &jar_signature {
+        schemes.push(ApkSignatureScheme::V1);
+        certificates.push(signature.certificate.clone());
+    }
+    schemes.sort();
+    if let Some(signature) = &jar_signature {
+        for id in &signature.apk_signed_schemes {
+            let stripped = match id {
+                2 => !schemes.contains(&ApkSignatureScheme::V2),
+                3 => !schemes.contains(&ApkSignatureScheme::V3),
+                _ => false,
+            };
+            if stripped {
+                return Err(failed(format!(
+                    "APK was signed with v{id} scheme, but the signature was stripped"
+                ))
+                .into());
+            }
+        }
+    }
+    let Some(certificate) = certificates.first().cloned() else {
+        return Err(failed("APK is not signed".to_owned()).into());
+    };
+    if certificates.iter().any(|other| *other != certificate) {
+        return Err(failed("signature schemes use different certificates".to_owned()).into());
+    }
+    Ok(ApkSignatures {
+        schemes,
+        certificate,
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::{super::keystore::tests::*, *};
+    use std::io::Write;
+    use zip::{CompressionMethod, ZipWriter, write::SimpleFileOptions};
+
+    pub(crate) fn unsigned_apk(path: &Path) {
+        let mut zip = ZipWriter::new(std::fs::File::create(path).unwrap());
+        let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        zip.start_file("AndroidManifest.xml", SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(b"<manifest package=\"com.crossbow.test\" />")
+            .unwrap();
+        zip.start_file("resources.arsc", stored).unwrap();
+        zip.write_all(&[7; 4096]).unwrap();
+        zip.start_file("lib/arm64-v8a/libcrossbow.so", stored)
+            .unwrap();
+        zip.write_all(b"native-library").unwrap();
+        zip.add_directory("assets/", SimpleFileOptions::default())
+            .unwrap();
+        zip.finish().unwrap();
+    }
+
+    fn verification_failure(error: crate::error::Error) -> String {
+        match error {
+            crate::error::Error::Android(AndroidError::ApkVerificationFailed(reason)) => reason,
+            error => panic!("unexpected error: {error}"),
+        }
+    }
+
+    #[test]
+    fn signs_and_verifies_all_schemes() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        for (name, key) in [("p12.apk", pkcs12_key()), ("jks.apk", jks_key())] {
+            let apk_path = temp_dir.path().join(name);
+            unsigned_apk(&apk_path);
+            sign_apk_with_key(&apk_path, &key, &DEFAULT_APK_SIGNATURE_SCHEMES).unwrap();
+
+            let signatures = verify_apk(&apk_path).unwrap();
+            assert_eq!(signatures.schemes, DEFAULT_APK_SIGNATURE_SCHEMES);
+            assert_eq!(&signatures.certificate, key.certificate());
+
+            let mut archive =
+                zip::ZipArchive::new(std::fs::File::open(&apk_path).unwrap()).unwrap();
+            assert!(archive.by_name("META-INF/MANIFEST.MF").is_ok());
+            assert!(archive.by_name("lib/arm64-v8a/libcrossbow.so").is_ok());
+        }
+    }
+
+    #[test]
+    fn signing_preserves_entry_offsets() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let apk_path = temp_dir.path().join("app.apk");
+        unsigned_apk(&apk_path);
+        let offsets = |path: &Path| {
+            let mut archive = zip::ZipArchive::new(std::fs::File::open(path).unwrap()).unwrap();
+            ["resources.arsc", "lib/arm64-v8a/libcrossbow.so"]
+                .map(|name| archive.by_name(name).unwrap().data_start())
+        };
+        let before = offsets(&apk_path);
+        sign_apk_with_key(&apk_path, &pkcs12_key(), &DEFAULT_APK_SIGNATURE_SCHEMES).unwrap();
+        assert_eq!(offsets(&apk_path), before);
+    }
+
+    #[test]
+    fn resigning_replaces_previous_signatures() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let apk_path = temp_dir.path().join("app.apk");
+        unsigned_apk(&apk_path);
+        sign_apk_with_key(&apk_path, &pkcs12_key(), &DEFAULT_APK_SIGNATURE_SCHEMES).unwrap();
+        sign_apk_with_key(&apk_path, &jks_key(), &DEFAULT_APK_SIGNATURE_SCHEMES).unwrap();
+
+        let signatures = verify_apk(&apk_path).unwrap();
+        assert_eq!(&signatures.certificate, jks_key().certificate());
+        let archive = zip::ZipArchive::new(std::fs::File::open(&apk_path).unwrap()).unwrap();
+        assert_eq!(
+            archive
+                .file_names()
+                .filter(|name| name.starts_with("META-INF/"))
+                .count(),
+            3
+        );
+    }
+
+    #[test]
+    fn verification_detects_tampering() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let apk_path = temp_dir.path().join("app.apk");
+        unsigned_apk(&apk_path);
+        sign_apk_with_key(&apk_path, &pkcs12_key(), &DEFAULT_APK_SIGNATURE_SCHEMES).unwrap();
+        let mut data = std::fs::read(&apk_path).unwrap();
+        let offset = zip::ZipArchive::new(std::io::Cursor::new(&data))
+            .unwrap()
+            .by_name("resources.arsc")
+            .unwrap()
+            .data_start()
+            .unwrap() as usize;
+        data[offset] ^= 0xff;
+
+        let reason = verification_failure(verify_apk_bytes(&data).unwrap_err());
+        assert_eq!(reason, "v3: APK contents digest doesn't match");
+    }
+
+    #[test]
+    fn verification_detects_stripped_signing_block() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let apk_path = temp_dir.path().join("app.apk");
+        unsigned_apk(&apk_path);
+        let key = pkcs12_key();
+        let unsigned = std::fs::read(&apk_path).unwrap();
+        let data = jar_sign_bytes(&unsigned, &key, &[2, 3]).unwrap();
+        let reason = verification_failure(verify_apk_bytes(&data).unwrap_err());
+        assert!(reason.contains("v2 scheme, but the signature was stripped"));
+
+        let data = jar_sign_bytes(&unsigned, &key, &[]).unwrap();
+        let signatures = verify_apk_bytes(&data).unwrap();
+        assert_eq!(signatures.schemes, [ApkSignatureScheme::V1]);
+    }
+
+    /// Copies an APK, replacing the contents of one entry.
+    fn replace_entry(data: &[u8], name: &str, contents: &[u8]) -> Vec<u8> {
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(data)).unwrap();
+        let mut zip = ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        for index in 0..archive.len() {
+            let entry = archive.by_index_raw(index).unwrap();
+            if entry.name() == name {
+                drop(entry);
+                zip.start_file(name, SimpleFileOptions::default()).unwrap();
+                zip.write_all(contents).unwrap();
+            } else {
+                zip.raw_copy_file(entry).unwrap();
+            }
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    /// `jarsigner-v1.apk` was signed with the key of `debug.p12` by the JDK's `jarsigner
+    /// -digestalg SHA-256 -sigalg SHA256withRSA`, independently of this crate.
+    #[test]
+    fn verifies_apk_signed_by_jarsigner() {
+        let data = std::fs::read(fixture("jarsigner-v1.apk")).unwrap();
+        let signatures = verify_apk_bytes(&data).unwrap();
+        assert_eq!(signatures.schemes, [ApkSignatureScheme::V1]);
+        assert_eq!(&signatures.certificate, pkcs12_key().certificate());
+
+        let tampered = replace_entry(&data, "resources.arsc", b"tampered");
+        let reason = verification_failure(verify_apk_bytes(&tampered).unwrap_err());
+        assert_eq!(reason, "v1: digest of resources.arsc doesn't match");
+        let tampered = replace_entry(
+            &data,
+            "META-INF/ANDROIDD.SF",
+            b"Signature-Version: 1.0\r\nCreated-By: tampered\r\n\r\n",
+        );
+        let reason = verification_failure(verify_apk_bytes(&tampered).unwrap_err());
+        assert_eq!(reason, "v1: signature file digest doesn't match");
+    }
+
+    #[test]
+    fn verification_rejects_unsigned_apk() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let apk_path = temp_dir.path().join("app.apk");
+        unsigned_apk(&apk_path);
+        let reason = verification_failure(verify_apk(&apk_path).unwrap_err());
+        assert_eq!(reason, "APK is not signed");
+    }
+}
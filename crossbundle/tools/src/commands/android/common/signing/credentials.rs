@@ -0,0 +1,183 @@
+use super::{SigningKey, detect_keystore_type};
+use crate::{
+    error::{AndroidError, Result},
+    types::{Secret, SigningConfig},
+};
+use android_tools::java_tools::Key;
+use std::{
+    io::{BufRead, Write},
+    path::{Path, PathBuf},
+};
+
+/// Keystore location and passwords used to sign an app. Passwords are redacted from the
+/// `Debug` output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeystoreCredentials {
+    pub keystore: PathBuf,
+    pub key_alias: String,
+    pub store_password: Secret,
+    /// Password of the key. Defaults to the store password.
+    pub key_password: Option<Secret>,
+}
+
+impl KeystoreCredentials {
+    /// Reads the passwords of a signing configuration. When both passwords come from
+    /// stdin, the store password is read from the first line and the key password from
+    /// the second one.
+    pub fn from_config(config: &SigningConfig) -> Result<Self> {
+        Self::from_config_with(
+            config,
+            |name| std::env::var(name).ok(),
+            &mut std::io::stdin().lock(),
+        )
+    }
+
+    /// Reads the passwords of a signing configuration with an injected environment and
+    /// stdin.
+    pub fn from_config_with(
+        config: &SigningConfig,
+        mut environment: impl FnMut(&str) -> Option<String>,
+        stdin: &mut dyn BufRead,
+    ) -> Result<Self> {
+        if let Some(expected) = config.store_type {
+            let detected = detect_keystore_type(&config.keystore)?;
+            if detected != expected {
+                return Err(AndroidError::InvalidSigningConfig(format!(
+                    "{:?} is a {detected:?} keystore, but `store_type` is {expected:?}",
+                    config.keystore
+                ))
+                .into());
+            }
+        }
+        let store_password = config.store_password.read_with(&mut environment, stdin)?;
+        let key_password = config
+            .key_password
+            .as_ref()
+            .map(|source| source.read_with(&mut environment, stdin))
+            .transpose()?;
+        Ok(Self {
+            keystore: config.keystore.clone(),
+            key_alias: config.key_alias.clone(),
+            store_password,
+            key_password,
+        })
+    }
+
+    /// Password of the key.
+    pub fn key_password(&self) -> &Secret {
+        self.key_password.as_ref().unwrap_or(&self.store_password)
+    }
+
+    /// Loads the signing key from the keystore.
+    pub fn signing_key(&self) -> Result<SigningKey> {
+        SigningKey::load(
+            &self.keystore,
+            self.store_password.expose(),
+            &self.key_alias,
+            self.key_password.as_ref().map(Secret::expose),
+        )
+    }
+}
+
+impl From<Key> for KeystoreCredentials {
+    fn from(key: Key) -> Self {
+        Self {
+            keystore: key.key_path,
+            key_alias: key.key_alias,
+            store_password: Secret::new(key.key_pass),
+            key_password: None,
+        }
+    }
+}
+
+/// Secret written to a file that only the current user can read, for tools that accept
+/// `file:` password arguments like bundletool. The file is removed on drop.
+pub struct SecretFile(tempfile::NamedTempFile);
+
+impl SecretFile {
+    pub fn new(secret: &Secret) -> Result<Self> {
+        let mut file = tempfile::Builder::new()
+            .prefix("crossbundle-secret-")
+            .tempfile()?;
+        file.write_all(secret.expose().as_bytes())?;
+        file.flush()?;
+        Ok(Self(file))
+    }
+
+    pub fn path(&self) -> &Path {
+        self.0.path()
+    }
+
+    /// Returns the `file:<path>` password argument.
+    pub fn password_arg(&self) -> String {
+        format!("file:{}", self.path().display())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        commands::android::common::signing::keystore::tests::fixture,
+        error::Error,
+        types::{KeystoreType, SecretSource},
+    };
+
+    fn config(keystore: &str) -> SigningConfig {
+        SigningConfig {
+            keystore: fixture(keystore),
+            key_alias: "androiddebugkey".to_owned(),
+            store_password: SecretSource::Env("STORE_PASS".to_owned()),
+            key_password: Some(SecretSource::Stdin),
+            store_type: None,
+        }
+    }
+
+    #[test]
+    fn loads_keys_from_signing_config() {
+        let environment = |name: &str| (name == "STORE_PASS").then(|| "android".to_owned());
+        let credentials = KeystoreCredentials::from_config_with(
+            &config("debug.jks"),
+            environment,
+            &mut std::io::Cursor::new("keypass\n"),
+        )
+        .unwrap();
+        assert_eq!(credentials.key_password().expose(), "keypass");
+        let key = credentials.signing_key().unwrap();
+        assert_eq!(
+            key.certificate().tbs_certificate.subject.to_string(),
+            "CN=Crossbow Test,O=Crossbow,C=US"
+        );
+        let output = format!("{credentials:?}");
+        assert!(!output.contains("android\"") && !output.contains("keypass"));
+
+        let mut pkcs12 = config("debug.p12");
+        pkcs12.key_password = None;
+        pkcs12.store_type = Some(KeystoreType::Jks);
+        assert!(matches!(
+            KeystoreCredentials::from_config_with(&pkcs12, environment, &mut std::io::empty()),
+            Err(Error::Android(AndroidError::InvalidSigningConfig(_)))
+        ));
+        pkcs12.store_type = Some(KeystoreType::Pkcs12);
+        let credentials =
+            KeystoreCredentials::from_config_with(&pkcs12, environment, &mut std::io::empty())
+                .unwrap();
+        assert!(credentials.signing_key().is_ok());
+    }
+
+    #[test]
+    fn writes_private_secret_files() {
+        let file = SecretFile::new(&Secret::new("android")).unwrap();
+        assert_eq!(std::fs::read_to_string(file.path()).unwrap(), "android");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(file.path()).unwrap().permissions().mode();
+            assert_eq!(mode & 0o077, 0);
+        }
+        assert!(file.password_arg().starts_with("file:"));
+        let path = file.path().to_owned();
+        drop(file);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,511 @@
+use super::{
+    super::zip_layout::{LOCAL_FILE_HEADER_SIGNATURE, ZipLayout, updated_eocd},
+    SigningKey,
+};
+use crate::error::{AndroidError, Result};
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use cms::{
+    cert::{CertificateChoices, IssuerAndSerialNumber},
+    content_info::{CmsVersion, ContentInfo},
+    signed_data::{
+        CertificateSet, EncapsulatedContentInfo, SignedData, SignerIdentifier, SignerInfo,
+        SignerInfos,
+    },
+};
+use der::{
+    Any, Decode, Encode,
+    asn1::{ObjectIdentifier, OctetString, SetOfVec},
+};
+use rsa::{RsaPublicKey, pkcs1v15, pkcs8::DecodePublicKey, signature::Verifier};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::{collections::BTreeMap, io::Read, path::Path};
+use x509_cert::{Certificate, spki::AlgorithmIdentifierOwned};
+
+const MANIFEST_NAME: &str = "META-INF/MANIFEST.MF";
+const SIGNER_NAME: &str = "CERT";
+const CREATED_BY: &str = "1.0 (crossbundle)";
+const MAX_LINE_LENGTH: usize = 72;
+/// DOS date of 1981-01-01, the same constant `apksigner` stamps on signature entries.
+const DOS_DATE: u16 = 0x0221;
+
+const ID_DATA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.7.1");
+const ID_SIGNED_DATA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.7.2");
+const ID_MESSAGE_DIGEST: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.4");
+const ID_SHA1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.14.3.2.26");
+const ID_SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.1");
+const RSA_ENCRYPTION: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.1");
+const SHA1_WITH_RSA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.5");
+const SHA256_WITH_RSA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.11");
+
+/// Result of a successful JAR signature verification.
+#[derive(Debug, Clone)]
+pub(crate) struct JarSignature {
+    pub certificate: Certificate,
+    /// APK Signature Scheme ids listed in the `X-Android-APK-Signed` attribute.
+    pub apk_signed_schemes: Vec<u32>,
+}
+
+/// Signs a ZIP archive with the JAR signing scheme (APK Signature Scheme v1).
+///
+/// Existing entries are kept byte-for-byte at their offsets, so alignment of the archive
+/// is preserved. `apk_signed_schemes` are written to `X-Android-APK-Signed` to protect
+/// newer signature schemes from being stripped.
+pub fn jar_sign(archive_path: &Path, key: &SigningKey, apk_signed_schemes: &[u32]) -> Result<()> {
+    let data = std::fs::read(archive_path)?;
+    let signed = jar_sign_bytes(&data, key, apk_signed_schemes)?;
+    std::fs::write(archive_path, signed)?;
+    Ok(())
+}
+
+pub(crate) fn jar_sign_bytes(
+    data: &[u8],
+    key: &SigningKey,
+    apk_signed_schemes: &[u32],
+) -> Result<Vec<u8>> {
+    let layout = ZipLayout::parse(data)?;
+    let records = layout
+        .records(data)?
+        .into_iter()
+        .filter(|record| !is_signature_entry(&record.name))
+        .collect::<Vec<_>>();
+
+    let mut manifest = Vec::new();
+    write_attribute(&mut manifest, "Manifest-Version", "1.0");
+    write_attribute(&mut manifest, "Created-By", CREATED_BY);
+    manifest.extend_from_slice(b"\r\n");
+    let mut sections = Vec::new();
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(data))?;
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index)?;
+        if file.is_dir() || is_signature_entry(file.name()) {
+            continue;
+        }
+        let mut section = Vec::new();
+        write_attribute(&mut section, "Name", file.name());
+        write_attribute(
+            &mut section,
+            "SHA-256-Digest",
+            &BASE64.encode(digest_reader::<Sha256>(&mut file)?),
+        );
+        section.extend_from_slice(b"\r\n");
+        manifest.extend_from_slice(&section);
+        sections.push((file.name().to_owned(), section));
+    }
+
+    let mut signature_file = Vec::new();
+    write_attribute(&mut signature_file, "Signature-Version", "1.0");
+    write_attribute(&mut signature_file, "Created-By", CREATED_BY);
+    write_attribute(
+        &mut signature_file,
+        "SHA-256-Digest-Manifest",
+        &BASE64.encode(Sha256::digest(&manifest)),
+    );
+    if !apk_signed_schemes.is_empty() {
+        let schemes = apk_signed_schemes
+            .iter()
+            .map(u32::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        write_attribute(&mut signature_file, "X-Android-APK-Signed", &schemes);
+    }
+    signature_file.extend_from_slice(b"\r\n");
+    for (name, section) in &sections {
+        write_attribute(&mut signature_file, "Name", name);
+        write_attribute(
+            &mut signature_file,
+            "SHA-256-Digest",
+            &BASE64.encode(Sha256::digest(section)),
+        );
+        signature_file.extend_from_slice(b"\r\n");
+    }
+    let signature_block = signature_block(key, &signature_file)?;
+
+    let mut output = data[layout.entries.clone()].to_vec();
+    let mut central_directory = Vec::new();
+    for record in &records {
+        central_directory.extend_from_slice(&data[record.range.clone()]);
+    }
+    for (name, contents) in [
+        (MANIFEST_NAME.to_owned(), manifest),
+        (format!("META-INF/{SIGNER_NAME}.SF"), signature_file),
+        (format!("META-INF/{SIGNER_NAME}.RSA"), signature_block),
+    ] {
+        let offset = u32::try_from(output.len())
+            .map_err(|_| AndroidError::ApkSigningFailed("archive is too large".to_owned()))?;
+        write_stored_entry(
+            &mut output,
+            &mut central_directory,
+            &name,
+            &contents,
+            offset,
+        );
+    }
+    let cd_offset = output.len();
+    let eocd = updated_eocd(
+        &data[layout.eocd.clone()],
+        records.len() + 3,
+        central_directory.len(),
+        cd_offset,
+    )?;
+    output.extend_from_slice(&central_directory);
+    output.extend_from_slice(&eocd);
+    Ok(output)
+}
+
+/// Verifies the JAR signature of an archive. Returns `None` if the archive is not
+/// JAR-signed.
+pub(crate) fn verify_jar_signature(data: &[u8]) -> Result<Option<JarSignature>> {
+    let failed = |reason: String| AndroidError::ApkVerificationFailed(format!("v1: {reason}"));
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(data))?;
+    let Some(signature_file_name) = archive
+        .file_names()
+        .filter(|name| is_signature_entry(name) && name.ends_with(".SF"))
+        .min()
+        .map(str::to_owned)
+    else {
+        return Ok(None);
+    };
+    let stem = signature_file_name.trim_end_matches(".SF");
+    let Some(block_name) = ["RSA", "EC", "DSA"]
+        .iter()
+        .map(|extension| format!("{stem}.{extension}"))
+        .find(|name| archive.index_for_name(name).is_some())
+    else {
+        return Err(failed(format!("no signature block for {signature_file_name}")).into());
+    };
+
+    let manifest = read_entry(&mut archive, MANIFEST_NAME)?;
+    let signature_file = read_entry(&mut archive, &signature_file_name)?;
+    let block = read_entry(&mut archive, &block_name)?;
+    let certificate = verify_signature_block(&block, &signature_file).map_err(failed)?;
+
+    let signature_sections = parse_manifest(&signature_file);
+    let main = &signature_sections[0];
+    let manifest_digest = if let Some(digest) = main.get("SHA-256-Digest-Manifest") {
+        digest == &BASE64.encode(Sha256::digest(&manifest))
+    } else if let Some(digest) = main.get("SHA1-Digest-Manifest") {
+        digest == &BASE64.encode(Sha1::digest(&manifest))
+    } else {
+        false
+    };
+    if !manifest_digest {
+        return Err(failed(format!("{signature_file_name} doesn't match the manifest")).into());
+    }
+    let apk_signed_schemes = main
+        .get("X-Android-APK-Signed")
+        .map(|schemes| {
+            schemes
+                .split(',')
+                .filter_map(|scheme| scheme.trim().parse().ok())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut digests = BTreeMap::new();
+    for section in parse_manifest(&manifest).into_iter().skip(1) {
+        if let Some(name) = section.get("Name") {
+            digests.insert(name.clone(), section);
+        }
+    }
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index)?;
+        if file.is_dir() || is_signature_entry(file.name()) {
+            continue;
+        }
+        let name = file.name().to_owned();
+        let section = digests
+            .get(&name)
+            .ok_or_else(|| failed(format!("entry {name} is not signed")))?;
+        let unreadable = |error| failed(format!("failed to read {name}: {error}"));
+        let matches = if let Some(expected) = section.get("SHA-256-Digest") {
+            expected == &BASE64.encode(digest_reader::<Sha256>(&mut file).map_err(unreadable)?)
+        } else if let Some(expected) = section.get("SHA1-Digest") {
+            expected == &BASE64.encode(digest_reader::<Sha1>(&mut file).map_err(unreadable)?)
+        } else {
+            false
+        };
+        if !matches {
+            return Err(failed(format!("digest of {name} doesn't match")).into());
+        }
+    }
+
+    Ok(Some(JarSignature {
+        certificate,
+        apk_signed_schemes,
+    }))
+}
+
+/// Builds a detached PKCS#7 `SignedData` over the signature file.
+fn signature_block(key: &SigningKey, signature_file: &[u8]) -> Result<Vec<u8>> {
+    let encode = |error: der::Error| AndroidError::ApkSigningFailed(error.to_string());
+    let certificate = key.certificate();
+    let sha256 = AlgorithmIdentifierOwned {
+        oid: ID_SHA256,
+        parameters: None,
+    };
+    let signer_info = SignerInfo {
+        version: CmsVersion::V1,
+        sid: SignerIdentifier::IssuerAndSerialNumber(IssuerAndSerialNumber {
+            issuer: certificate.tbs_certificate.issuer.clone(),
+            serial_number: certificate.tbs_certificate.serial_number.clone(),
+        }),
+        digest_alg: sha256.clone(),
+        signed_attrs: None,
+        signature_algorithm: AlgorithmIdentifierOwned {
+            oid: RSA_ENCRYPTION,
+            parameters: Some(Any::null()),
+        },
+        signature: OctetString::new(key.sign_sha256(signature_file)?).map_err(encode)?,
+        unsigned_attrs: None,
+    };
+    let certificates = key
+        .certificates()
+        .iter()
+        .cloned()
+        .map(CertificateChoices::Certificate)
+        .collect::<Vec<_>>();
+    let signed_data = SignedData {
+        version: CmsVersion::V1,
+        digest_algorithms: SetOfVec::try_from(vec![sha256]).map_err(encode)?,
+        encap_content_info: EncapsulatedContentInfo {
+            econtent_type: ID_DATA,
+            econtent: None,
+        },
+        certificates: Some(CertificateSet(
+            SetOfVec::try_from(certificates).map_err(encode)?,
+        )),
+        crls: None,
+        signer_infos: SignerInfos(SetOfVec::try_from(vec![signer_info]).map_err(encode)?),
+    };
+    let content_info = ContentInfo {
+        content_type: ID_SIGNED_DATA,
+        content: Any::encode_from(&signed_data).map_err(encode)?,
+    };
+    Ok(content_info.to_der().map_err(encode)?)
+}
+
+/// Checks PKCS#7 signature over the signature file and returns the signer certificate.
+fn verify_signature_block(
+    block: &[u8],
+    signature_file: &[u8],
+) -> std::result::Result<Certificate, String> {
+    let signed_data = ContentInfo::from_der(block)
+        .and_then(|info| info.content.decode_as::<SignedData>())
+        .map_err(|error| format!("invalid signature block: {error}"))?;
+    let signer = signed_data
+        .signer_infos
+        .0
+        .iter()
+        .next()
+        .ok_or("signature block has no signers")?;
+    let SignerIdentifier::IssuerAndSerialNumber(sid) = &signer.sid else {
+        return Err("unsupported signer identifier".to_owned());
+    };
+    let certificate = signed_data
+        .certificates
+        .iter()
+        .flat_map(|set| set.0.iter())
+        .find_map(|choice| match choice {
+            CertificateChoices::Certificate(cert)
+                if cert.tbs_certificate.issuer == sid.issuer
+                    && cert.tbs_certificate.serial_number == sid.serial_number =>
+            {
+                Some(cert.clone())
+            }
+            _ => None,
+        })
+        .ok_or("signer certificate is missing")?;
+
+    let digest_oid = signer.digest_alg.oid;
+    let digest = |data: &[u8]| match digest_oid {
+        ID_SHA256 => Ok(Sha256::digest(data).to_vec()),
+        ID_SHA1 => Ok(Sha1::digest(data).to_vec()),
+        oid => Err(format!("unsupported digest algorithm {oid}")),
+    };
+    let signed_content = match &signer.signed_attrs {
+        Some(attributes) => {
+            let message_digest = attributes
+                .iter()
+                .find(|attribute| attribute.oid == ID_MESSAGE_DIGEST)
+                .and_then(|attribute| attribute.values.iter().next())
+                .and_then(|value| value.decode_as::<OctetString>().ok())
+                .ok_or("signed attributes have no message digest")?;
+            if message_digest.as_bytes() != digest(signature_file)? {
+                return Err("signature file digest doesn't match".to_owned());
+            }
+            attributes.to_der().map_err(|error| error.to_string())?
+        }
+        None => signature_file.to_vec(),
+    };
+    if ![RSA_ENCRYPTION, SHA1_WITH_RSA, SHA256_WITH_RSA].contains(&signer.signature_algorithm.oid) {
+        return Err(format!(
+            "unsupported signature algorithm {}",
+            signer.signature_algorithm.oid
+        ));
+    }
+    let public_key = public_key(&certificate)?;
+    let signature = pkcs1v15::Signature::try_from(signer.signature.as_bytes())
+        .map_err(|error| error.to_string())?;
+    let verified = match digest_oid {
+        ID_SHA256 => {
+            pkcs1v15::VerifyingKey::<Sha256>::new(public_key).verify(&signed_content, &signature)
+        }
+        _ => pkcs1v15::VerifyingKey::<Sha1>::new(public_key).verify(&signed_content, &signature),
+    };
+    verified.map_err(|_| "signature doesn't match the signer certificate".to_owned())?;
+    Ok(certificate)
+}
+
+pub(crate) fn public_key(certificate: &Certificate) -> std::result::Result<RsaPublicKey, String> {
+    let spki = certificate
+        .tbs_certificate
+        .subject_public_key_info
+        .to_der()
+        .map_err(|error| error.to_string())?;
+    RsaPublicKey::from_public_key_der(&spki).map_err(|_| "only RSA keys are supported".to_owned())
+}
+
+/// Entries that belong to the JAR signature itself and are never digested.
+fn is_signature_entry(name: &str) -> bool {
+    if name.eq_ignore_ascii_case(MANIFEST_NAME) {
+        return true;
+    }
+    let Some(file_name) = name.strip_prefix("META-INF/") else {
+        return false;
+    };
+    !file_name.contains('/')
+        && [".SF", ".RSA", ".DSA", ".EC"]
+            .iter()
+            .any(|extension| file_name.to_ascii_uppercase().ends_with(extension))
+}
+
+fn read_entry<R: std::io::Read + std::io::Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    name: &str,
+) -> Result<Vec<u8>> {
+    let mut contents = Vec::new();
+    archive.by_name(name)?.read_to_end(&mut contents)?;
+    Ok(contents)
+}
+
+fn digest_reader<D: Digest>(reader: &mut impl Read) -> std::io::Result<Vec<u8>> {
+    let mut hasher = D::new();
+    let mut buffer = [0; 64 * 1024];
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            return Ok(hasher.finalize().to_vec());
+        }
+        hasher.update(&buffer[..read]);
+    }
+}
+
+/// Writes a manifest attribute, wrapping lines longer than 72 bytes.
+fn write_attribute(output: &mut Vec<u8>, name: &str, value: &str) {
+    let line = format!("{name}: {value}");
+    let mut bytes = line.as_bytes();
+    let mut limit = MAX_LINE_LENGTH;
+    while bytes.len() > limit {
+        output.extend_from_slice(&bytes[..limit]);
+        output.extend_from_slice(b"\r\n ");
+        bytes = &bytes[limit..];
+        limit = MAX_LINE_LENGTH - 1;
+    }
+    output.extend_from_slice(bytes);
+    output.extend_from_slice(b"\r\n");
+}
+
+/// Parses manifest sections into attribute maps. The first section holds main attributes.
+fn parse_manifest(contents: &[u8]) -> Vec<BTreeMap<String, String>> {
+    let text = String::from_utf8_lossy(contents);
+    let mut sections: Vec<BTreeMap<String, String>> = vec![BTreeMap::new()];
+    let mut last_name: Option<String> = None;
+    for line in text.split('\n').map(|line| line.trim_end_matches('\r')) {
+        if line.is_empty() {
+            if !sections.last().unwrap().is_empty() {
+                sections.push(BTreeMap::new());
+            }
+            last_name = None;
+        } else if let (Some(continuation), Some(name)) = (line.strip_prefix(' '), &last_name) {
+            if let Some(value) = sections.last_mut().unwrap().get_mut(name) {
+                value.push_str(continuation);
+            }
+        } else if let Some((name, value)) = line.split_once(": ") {
+            sections
+                .last_mut()
+                .unwrap()
+                .insert(name.to_owned(), value.to_owned());
+            last_name = Some(name.to_owned());
+        }
+    }
+    sections
+}
+
+/// Appends an uncompressed entry with 4-byte aligned data and its central directory record.
+fn write_stored_entry(
+    output: &mut Vec<u8>,
+    central_directory: &mut Vec<u8>,
+    name: &str,
+    contents: &[u8],
+    offset: u32,
+) {
+    let crc = crc32fast::hash(contents);
+    let header_length = 30 + name.len();
+    let padding = (4 - (output.len() + header_length) % 4) % 4;
+    let common = |buffer: &mut Vec<u8>| {
+        buffer.extend_from_slice(&10u16.to_le_bytes());
+        buffer.extend_from_slice(&0u16.to_le_bytes());
+        buffer.extend_from_slice(&0u16.to_le_bytes());
+        buffer.extend_from_slice(&0u16.to_le_bytes());
+        buffer.extend_from_slice(&DOS_DATE.to_le_bytes());
+        buffer.extend_from_slice(&crc.to_le_bytes());
+        buffer.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+        buffer.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+        buffer.extend_from_slice(&(name.len() as u16).to_le_bytes());
+    };
+
+    output.extend_from_slice(&LOCAL_FILE_HEADER_SIGNATURE.to_le_bytes());
+    common(output);
+    output.extend_from_slice(&(padding as u16).to_le_bytes());
+    output.extend_from_slice(name.as_bytes());
+    output.extend(std::iter::repeat_n(0, padding));
+    output.extend_from_slice(contents);
+
+    central_directory.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+    central_directory.extend_from_slice(&20u16.to_le_bytes());
+    common(central_directory);
+    central_directory.extend_from_slice(&[0; 12]);
+    central_directory.extend_from_slice(&offset.to_le_bytes());
+    central_directory.extend_from_slice(name.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifest_lines_are_wrapped_and_parsed_back() {
+        let name = format!("assets/{}.txt", "a".repeat(120));
+        let mut manifest = Vec::new();
+        write_attribute(&mut manifest, "Name", &name);
+        manifest.extend_from_slice(b"\r\n");
+        assert!(
+            String::from_utf8_lossy(&manifest)
+                .lines()
+                .all(|line| line.len() <= MAX_LINE_LENGTH)
+        );
+        let sections = parse_manifest(&manifest);
+        assert_eq!(sections[0].get("Name"), Some(&name));
+    }
+
+    #[test]
+    fn detects_signature_entries() {
+        assert!(is_signature_entry("META-INF/MANIFEST.MF"));
+        assert!(is_signature_entry("META-INF/CERT.SF"));
+        assert!(is_signature_entry("META-INF/release.rsa"));
+        assert!(!is_signature_entry("META-INF/services/CERT.SF"));
+        assert!(!is_signature_entry("META-INF/kotlin.kotlin_module"));
+        assert!(!is_signature_entry("classes.dex"));
+    }
+}
@@ -0,0 +1,372 @@
+use super::load_certificates;
+use crate::{
+    commands::CertificateInfo,
+    error::{AndroidError, Result},
+};
+use base64::Engine;
+use der::{Encode, asn1::ObjectIdentifier};
+use displaydoc::Display;
+use rsa::{RsaPublicKey, pkcs1::DecodeRsaPublicKey, traits::PublicKeyParts};
+use serde::Serialize;
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+use x509_cert::Certificate;
+
+/// Certificates expiring within this many days are reported by [`KeyReport`].
+pub const EXPIRY_WARNING_DAYS: u64 = 365;
+
+/// Google Play requires keys of new apps to be valid until at least 22 October 2033.
+const PLAY_MINIMUM_NOT_AFTER: Duration = Duration::from_secs(2_013_552_000);
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+const RSA_ENCRYPTION: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.1");
+const EC_PUBLIC_KEY: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.2.1");
+const DSA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10040.4.1");
+
+/// Problem of a signing key that should be fixed before the key is used for releases.
+#[derive(Debug, Display, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum KeyWarning {
+    /// certificate expired on {not_after}
+    Expired { not_after: String },
+    /// certificate expires in {days} days, on {not_after}
+    ExpiresSoon { days: u64, not_after: String },
+    /// certificate expires on {not_after}, Google Play requires keys valid until at least 2033-10-22
+    ShortValidity { not_after: String },
+    /// {algorithm} key of {key_size} bits is weak, use at least {minimum} bits
+    WeakKey {
+        algorithm: String,
+        key_size: u32,
+        minimum: u32,
+    },
+    /// {algorithm} keys are deprecated, use RSA keys
+    DeprecatedKeyAlgorithm { algorithm: String },
+    /// certificate is signed with {algorithm}, which is considered insecure
+    WeakSignatureAlgorithm { algorithm: String },
+}
+
+/// Description of a key in a keystore, printed by `crossbundle keystore inspect`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct KeyReport {
+    pub keystore: PathBuf,
+    pub alias: String,
+    /// Key algorithm, e.g. `RSA` or `EC`.
+    pub key_algorithm: String,
+    /// Key size in bits, if known for the algorithm.
+    pub key_size: Option<u32>,
+    /// Signature algorithm of the signer certificate, e.g. `SHA256withRSA`.
+    pub signature_algorithm: String,
+    pub certificate: CertificateInfo,
+    /// Number of certificates in the chain, including the signer certificate.
+    pub chain_length: usize,
+    pub warnings: Vec<KeyWarning>,
+}
+
+impl KeyReport {
+    /// Describes the signer certificate of a certificate chain. Expiry warnings are
+    /// relative to `now`.
+    pub fn new(
+        keystore: &Path,
+        alias: &str,
+        certificates: &[Certificate],
+        now: SystemTime,
+    ) -> Result<Self> {
+        let certificate = certificates.first().ok_or_else(|| {
+            AndroidError::InvalidKeystore(keystore.into(), "empty certificate chain".to_owned())
+        })?;
+        let (key_algorithm, key_size) = public_key(certificate)?;
+        let signature_algorithm = signature_algorithm_name(&certificate.signature_algorithm.oid);
+        let info = CertificateInfo::new(certificate)?;
+
+        let mut warnings = Vec::new();
+        let not_after = certificate
+            .tbs_certificate
+            .validity
+            .not_after
+            .to_unix_duration();
+        let now = now
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        let days_left = not_after.saturating_sub(now).as_secs() / SECONDS_PER_DAY;
+        if not_after <= now {
+            warnings.push(KeyWarning::Expired {
+                not_after: info.not_after.clone(),
+            });
+        } else if days_left < EXPIRY_WARNING_DAYS {
+            warnings.push(KeyWarning::ExpiresSoon {
+                days: days_left,
+                not_after: info.not_after.clone(),
+            });
+        } else if not_after < PLAY_MINIMUM_NOT_AFTER {
+            warnings.push(KeyWarning::ShortValidity {
+                not_after: info.not_after.clone(),
+            });
+        }
+        let minimum = match key_algorithm.as_str() {
+            "RSA" => Some(2048),
+            "EC" => Some(256),
+            _ => None,
+        };
+        match (minimum, key_size) {
+            (Some(minimum), Some(key_size)) if key_size < minimum => {
+                warnings.push(KeyWarning::WeakKey {
+                    algorithm: key_algorithm.clone(),
+                    key_size,
+                    minimum,
+                });
+            }
+            (None, _) if key_algorithm == "DSA" => {
+                warnings.push(KeyWarning::DeprecatedKeyAlgorithm {
+                    algorithm: key_algorithm.clone(),
+                });
+            }
+            _ => {}
+        }
+        if signature_algorithm.starts_with("MD5") || signature_algorithm.starts_with("SHA1") {
+            warnings.push(KeyWarning::WeakSignatureAlgorithm {
+                algorithm: signature_algorithm.clone(),
+            });
+        }
+
+        Ok(Self {
+            keystore: keystore.to_owned(),
+            alias: alias.to_owned(),
+            key_algorithm,
+            key_size,
+            signature_algorithm,
+            certificate: info,
+            chain_length: certificates.len(),
+            warnings,
+        })
+    }
+}
+
+/// Loads the key with given alias from a JKS or PKCS#12 keystore and describes it.
+pub fn inspect_keystore(
+    keystore_path: &Path,
+    store_pass: &str,
+    alias: &str,
+    key_pass: Option<&str>,
+) -> Result<KeyReport> {
+    let certificates = load_certificates(keystore_path, store_pass, alias, key_pass)?;
+    KeyReport::new(keystore_path, alias, &certificates, SystemTime::now())
+}
+
+/// Encoding of an exported certificate.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CertificateFormat {
+    /// Base64 text between `BEGIN CERTIFICATE` lines, as uploaded to Play Console.
+    #[default]
+    Pem,
+    /// Binary DER encoding.
+    Der,
+}
+
+impl std::str::FromStr for CertificateFormat {
+    type Err = AndroidError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "pem" => Ok(Self::Pem),
+            "der" => Ok(Self::Der),
+            _ => Err(AndroidError::InvalidKeystoreOptions(format!(
+                "unsupported certificate format `{s}`, use `pem` or `der`"
+            ))),
+        }
+    }
+}
+
+/// Encodes a certificate in the given format.
+pub fn export_certificate(certificate: &Certificate, format: CertificateFormat) -> Result<Vec<u8>> {
+    let der = certificate
+        .to_der()
+        .map_err(|error| anyhow::anyhow!("failed to encode certificate: {error}"))?;
+    Ok(match format {
+        CertificateFormat::Der => der,
+        CertificateFormat::Pem => {
+            let encoded = base64::engine::general_purpose::STANDARD.encode(der);
+            let mut pem = String::from("-----BEGIN CERTIFICATE-----\n");
+            for line in encoded.as_bytes().chunks(64) {
+                pem.push_str(std::str::from_utf8(line).unwrap_or_default());
+                pem.push('\n');
+            }
+            pem.push_str("-----END CERTIFICATE-----\n");
+            pem.into_bytes()
+        }
+    })
+}
+
+fn public_key(certificate: &Certificate) -> Result<(String, Option<u32>)> {
+    let spki = &certificate.tbs_certificate.subject_public_key_info;
+    let oid = spki.algorithm.oid;
+    Ok(if oid == RSA_ENCRYPTION {
+        let key = RsaPublicKey::from_pkcs1_der(spki.subject_public_key.raw_bytes())
+            .map_err(|error| anyhow::anyhow!("invalid RSA public key: {error}"))?;
+        ("RSA".to_owned(), Some(key.n().bits() as u32))
+    } else if oid == EC_PUBLIC_KEY {
+        let curve = spki
+            .algorithm
+            .parameters
+            .as_ref()
+            .and_then(|parameters| parameters.decode_as::<ObjectIdentifier>().ok())
+            .map(|curve| curve.to_string());
+        let key_size = match curve.as_deref() {
+            Some("1.2.840.10045.3.1.7") => Some(256),
+            Some("1.3.132.0.34") => Some(384),
+            Some("1.3.132.0.35") => Some(521),
+            _ => None,
+        };
+        ("EC".to_owned(), key_size)
+    } else if oid == DSA {
+        ("DSA".to_owned(), None)
+    } else {
+        (oid.to_string(), None)
+    })
+}
+
+fn signature_algorithm_name(oid: &ObjectIdentifier) -> String {
+    let name = match oid.to_string().as_str() {
+        "1.2.840.113549.1.1.4" => "MD5withRSA",
+        "1.2.840.113549.1.1.5" => "SHA1withRSA",
+        "1.2.840.113549.1.1.11" => "SHA256withRSA",
+        "1.2.840.113549.1.1.12" => "SHA384withRSA",
+        "1.2.840.113549.1.1.13" => "SHA512withRSA",
+        "1.2.840.10045.4.1" => "SHA1withECDSA",
+        "1.2.840.10045.4.3.2" => "SHA256withECDSA",
+        "1.2.840.10045.4.3.3" => "SHA384withECDSA",
+        "1.2.840.10045.4.3.4" => "SHA512withECDSA",
+        "1.2.840.10040.4.3" => "SHA1withDSA",
+        "2.16.840.1.101.3.4.3.2" => "SHA256withDSA",
+        other => return other.to_owned(),
+    };
+    name.to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::android::common::signing::keystore::tests::fixture;
+
+    fn certificates(name: &str, alias: &str) -> Vec<Certificate> {
+        load_certificates(&fixture(name), "android", alias, None).unwrap()
+    }
+
+    fn not_after(certificate: &Certificate) -> SystemTime {
+        SystemTime::UNIX_EPOCH
+            + certificate
+                .tbs_certificate
+                .validity
+                .not_after
+                .to_unix_duration()
+    }
+
+    #[test]
+    fn describes_strong_keys() {
+        let chain = certificates("debug.p12", "androiddebugkey");
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_800_000_000);
+        let report = KeyReport::new(&fixture("debug.p12"), "androiddebugkey", &chain, now).unwrap();
+        assert_eq!(report.key_algorithm, "RSA");
+        assert_eq!(report.key_size, Some(2048));
+        assert_eq!(report.signature_algorithm, "SHA256withRSA");
+        assert_eq!(
+            report.certificate.subject,
+            "CN=Crossbow Test,O=Crossbow,C=US"
+        );
+        assert_eq!(report.chain_length, 1);
+        assert!(report.warnings.is_empty());
+
+        let jks = load_certificates(
+            &fixture("debug.jks"),
+            "android",
+            "androiddebugkey",
+            Some("keypass"),
+        )
+        .unwrap();
+        assert_eq!(
+            jks[0].tbs_certificate.subject.to_string(),
+            report.certificate.subject
+        );
+
+        let chain = certificates("ec.p12", "eckey");
+        let report = KeyReport::new(&fixture("ec.p12"), "eckey", &chain, now).unwrap();
+        assert_eq!(report.key_algorithm, "EC");
+        assert_eq!(report.key_size, Some(256));
+        assert_eq!(report.signature_algorithm, "SHA256withECDSA");
+        assert!(report.warnings.is_empty());
+    }
+
+    #[test]
+    fn warns_about_weak_and_expiring_keys() {
+        let chain = certificates("legacy.p12", "legacykey");
+        let expires = not_after(&chain[0]);
+        let report = KeyReport::new(
+            &fixture("legacy.p12"),
+            "legacykey",
+            &chain,
+            expires - Duration::from_secs(30 * SECONDS_PER_DAY + 60),
+        )
+        .unwrap();
+        assert_eq!(report.key_size, Some(1024));
+        assert!(matches!(
+            report.warnings.as_slice(),
+            [
+                KeyWarning::ExpiresSoon { days: 30, .. },
+                KeyWarning::WeakKey {
+                    key_size: 1024,
+                    minimum: 2048,
+                    ..
+                },
+                KeyWarning::WeakSignatureAlgorithm { .. },
+            ]
+        ));
+        assert_eq!(
+            report.warnings[2].to_string(),
+            "certificate is signed with SHA1withRSA, which is considered insecure"
+        );
+
+        let report = KeyReport::new(&fixture("legacy.p12"), "legacykey", &chain, expires).unwrap();
+        assert!(matches!(report.warnings[0], KeyWarning::Expired { .. }));
+        let json = serde_json::to_value(&report.warnings[0]).unwrap();
+        assert_eq!(json["kind"], "expired");
+
+        let report = KeyReport::new(
+            &fixture("legacy.p12"),
+            "legacykey",
+            &chain,
+            expires - Duration::from_secs(2 * EXPIRY_WARNING_DAYS * SECONDS_PER_DAY),
+        )
+        .unwrap();
+        assert!(matches!(
+            report.warnings[0],
+            KeyWarning::ShortValidity { .. }
+        ));
+    }
+
+    #[test]
+    fn exports_pem_and_der_certificates() {
+        let chain = certificates("debug.p12", "androiddebugkey");
+        let der = export_certificate(&chain[0], CertificateFormat::Der).unwrap();
+        assert_eq!(der, chain[0].to_der().unwrap());
+        let pem = String::from_utf8(export_certificate(&chain[0], CertificateFormat::Pem).unwrap())
+            .unwrap();
+        assert!(pem.starts_with("-----BEGIN CERTIFICATE-----\n"));
+        assert!(pem.ends_with("\n-----END CERTIFICATE-----\n"));
+        assert!(pem.lines().all(|line| line.len() <= 64));
+        let body: String = pem
+            .lines()
+            .filter(|line| !line.starts_with("-----"))
+            .collect();
+        assert_eq!(
+            base64::engine::general_purpose::STANDARD
+                .decode(body)
+                .unwrap(),
+            der
+        );
+        assert_eq!(
+            "DER".parse::<CertificateFormat>().unwrap(),
+            CertificateFormat::Der
+        );
+    }
+}
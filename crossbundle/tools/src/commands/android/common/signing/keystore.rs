@@ -0,0 +1,475 @@
+use crate::{
+    commands::external_signature,
+    error::{AndroidError, Error, Result},
+    types::{ExternalSigner, KeystoreType, SignedArtifactKind, SignerMode},
+};
+use android_tools::java_tools::Key;
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use der::{
+    Decode, Encode, Sequence,
+    asn1::{ObjectIdentifier, OctetString},
+};
+use rsa::{
+    RsaPrivateKey, RsaPublicKey, pkcs1::DecodeRsaPublicKey, pkcs1v15, pkcs8::DecodePrivateKey,
+    signature::SignatureEncoding,
+};
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+use std::path::Path;
+use x509_cert::{Certificate, spki::AlgorithmIdentifierOwned};
+
+const JKS_MAGIC: u32 = 0xfeed_feed;
+const JCEKS_MAGIC: u32 = 0xcece_cece;
+const JKS_PRIVATE_KEY_ENTRY: u32 = 1;
+const JKS_TRUSTED_CERT_ENTRY: u32 = 2;
+const JKS_INTEGRITY_SALT: &[u8] = b"Mighty Aphrodite";
+/// Sun's proprietary key protection algorithm used by JKS private key entries.
+const JKS_KEY_PROTECTOR_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.42.2.17.1.1");
+
+/// Private key and certificate chain loaded from a JKS or PKCS#12 keystore.
+#[derive(Clone)]
+pub struct SigningKey {
+    private_key: PrivateKey,
+    certificates: Vec<Certificate>,
+}
+
+#[derive(Clone)]
+enum PrivateKey {
+    Rsa(RsaPrivateKey),
+    /// Key held by an [`ExternalSigner`] in `digest` mode. Signatures are checked against
+    /// the public key of the certificate.
+    External {
+        signer: ExternalSigner,
+        kind: SignedArtifactKind,
+        public_key: RsaPublicKey,
+    },
+}
+
+impl SigningKey {
+    /// Loads the key with given alias from a JKS or PKCS#12 keystore.
+    /// Store type is detected from the file contents. If `key_pass` is `None`,
+    /// the store password is used to decrypt the key as well.
+    pub fn load(
+        keystore_path: &Path,
+        store_pass: &str,
+        alias: &str,
+        key_pass: Option<&str>,
+    ) -> Result<Self> {
+        let (private_key, certificates) =
+            load_key_entry(keystore_path, store_pass, alias, key_pass)?;
+        let private_key = RsaPrivateKey::from_pkcs8_der(&private_key).map_err(|_| {
+            AndroidError::InvalidKeystore(
+                keystore_path.into(),
+                "only RSA signing keys are supported".to_owned(),
+            )
+        })?;
+        Ok(Self {
+            private_key: PrivateKey::Rsa(private_key),
+            certificates,
+        })
+    }
+
+    /// Signing key whose signatures are made by an [`ExternalSigner`] in `digest` mode,
+    /// with the certificate chain from [`ExternalSigner::certificate`].
+    pub fn external(signer: &ExternalSigner, kind: SignedArtifactKind) -> Result<Self> {
+        let invalid = |reason: &str| Error::InvalidSignerConfig(reason.to_owned());
+        if signer.mode != SignerMode::Digest {
+            return Err(invalid("signing keys require the `digest` mode"));
+        }
+        let path = signer
+            .certificate
+            .as_deref()
+            .ok_or_else(|| invalid("the `digest` mode requires a `certificate`"))?;
+        let certificates = read_certificates(path)?;
+        let spki = &certificates[0].tbs_certificate.subject_public_key_info;
+        let public_key = RsaPublicKey::from_pkcs1_der(spki.subject_public_key.raw_bytes())
+            .map_err(|_| invalid("only RSA signing keys are supported"))?;
+        Ok(Self {
+            private_key: PrivateKey::External {
+                signer: signer.clone(),
+                kind,
+                public_key,
+            },
+            certificates,
+        })
+    }
+
+    /// Loads the key described by android-tools [`Key`].
+    pub fn from_key(key: &Key) -> Result<Self> {
+        Self::load(&key.key_path, &key.key_pass, &key.key_alias, None)
+    }
+
+    /// Signer certificate.
+    pub fn certificate(&self) -> &Certificate {
+        &self.certificates[0]
+    }
+
+    /// Full certificate chain, signer certificate first.
+    pub fn certificates(&self) -> &[Certificate] {
+        &self.certificates
+    }
+
+    /// DER-encoded signer certificate.
+    pub fn certificate_der(&self) -> Result<Vec<u8>> {
+        Ok(self
+            .certificate()
+            .to_der()
+            .map_err(|error| anyhow::anyhow!("failed to encode certificate: {error}"))?)
+    }
+
+    /// Signs data with RSASSA-PKCS1-v1_5 using SHA2-256.
+    pub(crate) fn sign_sha256(&self, data: &[u8]) -> Result<Vec<u8>> {
+        use rsa::signature::{Signer, hazmat::PrehashVerifier};
+        match &self.private_key {
+            PrivateKey::Rsa(private_key) => {
+                Ok(pkcs1v15::SigningKey::<Sha256>::new(private_key.clone())
+                    .sign(data)
+                    .to_vec())
+            }
+            PrivateKey::External {
+                signer,
+                kind,
+                public_key,
+            } => {
+                let digest = Sha256::digest(data);
+                let signature = external_signature(signer, *kind, &digest)?;
+                pkcs1v15::Signature::try_from(signature.as_slice())
+                    .and_then(|parsed| {
+                        pkcs1v15::VerifyingKey::<Sha256>::new(public_key.clone())
+                            .verify_prehash(&digest, &parsed)
+                    })
+                    .map_err(|_| {
+                        Error::ExternalSignerFailed(format!(
+                            "signature returned by `{}` doesn't match the certificate",
+                            signer.command[0]
+                        ))
+                    })?;
+                Ok(signature)
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SigningKey")
+            .field("private_key", &"<redacted>")
+            .field(
+                "subject",
+                &self.certificate().tbs_certificate.subject.to_string(),
+            )
+            .finish()
+    }
+}
+
+type KeyEntry = (Vec<u8>, Vec<Vec<u8>>);
+
+/// Reads a PEM certificate chain, signer certificate first, or a single DER certificate.
+fn read_certificates(path: &Path) -> Result<Vec<Certificate>> {
+    let data = std::fs::read(path)?;
+    let invalid = |reason: String| Error::InvalidCertificate(format!("{path:?}: {reason}"));
+    let Ok(pem) = std::str::from_utf8(&data) else {
+        return Ok(vec![
+            Certificate::from_der(&data).map_err(|error| invalid(error.to_string()))?,
+        ]);
+    };
+    let mut certificates = Vec::new();
+    let mut blocks = pem.split("-----BEGIN CERTIFICATE-----").skip(1);
+    for block in &mut blocks {
+        let (encoded, _) = block
+            .split_once("-----END CERTIFICATE-----")
+            .ok_or_else(|| invalid("unterminated PEM block".to_owned()))?;
+        let encoded = encoded.split_whitespace().collect::<String>();
+        let der = BASE64
+            .decode(encoded)
+            .map_err(|error| invalid(error.to_string()))?;
+        certificates.push(Certificate::from_der(&der).map_err(|error| invalid(error.to_string()))?);
+    }
+    if certificates.is_empty() {
+        return Err(invalid("no PEM certificate found".to_owned()));
+    }
+    Ok(certificates)
+}
+
+/// Detects the format of a keystore from its contents.
+pub fn detect_keystore_type(keystore_path: &Path) -> Result<KeystoreType> {
+    let data = std::fs::read(keystore_path)?;
+    Ok(match data.get(..4).map(|magic| read_u32(magic, 0)) {
+        Some(JKS_MAGIC) => KeystoreType::Jks,
+        _ => KeystoreType::Pkcs12,
+    })
+}
+
+/// Loads the certificate chain of the key with given alias, signer certificate first.
+/// Unlike [`SigningKey::load`] this works for keys of any algorithm.
+pub fn load_certificates(
+    keystore_path: &Path,
+    store_pass: &str,
+    alias: &str,
+    key_pass: Option<&str>,
+) -> Result<Vec<Certificate>> {
+    Ok(load_key_entry(keystore_path, store_pass, alias, key_pass)?.1)
+}
+
+fn load_key_entry(
+    keystore_path: &Path,
+    store_pass: &str,
+    alias: &str,
+    key_pass: Option<&str>,
+) -> Result<(Vec<u8>, Vec<Certificate>)> {
+    let data = std::fs::read(keystore_path)?;
+    let key_pass = key_pass.unwrap_or(store_pass);
+    let invalid = |reason: String| AndroidError::InvalidKeystore(keystore_path.into(), reason);
+    let (private_key, chain) = match data.get(..4).map(|magic| read_u32(magic, 0)) {
+        Some(JKS_MAGIC) => read_jks(&data, store_pass, alias, key_pass).map_err(invalid)?,
+        Some(JCEKS_MAGIC) => {
+            return Err(invalid("JCEKS keystores are not supported".to_owned()).into());
+        }
+        _ => read_pkcs12(&data, store_pass, alias).map_err(invalid)?,
+    }
+    .ok_or_else(|| AndroidError::KeyAliasNotFound(alias.to_owned()))?;
+    let certificates = chain
+        .iter()
+        .map(|der| Certificate::from_der(der))
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|error| invalid(format!("invalid certificate: {error}")))?;
+    if certificates.is_empty() {
+        return Err(invalid(format!("key `{alias}` has no certificate chain")).into());
+    }
+    Ok((private_key, certificates))
+}
+
+fn read_pkcs12(
+    data: &[u8],
+    store_pass: &str,
+    alias: &str,
+) -> std::result::Result<Option<KeyEntry>, String> {
+    let keystore = p12_keystore::KeyStore::from_pkcs12(data, store_pass)
+        .map_err(|error| format!("failed to decode PKCS#12 keystore: {error}"))?;
+    Ok(keystore.entries().find_map(|(name, entry)| match entry {
+        p12_keystore::KeyStoreEntry::PrivateKeyChain(chain) if name.eq_ignore_ascii_case(alias) => {
+            Some((
+                chain.key().to_vec(),
+                chain
+                    .chain()
+                    .iter()
+                    .map(|cert| cert.as_der().to_vec())
+                    .collect(),
+            ))
+        }
+        _ => None,
+    }))
+}
+
+#[derive(Sequence)]
+struct EncryptedPrivateKeyInfo {
+    algorithm: AlgorithmIdentifierOwned,
+    encrypted_data: OctetString,
+}
+
+/// Reads a private key entry from a legacy Java KeyStore (JKS) file.
+fn read_jks(
+    data: &[u8],
+    store_pass: &str,
+    alias: &str,
+    key_pass: &str,
+) -> std::result::Result<Option<KeyEntry>, String> {
+    let truncated = || "keystore is truncated".to_owned();
+    if data.len() < 20 {
+        return Err(truncated());
+    }
+    let (contents, digest) = data.split_at(data.len() - 20);
+    let mut hasher = Sha1::new();
+    hasher.update(password_bytes(store_pass));
+    hasher.update(JKS_INTEGRITY_SALT);
+    hasher.update(contents);
+    if hasher.finalize().as_slice() != digest {
+        return Err("keystore was tampered with, or password was incorrect".to_owned());
+    }
+
+    let mut reader = Reader::new(contents);
+    reader.u32().ok_or_else(truncated)?;
+    let version = reader.u32().ok_or_else(truncated)?;
+    let count = reader.u32().ok_or_else(truncated)?;
+    for _ in 0..count {
+        let tag = reader.u32().ok_or_else(truncated)?;
+        let name = reader.utf().ok_or_else(truncated)?;
+        reader.bytes(8).ok_or_else(truncated)?;
+        match tag {
+            JKS_PRIVATE_KEY_ENTRY => {
+                let length = reader.u32().ok_or_else(truncated)? as usize;
+                let protected_key = reader.bytes(length).ok_or_else(truncated)?;
+                let chain_length = reader.u32().ok_or_else(truncated)?;
+                let mut chain = Vec::new();
+                for _ in 0..chain_length {
+                    chain.push(reader.certificate(version).ok_or_else(truncated)?);
+                }
+                if name.eq_ignore_ascii_case(alias) {
+                    let key = recover_jks_key(protected_key, key_pass)?;
+                    return Ok(Some((key, chain)));
+                }
+            }
+            JKS_TRUSTED_CERT_ENTRY => {
+                reader.certificate(version).ok_or_else(truncated)?;
+            }
+            tag => return Err(format!("unknown keystore entry type {tag}")),
+        }
+    }
+    Ok(None)
+}
+
+/// Decrypts a key protected with Sun's `KeyProtector` scheme.
+fn recover_jks_key(protected_key: &[u8], key_pass: &str) -> std::result::Result<Vec<u8>, String> {
+    let info = EncryptedPrivateKeyInfo::from_der(protected_key)
+        .map_err(|error| format!("invalid protected key: {error}"))?;
+    if info.algorithm.oid != JKS_KEY_PROTECTOR_OID {
+        return Err(format!(
+            "unsupported key protection algorithm {}",
+            info.algorithm.oid
+        ));
+    }
+    let encrypted = info.encrypted_data.as_bytes();
+    if encrypted.len() < 40 {
+        return Err("protected key is truncated".to_owned());
+    }
+    let (salt, rest) = encrypted.split_at(20);
+    let (encrypted_key, check) = rest.split_at(rest.len() - 20);
+    let password = password_bytes(key_pass);
+
+    let mut digest = salt.to_vec();
+    let mut key = Vec::with_capacity(encrypted_key.len());
+    for chunk in encrypted_key.chunks(20) {
+        digest = Sha1::new()
+            .chain_update(&password)
+            .chain_update(&digest)
+            .finalize()
+            .to_vec();
+        key.extend(chunk.iter().zip(&digest).map(|(byte, mask)| byte ^ mask));
+    }
+    let expected = Sha1::new()
+        .chain_update(&password)
+        .chain_update(&key)
+        .finalize();
+    if expected.as_slice() != check {
+        return Err("key password was incorrect".to_owned());
+    }
+    Ok(key)
+}
+
+/// Java keystores hash passwords as big-endian UTF-16 code units.
+fn password_bytes(password: &str) -> Vec<u8> {
+    password.encode_utf16().flat_map(u16::to_be_bytes).collect()
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    fn bytes(&mut self, length: usize) -> Option<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.offset..self.offset.checked_add(length)?)?;
+        self.offset += length;
+        Some(bytes)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.bytes(4).map(|bytes| read_u32(bytes, 0))
+    }
+
+    fn utf(&mut self) -> Option<String> {
+        let length = self
+            .bytes(2)
+            .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))?;
+        self.bytes(length as usize)
+            .map(|bytes| String::from_utf8_lossy(bytes).into_owned())
+    }
+
+    fn certificate(&mut self, version: u32) -> Option<Vec<u8>> {
+        if version == 2 {
+            self.utf()?;
+        }
+        let length = self.u32()? as usize;
+        self.bytes(length).map(<[u8]>::to_vec)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    pub(crate) fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("fixtures")
+            .join("signing")
+            .join(name)
+    }
+
+    pub(crate) fn pkcs12_key() -> SigningKey {
+        SigningKey::load(&fixture("debug.p12"), "android", "androiddebugkey", None).unwrap()
+    }
+
+    pub(crate) fn jks_key() -> SigningKey {
+        SigningKey::load(
+            &fixture("debug.jks"),
+            "android",
+            "androiddebugkey",
+            Some("keypass"),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn loads_pkcs12_and_jks_keys() {
+        for key in [pkcs12_key(), jks_key()] {
+            assert_eq!(
+                key.certificate().tbs_certificate.subject.to_string(),
+                "CN=Crossbow Test,O=Crossbow,C=US"
+            );
+            assert!(!key.certificate_der().unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn rejects_wrong_passwords_and_aliases() {
+        let error =
+            SigningKey::load(&fixture("debug.jks"), "wrong", "androiddebugkey", None).unwrap_err();
+        assert!(error.to_string().contains("password was incorrect"));
+
+        let error = SigningKey::load(
+            &fixture("debug.jks"),
+            "android",
+            "androiddebugkey",
+            Some("android"),
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("key password was incorrect"));
+
+        let error =
+            SigningKey::load(&fixture("debug.p12"), "android", "release", None).unwrap_err();
+        assert!(matches!(
+            error,
+            crate::error::Error::Android(AndroidError::KeyAliasNotFound(alias)) if alias == "release"
+        ));
+    }
+
+    #[test]
+    fn debug_output_redacts_private_key() {
+        let output = format!("{:?}", pkcs12_key());
+        assert!(output.contains("<redacted>"));
+        assert!(!output.contains("RsaPrivateKey"));
+    }
+}
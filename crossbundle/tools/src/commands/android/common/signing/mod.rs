@@ -0,0 +1,19 @@
+mod apk_signer;
+mod credentials;
+mod jar_signer;
+mod key_report;
+mod keystore;
+mod signer;
+mod signing_block;
+
+pub use apk_signer::*;
+pub use credentials::*;
+pub use jar_signer::jar_sign;
+pub(crate) use jar_signer::verify_jar_signature;
+pub use key_report::*;
+pub use keystore::*;
+pub use signer::*;
+pub use signing_block::ApkSignatureScheme;
+
+#[cfg(test)]
+pub(crate) use keystore::tests::pkcs12_key;
@@ -0,0 +1,176 @@
+use super::{
+    DEFAULT_APK_SIGNATURE_SCHEMES, KeystoreCredentials, SigningKey, jar_sign, sign_apk_with_key,
+    verify_apk, verify_jar_signature,
+};
+use crate::{
+    commands::sign_artifact_externally,
+    error::{AndroidError, Result},
+    types::{ExternalSigner, SignedArtifactKind, SignerMode},
+};
+use std::path::{Path, PathBuf};
+
+/// Signs APKs and AABs with a keystore or with an external program.
+#[derive(Debug, Clone)]
+pub enum AndroidSigner {
+    Keystore(KeystoreCredentials),
+    External(ExternalSigner),
+}
+
+impl AndroidSigner {
+    /// Signs APK in place with APK Signature Schemes v1, v2 and v3. APKs signed by an
+    /// external program in `artifact` mode are verified before they replace the unsigned
+    /// one.
+    pub fn sign_apk(&self, apk_path: &Path) -> Result<()> {
+        self.sign(
+            apk_path,
+            SignedArtifactKind::Apk,
+            |key| sign_apk_with_key(apk_path, key, &DEFAULT_APK_SIGNATURE_SCHEMES),
+            |signed| verify_apk(signed).map(|_| ()),
+        )
+    }
+
+    /// Signs AAB in place with a JAR signature, as Google Play expects.
+    pub fn sign_aab(&self, aab_path: &Path) -> Result<()> {
+        self.sign(
+            aab_path,
+            SignedArtifactKind::Aab,
+            |key| jar_sign(aab_path, key, &[]),
+            |signed| match verify_jar_signature(&std::fs::read(signed)?)? {
+                Some(_) => Ok(()),
+                None => Err(AndroidError::ApkVerificationFailed(
+                    "external signer returned an unsigned AAB".to_owned(),
+                )
+                .into()),
+            },
+        )
+    }
+
+    fn sign(
+        &self,
+        path: &Path,
+        kind: SignedArtifactKind,
+        sign_with_key: impl FnOnce(&SigningKey) -> Result<()>,
+        verify: impl FnOnce(&Path) -> Result<()>,
+    ) -> Result<()> {
+        match self {
+            Self::Keystore(credentials) => sign_with_key(&credentials.signing_key()?),
+            Self::External(signer) if signer.mode == SignerMode::Digest => {
+                sign_with_key(&SigningKey::external(signer, kind)?)
+            }
+            Self::External(signer) => {
+                let signed = signed_path(path, kind);
+                sign_artifact_externally(signer, kind, path, &signed)?;
+                verify(&signed)?;
+                std::fs::rename(&signed, path)?;
+                Ok(())
+            }
+        }
+    }
+}
+
+impl From<KeystoreCredentials> for AndroidSigner {
+    fn from(credentials: KeystoreCredentials) -> Self {
+        Self::Keystore(credentials)
+    }
+}
+
+/// `app.apk` is signed to `app.signed.apk`.
+fn signed_path(path: &Path, kind: SignedArtifactKind) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{stem}.signed.{kind}"))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::{
+        commands::{
+            android::common::signing::{
+                apk_signer::tests::unsigned_apk, keystore::tests::fixture, pkcs12_key,
+            },
+            signer_script,
+        },
+        error::Error,
+    };
+
+    #[test]
+    fn signs_digests_with_an_external_signer() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let apk = temp_dir.path().join("app.apk");
+        unsigned_apk(&apk);
+        let invalid_signature = AndroidSigner::External(ExternalSigner {
+            command: vec![signer_script(
+                temp_dir.path(),
+                "cat > /dev/null; printf invalid",
+            )],
+            mode: SignerMode::Digest,
+            certificate: Some(fixture("debug.pem")),
+        });
+        assert!(matches!(
+            invalid_signature.sign_aab(&apk),
+            Err(Error::ExternalSignerFailed(_))
+        ));
+
+        if std::process::Command::new("openssl")
+            .arg("version")
+            .output()
+            .is_err()
+        {
+            eprintln!("skipping signing with `openssl pkeyutl`: `openssl` is not installed");
+            return;
+        }
+        // Stands in for a signing service holding the key of `debug.p12`.
+        let program = signer_script(
+            temp_dir.path(),
+            &format!(
+                "exec openssl pkeyutl -sign -inkey {:?} -pkeyopt digest:sha256",
+                fixture("debug-key.pem")
+            ),
+        );
+        let signer = AndroidSigner::External(ExternalSigner {
+            command: vec![program],
+            mode: SignerMode::Digest,
+            certificate: Some(fixture("debug.pem")),
+        });
+        signer.sign_apk(&apk).unwrap();
+        let signatures = verify_apk(&apk).unwrap();
+        assert_eq!(signatures.schemes, DEFAULT_APK_SIGNATURE_SCHEMES);
+        assert_eq!(&signatures.certificate, pkcs12_key().certificate());
+    }
+
+    #[test]
+    fn verifies_artifacts_from_an_external_signer() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let signed = temp_dir.path().join("presigned.apk");
+        unsigned_apk(&signed);
+        sign_apk_with_key(&signed, &pkcs12_key(), &DEFAULT_APK_SIGNATURE_SCHEMES).unwrap();
+        let unsigned = temp_dir.path().join("unsigned.apk");
+        unsigned_apk(&unsigned);
+        let copy = |source: &Path| {
+            AndroidSigner::External(ExternalSigner {
+                command: vec![
+                    signer_script(temp_dir.path(), &format!("cp {source:?} \"$1\"")),
+                    "{output}".to_owned(),
+                ],
+                mode: SignerMode::Artifact,
+                certificate: None,
+            })
+        };
+
+        let apk = temp_dir.path().join("app.apk");
+        std::fs::copy(&unsigned, &apk).unwrap();
+        copy(&signed).sign_apk(&apk).unwrap();
+        assert_eq!(
+            std::fs::read(&apk).unwrap(),
+            std::fs::read(&signed).unwrap()
+        );
+        assert!(!temp_dir.path().join("app.signed.apk").exists());
+
+        std::fs::copy(&unsigned, &apk).unwrap();
+        assert!(copy(&unsigned).sign_apk(&apk).is_err());
+        assert_eq!(
+            std::fs::read(&apk).unwrap(),
+            std::fs::read(&unsigned).unwrap()
+        );
+    }
+}
@@ -0,0 +1,309 @@
+use super::{
+    super::zip_layout::{
+        APK_SIG_BLOCK_MAGIC, ZipLayout, read_u16, read_u32, read_u64, updated_eocd,
+    },
+    SigningKey,
+    jar_signer::public_key,
+};
+use crate::error::{AndroidError, Result};
+use der::{Decode, Encode};
+use rsa::{pkcs1v15, signature::Verifier};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use x509_cert::Certificate;
+
+pub(crate) const APK_SIGNATURE_SCHEME_V2_BLOCK_ID: u32 = 0x7109_871a;
+pub(crate) const APK_SIGNATURE_SCHEME_V3_BLOCK_ID: u32 = 0xf053_68c0;
+const SIGNATURE_RSA_PKCS1_V1_5_WITH_SHA2_256: u32 = 0x0103;
+const STRIPPING_PROTECTION_ATTRIBUTE_ID: u32 = 0xbeef_f00d;
+/// APK Signature Scheme v3 is only honored on Android 9 (API level 28) and later.
+const V3_MIN_SDK: u32 = 28;
+const V3_MAX_SDK: u32 = i32::MAX as u32;
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// APK signature scheme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApkSignatureScheme {
+    /// JAR signing.
+    V1,
+    /// APK Signature Scheme v2, Android 7.0+.
+    V2,
+    /// APK Signature Scheme v3, Android 9+.
+    V3,
+}
+
+impl ApkSignatureScheme {
+    /// Value used for this scheme in the `X-Android-APK-Signed` JAR attribute.
+    pub fn id(&self) -> u32 {
+        match self {
+            Self::V1 => 1,
+            Self::V2 => 2,
+            Self::V3 => 3,
+        }
+    }
+}
+
+impl std::fmt::Display for ApkSignatureScheme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "v{}", self.id())
+    }
+}
+
+/// Inserts an APK Signing Block with v2 and/or v3 signatures in front of the central
+/// directory. An existing signing block is replaced.
+pub(crate) fn add_signing_block(
+    data: &[u8],
+    key: &SigningKey,
+    schemes: &[ApkSignatureScheme],
+) -> Result<Vec<u8>> {
+    let layout = ZipLayout::parse(data)?;
+    let digest = content_digest(data, &layout)?;
+    let certificate = key.certificate_der()?;
+    let public_key = key
+        .certificate()
+        .tbs_certificate
+        .subject_public_key_info
+        .to_der()
+        .map_err(|error| AndroidError::ApkSigningFailed(error.to_string()))?;
+    let has_v3 = schemes.contains(&ApkSignatureScheme::V3);
+
+    let mut pairs = Vec::new();
+    for scheme in schemes {
+        let (id, signer) = match scheme {
+            ApkSignatureScheme::V1 => continue,
+            ApkSignatureScheme::V2 => {
+                let attributes = if has_v3 {
+                    let mut attribute = STRIPPING_PROTECTION_ATTRIBUTE_ID.to_le_bytes().to_vec();
+                    attribute.extend_from_slice(&3u32.to_le_bytes());
+                    length_prefixed(&attribute)
+                } else {
+                    Vec::new()
+                };
+                let signed_data = [
+                    digests(&digest),
+                    length_prefixed(&length_prefixed(&certificate)),
+                    length_prefixed(&attributes),
+                ]
+                .concat();
+                let signer = [
+                    length_prefixed(&signed_data),
+                    signatures(key, &signed_data)?,
+                    length_prefixed(&public_key),
+                ]
+                .concat();
+                (APK_SIGNATURE_SCHEME_V2_BLOCK_ID, signer)
+            }
+            ApkSignatureScheme::V3 => {
+                let sdk_range = [V3_MIN_SDK.to_le_bytes(), V3_MAX_SDK.to_le_bytes()].concat();
+                let signed_data = [
+                    digests(&digest),
+                    length_prefixed(&length_prefixed(&certificate)),
+                    sdk_range.clone(),
+                    length_prefixed(&[]),
+                ]
+                .concat();
+                let signer = [
+                    length_prefixed(&signed_data),
+                    sdk_range,
+                    signatures(key, &signed_data)?,
+                    length_prefixed(&public_key),
+                ]
+                .concat();
+                (APK_SIGNATURE_SCHEME_V3_BLOCK_ID, signer)
+            }
+        };
+        let value = length_prefixed(&length_prefixed(&signer));
+        pairs.extend_from_slice(&((value.len() + 4) as u64).to_le_bytes());
+        pairs.extend_from_slice(&id.to_le_bytes());
+        pairs.extend_from_slice(&value);
+    }
+
+    let block_size = (pairs.len() + 8 + APK_SIG_BLOCK_MAGIC.len()) as u64;
+    let mut output = data[layout.entries.clone()].to_vec();
+    output.extend_from_slice(&block_size.to_le_bytes());
+    output.extend_from_slice(&pairs);
+    output.extend_from_slice(&block_size.to_le_bytes());
+    output.extend_from_slice(APK_SIG_BLOCK_MAGIC);
+    let cd_offset = output.len();
+    output.extend_from_slice(&data[layout.central_directory.clone()]);
+    let eocd = &data[layout.eocd.clone()];
+    output.extend_from_slice(&updated_eocd(
+        eocd,
+        read_u16(eocd, 10) as usize,
+        layout.central_directory.len(),
+        cd_offset,
+    )?);
+    Ok(output)
+}
+
+/// Verifies the signature with given scheme id in the APK Signing Block. Returns the
+/// signer certificate, or `None` if there is no signature of this scheme.
+pub(crate) fn verify_signing_block(data: &[u8], block_id: u32) -> Result<Option<Certificate>> {
+    let layout = ZipLayout::parse(data)?;
+    let Some(block) = &layout.signing_block else {
+        return Ok(None);
+    };
+    let scheme = if block_id == APK_SIGNATURE_SCHEME_V3_BLOCK_ID {
+        "v3"
+    } else {
+        "v2"
+    };
+    let failed = |reason: &str| AndroidError::ApkVerificationFailed(format!("{scheme}: {reason}"));
+    let Some(value) = block_pairs(&data[block.clone()])
+        .ok_or_else(|| failed("malformed APK Signing Block"))?
+        .into_iter()
+        .find_map(|(id, value)| (id == block_id).then_some(value))
+    else {
+        return Ok(None);
+    };
+
+    let malformed = || failed("malformed signer");
+    let mut signers = Slice(value).length_prefixed().ok_or_else(malformed)?;
+    let mut signer = signers.length_prefixed().ok_or_else(malformed)?;
+    let signed_data_bytes = signer.length_prefixed().ok_or_else(malformed)?.0;
+    if block_id == APK_SIGNATURE_SCHEME_V3_BLOCK_ID {
+        signer.u32().ok_or_else(malformed)?;
+        signer.u32().ok_or_else(malformed)?;
+    }
+    let mut signatures = signer.length_prefixed().ok_or_else(malformed)?;
+    let public_key_bytes = signer.length_prefixed().ok_or_else(malformed)?.0;
+
+    let mut signed_data = Slice(signed_data_bytes);
+    let mut digests = signed_data.length_prefixed().ok_or_else(malformed)?;
+    let mut certificates = signed_data.length_prefixed().ok_or_else(malformed)?;
+    let certificate = certificates
+        .length_prefixed()
+        .and_then(|certificate| Certificate::from_der(certificate.0).ok())
+        .ok_or_else(|| failed("signer has no certificate"))?;
+    let certificate_key = certificate
+        .tbs_certificate
+        .subject_public_key_info
+        .to_der()
+        .map_err(|_| malformed())?;
+    if certificate_key != public_key_bytes {
+        return Err(failed("public key doesn't match the signer certificate").into());
+    }
+
+    let public_key = public_key(&certificate).map_err(|reason| failed(&reason))?;
+    let mut verified = false;
+    while let Some(mut signature) = signatures.length_prefixed() {
+        let algorithm = signature.u32().ok_or_else(malformed)?;
+        let signature = signature.length_prefixed().ok_or_else(malformed)?.0;
+        if algorithm != SIGNATURE_RSA_PKCS1_V1_5_WITH_SHA2_256 {
+            continue;
+        }
+        let signature = pkcs1v15::Signature::try_from(signature).map_err(|_| malformed())?;
+        pkcs1v15::VerifyingKey::<Sha256>::new(public_key.clone())
+            .verify(signed_data_bytes, &signature)
+            .map_err(|_| failed("signature doesn't match the signer certificate"))?;
+        verified = true;
+    }
+    if !verified {
+        return Err(failed("no supported signature algorithm").into());
+    }
+
+    let expected = content_digest(data, &layout)?;
+    let mut digest_found = false;
+    while let Some(mut digest) = digests.length_prefixed() {
+        let algorithm = digest.u32().ok_or_else(malformed)?;
+        let digest = digest.length_prefixed().ok_or_else(malformed)?.0;
+        if algorithm == SIGNATURE_RSA_PKCS1_V1_5_WITH_SHA2_256 {
+            if digest != expected {
+                return Err(failed("APK contents digest doesn't match").into());
+            }
+            digest_found = true;
+        }
+    }
+    if !digest_found {
+        return Err(failed("no supported content digest").into());
+    }
+    Ok(Some(certificate))
+}
+
+/// Chunked SHA2-256 digest of the entries, central directory and EOCD sections.
+fn content_digest(data: &[u8], layout: &ZipLayout) -> Result<Vec<u8>> {
+    let eocd = &data[layout.eocd.clone()];
+    let eocd = updated_eocd(
+        eocd,
+        read_u16(eocd, 10) as usize,
+        layout.central_directory.len(),
+        layout.entries.end,
+    )?;
+    let sections = [
+        &data[layout.entries.clone()],
+        &data[layout.central_directory.clone()],
+        &eocd[..],
+    ];
+    let chunks = sections
+        .iter()
+        .flat_map(|section| section.chunks(CHUNK_SIZE))
+        .collect::<Vec<_>>();
+    let mut top = Sha256::new();
+    top.update([0x5a]);
+    top.update((chunks.len() as u32).to_le_bytes());
+    for chunk in chunks {
+        let mut hasher = Sha256::new();
+        hasher.update([0xa5]);
+        hasher.update((chunk.len() as u32).to_le_bytes());
+        hasher.update(chunk);
+        top.update(hasher.finalize());
+    }
+    Ok(top.finalize().to_vec())
+}
+
+fn digests(digest: &[u8]) -> Vec<u8> {
+    let mut record = SIGNATURE_RSA_PKCS1_V1_5_WITH_SHA2_256
+        .to_le_bytes()
+        .to_vec();
+    record.extend_from_slice(&length_prefixed(digest));
+    length_prefixed(&length_prefixed(&record))
+}
+
+fn signatures(key: &SigningKey, signed_data: &[u8]) -> Result<Vec<u8>> {
+    let mut record = SIGNATURE_RSA_PKCS1_V1_5_WITH_SHA2_256
+        .to_le_bytes()
+        .to_vec();
+    record.extend_from_slice(&length_prefixed(&key.sign_sha256(signed_data)?));
+    Ok(length_prefixed(&length_prefixed(&record)))
+}
+
+fn length_prefixed(value: &[u8]) -> Vec<u8> {
+    let mut output = (value.len() as u32).to_le_bytes().to_vec();
+    output.extend_from_slice(value);
+    output
+}
+
+fn block_pairs(block: &[u8]) -> Option<Vec<(u32, &[u8])>> {
+    let pairs_end = block.len().checked_sub(24)?;
+    let mut offset = 8;
+    let mut pairs = Vec::new();
+    while offset < pairs_end {
+        let length = usize::try_from(read_u64(block, offset)).ok()?;
+        let end = offset.checked_add(8)?.checked_add(length)?;
+        if length < 4 || end > pairs_end {
+            return None;
+        }
+        pairs.push((read_u32(block, offset + 8), &block[offset + 12..end]));
+        offset = end;
+    }
+    Some(pairs)
+}
+
+/// Cursor over little-endian, length-prefixed signing block structures.
+struct Slice<'a>(&'a [u8]);
+
+impl<'a> Slice<'a> {
+    fn u32(&mut self) -> Option<u32> {
+        let value = read_u32(self.0.get(..4)?, 0);
+        self.0 = &self.0[4..];
+        Some(value)
+    }
+
+    fn length_prefixed(&mut self) -> Option<Slice<'a>> {
+        let length = read_u32(self.0.get(..4)?, 0) as usize;
+        let value = self.0.get(4..4usize.checked_add(length)?)?;
+        self.0 = &self.0[4 + length..];
+        Some(Slice(value))
+    }
+}
@@ -0,0 +1,274 @@
+use super::zip_layout::{LOCAL_FILE_HEADER_SIGNATURE, ZipLayout, read_u16, read_u32, updated_eocd};
+use crate::error::{AndroidError, Result};
+use std::path::Path;
+use zip::DateTime;
+
+/// Alignment of uncompressed entries that aren't native libraries.
+pub const ZIP_ENTRY_ALIGNMENT: u64 = 4;
+/// Extra field used by Android tooling to record requested entry alignment.
+const ALIGNMENT_EXTRA_FIELD_ID: u16 = 0xd935;
+const ALIGNMENT_EXTRA_FIELD_MIN_SIZE: usize = 6;
+const LOCAL_FILE_HEADER_SIZE: usize = 30;
+const DATA_DESCRIPTOR_SIGNATURE: u32 = 0x0807_4b50;
+const DATA_DESCRIPTOR_FLAG: u16 = 1 << 3;
+const STORED: u16 = 0;
+
+/// Rewrites ZIP archive so that data of every uncompressed entry starts at a 4-byte
+/// boundary, and data of uncompressed `lib/<abi>/*.so` files starts at a
+/// `library_alignment` boundary, so they can be memory-mapped directly from the APK.
+///
+/// Entries are copied without recompression. Any APK Signing Block is dropped, as
+/// moving entries invalidates it. If `last_modified` is given, it replaces modification
+/// time of every entry, so the output doesn't depend on when its files were created.
+pub fn zip_align(
+    input_path: &Path,
+    output_path: &Path,
+    library_alignment: u64,
+    last_modified: Option<DateTime>,
+) -> Result<()> {
+    let data = std::fs::read(input_path)?;
+    let aligned = zip_align_bytes(&data, library_alignment, last_modified)?;
+    std::fs::write(output_path, aligned)?;
+    Ok(())
+}
+
+pub(crate) fn zip_align_bytes(
+    data: &[u8],
+    library_alignment: u64,
+    last_modified: Option<DateTime>,
+) -> Result<Vec<u8>> {
+    let invalid = |reason: String| AndroidError::InvalidApkArchive(reason);
+    if !library_alignment.is_power_of_two() || library_alignment < ZIP_ENTRY_ALIGNMENT {
+        return Err(invalid(format!("unsupported alignment {library_alignment}")).into());
+    }
+    let layout = ZipLayout::parse(data)?;
+    let records = layout.records(data)?;
+
+    let mut output = Vec::with_capacity(data.len());
+    let mut central_directory = Vec::with_capacity(layout.central_directory.len());
+    for record in &records {
+        let header = record.local_header_offset;
+        if header + LOCAL_FILE_HEADER_SIZE > layout.entries.end
+            || read_u32(data, header) != LOCAL_FILE_HEADER_SIGNATURE
+        {
+            return Err(invalid(format!("malformed local header of {}", record.name)).into());
+        }
+        let name_length = read_u16(data, header + 26) as usize;
+        let extra_length = read_u16(data, header + 28) as usize;
+        let extra_start = header + LOCAL_FILE_HEADER_SIZE + name_length;
+        let data_start = extra_start + extra_length;
+        let mut data_end = data_start + record.compressed_size;
+        if record.flags & DATA_DESCRIPTOR_FLAG != 0 {
+            let signed = data_end + 4 <= layout.entries.end
+                && read_u32(data, data_end) == DATA_DESCRIPTOR_SIGNATURE;
+            data_end += if signed { 16 } else { 12 };
+        }
+        if data_end > layout.entries.end {
+            return Err(invalid(format!("data of {} is truncated", record.name)).into());
+        }
+
+        let new_header_offset =
+            u32::try_from(output.len()).map_err(|_| invalid("archive is too large".to_owned()))?;
+        let mut extra = strip_alignment(&data[extra_start..data_start]);
+        let alignment = entry_alignment(record, library_alignment);
+        if record.compression_method == STORED && !record.name.ends_with('/') {
+            let unaligned = output.len() + LOCAL_FILE_HEADER_SIZE + name_length + extra.len();
+            extra.extend(alignment_extra_field(unaligned, alignment as usize));
+        }
+        let extra_length = u16::try_from(extra.len())
+            .map_err(|_| invalid(format!("extra field of {} is too large", record.name)))?;
+
+        output.extend_from_slice(&data[header..header + 28]);
+        let new_header = new_header_offset as usize;
+        if let Some(time) = last_modified {
+            output[new_header + 10..new_header + 14].copy_from_slice(&dos_time(time));
+        }
+        output.extend_from_slice(&extra_length.to_le_bytes());
+        output.extend_from_slice(&data[header + LOCAL_FILE_HEADER_SIZE..extra_start]);
+        output.extend_from_slice(&extra);
+        output.extend_from_slice(&data[data_start..data_end]);
+
+        let record_start = central_directory.len();
+        central_directory.extend_from_slice(&data[record.range.clone()]);
+        central_directory[record_start + 42..record_start + 46]
+            .copy_from_slice(&new_header_offset.to_le_bytes());
+        if let Some(time) = last_modified {
+            central_directory[record_start + 12..record_start + 16]
+                .copy_from_slice(&dos_time(time));
+        }
+    }
+
+    let cd_offset = output.len();
+    let eocd = updated_eocd(
+        &data[layout.eocd.clone()],
+        records.len(),
+        central_directory.len(),
+        cd_offset,
+    )?;
+    output.extend_from_slice(&central_directory);
+    output.extend_from_slice(&eocd);
+    Ok(output)
+}
+
+/// MS-DOS time and date fields, in the order they're stored in ZIP headers.
+fn dos_time(time: DateTime) -> [u8; 4] {
+    let [time_low, time_high] = time.timepart().to_le_bytes();
+    let [date_low, date_high] = time.datepart().to_le_bytes();
+    [time_low, time_high, date_low, date_high]
+}
+
+fn entry_alignment(
+    record: &super::zip_layout::CentralDirectoryRecord,
+    library_alignment: u64,
+) -> u64 {
+    let mut components = record.name.split('/');
+    let is_library = matches!(
+        (components.next(), components.next(), components.next(), components.next()),
+        (Some("lib"), Some(_), Some(file), None) if file.ends_with(".so")
+    );
+    if is_library {
+        library_alignment
+    } else {
+        ZIP_ENTRY_ALIGNMENT
+    }
+}
+
+/// Removes alignment padding previously added by this function or by `zipalign`.
+fn strip_alignment(extra: &[u8]) -> Vec<u8> {
+    let mut kept = Vec::with_capacity(extra.len());
+    let mut offset = 0;
+    while offset + 4 <= extra.len() {
+        let id = read_u16(extra, offset);
+        let size = read_u16(extra, offset + 2) as usize;
+        let end = offset + 4 + size;
+        if end > extra.len() {
+            break;
+        }
+        if id != ALIGNMENT_EXTRA_FIELD_ID && (id != 0 || size != 0) {
+            kept.extend_from_slice(&extra[offset..end]);
+        }
+        offset = end;
+    }
+    kept
+}
+
+/// Builds an extra field that moves entry data from `unaligned_offset` to a multiple
+/// of `alignment`.
+fn alignment_extra_field(unaligned_offset: usize, alignment: usize) -> Vec<u8> {
+    let mut padding = (alignment - unaligned_offset % alignment) % alignment;
+    if padding == 0 {
+        return Vec::new();
+    }
+    while padding < ALIGNMENT_EXTRA_FIELD_MIN_SIZE {
+        padding += alignment;
+    }
+    let mut field = Vec::with_capacity(padding);
+    field.extend_from_slice(&ALIGNMENT_EXTRA_FIELD_ID.to_le_bytes());
+    field.extend_from_slice(&((padding - 4) as u16).to_le_bytes());
+    field.extend_from_slice(&(alignment as u16).to_le_bytes());
+    field.resize(padding, 0);
+    field
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use zip::{CompressionMethod, ZipWriter, write::SimpleFileOptions};
+
+    fn unaligned_apk() -> Vec<u8> {
+        let mut zip = ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        zip.start_file("AndroidManifest.xml", SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(&[1; 1000]).unwrap();
+        zip.start_file("res/a.png", stored).unwrap();
+        zip.write_all(&[2; 13]).unwrap();
+        zip.start_file("lib/arm64-v8a/libmain.so", stored).unwrap();
+        zip.write_all(&[3; 5000]).unwrap();
+        zip.start_file("lib/x86_64/libc++_shared.so", stored)
+            .unwrap();
+        zip.write_all(&[4; 77]).unwrap();
+        zip.start_file(
+            "lib/arm64-v8a/libcompressed.so",
+            SimpleFileOptions::default(),
+        )
+        .unwrap();
+        zip.write_all(&[5; 3000]).unwrap();
+        zip.add_directory("assets/", SimpleFileOptions::default())
+            .unwrap();
+        zip.finish().unwrap().into_inner()
+    }
+
+    fn entries(data: &[u8]) -> Vec<(String, bool, u64, Vec<u8>)> {
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(data)).unwrap();
+        (0..archive.len())
+            .map(|index| {
+                let mut file = archive.by_index(index).unwrap();
+                let mut contents = Vec::new();
+                file.read_to_end(&mut contents).unwrap();
+                let stored = file.compression() == CompressionMethod::Stored && !file.is_dir();
+                (
+                    file.name().to_owned(),
+                    stored,
+                    file.data_start().unwrap(),
+                    contents,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn aligns_stored_entries_and_libraries() {
+        for library_alignment in [4096, 16384] {
+            let original = unaligned_apk();
+            let aligned = zip_align_bytes(&original, library_alignment, None).unwrap();
+            let entries = entries(&aligned);
+            for (name, stored, data_start, _) in &entries {
+                if !stored {
+                    continue;
+                }
+                let expected = if name.ends_with(".so") {
+                    library_alignment
+                } else {
+                    ZIP_ENTRY_ALIGNMENT
+                };
+                assert_eq!(data_start % expected, 0, "{name} is not aligned");
+            }
+            let contents = |entries: Vec<(String, bool, u64, Vec<u8>)>| {
+                entries
+                    .into_iter()
+                    .map(|(name, _, _, contents)| (name, contents))
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(contents(entries), contents(self::entries(&original)));
+        }
+    }
+
+    #[test]
+    fn alignment_is_idempotent() {
+        let aligned = zip_align_bytes(&unaligned_apk(), 16384, None).unwrap();
+        assert_eq!(zip_align_bytes(&aligned, 16384, None).unwrap(), aligned);
+        let realigned = zip_align_bytes(&aligned, 4096, None).unwrap();
+        assert!(realigned.len() < aligned.len());
+    }
+
+    #[test]
+    fn rejects_invalid_alignment() {
+        assert!(zip_align_bytes(&unaligned_apk(), 3000, None).is_err());
+    }
+
+    #[test]
+    fn normalises_modification_times() {
+        let time = DateTime::from_date_and_time(2023, 11, 14, 22, 13, 20).unwrap();
+        let aligned = zip_align_bytes(&unaligned_apk(), 16384, Some(time)).unwrap();
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(&aligned)).unwrap();
+        for index in 0..archive.len() {
+            assert_eq!(archive.by_index(index).unwrap().last_modified(), Some(time));
+        }
+        assert_eq!(
+            zip_align_bytes(&aligned, 16384, Some(time)).unwrap(),
+            aligned
+        );
+    }
+}
@@ -0,0 +1,144 @@
+use crate::error::{AndroidError, Result};
+use std::ops::Range;
+
+pub(crate) const APK_SIG_BLOCK_MAGIC: &[u8; 16] = b"APK Sig Block 42";
+const EOCD_SIGNATURE: u32 = 0x0605_4b50;
+const EOCD_SIZE: usize = 22;
+const CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0201_4b50;
+const CENTRAL_DIRECTORY_RECORD_SIZE: usize = 46;
+pub(crate) const LOCAL_FILE_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+
+/// Sections of a ZIP archive as seen by the APK signature schemes.
+#[derive(Debug, Clone)]
+pub(crate) struct ZipLayout {
+    /// Local file headers and data of every entry.
+    pub entries: Range<usize>,
+    /// APK Signing Block placed between entries and the central directory.
+    pub signing_block: Option<Range<usize>>,
+    pub central_directory: Range<usize>,
+    pub eocd: Range<usize>,
+}
+
+/// Single record of the ZIP central directory.
+#[derive(Debug, Clone)]
+pub(crate) struct CentralDirectoryRecord {
+    pub name: String,
+    /// Raw record bytes inside the archive.
+    pub range: Range<usize>,
+    pub flags: u16,
+    pub compression_method: u16,
+    pub compressed_size: usize,
+    pub local_header_offset: usize,
+}
+
+impl ZipLayout {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let eocd_start =
+            find_eocd(data).ok_or_else(|| invalid("end of central directory record not found"))?;
+        let cd_size = read_u32(data, eocd_start + 12) as usize;
+        let cd_start = read_u32(data, eocd_start + 16) as usize;
+        if cd_start == u32::MAX as usize {
+            return Err(invalid("ZIP64 archives are not supported").into());
+        }
+        if cd_start.checked_add(cd_size) != Some(eocd_start) {
+            return Err(invalid("central directory is not followed by its end record").into());
+        }
+
+        let mut entries_end = cd_start;
+        let mut signing_block = None;
+        if cd_start >= 32 && &data[cd_start - 16..cd_start] == APK_SIG_BLOCK_MAGIC {
+            let size = read_u64(data, cd_start - 24) as usize;
+            let block_start = cd_start
+                .checked_sub(size)
+                .and_then(|offset| offset.checked_sub(8))
+                .ok_or_else(|| invalid("APK Signing Block size is out of range"))?;
+            if read_u64(data, block_start) as usize != size {
+                return Err(invalid("APK Signing Block sizes don't match").into());
+            }
+            entries_end = block_start;
+            signing_block = Some(block_start..cd_start);
+        }
+
+        Ok(Self {
+            entries: 0..entries_end,
+            signing_block,
+            central_directory: cd_start..eocd_start,
+            eocd: eocd_start..data.len(),
+        })
+    }
+
+    /// Parses central directory records in their stored order.
+    pub fn records(&self, data: &[u8]) -> Result<Vec<CentralDirectoryRecord>> {
+        let mut records = Vec::new();
+        let mut offset = self.central_directory.start;
+        while offset < self.central_directory.end {
+            if offset + CENTRAL_DIRECTORY_RECORD_SIZE > self.central_directory.end
+                || read_u32(data, offset) != CENTRAL_DIRECTORY_SIGNATURE
+            {
+                return Err(invalid("malformed central directory record").into());
+            }
+            let name_length = read_u16(data, offset + 28) as usize;
+            let extra_length = read_u16(data, offset + 30) as usize;
+            let comment_length = read_u16(data, offset + 32) as usize;
+            let name_start = offset + CENTRAL_DIRECTORY_RECORD_SIZE;
+            let end = name_start + name_length + extra_length + comment_length;
+            if end > self.central_directory.end {
+                return Err(invalid("central directory record is truncated").into());
+            }
+            records.push(CentralDirectoryRecord {
+                name: String::from_utf8_lossy(&data[name_start..name_start + name_length])
+                    .into_owned(),
+                range: offset..end,
+                flags: read_u16(data, offset + 8),
+                compression_method: read_u16(data, offset + 10),
+                compressed_size: read_u32(data, offset + 20) as usize,
+                local_header_offset: read_u32(data, offset + 42) as usize,
+            });
+            offset = end;
+        }
+        Ok(records)
+    }
+}
+
+/// Returns a copy of the EOCD record that points at the given central directory.
+pub(crate) fn updated_eocd(
+    eocd: &[u8],
+    records: usize,
+    cd_size: usize,
+    cd_offset: usize,
+) -> Result<Vec<u8>> {
+    let records = u16::try_from(records).map_err(|_| invalid("too many archive entries"))?;
+    let cd_size = u32::try_from(cd_size).map_err(|_| invalid("central directory too large"))?;
+    let cd_offset = u32::try_from(cd_offset).map_err(|_| invalid("archive is too large"))?;
+    let mut eocd = eocd.to_vec();
+    eocd[8..10].copy_from_slice(&records.to_le_bytes());
+    eocd[10..12].copy_from_slice(&records.to_le_bytes());
+    eocd[12..16].copy_from_slice(&cd_size.to_le_bytes());
+    eocd[16..20].copy_from_slice(&cd_offset.to_le_bytes());
+    Ok(eocd)
+}
+
+fn find_eocd(data: &[u8]) -> Option<usize> {
+    let last = data.len().checked_sub(EOCD_SIZE)?;
+    let first = last.saturating_sub(u16::MAX as usize);
+    (first..=last).rev().find(|&offset| {
+        read_u32(data, offset) == EOCD_SIGNATURE
+            && offset + EOCD_SIZE + read_u16(data, offset + 20) as usize == data.len()
+    })
+}
+
+fn invalid(reason: &str) -> AndroidError {
+    AndroidError::InvalidApkArchive(reason.to_owned())
+}
+
+pub(crate) fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(data[offset..offset + 2].try_into().unwrap())
+}
+
+pub(crate) fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+pub(crate) fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
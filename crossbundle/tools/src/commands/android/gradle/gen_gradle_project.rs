@@ -1,7 +1,7 @@
 use crate::error::*;
 use crate::{
     commands::{CargoProject, ExistingFile, copy_directory_contents},
-    types::{AndroidGradlePlugins, AndroidRuntime, GradleDependencyProject},
+    types::{AndroidGradlePlugins, AndroidRuntime, GradleDependencyProject, Profile},
 };
 use crossbow_android::embed::CrossbowAndroidAppTemplate;
 use std::path::{Path, PathBuf};
@@ -12,6 +12,15 @@ pub struct AndroidSdkVersions {
     pub target_sdk: u32,
 }
 
+/// Directory the Gradle project reads the native libraries of an ABI from. Gradle only has
+/// the `debug` and `release` build types, so custom profiles use the one they inherit.
+pub fn gradle_jni_libs_dir(android_build_dir: &Path, profile: &Profile, abi: &str) -> PathBuf {
+    android_build_dir
+        .join("libs")
+        .join(profile.base())
+        .join(abi)
+}
+
 pub fn gen_gradle_project(
     package_name: &str,
     version_code: u32,
@@ -353,6 +362,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn custom_profiles_use_the_jni_libs_of_their_build_type() {
+        let profile = Profile::Custom {
+            name: "release-lto".to_owned(),
+            release: true,
+        };
+        assert_eq!(
+            gradle_jni_libs_dir(Path::new("android"), &profile, "arm64-v8a"),
+            Path::new("android/libs/release/arm64-v8a")
+        );
+        let build_gradle = include_str!("../../../../../../platform/android/java/app/build.gradle");
+        assert!(build_gradle.contains("release.jniLibs.setSrcDirs([\"../libs/release\"])"));
+    }
+
     #[test]
     fn test_crossbow_settings_gradle() {
         let dep = GradleDependencyProject {
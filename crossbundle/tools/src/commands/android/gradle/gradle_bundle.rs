@@ -0,0 +1,106 @@
+use crate::{
+    commands::android::{KeystoreCredentials, detect_keystore_type},
+    error::{AndroidError, Result},
+    types::{KeystoreType, Profile},
+};
+use std::path::{Path, PathBuf};
+
+/// Gradle task that builds the app bundle of the profile. The Gradle project only has
+/// the `debug` and `release` build types, so custom profiles use the one they inherit.
+pub fn gradle_bundle_task(profile: &Profile) -> &'static str {
+    match profile.is_release() {
+        true => "bundleRelease",
+        false => "bundleDebug",
+    }
+}
+
+/// Environment variables that make the generated Gradle project sign with the keystore.
+/// Passing them in the environment keeps passwords out of `gradle.properties`.
+pub fn gradle_signing_env(
+    credentials: &KeystoreCredentials,
+) -> Result<Vec<(&'static str, String)>> {
+    let store_type = match detect_keystore_type(&credentials.keystore)? {
+        KeystoreType::Pkcs12 => "pkcs12",
+        KeystoreType::Jks => "jks",
+    };
+    Ok(vec![
+        (
+            "CROSSBOW_SIGNING_STORE_FILE",
+            dunce::simplified(&credentials.keystore)
+                .to_string_lossy()
+                .into_owned(),
+        ),
+        ("CROSSBOW_SIGNING_STORE_TYPE", store_type.to_owned()),
+        (
+            "CROSSBOW_SIGNING_STORE_PASSWORD",
+            credentials.store_password.expose().to_owned(),
+        ),
+        ("CROSSBOW_SIGNING_KEY_ALIAS", credentials.key_alias.clone()),
+        (
+            "CROSSBOW_SIGNING_KEY_PASSWORD",
+            credentials.key_password().expose().to_owned(),
+        ),
+    ])
+}
+
+/// Finds the app bundle built by [`gradle_bundle_task`] in the Gradle project.
+pub fn gradle_bundle_path(gradle_project: &Path, profile: &Profile) -> Result<PathBuf> {
+    let outputs = gradle_project
+        .join("build")
+        .join("outputs")
+        .join("bundle")
+        .join(profile.base());
+    std::fs::read_dir(&outputs)
+        .ok()
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .find(|path| path.extension().is_some_and(|extension| extension == "aab"))
+        .ok_or_else(|| AndroidError::GradleBundleNotFound(outputs).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Secret;
+
+    #[test]
+    fn passes_signing_config_in_the_environment() {
+        let credentials = KeystoreCredentials {
+            keystore: Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/fixtures/signing/debug.jks"),
+            key_alias: "androiddebugkey".to_owned(),
+            store_password: Secret::new("android"),
+            key_password: Some(Secret::new("keypass")),
+        };
+        let env = gradle_signing_env(&credentials).unwrap();
+        let var = |name| {
+            env.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.as_str())
+                .unwrap()
+        };
+        assert_eq!(var("CROSSBOW_SIGNING_STORE_TYPE"), "jks");
+        assert_eq!(var("CROSSBOW_SIGNING_STORE_PASSWORD"), "android");
+        assert_eq!(var("CROSSBOW_SIGNING_KEY_PASSWORD"), "keypass");
+
+        // Every variable must be read by the Gradle template.
+        let config = include_str!("../../../../../../platform/android/java/app/config.gradle");
+        assert!(env.iter().all(|(key, _)| config.contains(key)));
+    }
+
+    #[test]
+    fn finds_bundles_of_the_profile() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        assert!(gradle_bundle_path(temp_dir.path(), &Profile::Release).is_err());
+        let release = temp_dir.path().join("build/outputs/bundle/release");
+        std::fs::create_dir_all(&release).unwrap();
+        std::fs::write(release.join("output-metadata.json"), "{}").unwrap();
+        std::fs::write(release.join("gradle-release.aab"), "aab").unwrap();
+        assert_eq!(
+            gradle_bundle_path(temp_dir.path(), &Profile::Release).unwrap(),
+            release.join("gradle-release.aab")
+        );
+        assert!(gradle_bundle_path(temp_dir.path(), &Profile::Debug).is_err());
+    }
+}
@@ -1,5 +1,7 @@
 pub mod gen_gradle_project;
+pub mod gradle_bundle;
 pub mod gradle_init;
 
 pub use gen_gradle_project::*;
+pub use gradle_bundle::*;
 pub use gradle_init::*;
@@ -1,22 +1,31 @@
 use crate::{
-    commands::android::native::{
-        get_libs_in_dir, library_name, recursively_define_needed_libs, search_dylibs,
+    commands::android::{
+        check_elf_page_alignment,
+        native::{
+            copy_native_lib, get_libs_in_dir, library_name, recursively_define_needed_libs,
+            search_dylibs,
+        },
     },
     error::*,
-    types::{AndroidNdk, AndroidTarget, IntoRustTriple, Profile},
+    types::{AndroidNdk, AndroidPageSize, AndroidTarget, IntoRustTriple, Profile},
 };
 use std::path::{Path, PathBuf};
 
-/// Adds given lib and all reletad libs into APK
+/// Adds given lib and all reletad libs into APK.
+/// Fails if any of the libs isn't aligned for `page_size`.
+/// With `debug_symbols` the libs are stripped, see [`copy_native_lib`].
+#[allow(clippy::too_many_arguments)]
 pub fn add_libs_into_aapt2(
     ndk: &AndroidNdk,
     lib_path: &Path,
     build_target: AndroidTarget,
-    profile: Profile,
+    profile: &Profile,
     min_sdk_version: u32,
+    page_size: AndroidPageSize,
     build_dir: &Path,
     target_dir: &Path,
     package_name: &str,
+    debug_symbols: Option<&Path>,
 ) -> Result<PathBuf> {
     // Get list of android system libs (https://developer.android.com/ndk/guides/stable_apis)
     let mut system_libs = Vec::new();
@@ -26,9 +35,7 @@ pub fn add_libs_into_aapt2(
     }
 
     // Get list of dylibs_paths
-    let build_path = target_dir
-        .join(build_target.rust_triple())
-        .join(profile.as_ref());
+    let build_path = target_dir.join(build_target.rust_triple()).join(profile);
     let mut dylibs_paths = search_dylibs(&build_path.join("build"))?;
     dylibs_paths.push(build_path.join("tools"));
 
@@ -37,12 +44,16 @@ pub fn add_libs_into_aapt2(
     let mut needed_libs = vec![];
     recursively_define_needed_libs(
         (lib_name, lib_path.to_owned()),
-        &ndk.toolchain_bin("readelf", build_target)?,
+        build_target,
         &ndk.sysroot_lib_dir(&build_target)?.join("libc++_shared.so"),
         &system_libs,
         &dylibs_paths,
         &mut needed_libs,
     )?;
+    // Every packaged library, including third-party ones, must be loadable on devices
+    // with the configured page size
+    let packaged_libs: Vec<_> = needed_libs.iter().map(|(_, path)| path).collect();
+    check_elf_page_alignment(&packaged_libs, page_size)?;
 
     // Add all needed libs into apk archive
     let abi = build_target.android_abi();
@@ -55,6 +66,10 @@ pub fn add_libs_into_aapt2(
         .join(abi);
     for (_lib_name, lib_path) in needed_libs {
         add_lib_aapt2(&lib_path, &out_dir, &project_dir)?;
+        if debug_symbols.is_some() {
+            let output = out_dir.join(library_name(&lib_path)?);
+            copy_native_lib(ndk, build_target, &lib_path, &output, debug_symbols)?;
+        }
     }
     Ok(out_dir)
 }
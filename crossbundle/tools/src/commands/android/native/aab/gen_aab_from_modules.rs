@@ -13,9 +13,13 @@ pub fn gen_aab_from_modules(
     Ok(aab)
 }
 
+/// Generates aab with the given bundletool. `metadata_files` are `(name, path)` pairs
+/// stored as `BUNDLE-METADATA/<name>`, e.g. from
+/// [`debug_symbols_metadata`](crate::commands::android::debug_symbols_metadata).
 pub fn gen_aab_from_modules_with_toolchain(
     package_name: &str,
     zip_modules: &[PathBuf],
+    metadata_files: &[(String, PathBuf)],
     build_dir: &Path,
     java: &Path,
     bundletool: &Path,
@@ -36,6 +40,9 @@ pub fn gen_aab_from_modules_with_toolchain(
         )
         .arg("--output")
         .arg(&aab);
+    for (name, path) in metadata_files {
+        command.arg(format!("--metadata-file={name}:{}", path.display()));
+    }
     command.output_err(true)?;
     Ok(aab)
 }
@@ -0,0 +1,162 @@
+use crate::{
+    commands::{android::*, zip_write},
+    error::*,
+    types::*,
+};
+use std::path::{Path, PathBuf};
+
+/// Namespace URI that the `dist:` prefix of module manifests is bound to.
+pub const DIST_NAMESPACE: &str = "http://schemas.android.com/apk/distribution";
+
+/// Generates the manifest of an asset pack module.
+pub fn asset_pack_manifest(package_name: &str, pack: &AssetPack) -> XmlElement {
+    let dist = |name: &str, value: &str| XmlAttribute {
+        namespace: Some(DIST_NAMESPACE.to_owned()),
+        name: name.to_owned(),
+        value: value.to_owned(),
+    };
+    let element = |name: &str, attributes, children| XmlElement {
+        name: format!("dist:{name}"),
+        namespaces: Vec::new(),
+        attributes,
+        children,
+    };
+    XmlElement {
+        name: "manifest".to_owned(),
+        namespaces: vec![("dist".to_owned(), DIST_NAMESPACE.to_owned())],
+        attributes: vec![
+            XmlAttribute {
+                namespace: None,
+                name: "package".to_owned(),
+                value: package_name.to_owned(),
+            },
+            XmlAttribute {
+                namespace: None,
+                name: "split".to_owned(),
+                value: pack.name.clone(),
+            },
+        ],
+        children: vec![element(
+            "module",
+            vec![dist("type", "asset-pack")],
+            vec![
+                element("fusing", vec![dist("include", "true")], Vec::new()),
+                element(
+                    "delivery",
+                    Vec::new(),
+                    vec![element(
+                        pack.delivery.element_name(),
+                        Vec::new(),
+                        Vec::new(),
+                    )],
+                ),
+            ],
+        )],
+    }
+}
+
+/// Generates an asset pack module archive to pass to `bundletool build-bundle` next to
+/// the base module. The manifest is compiled with aapt2 and the pack directory is
+/// packaged into `assets/`.
+pub fn gen_asset_pack_module(
+    sdk: &AndroidSdk,
+    build_dir: &Path,
+    package_name: &str,
+    pack: &AssetPack,
+    target_sdk_version: u32,
+) -> Result<PathBuf> {
+    let pack_dir = build_dir.join("asset_packs").join(&pack.name);
+    if pack_dir.exists() {
+        std::fs::remove_dir_all(&pack_dir)?;
+    }
+    std::fs::create_dir_all(&pack_dir)?;
+    let manifest_path = pack_dir.join("AndroidManifest.xml");
+    std::fs::write(
+        &manifest_path,
+        asset_pack_manifest(package_name, pack).to_xml_string(),
+    )?;
+
+    let apk_path = pack_dir.join(format!("{}.apk", pack.name));
+    sdk.aapt2()?
+        .link_compiled_res(None, &apk_path, &manifest_path)
+        .android_jar(sdk.android_jar(target_sdk_version)?)
+        .assets(pack.directory.clone())
+        .proto_format(true)
+        .run()?;
+
+    let extracted_apk_path = extract_archive(&apk_path, &pack_dir.join("extracted_apk_files"))?;
+    let zip_path = build_dir
+        .join("asset_packs")
+        .join(format!("{}.zip", pack.name));
+    write_asset_pack_module(&extracted_apk_path, &zip_path)?;
+    Ok(zip_path)
+}
+
+/// Zips the files of the proto APK linked by aapt2 into the module layout. bundletool
+/// rejects asset packs with a resource table, so aapt2's `resources.pb` is left out.
+fn write_asset_pack_module(extracted_apk_path: &Path, zip_path: &Path) -> Result<()> {
+    let resource_table = extracted_apk_path.join("resources.pb");
+    if resource_table.exists() {
+        std::fs::remove_file(resource_table)?;
+    }
+    zip_dirs_to_write(extracted_apk_path)?;
+    zip_write(extracted_apk_path, zip_path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn asset_pack_module_has_no_resource_table() {
+        let dir = tempfile::tempdir().unwrap();
+        let extracted = dir.path().join("extracted_apk_files");
+        std::fs::create_dir_all(extracted.join("assets/levels")).unwrap();
+        std::fs::write(extracted.join("AndroidManifest.xml"), "manifest").unwrap();
+        std::fs::write(extracted.join("resources.pb"), "resources").unwrap();
+        std::fs::write(extracted.join("assets/levels/1.bin"), "level").unwrap();
+
+        let zip_path = dir.path().join("levels.zip");
+        write_asset_pack_module(&extracted, &zip_path).unwrap();
+
+        let archive = zip::ZipArchive::new(std::fs::File::open(zip_path).unwrap()).unwrap();
+        assert_eq!(
+            archive.file_names().collect::<Vec<_>>(),
+            [
+                "assets/",
+                "assets/levels/",
+                "assets/levels/1.bin",
+                "manifest/",
+                "manifest/AndroidManifest.xml"
+            ]
+        );
+    }
+
+    #[test]
+    fn generates_asset_pack_manifest() {
+        let pack = AssetPack {
+            name: "levels".to_owned(),
+            delivery: AssetPackDelivery::OnDemand,
+            directory: PathBuf::from("packs/levels"),
+        };
+        let manifest = asset_pack_manifest("com.crossbow.game", &pack).to_xml_string();
+        assert_eq!(
+            manifest,
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<manifest xmlns:dist="http://schemas.android.com/apk/distribution"
+    package="com.crossbow.game"
+    split="levels">
+    <dist:module
+        dist:type="asset-pack">
+        <dist:fusing
+            dist:include="true" />
+        <dist:delivery>
+            <dist:on-demand />
+        </dist:delivery>
+    </dist:module>
+</manifest>
+"#
+        );
+    }
+}
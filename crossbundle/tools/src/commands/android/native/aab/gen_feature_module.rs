@@ -0,0 +1,241 @@
+use super::DIST_NAMESPACE;
+use crate::{commands::android::*, error::*, types::*};
+use android_manifest::{AndroidManifest, VarOrBool};
+use std::path::{Path, PathBuf};
+
+/// Generates the manifest of a dynamic feature module from its manifest fragment.
+///
+/// The `dist:title` attribute references the [`FeatureModule::title_resource`] string
+/// that has to be defined in the base module.
+pub fn feature_module_manifest(package_name: &str, module: &FeatureModule) -> Result<XmlElement> {
+    let fragment = module.manifest.clone().unwrap_or_default();
+    let mut application = fragment.application;
+    if application.has_code.is_none() {
+        application.has_code = Some(VarOrBool::Bool(false));
+    }
+    let manifest = AndroidManifest {
+        package: Some(package_name.to_owned()),
+        application,
+        uses_feature: fragment.uses_feature,
+        uses_permission: fragment.uses_permission,
+        ..Default::default()
+    };
+    let manifest = android_manifest::to_string(&manifest).map_err(AndroidError::from)?;
+    let mut manifest = XmlElement::parse(manifest.as_bytes())?;
+
+    let dist = |name: &str, value: &str| XmlAttribute {
+        namespace: Some(DIST_NAMESPACE.to_owned()),
+        name: name.to_owned(),
+        value: value.to_owned(),
+    };
+    let element = |name: &str, attributes, children| XmlElement {
+        name: format!("dist:{name}"),
+        namespaces: Vec::new(),
+        attributes,
+        children,
+    };
+    manifest
+        .namespaces
+        .push(("dist".to_owned(), DIST_NAMESPACE.to_owned()));
+    manifest.attributes.extend([
+        XmlAttribute {
+            namespace: None,
+            name: "split".to_owned(),
+            value: module.name.clone(),
+        },
+        XmlAttribute {
+            namespace: Some(ANDROID_NAMESPACE.to_owned()),
+            name: "isFeatureSplit".to_owned(),
+            value: "true".to_owned(),
+        },
+    ]);
+    manifest.children.insert(
+        0,
+        element(
+            "module",
+            vec![
+                dist("instant", "false"),
+                dist("title", &format!("@string/{}", module.title_resource())),
+            ],
+            vec![
+                element(
+                    "delivery",
+                    Vec::new(),
+                    vec![element(
+                        module.delivery.element_name(),
+                        Vec::new(),
+                        Vec::new(),
+                    )],
+                ),
+                element("fusing", vec![dist("include", "true")], Vec::new()),
+            ],
+        ),
+    );
+    Ok(manifest)
+}
+
+/// Generates string resources with titles of feature modules into the base module
+/// resources directory.
+pub fn gen_feature_module_titles(
+    resources_dir: &Path,
+    feature_modules: &[FeatureModule],
+) -> Result<PathBuf> {
+    let values_dir = resources_dir.join("values");
+    std::fs::create_dir_all(&values_dir)?;
+    let mut strings = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<resources>\n");
+    for module in feature_modules {
+        let title = module.title.as_deref().unwrap_or(&module.name);
+        strings.push_str(&format!(
+            "    <string name=\"{}\">{}</string>\n",
+            module.title_resource(),
+            escape_string_resource(title)
+        ));
+    }
+    strings.push_str("</resources>\n");
+    let path = values_dir.join("crossbow_feature_modules.xml");
+    std::fs::write(&path, strings)?;
+    Ok(path)
+}
+
+/// Escapes text of a `<string>` resource, where apostrophes, quotes and leading `@` or
+/// `?` have special meaning.
+fn escape_string_resource(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    if value.starts_with(['@', '?']) {
+        escaped.push('\\');
+    }
+    for character in value.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '\'' | '"' | '\\' => {
+                escaped.push('\\');
+                escaped.push(character);
+            }
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+/// Converts the proto format base module APK generated by aapt2 into binary format, so
+/// feature modules can be linked against resources of the base module.
+pub fn gen_binary_base_apk(sdk: &AndroidSdk, base_apk: &Path, output: &Path) -> Result<PathBuf> {
+    let mut aapt2 = sdk.build_tool(bin!("aapt2"), None)?;
+    aapt2
+        .arg("convert")
+        .arg("-o")
+        .arg(output)
+        .arg("--output-format")
+        .arg("binary")
+        .arg(base_apk);
+    aapt2.output_err(true)?;
+    Ok(output.to_owned())
+}
+
+/// Links the manifest and assets of a feature module with aapt2 and extracts the
+/// result. Native libraries are added to the returned directory before it is zipped
+/// with [`gen_zip_modules`].
+///
+/// Feature modules get package IDs below the `0x7f` of the base module, starting with
+/// `0x7e` for the first module.
+pub fn gen_feature_module(
+    sdk: &AndroidSdk,
+    build_dir: &Path,
+    package_name: &str,
+    module: &FeatureModule,
+    index: usize,
+    binary_base_apk: &Path,
+    target_sdk_version: u32,
+) -> Result<PathBuf> {
+    let module_dir = build_dir.join("feature_modules").join(&module.name);
+    if module_dir.exists() {
+        std::fs::remove_dir_all(&module_dir)?;
+    }
+    std::fs::create_dir_all(&module_dir)?;
+    let manifest_path = module_dir.join("AndroidManifest.xml");
+    std::fs::write(
+        &manifest_path,
+        feature_module_manifest(package_name, module)?.to_xml_string(),
+    )?;
+
+    let apk_path = module_dir.join(format!("{}.apk", module.name));
+    let mut aapt2 = sdk.build_tool(bin!("aapt2"), None)?;
+    aapt2
+        .arg("link")
+        .arg("--proto-format")
+        .arg("-o")
+        .arg(&apk_path)
+        .arg("--manifest")
+        .arg(&manifest_path)
+        .arg("-I")
+        .arg(sdk.android_jar(target_sdk_version)?)
+        .arg("-I")
+        .arg(binary_base_apk)
+        .arg("--package-id")
+        .arg(format!("{:#x}", FeatureModule::package_id(index)?))
+        .arg("--allow-reserved-package-id");
+    if !module.assets.is_empty() {
+        let assets = module_dir.join("gen_assets");
+        crate::commands::combine_folders(&module.assets, &assets)?;
+        aapt2.arg("-A").arg(assets);
+    }
+    aapt2.output_err(true)?;
+
+    extract_archive(&apk_path, &module_dir.join("extracted_apk_files"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn module() -> FeatureModule {
+        FeatureModule {
+            name: "arena".to_owned(),
+            title: Some("Bob's Arena & Co".to_owned()),
+            delivery: FeatureModuleDelivery::OnDemand,
+            path: PathBuf::from("features/arena"),
+            features: Vec::new(),
+            assets: Vec::new(),
+            manifest: None,
+        }
+    }
+
+    #[test]
+    fn generates_feature_module_manifest() {
+        let manifest = feature_module_manifest("com.crossbow.game", &module()).unwrap();
+        assert_eq!(
+            manifest.to_xml_string(),
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<manifest xmlns:android="http://schemas.android.com/apk/res/android" xmlns:tools="http://schemas.android.com/tools" xmlns:dist="http://schemas.android.com/apk/distribution"
+    package="com.crossbow.game"
+    split="arena"
+    android:isFeatureSplit="true">
+    <dist:module
+        dist:instant="false"
+        dist:title="@string/crossbow_feature_arena">
+        <dist:delivery>
+            <dist:on-demand />
+        </dist:delivery>
+        <dist:fusing
+            dist:include="true" />
+    </dist:module>
+    <application
+        android:hasCode="false" />
+</manifest>
+"#
+        );
+    }
+
+    #[test]
+    fn generates_feature_module_titles() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = gen_feature_module_titles(temp_dir.path(), &[module()]).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(path).unwrap(),
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<resources>\n    \
+             <string name=\"crossbow_feature_arena\">Bob\\'s Arena &amp; Co</string>\n</resources>\n"
+        );
+    }
+}
@@ -20,6 +20,7 @@ pub fn gen_minimal_unsigned_aab(
         AndroidStrategy::NativeAab,
         AndroidRuntime::NativeActivity,
         false,
+        false,
     );
 
     let manifest_path = save_android_manifest(aab_build_dir, &manifest)?;
@@ -1,4 +1,7 @@
-use crate::{commands::android::*, error::*};
+use crate::{
+    commands::{android::*, zip_write},
+    error::*,
+};
 use std::path::{Path, PathBuf};
 
 /// Allows to generate archive from files extracted from APK
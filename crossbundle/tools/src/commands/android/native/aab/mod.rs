@@ -1,9 +1,13 @@
 pub mod add_libs_into_aapt2;
 pub mod gen_aab_from_modules;
+pub mod gen_asset_pack_module;
+pub mod gen_feature_module;
 pub mod gen_minimal_unsigned_aab;
 pub mod gen_zip_modules;
 
 pub use add_libs_into_aapt2::*;
 pub use gen_aab_from_modules::*;
+pub use gen_asset_pack_module::*;
+pub use gen_feature_module::*;
 pub use gen_minimal_unsigned_aab::*;
 pub use gen_zip_modules::*;
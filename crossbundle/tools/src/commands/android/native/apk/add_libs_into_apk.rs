@@ -1,7 +1,10 @@
 use crate::{
-    commands::android::native::library_name,
+    commands::android::{
+        ElfFile, check_elf_page_alignment,
+        native::{copy_native_lib, library_name},
+    },
     error::*,
-    types::{AndroidNdk, AndroidSdk, AndroidTarget, IntoRustTriple, Profile},
+    types::{AndroidNdk, AndroidPageSize, AndroidSdk, AndroidTarget, IntoRustTriple, Profile},
 };
 use std::{
     fs::File,
@@ -10,17 +13,21 @@ use std::{
 };
 
 /// Adds given lib and all reletad libs into APK.
-/// Uses `readelf`, `aapt` tools
+/// Uses `aapt` tool. Fails if any of the libs isn't aligned for `page_size`.
+/// With `debug_symbols` the libs are stripped, see [`copy_native_lib`].
+#[allow(clippy::too_many_arguments)]
 pub fn add_libs_into_apk(
     sdk: &AndroidSdk,
     ndk: &AndroidNdk,
     apk_path: &Path,
     lib_path: &Path,
     build_target: AndroidTarget,
-    profile: Profile,
+    profile: &Profile,
     min_sdk_version: u32,
+    page_size: AndroidPageSize,
     build_dir: &Path,
     target_dir: &Path,
+    debug_symbols: Option<&Path>,
 ) -> Result<PathBuf> {
     // Get list of android system libs (https://developer.android.com/ndk/guides/stable_apis)
     let mut system_libs = Vec::new();
@@ -29,9 +36,7 @@ pub fn add_libs_into_apk(
         system_libs.push(lib);
     }
     // Get list of dylibs_paths
-    let build_path = target_dir
-        .join(build_target.rust_triple())
-        .join(profile.as_ref());
+    let build_path = target_dir.join(build_target.rust_triple()).join(profile);
     let mut dylibs_paths = search_dylibs(&build_path.join("build"))?;
     dylibs_paths.push(build_path.join("tools"));
     // Get list of libs that main lib need for work
@@ -39,16 +44,28 @@ pub fn add_libs_into_apk(
     let mut needed_libs = vec![];
     recursively_define_needed_libs(
         (lib_name, lib_path.to_owned()),
-        &ndk.toolchain_bin("readelf", build_target)?,
+        build_target,
         &ndk.sysroot_lib_dir(&build_target)?.join("libc++_shared.so"),
         &system_libs,
         &dylibs_paths,
         &mut needed_libs,
     )?;
+    // Every packaged library, including third-party ones, must be loadable on devices
+    // with the configured page size
+    let packaged_libs: Vec<_> = needed_libs.iter().map(|(_, path)| path).collect();
+    check_elf_page_alignment(&packaged_libs, page_size)?;
     let abi = build_target.android_abi();
     let out_dir = build_dir.join("libs").join(profile).join(abi);
     for (_lib_name, lib_path) in needed_libs {
-        aapt_add_lib(sdk, apk_path, &lib_path, &out_dir, abi)?;
+        aapt_add_lib(
+            sdk,
+            ndk,
+            apk_path,
+            &lib_path,
+            &out_dir,
+            build_target,
+            debug_symbols,
+        )?;
     }
     Ok(out_dir)
 }
@@ -56,11 +73,14 @@ pub fn add_libs_into_apk(
 /// Copy lib into `out_dir` then add this lib into apk file
 fn aapt_add_lib(
     sdk: &AndroidSdk,
+    ndk: &AndroidNdk,
     apk_path: &Path,
     lib_path: &Path,
     out_dir: &Path,
-    abi: &str,
+    build_target: AndroidTarget,
+    debug_symbols: Option<&Path>,
 ) -> Result<()> {
+    let abi = build_target.android_abi();
     if !lib_path.exists() {
         return Err(Error::PathNotFound(lib_path.to_owned()));
     }
@@ -72,7 +92,13 @@ fn aapt_add_lib(
     std::fs::copy(lib_path, out_dir.join(file_name))?;
     let native_lib_path = apk_dir.join("lib").join(abi);
     std::fs::create_dir_all(&native_lib_path)?;
-    std::fs::copy(lib_path, native_lib_path.join(file_name))?;
+    copy_native_lib(
+        ndk,
+        build_target,
+        lib_path,
+        &native_lib_path.join(file_name),
+        debug_symbols,
+    )?;
     // `aapt a[dd] [-v] file.{zip,jar,apk} file1 [file2 ...]`
     // Add specified files to Zip-compatible archive
     let mut aapt = sdk.build_tool(bin!("aapt"), Some(apk_dir))?;
@@ -110,75 +136,104 @@ pub fn search_dylibs(deps_dir: &Path) -> Result<Vec<PathBuf>> {
     Ok(paths)
 }
 
-/// Update `needed_libs` hashset with given lib and all related libs.
+/// Update `needed_libs` hashset with given lib and all related libs, found through
+/// `DT_NEEDED` entries of their dynamic sections.
+///
+/// Fails if the lib isn't built for `build_target`, or lists every dependency that
+/// wasn't found in `dylibs_paths` together with the chain of libs that required it.
 /// Note: libc++ is not a system lib. If you use libc++_shared.so, it must be included in
 /// your APK. https://developer.android.com/ndk/guides/cpp-support
 pub fn recursively_define_needed_libs(
     (lib_name, lib_path): (String, PathBuf),
-    readelf_path: &Path,
+    build_target: AndroidTarget,
     libcpp_shared_path: &Path,
     system_libs: &[String],
     dylibs_paths: &[PathBuf],
     needed_libs: &mut Vec<(String, PathBuf)>,
 ) -> Result<()> {
-    let shared_libs = readelf_list_shared_libs(readelf_path, &lib_path)?;
-    needed_libs.push((lib_name, lib_path));
-    for lib_name in shared_libs {
-        if lib_name == "libc++_shared.so" {
-            needed_libs.push((lib_name, libcpp_shared_path.to_owned()));
-        } else if system_libs.contains(&lib_name) {
-            continue;
-        } else if !needed_libs.iter().any(|(name, _)| name == &lib_name) {
-            if let Some(lib_path) = find_library_path(dylibs_paths, &lib_name)? {
-                recursively_define_needed_libs(
-                    (lib_name, lib_path),
-                    readelf_path,
-                    libcpp_shared_path,
-                    system_libs,
-                    dylibs_paths,
-                    needed_libs,
-                )?;
-            } else {
-                eprintln!("Shared library \"{}\" not found.", lib_name);
-            }
-        };
+    let elf = ElfFile::read(&lib_path)?;
+    if elf.machine != build_target.elf_machine() {
+        return Err(AndroidError::NativeLibraryTargetMismatch(
+            lib_path,
+            build_target.android_abi().to_owned(),
+        )
+        .into());
+    }
+    let resolver = NeededLibsResolver {
+        build_target,
+        libcpp_shared_path,
+        system_libs,
+        dylibs_paths,
+    };
+    let mut unresolved = Vec::new();
+    resolver.resolve(
+        &mut vec![lib_name],
+        lib_path,
+        elf,
+        needed_libs,
+        &mut unresolved,
+    )?;
+    if !unresolved.is_empty() {
+        return Err(AndroidError::UnresolvedNativeLibraries(unresolved.join(", ")).into());
     }
     Ok(())
 }
 
-/// List all linked shared libraries
-pub fn readelf_list_shared_libs(readelf_path: &Path, lib_path: &Path) -> Result<Vec<String>> {
-    let mut readelf = std::process::Command::new(readelf_path);
-    readelf.arg("-d").arg(lib_path);
-    let output = readelf.output_err(false)?;
-    let mut needed = Vec::new();
-    for line in output.stdout.lines() {
-        let line = line?;
-        if line.contains("(NEEDED)") {
-            let lib = line
-                .split("Shared library: [")
-                .last()
-                .and_then(|line| line.split(']').next());
-            if let Some(lib) = lib {
-                needed.push(lib.to_owned());
+struct NeededLibsResolver<'a> {
+    build_target: AndroidTarget,
+    libcpp_shared_path: &'a Path,
+    system_libs: &'a [String],
+    dylibs_paths: &'a [PathBuf],
+}
+
+impl NeededLibsResolver<'_> {
+    /// Adds the last lib of `chain` and its dependencies into `needed_libs`.
+    fn resolve(
+        &self,
+        chain: &mut Vec<String>,
+        lib_path: PathBuf,
+        elf: ElfFile,
+        needed_libs: &mut Vec<(String, PathBuf)>,
+        unresolved: &mut Vec<String>,
+    ) -> Result<()> {
+        needed_libs.push((chain[chain.len() - 1].clone(), lib_path));
+        for lib_name in elf.needed {
+            if self.system_libs.contains(&lib_name)
+                || needed_libs.iter().any(|(name, _)| name == &lib_name)
+            {
+                continue;
+            }
+            if lib_name == "libc++_shared.so" {
+                needed_libs.push((lib_name, self.libcpp_shared_path.to_owned()));
+                continue;
+            }
+            match self.find_library(&lib_name)? {
+                Some((lib_path, elf)) => {
+                    chain.push(lib_name);
+                    self.resolve(chain, lib_path, elf, needed_libs, unresolved)?;
+                    chain.pop();
+                }
+                None => unresolved.push(format!("{lib_name} (required by {})", chain.join(" -> "))),
             }
         }
+        Ok(())
     }
-    Ok(needed)
-}
 
-/// Resolves native library using search paths
-pub fn find_library_path<S: AsRef<Path>>(
-    paths: &[PathBuf],
-    lib_name: S,
-) -> Result<Option<PathBuf>> {
-    for path in paths {
-        let lib_path = path.join(&lib_name);
-        if lib_path.exists() {
-            return Ok(Some(dunce::canonicalize(lib_path)?));
+    /// Finds the first lib with given name built for the target, skipping libs built
+    /// for other architectures as the dynamic linker does.
+    fn find_library(&self, lib_name: &str) -> Result<Option<(PathBuf, ElfFile)>> {
+        for path in self.dylibs_paths {
+            let lib_path = path.join(lib_name);
+            if !lib_path.is_file() {
+                continue;
+            }
+            let elf = ElfFile::read(&lib_path)?;
+            if elf.machine == self.build_target.elf_machine() {
+                return Ok(Some((dunce::canonicalize(lib_path)?, elf)));
+            }
         }
+        Ok(None)
     }
-    Ok(None)
 }
 
 /// Return all files in directory with `.so` ending
@@ -197,3 +252,127 @@ pub fn get_libs_in_dir(dir: &Path) -> std::io::Result<Vec<String>> {
     };
     Ok(libs)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Libs built with `gcc -shared -nostdlib`: `libmain.so` needs `libdep.so`,
+    /// `liblog.so` and `libc++_shared.so`, and `libdep.so` needs `leaf/libleaf.so`.
+    fn fixtures(abi: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/elf")
+            .join(abi)
+    }
+
+    /// Copies fixture libs into a temporary dir, setting the ELF machine of `target`, so
+    /// ARM targets are covered by the x86 fixtures.
+    fn target_fixtures(target: AndroidTarget) -> tempfile::TempDir {
+        let abi = match target {
+            AndroidTarget::Armv7 | AndroidTarget::I686 => "x86",
+            AndroidTarget::Aarch64 | AndroidTarget::X8664 => "x86_64",
+        };
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["libmain.so", "libdep.so", "leaf/libleaf.so"] {
+            let mut data = std::fs::read(fixtures(abi).join(name)).unwrap();
+            data[18..20].copy_from_slice(&target.elf_machine().to_le_bytes());
+            let path = dir.path().join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, data).unwrap();
+        }
+        dir
+    }
+
+    fn define_needed_libs(
+        target: AndroidTarget,
+        lib_path: &Path,
+        dylibs_paths: &[PathBuf],
+    ) -> Result<Vec<(String, PathBuf)>> {
+        let mut needed_libs = Vec::new();
+        recursively_define_needed_libs(
+            ("libmain.so".to_owned(), lib_path.to_owned()),
+            target,
+            Path::new("/ndk/libc++_shared.so"),
+            &["liblog.so".to_owned()],
+            dylibs_paths,
+            &mut needed_libs,
+        )?;
+        Ok(needed_libs)
+    }
+
+    #[test]
+    fn resolves_needed_libs_for_every_target() {
+        for target in [
+            AndroidTarget::Armv7,
+            AndroidTarget::Aarch64,
+            AndroidTarget::I686,
+            AndroidTarget::X8664,
+        ] {
+            let dir = target_fixtures(target);
+            let root = dunce::canonicalize(dir.path()).unwrap();
+            let needed_libs = define_needed_libs(
+                target,
+                &root.join("libmain.so"),
+                &[root.clone(), root.join("leaf")],
+            )
+            .unwrap();
+            assert_eq!(
+                needed_libs,
+                [
+                    ("libmain.so".to_owned(), root.join("libmain.so")),
+                    ("libdep.so".to_owned(), root.join("libdep.so")),
+                    ("libleaf.so".to_owned(), root.join("leaf/libleaf.so")),
+                    (
+                        "libc++_shared.so".to_owned(),
+                        PathBuf::from("/ndk/libc++_shared.so")
+                    ),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn reports_the_chain_of_unresolved_libs() {
+        let root = fixtures("x86_64");
+        let result = define_needed_libs(
+            AndroidTarget::X8664,
+            &root.join("libmain.so"),
+            std::slice::from_ref(&root),
+        );
+        match result {
+            Err(Error::Android(AndroidError::UnresolvedNativeLibraries(libs))) => {
+                assert_eq!(libs, "libleaf.so (required by libmain.so -> libdep.so)")
+            }
+            result => panic!("unexpected result: {result:?}"),
+        }
+    }
+
+    #[test]
+    fn skips_libs_built_for_other_targets() {
+        let x86 = fixtures("x86");
+        let x86_64 = fixtures("x86_64");
+        let needed_libs = define_needed_libs(
+            AndroidTarget::X8664,
+            &x86_64.join("libmain.so"),
+            &[
+                x86.clone(),
+                x86.join("leaf"),
+                x86_64.clone(),
+                x86_64.join("leaf"),
+            ],
+        )
+        .unwrap();
+        assert!(
+            needed_libs
+                .iter()
+                .skip(1)
+                .all(|(_, path)| !path.starts_with(&x86))
+        );
+        assert!(matches!(
+            define_needed_libs(AndroidTarget::Aarch64, &x86_64.join("libmain.so"), &[]),
+            Err(Error::Android(AndroidError::NativeLibraryTargetMismatch(
+                ..
+            )))
+        ));
+    }
+}
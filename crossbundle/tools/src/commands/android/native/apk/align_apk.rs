@@ -1,22 +1,25 @@
-use crate::{error::*, types::*};
+use crate::{
+    commands::{android::zip_align, reproducible_timestamp},
+    error::*,
+    types::*,
+};
 use std::path::{Path, PathBuf};
 
-/// Aligns APK on 4-byte memory boundary.
-/// Uses `zipalign` build tools
+/// Aligns APK entries on 4-byte memory boundary, and uncompressed native libraries on
+/// the given page size boundary. Modification times of entries are normalised, so the
+/// unsigned APK is reproducible.
 pub fn align_apk(
-    sdk: &AndroidSdk,
     unaligned_apk_path: &Path,
     package_name: &str,
     build_dir: &Path,
+    page_size: AndroidPageSize,
 ) -> Result<PathBuf> {
     let unsigned_apk_path = build_dir.join(format!("{}.apk", package_name));
-    let mut zipalign = sdk.build_tool(bin!("zipalign"), None)?;
-    zipalign
-        .arg("-f")
-        .arg("-v")
-        .arg("4")
-        .arg(unaligned_apk_path)
-        .arg(&unsigned_apk_path);
-    zipalign.output_err(true)?;
+    zip_align(
+        unaligned_apk_path,
+        &unsigned_apk_path,
+        page_size.bytes(),
+        Some(reproducible_timestamp()),
+    )?;
     Ok(unsigned_apk_path)
 }
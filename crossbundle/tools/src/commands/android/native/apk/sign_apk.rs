@@ -1,19 +1,9 @@
-use crate::{error::*, types::AndroidSdk};
-use android_tools::java_tools::Key;
-use std::path::Path;
+use crate::{commands::android::AndroidSigner, error::*};
+use std::path::{Path, PathBuf};
 
-/// Signs APK with given key.
-/// Uses `apksigner` build tool
-pub fn sign_apk(sdk: &AndroidSdk, apk_path: &Path, key: Key) -> Result<std::path::PathBuf> {
-    let mut apksigner = sdk.build_tool(bat!("apksigner"), None)?;
-    apksigner
-        .arg("sign")
-        .arg("--ks")
-        .arg(&key.key_path)
-        .arg("--ks-pass")
-        .arg(format!("pass:{}", key.key_pass))
-        .arg(apk_path);
-    apksigner.output_err(true)?;
-    let apk_path = apk_path.to_path_buf();
-    Ok(apk_path)
+/// Signs APK with given signer using APK Signature Schemes v1, v2 and v3.
+/// Keystores are read directly, so passwords never reach a command line.
+pub fn sign_apk(apk_path: &Path, signer: &AndroidSigner) -> Result<PathBuf> {
+    signer.sign_apk(apk_path)?;
+    Ok(apk_path.to_path_buf())
 }
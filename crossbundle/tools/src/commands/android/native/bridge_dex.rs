@@ -0,0 +1,86 @@
+use crate::{error::*, types::AndroidSdk};
+use crossbow_android::embed::{CROSSBOW_BRIDGE_DEX, CROSSBOW_BRIDGE_DEX_STAMP};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// Returns the prebuilt `classes.dex` of the Crossbow Kotlin bridge embedded into
+/// crossbundle, after checking it against its stamp.
+pub fn crossbow_bridge_dex() -> Result<&'static [u8]> {
+    validate_dex(CROSSBOW_BRIDGE_DEX, CROSSBOW_BRIDGE_DEX_STAMP)
+}
+
+fn validate_dex<'a>(dex: &'a [u8], stamp: &str) -> Result<&'a [u8]> {
+    if !dex.starts_with(b"dex\n") {
+        return Err(AndroidError::CrossbowBridgeDexMissing.into());
+    }
+    let digest: String = Sha256::digest(dex)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    if !stamp
+        .lines()
+        .any(|line| line.strip_prefix("dex ") == Some(digest.as_str()))
+    {
+        return Err(AndroidError::CrossbowBridgeDexStale.into());
+    }
+    Ok(dex)
+}
+
+/// Adds the Crossbow bridge `classes.dex` into APK.
+/// Uses `aapt` tool.
+pub fn add_bridge_dex_into_apk(sdk: &AndroidSdk, apk_path: &Path) -> Result<()> {
+    let dex = crossbow_bridge_dex()?;
+    let apk_dir = apk_path
+        .parent()
+        .ok_or_else(|| Error::PathNotFound(apk_path.to_owned()))?;
+    std::fs::write(apk_dir.join("classes.dex"), dex)?;
+    // `aapt a[dd] [-v] file.{zip,jar,apk} file1 [file2 ...]`
+    // Add specified files to Zip-compatible archive
+    let mut aapt = sdk.build_tool(bin!("aapt"), Some(apk_dir))?;
+    aapt.arg("add").arg(apk_path).arg("classes.dex");
+    aapt.output_err(true)?;
+    Ok(())
+}
+
+/// Writes the Crossbow bridge `classes.dex` into `dex/` of the files extracted from the
+/// `aapt2` proto APK, before they are zipped into the base module.
+pub fn add_bridge_dex_into_aab_module(extracted_apk_files: &Path) -> Result<PathBuf> {
+    write_module_dex(extracted_apk_files, crossbow_bridge_dex()?)
+}
+
+fn write_module_dex(extracted_apk_files: &Path, dex: &[u8]) -> Result<PathBuf> {
+    let dex_dir = extracted_apk_files.join("dex");
+    std::fs::create_dir_all(&dex_dir)?;
+    let dex_path = dex_dir.join("classes.dex");
+    std::fs::write(&dex_path, dex)?;
+    Ok(dex_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_placeholder_and_stale_dex() {
+        let stamp =
+            "inputs 00\ndex 2c54a4c51192601674623d247e827ff8fcee7212eda7fed6bab5a4e959ace58e\n";
+        assert!(matches!(
+            validate_dex(b"", stamp),
+            Err(Error::Android(AndroidError::CrossbowBridgeDexMissing))
+        ));
+        assert!(matches!(
+            validate_dex(b"dex\n036\0", stamp),
+            Err(Error::Android(AndroidError::CrossbowBridgeDexStale))
+        ));
+        assert!(validate_dex(b"dex\n035\0", stamp).is_ok());
+    }
+
+    #[test]
+    fn bundle_module_dex_is_placed_under_dex_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let dex_path = write_module_dex(dir.path(), b"dex\n035\0").unwrap();
+
+        assert_eq!(dex_path, dir.path().join("dex").join("classes.dex"));
+        assert_eq!(std::fs::read(dex_path).unwrap(), b"dex\n035\0");
+    }
+}
@@ -0,0 +1,146 @@
+use super::library_name;
+use crate::{
+    commands::zip_write,
+    error::*,
+    types::{AndroidNdk, AndroidTarget},
+};
+use std::path::{Path, PathBuf};
+
+/// Directory inside `BUNDLE-METADATA` of an AAB that Google Play reads native debug
+/// symbols from.
+pub const DEBUG_SYMBOLS_METADATA_DIR: &str = "com.android.tools.build.debugsymbols";
+
+/// Copies a native library to `output`.
+///
+/// With `debug_symbols` the packaged copy is stripped with the NDK `strip` tool, while the
+/// unstripped library is kept in `debug_symbols/<abi>/` for symbolication of crashes.
+pub fn copy_native_lib(
+    ndk: &AndroidNdk,
+    build_target: AndroidTarget,
+    lib_path: &Path,
+    output: &Path,
+    debug_symbols: Option<&Path>,
+) -> Result<()> {
+    let Some(debug_symbols) = debug_symbols else {
+        std::fs::copy(lib_path, output)?;
+        return Ok(());
+    };
+    let symbols_dir = debug_symbols.join(build_target.android_abi());
+    std::fs::create_dir_all(&symbols_dir)?;
+    std::fs::copy(lib_path, symbols_dir.join(library_name(lib_path)?))?;
+    let mut strip = std::process::Command::new(ndk.toolchain_bin("strip", build_target)?);
+    strip
+        .arg("--strip-unneeded")
+        .arg("-o")
+        .arg(output)
+        .arg(lib_path);
+    strip.output_err(true)?;
+    Ok(())
+}
+
+/// Lists unstripped libraries kept by [`copy_native_lib`] as `(abi, library)` pairs.
+pub fn native_debug_symbols(debug_symbols: &Path) -> Result<Vec<(String, PathBuf)>> {
+    let mut libs = Vec::new();
+    if !debug_symbols.exists() {
+        return Ok(libs);
+    }
+    for abi_dir in std::fs::read_dir(debug_symbols)? {
+        let abi_dir = abi_dir?.path();
+        let Some(abi) = abi_dir.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        for lib in std::fs::read_dir(&abi_dir)? {
+            let lib = lib?.path();
+            if lib.extension().is_some_and(|extension| extension == "so") {
+                libs.push((abi.to_owned(), lib));
+            }
+        }
+    }
+    libs.sort();
+    Ok(libs)
+}
+
+/// Generates `native-debug-symbols.zip` with `<abi>/<library>.so` entries that can be
+/// uploaded to Play Console together with an APK.
+pub fn gen_native_debug_symbols_zip(debug_symbols: &Path, output: &Path) -> Result<PathBuf> {
+    zip_write(debug_symbols, output)?;
+    Ok(output.to_owned())
+}
+
+/// Returns `bundletool build-bundle --metadata-file` entries that embed unstripped
+/// libraries as `BUNDLE-METADATA/com.android.tools.build.debugsymbols/<abi>/<library>.dbg`.
+pub fn debug_symbols_metadata(debug_symbols: &Path) -> Result<Vec<(String, PathBuf)>> {
+    native_debug_symbols(debug_symbols)?
+        .into_iter()
+        .map(|(abi, lib)| {
+            let name = format!(
+                "{DEBUG_SYMBOLS_METADATA_DIR}/{abi}/{}.dbg",
+                library_name(&lib)?
+            );
+            Ok((name, lib))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_debug_symbols_per_abi() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let debug_symbols = temp_dir.path().join("native-debug-symbols");
+        for (abi, lib) in [
+            ("x86_64", "libgame.so"),
+            ("arm64-v8a", "libgame.so"),
+            ("arm64-v8a", "libc++_shared.so"),
+        ] {
+            std::fs::create_dir_all(debug_symbols.join(abi)).unwrap();
+            std::fs::write(debug_symbols.join(abi).join(lib), lib).unwrap();
+        }
+
+        assert_eq!(
+            debug_symbols_metadata(&debug_symbols).unwrap(),
+            [
+                (
+                    "com.android.tools.build.debugsymbols/arm64-v8a/libc++_shared.so.dbg"
+                        .to_owned(),
+                    debug_symbols.join("arm64-v8a/libc++_shared.so")
+                ),
+                (
+                    "com.android.tools.build.debugsymbols/arm64-v8a/libgame.so.dbg".to_owned(),
+                    debug_symbols.join("arm64-v8a/libgame.so")
+                ),
+                (
+                    "com.android.tools.build.debugsymbols/x86_64/libgame.so.dbg".to_owned(),
+                    debug_symbols.join("x86_64/libgame.so")
+                ),
+            ]
+        );
+
+        let zip_path = gen_native_debug_symbols_zip(
+            &debug_symbols,
+            &temp_dir.path().join("native-debug-symbols.zip"),
+        )
+        .unwrap();
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(zip_path).unwrap()).unwrap();
+        let mut names: Vec<_> = archive.file_names().map(ToOwned::to_owned).collect();
+        names.sort();
+        assert!(names.contains(&"arm64-v8a/libgame.so".to_owned()));
+        assert!(names.contains(&"x86_64/libgame.so".to_owned()));
+        let mut lib = String::new();
+        std::io::Read::read_to_string(&mut archive.by_name("x86_64/libgame.so").unwrap(), &mut lib)
+            .unwrap();
+        assert_eq!(lib, "libgame.so");
+    }
+
+    #[test]
+    fn missing_debug_symbols_are_empty() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        assert!(
+            native_debug_symbols(&temp_dir.path().join("missing"))
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
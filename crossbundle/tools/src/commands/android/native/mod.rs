@@ -1,8 +1,12 @@
 pub mod aab;
 pub mod apk;
+pub mod bridge_dex;
+pub mod debug_symbols;
 
 pub use aab::*;
 pub use apk::*;
+pub use bridge_dex::*;
+pub use debug_symbols::*;
 
 pub(crate) fn library_name(path: &std::path::Path) -> crate::error::Result<&str> {
     path.file_name()
@@ -1,4 +1,8 @@
-use crate::error::*;
+use crate::{
+    commands::sign_artifact_externally,
+    error::*,
+    types::{ExternalSigner, SignedArtifactKind},
+};
 use std::{path::Path, process::Command};
 
 /// Signs app. Runs `codesign ...` command.
@@ -32,3 +36,73 @@ pub fn codesign(
     cmd.output_err(false)?;
     Ok(())
 }
+
+/// Signs app bundle in place with an [`ExternalSigner`] instead of `codesign`. The
+/// program gets the unsigned bundle and must write the signed bundle, including its
+/// `_CodeSignature` directory.
+pub fn codesign_externally(app_path: &Path, signer: &ExternalSigner) -> Result<()> {
+    if !app_path.exists() {
+        return Err(Error::PathNotFound(app_path.to_owned()));
+    }
+    let signed = app_path.with_extension("signed.app");
+    sign_artifact_externally(signer, SignedArtifactKind::App, app_path, &signed)?;
+    if !signed.join("_CodeSignature").join("CodeResources").exists() {
+        return Err(Error::ExternalSignerFailed(format!(
+            "`{}` returned an app bundle without a code signature",
+            signer.command[0]
+        )));
+    }
+    std::fs::remove_dir_all(app_path)?;
+    std::fs::rename(&signed, app_path)?;
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::{commands::signer_script, types::SignerMode};
+
+    fn signer(dir: &Path, body: &str) -> ExternalSigner {
+        ExternalSigner {
+            command: vec![
+                signer_script(dir, body),
+                "{input}".to_owned(),
+                "{output}".to_owned(),
+            ],
+            mode: SignerMode::Artifact,
+            certificate: None,
+        }
+    }
+
+    #[test]
+    fn replaces_app_with_externally_signed_bundle() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let app_path = temp_dir.path().join("Game.app");
+        std::fs::create_dir(&app_path).unwrap();
+        std::fs::write(app_path.join("Game"), "binary").unwrap();
+
+        let unsigned = signer(temp_dir.path(), "cp -R \"$1\" \"$2\"");
+        assert!(matches!(
+            codesign_externally(&app_path, &unsigned),
+            Err(Error::ExternalSignerFailed(_))
+        ));
+        assert!(app_path.join("Game").exists());
+
+        let signed = signer(
+            temp_dir.path(),
+            "cp -R \"$1\" \"$2\" && mkdir \"$2/_CodeSignature\" && touch \"$2/_CodeSignature/CodeResources\"",
+        );
+        codesign_externally(&app_path, &signed).unwrap();
+        assert!(
+            app_path
+                .join("_CodeSignature")
+                .join("CodeResources")
+                .exists()
+        );
+        assert_eq!(
+            std::fs::read_to_string(app_path.join("Game")).unwrap(),
+            "binary"
+        );
+        assert!(!temp_dir.path().join("Game.signed.app").exists());
+    }
+}
@@ -0,0 +1,252 @@
+use crate::{commands::render_flat_icon, error::*, types::IconConfig};
+use apple_bundle::prelude::{BundleIcons, BundlePrimaryIcon, InfoPlist};
+use image::{DynamicImage, ImageFormat};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+/// One image of an iOS app icon.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AppIconImage {
+    pub idiom: &'static str,
+    /// Size in points.
+    pub size: f32,
+    pub scale: u32,
+}
+
+/// Every icon an iOS app for iPhone and iPad needs, including the App Store icon.
+pub const IOS_APP_ICONS: &[AppIconImage] = &[
+    icon("iphone", 20.0, 2),
+    icon("iphone", 20.0, 3),
+    icon("iphone", 29.0, 2),
+    icon("iphone", 29.0, 3),
+    icon("iphone", 40.0, 2),
+    icon("iphone", 40.0, 3),
+    icon("iphone", 60.0, 2),
+    icon("iphone", 60.0, 3),
+    icon("ipad", 20.0, 1),
+    icon("ipad", 20.0, 2),
+    icon("ipad", 29.0, 1),
+    icon("ipad", 29.0, 2),
+    icon("ipad", 40.0, 1),
+    icon("ipad", 40.0, 2),
+    icon("ipad", 76.0, 1),
+    icon("ipad", 76.0, 2),
+    icon("ipad", 83.5, 2),
+    icon("ios-marketing", 1024.0, 1),
+];
+
+const fn icon(idiom: &'static str, size: f32, scale: u32) -> AppIconImage {
+    AppIconImage { idiom, size, scale }
+}
+
+impl AppIconImage {
+    pub fn pixels(&self) -> u32 {
+        (self.size * self.scale as f32) as u32
+    }
+
+    /// Points as written in icon names and `Contents.json`, like `60x60` or `83.5x83.5`.
+    pub fn points(&self) -> String {
+        format!("{0}x{0}", self.size)
+    }
+
+    /// Name without scale and device modifiers, as listed in `CFBundleIconFiles`.
+    pub fn base_name(&self) -> String {
+        format!("AppIcon{}", self.points())
+    }
+
+    /// File name that iOS finds from the base name, like `AppIcon76x76@2x~ipad.png`.
+    pub fn file_name(&self) -> String {
+        let scale = match self.scale {
+            1 => String::new(),
+            scale => format!("@{scale}x"),
+        };
+        let device = match self.idiom {
+            "ipad" => "~ipad",
+            _ => "",
+        };
+        format!("{}{scale}{device}.png", self.base_name())
+    }
+
+    /// Icons that are bundled as loose files. The App Store icon is uploaded separately.
+    fn is_bundled(&self) -> bool {
+        self.idiom != "ios-marketing"
+    }
+}
+
+/// Writes the loose icon files referenced by [`update_info_plist_with_app_icons`] into
+/// `out_dir`, usually the resources copied into the `.app` folder.
+pub fn gen_ios_app_icons(icon: &IconConfig, out_dir: &Path) -> Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(out_dir)?;
+    let mut paths = Vec::new();
+    for image in IOS_APP_ICONS.iter().filter(|image| image.is_bundled()) {
+        let path = out_dir.join(image.file_name());
+        if !paths.contains(&path) {
+            write_icon(icon, image.pixels(), &path)?;
+            paths.push(path);
+        }
+    }
+    Ok(paths)
+}
+
+#[derive(Serialize)]
+struct Contents {
+    images: Vec<ContentsImage>,
+    info: ContentsInfo,
+}
+
+#[derive(Serialize)]
+struct ContentsImage {
+    filename: String,
+    idiom: &'static str,
+    scale: String,
+    size: String,
+}
+
+#[derive(Serialize)]
+struct ContentsInfo {
+    author: &'static str,
+    version: u32,
+}
+
+/// Writes `AppIcon.appiconset` with every image and its `Contents.json` into `out_dir`, to
+/// be added to the asset catalog of an Xcode project.
+pub fn gen_app_icon_set(icon: &IconConfig, out_dir: &Path) -> Result<PathBuf> {
+    let set = out_dir.join("AppIcon.appiconset");
+    std::fs::remove_dir_all(&set).ok();
+    std::fs::create_dir_all(&set)?;
+    let mut written = BTreeMap::new();
+    let mut images = Vec::new();
+    for image in IOS_APP_ICONS {
+        let pixels = image.pixels();
+        let filename = written
+            .entry(pixels)
+            .or_insert_with(|| format!("icon-{pixels}.png"))
+            .clone();
+        let path = set.join(&filename);
+        if !path.exists() {
+            write_icon(icon, pixels, &path)?;
+        }
+        images.push(ContentsImage {
+            filename,
+            idiom: image.idiom,
+            scale: format!("{}x", image.scale),
+            size: image.points(),
+        });
+    }
+    let contents = Contents {
+        images,
+        info: ContentsInfo {
+            author: "crossbundle",
+            version: 1,
+        },
+    };
+    let json = serde_json::to_string_pretty(&contents).map_err(anyhow::Error::from)?;
+    std::fs::write(set.join("Contents.json"), json)?;
+    Ok(set)
+}
+
+/// Lists the icons written by [`gen_ios_app_icons`] in `CFBundleIcons` and
+/// `CFBundleIconFiles`, unless the Info.plist already configures its icons.
+pub fn update_info_plist_with_app_icons(info_plist: &mut InfoPlist) {
+    let mut names = Vec::new();
+    for image in IOS_APP_ICONS.iter().filter(|image| image.is_bundled()) {
+        let name = image.base_name();
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    let icons = &mut info_plist.icons;
+    if icons.bundle_icons.is_none() {
+        icons.bundle_icons = Some(BundleIcons {
+            bundle_primary_icon: BundlePrimaryIcon {
+                bundle_icon_files: names.clone(),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+    }
+    if icons.bundle_icon_files.is_none() {
+        icons.bundle_icon_files = Some(names);
+    }
+}
+
+/// Renders the icon at the size of the image, so SVG sources are rasterised per size.
+fn write_icon(icon: &IconConfig, pixels: u32, path: &Path) -> Result<()> {
+    // App icons must be opaque, so drop the alpha channel of the rendered icon.
+    let image = DynamicImage::from(render_flat_icon(icon, pixels)?).to_rgb8();
+    let mut output = File::create(path)?;
+    image.write_to(&mut output, ImageFormat::Png)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GenericImageView, Rgba, RgbaImage};
+
+    fn icon(dir: &Path) -> IconConfig {
+        let path = dir.join("icon.png");
+        RgbaImage::from_pixel(256, 256, Rgba([255, 0, 0, 255]))
+            .save(&path)
+            .unwrap();
+        IconConfig::Path(path)
+    }
+
+    #[test]
+    fn writes_loose_icons_listed_in_info_plist() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = gen_ios_app_icons(&icon(dir.path()), &dir.path().join("res")).unwrap();
+
+        let ipad = dir.path().join("res/AppIcon83.5x83.5@2x~ipad.png");
+        assert!(paths.contains(&ipad));
+        assert_eq!(image::open(&ipad).unwrap().dimensions(), (167, 167));
+        assert_eq!(
+            image::open(dir.path().join("res/AppIcon60x60@3x.png"))
+                .unwrap()
+                .dimensions(),
+            (180, 180)
+        );
+        assert!(!dir.path().join("res/AppIcon1024x1024.png").exists());
+
+        let mut info_plist = InfoPlist::default();
+        update_info_plist_with_app_icons(&mut info_plist);
+        let files = info_plist.icons.bundle_icon_files.unwrap();
+        assert!(files.contains(&"AppIcon60x60".to_owned()));
+        assert!(files.contains(&"AppIcon83.5x83.5".to_owned()));
+        assert_eq!(
+            info_plist
+                .icons
+                .bundle_icons
+                .unwrap()
+                .bundle_primary_icon
+                .bundle_icon_files,
+            files
+        );
+    }
+
+    #[test]
+    fn writes_app_icon_set_for_xcode() {
+        let dir = tempfile::tempdir().unwrap();
+        let set = gen_app_icon_set(&icon(dir.path()), dir.path()).unwrap();
+
+        let contents: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(set.join("Contents.json")).unwrap()).unwrap();
+        let images = contents["images"].as_array().unwrap();
+        assert_eq!(images.len(), IOS_APP_ICONS.len());
+        let marketing = images
+            .iter()
+            .find(|image| image["idiom"] == "ios-marketing")
+            .unwrap();
+        assert_eq!(marketing["size"], "1024x1024");
+        assert_eq!(marketing["scale"], "1x");
+        let file = set.join(marketing["filename"].as_str().unwrap());
+        assert_eq!(image::open(file).unwrap().dimensions(), (1024, 1024));
+        for image in images {
+            assert!(set.join(image["filename"].as_str().unwrap()).exists());
+        }
+    }
+}
@@ -1,18 +1,18 @@
-use crate::commands::{ExistingFile, copy_directory_contents};
+use crate::commands::{ExistingFile, copy_directory_contents, zip_write};
 use crate::error::*;
 use std::fs::{create_dir_all, remove_dir_all};
 use std::io;
 use std::path::{Path, PathBuf};
-use std::process::Command;
 
-/// Generates an apple ipa.
+/// Generates an apple ipa. The archive is reproducible, see [`zip_write`].
 pub fn gen_apple_ipa(target_dir: &Path, app_dir: &Path, project_name: &str) -> Result<PathBuf> {
     if !target_dir.exists() {
         create_dir_all(target_dir)?;
     }
-    // Create Payload folder
-    let payload_path = target_dir.join("Payload");
-    remove_dir_all(&payload_path).ok();
+    // Create Payload folder in a directory that contains nothing else to archive
+    let staging_path = target_dir.join("ipa");
+    remove_dir_all(&staging_path).ok();
+    let payload_path = staging_path.join("Payload");
     create_dir_all(&payload_path)?;
     let app_name = app_dir.file_name().ok_or_else(|| {
         io::Error::new(
@@ -26,18 +26,41 @@ pub fn gen_apple_ipa(target_dir: &Path, app_dir: &Path, project_name: &str) -> R
         ExistingFile::Overwrite,
     )?;
     // Generate result ipa path
-    let ipa_file = format!("{}.ipa", project_name);
-    let ipa_path = target_dir.join(&ipa_file);
+    let ipa_path = target_dir.join(format!("{}.ipa", project_name));
     // Archive Payload into ipa file
-    let mut cmd = Command::new("zip");
-    cmd.current_dir(target_dir);
-    cmd.arg("-Xvr");
-    cmd.arg(ipa_file);
-    cmd.arg("Payload");
-    let output = cmd.output()?;
-    if !output.status.success() {
-        return Err(AppleError::ZipCommandFailed.into());
-    }
-    remove_dir_all(&payload_path).ok();
+    zip_write(&staging_path, &ipa_path)?;
+    remove_dir_all(&staging_path).ok();
     Ok(ipa_path)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generates_reproducible_ipa() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let app_dir = temp_dir.path().join("Game.app");
+        create_dir_all(&app_dir).unwrap();
+        std::fs::write(app_dir.join("Info.plist"), "plist").unwrap();
+        std::fs::write(app_dir.join("Game"), "binary").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(app_dir.join("Game"), PermissionsExt::from_mode(0o775))
+                .unwrap();
+        }
+
+        let first = temp_dir.path().join("first");
+        let second = temp_dir.path().join("second");
+        let first_ipa = std::fs::read(gen_apple_ipa(&first, &app_dir, "Game").unwrap()).unwrap();
+        let second_ipa = std::fs::read(gen_apple_ipa(&second, &app_dir, "Game").unwrap()).unwrap();
+        assert_eq!(first_ipa, second_ipa);
+        assert!(!first.join("ipa").exists());
+
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(first_ipa)).unwrap();
+        let binary = archive.by_name("Payload/Game.app/Game").unwrap();
+        let expected = if cfg!(unix) { 0o755 } else { 0o644 };
+        assert_eq!(binary.unix_mode().map(|mode| mode & 0o777), Some(expected));
+    }
+}
@@ -0,0 +1,181 @@
+use crate::{
+    commands::render_centered_image,
+    error::*,
+    types::{IconBackground, SplashConfig},
+};
+use apple_bundle::prelude::{InfoPlist, LaunchScreen};
+use image::DynamicImage;
+use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
+
+/// Asset catalog color of the launch screen background.
+pub const LAUNCH_BACKGROUND: &str = "LaunchBackground";
+/// Image centered on the launch screen.
+pub const LAUNCH_IMAGE: &str = "LaunchImage";
+/// Size of the launch image in points.
+const LAUNCH_IMAGE_POINTS: u32 = 192;
+
+/// Writes `LaunchImage.png` at every scale into `out_dir`, usually the resources copied
+/// into the `.app` folder. Loose files have no dark variant.
+pub fn gen_launch_screen_images(splash: &SplashConfig, out_dir: &Path) -> Result<Vec<PathBuf>> {
+    let Some(image) = &splash.image else {
+        return Ok(Vec::new());
+    };
+    std::fs::create_dir_all(out_dir)?;
+    let mut paths = Vec::new();
+    for scale in 1..=3 {
+        let path = out_dir.join(image_file_name(scale, false));
+        write_image(image, scale, &path)?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+/// Writes `LaunchBackground.colorset` and `LaunchImage.imageset` with their dark
+/// appearances into `out_dir`, to be added to the asset catalog of an Xcode project.
+pub fn gen_launch_screen_assets(splash: &SplashConfig, out_dir: &Path) -> Result<()> {
+    let colorset = out_dir.join(format!("{LAUNCH_BACKGROUND}.colorset"));
+    std::fs::create_dir_all(&colorset)?;
+    let mut colors = vec![json!({ "idiom": "universal", "color": color(&splash.background)? })];
+    if splash.dark.is_some() {
+        colors.push(json!({
+            "idiom": "universal",
+            "appearances": [dark_appearance()],
+            "color": color(splash.dark_background())?,
+        }));
+    }
+    write_contents(&colorset, json!({ "colors": colors }))?;
+
+    let imageset = out_dir.join(format!("{LAUNCH_IMAGE}.imageset"));
+    std::fs::remove_dir_all(&imageset).ok();
+    let Some(image) = &splash.image else {
+        return Ok(());
+    };
+    std::fs::create_dir_all(&imageset)?;
+    let dark = splash.dark_image().filter(|dark| dark != image);
+    let mut images = Vec::new();
+    for scale in 1..=3 {
+        let filename = image_file_name(scale, false);
+        write_image(image, scale, &imageset.join(&filename))?;
+        images.push(
+            json!({ "idiom": "universal", "filename": filename, "scale": format!("{scale}x") }),
+        );
+        if let Some(dark) = dark {
+            let filename = image_file_name(scale, true);
+            write_image(dark, scale, &imageset.join(&filename))?;
+            images.push(json!({
+                "idiom": "universal",
+                "appearances": [dark_appearance()],
+                "filename": filename,
+                "scale": format!("{scale}x"),
+            }));
+        }
+    }
+    write_contents(&imageset, json!({ "images": images }))
+}
+
+/// Sets `UILaunchScreen` to show the splash screen, unless the Info.plist already
+/// configures it.
+pub fn update_info_plist_with_launch_screen(info_plist: &mut InfoPlist, splash: &SplashConfig) {
+    let launch = &mut info_plist.launch_interface;
+    if launch.launch_screen.is_none() {
+        launch.launch_screen = Some(LaunchScreen {
+            color_name: Some(LAUNCH_BACKGROUND.to_owned()),
+            image_name: splash.image.as_ref().map(|_| LAUNCH_IMAGE.to_owned()),
+            ..Default::default()
+        });
+    }
+}
+
+fn image_file_name(scale: u32, dark: bool) -> String {
+    let dark = if dark { "-dark" } else { "" };
+    match scale {
+        1 => format!("{LAUNCH_IMAGE}{dark}.png"),
+        scale => format!("{LAUNCH_IMAGE}{dark}@{scale}x.png"),
+    }
+}
+
+fn write_image(image: &Path, scale: u32, path: &Path) -> Result<()> {
+    let image = render_centered_image(image, LAUNCH_IMAGE_POINTS * scale)?;
+    DynamicImage::from(image).save(path)?;
+    Ok(())
+}
+
+fn color(color: &str) -> Result<Value> {
+    let [red, green, blue, alpha] = IconBackground::rgba(color)
+        .ok_or_else(|| anyhow::anyhow!("invalid splash background color `{color}`"))?;
+    Ok(json!({
+        "color-space": "srgb",
+        "components": {
+            "red": format!("0x{red:02X}"),
+            "green": format!("0x{green:02X}"),
+            "blue": format!("0x{blue:02X}"),
+            "alpha": format!("{:.3}", alpha as f32 / 255.0),
+        }
+    }))
+}
+
+fn dark_appearance() -> Value {
+    json!({ "appearance": "luminosity", "value": "dark" })
+}
+
+fn write_contents(dir: &Path, mut contents: Value) -> Result<()> {
+    contents["info"] = json!({ "author": "crossbundle", "version": 1 });
+    let json = serde_json::to_string_pretty(&contents).map_err(anyhow::Error::from)?;
+    std::fs::write(dir.join("Contents.json"), json)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GenericImageView, Rgba, RgbaImage};
+
+    #[test]
+    fn writes_launch_screen_with_dark_appearance() {
+        let dir = tempfile::tempdir().unwrap();
+        let logo = dir.path().join("logo.png");
+        RgbaImage::from_pixel(16, 16, Rgba([255, 0, 0, 255]))
+            .save(&logo)
+            .unwrap();
+        let splash: SplashConfig = serde_json::from_value(serde_json::json!({
+            "background": "#1B1B1F",
+            "image": logo,
+            "dark": { "background": "#80000000" }
+        }))
+        .unwrap();
+
+        let paths = gen_launch_screen_images(&splash, &dir.path().join("res")).unwrap();
+        assert_eq!(paths.len(), 3);
+        assert_eq!(
+            image::open(dir.path().join("res/LaunchImage@3x.png"))
+                .unwrap()
+                .dimensions(),
+            (576, 576)
+        );
+
+        gen_launch_screen_assets(&splash, dir.path()).unwrap();
+        let colors: Value = serde_json::from_slice(
+            &std::fs::read(dir.path().join("LaunchBackground.colorset/Contents.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(colors["colors"][0]["color"]["components"]["red"], "0x1B");
+        assert_eq!(colors["colors"][1]["appearances"][0]["value"], "dark");
+        assert_eq!(colors["colors"][1]["color"]["components"]["alpha"], "0.502");
+        let images: Value = serde_json::from_slice(
+            &std::fs::read(dir.path().join("LaunchImage.imageset/Contents.json")).unwrap(),
+        )
+        .unwrap();
+        // The dark splash screen reuses the light image.
+        assert_eq!(images["images"].as_array().unwrap().len(), 3);
+
+        let mut info_plist = InfoPlist::default();
+        update_info_plist_with_launch_screen(&mut info_plist, &splash);
+        let launch_screen = info_plist.launch_interface.launch_screen.unwrap();
+        assert_eq!(
+            launch_screen.color_name.as_deref(),
+            Some("LaunchBackground")
+        );
+        assert_eq!(launch_screen.image_name.as_deref(), Some("LaunchImage"));
+    }
+}
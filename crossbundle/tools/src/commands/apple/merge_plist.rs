@@ -0,0 +1,344 @@
+use super::{
+    read_plist_value_with_variables, update_info_plist_with_app_icons,
+    update_info_plist_with_launch_screen,
+};
+use crate::{
+    error::Result,
+    types::{BuildVariables, ProjectConfig, update_info_plist_with_default},
+};
+use apple_bundle::{
+    plist::{self, Dictionary, Value},
+    prelude::InfoPlist,
+};
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt, path::Path};
+
+/// One input of [`merge_info_plists`].
+pub struct InfoPlistSource {
+    name: String,
+    dictionary: Dictionary,
+    defaults: bool,
+}
+
+impl InfoPlistSource {
+    /// Reads an XML or binary Info.plist file, expanding build variables.
+    pub fn from_path(
+        name: impl Into<String>,
+        path: &Path,
+        variables: &BuildVariables,
+    ) -> Result<Self> {
+        let value = read_plist_value_with_variables(path, variables)?;
+        Ok(Self {
+            name: name.into(),
+            dictionary: value.into_dictionary().unwrap_or_default(),
+            defaults: false,
+        })
+    }
+
+    /// Typed Info.plist, e.g. inline metadata or a plugin fragment.
+    pub fn from_info_plist(name: impl Into<String>, info_plist: &InfoPlist) -> Result<Self> {
+        let mut dictionary = plist::to_value(info_plist)?
+            .into_dictionary()
+            .unwrap_or_default();
+        // The typed model always serializes the identifier, even when it wasn't set.
+        if dictionary
+            .get("CFBundleIdentifier")
+            .and_then(Value::as_string)
+            .is_some_and(str::is_empty)
+        {
+            dictionary.remove("CFBundleIdentifier");
+        }
+        Ok(Self {
+            name: name.into(),
+            dictionary,
+            defaults: false,
+        })
+    }
+
+    /// Generated values, like permission usage descriptions, that any other source may
+    /// change without a conflict.
+    pub fn defaults(name: impl Into<String>, info_plist: &InfoPlist) -> Result<Self> {
+        Ok(Self {
+            defaults: true,
+            ..Self::from_info_plist(name, info_plist)?
+        })
+    }
+}
+
+/// Key set to different values by two sources. The value of the higher-priority source
+/// is used.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InfoPlistConflict {
+    /// Key path, e.g. `NSAppTransportSecurity/NSAllowsArbitraryLoads`.
+    pub key: String,
+    /// Source whose value is used.
+    pub kept: String,
+    /// Source whose value is ignored.
+    pub ignored: String,
+}
+
+impl fmt::Display for InfoPlistConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Info.plist key `{}` of {} conflicts with {}; the value of {} is used",
+            self.key, self.ignored, self.kept, self.kept
+        )
+    }
+}
+
+/// Info.plist merged from every source of the project.
+#[derive(Debug, Clone)]
+pub struct MergedInfoPlist {
+    pub info_plist: InfoPlist,
+    pub conflicts: Vec<InfoPlistConflict>,
+}
+
+/// Merges Info.plist sources given from the highest to the lowest priority.
+///
+/// Dictionaries are merged key by key and arrays are combined without duplicates. A key
+/// with different values in two sources is taken from the higher-priority one and
+/// reported as a conflict, unless the lower-priority source only provides
+/// [defaults](InfoPlistSource::defaults).
+pub fn merge_info_plists(sources: &[InfoPlistSource]) -> (Dictionary, Vec<InfoPlistConflict>) {
+    let mut merged = Dictionary::new();
+    let mut origins = BTreeMap::new();
+    let mut conflicts = Vec::new();
+    for (index, source) in sources.iter().enumerate() {
+        merge_dictionary(
+            &mut merged,
+            &source.dictionary,
+            "",
+            index,
+            sources,
+            &mut origins,
+            &mut conflicts,
+        );
+    }
+    (merged, conflicts)
+}
+
+/// Merges the Info.plist sources of the project and completes the result with defaults.
+///
+/// Sources from the highest to the lowest priority: inline `apple.info_plist`, the
+/// `info_plist_path` file, fragments of discovered Crossbow plugins and the usage
+/// descriptions of `permissions`. Icons generated from `icon` and the launch screen of
+/// `splash` are added unless a source configures them.
+pub fn merge_project_info_plist(
+    metadata: &ProjectConfig,
+    package_name: &str,
+    configured_path: Option<&Path>,
+) -> Result<MergedInfoPlist> {
+    let mut sources = Vec::new();
+    if let Some(info_plist) = &metadata.apple.info_plist {
+        sources.push(InfoPlistSource::from_info_plist(
+            "apple.info_plist",
+            info_plist,
+        )?);
+    }
+    if let Some(path) = configured_path {
+        sources.push(InfoPlistSource::from_path(
+            path.display().to_string(),
+            path,
+            metadata.build_variables(),
+        )?);
+    }
+    for plugin in &metadata.apple.discovered_plugins {
+        if let Some(info_plist) = &plugin.metadata.info_plist {
+            sources.push(InfoPlistSource::from_info_plist(
+                format!("plugin {}", plugin.package),
+                info_plist,
+            )?);
+        }
+    }
+    if !metadata.permissions.is_empty() {
+        let mut descriptions = InfoPlist::default();
+        for permission in &metadata.permissions {
+            permission.update_info_plist(&mut descriptions);
+        }
+        sources.push(InfoPlistSource::defaults("permissions", &descriptions)?);
+    }
+    let (mut dictionary, conflicts) = merge_info_plists(&sources);
+    if !dictionary.contains_key("CFBundleIdentifier") {
+        dictionary.insert(
+            "CFBundleIdentifier".to_owned(),
+            Value::String(String::new()),
+        );
+    }
+    let mut info_plist: InfoPlist = plist::from_value(&Value::Dictionary(dictionary))?;
+    if let Some(splash) = &metadata.splash {
+        update_info_plist_with_launch_screen(&mut info_plist, splash);
+    }
+    update_info_plist_with_default(&mut info_plist, package_name, metadata.app_name.clone());
+    if metadata.icon.is_some() {
+        update_info_plist_with_app_icons(&mut info_plist);
+    }
+    Ok(MergedInfoPlist {
+        info_plist,
+        conflicts,
+    })
+}
+
+fn merge_dictionary(
+    higher: &mut Dictionary,
+    lower: &Dictionary,
+    parent: &str,
+    source: usize,
+    sources: &[InfoPlistSource],
+    origins: &mut BTreeMap<String, usize>,
+    conflicts: &mut Vec<InfoPlistConflict>,
+) {
+    for (key, value) in lower {
+        let path = if parent.is_empty() {
+            key.clone()
+        } else {
+            format!("{parent}/{key}")
+        };
+        let Some(existing) = higher.get_mut(key) else {
+            higher.insert(key.clone(), value.clone());
+            origins.insert(path, source);
+            continue;
+        };
+        match (existing, value) {
+            (Value::Dictionary(existing), Value::Dictionary(value)) => {
+                merge_dictionary(existing, value, &path, source, sources, origins, conflicts)
+            }
+            (Value::Array(existing), Value::Array(values)) => {
+                for value in values {
+                    if !existing.contains(value) {
+                        existing.push(value.clone());
+                    }
+                }
+            }
+            (existing, value) if *existing == *value => {}
+            _ if sources[source].defaults => {}
+            _ => {
+                let kept = origin(origins, &path);
+                conflicts.push(InfoPlistConflict {
+                    key: path,
+                    kept: sources[kept].name.clone(),
+                    ignored: sources[source].name.clone(),
+                });
+            }
+        }
+    }
+}
+
+/// Source that set the value at `path` or its closest parent.
+fn origin(origins: &BTreeMap<String, usize>, path: &str) -> usize {
+    let mut path = path;
+    loop {
+        if let Some(source) = origins.get(path) {
+            return *source;
+        }
+        match path.rsplit_once('/') {
+            Some((parent, _)) => path = parent,
+            None => return 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossbow::Permission;
+
+    fn source(name: &str, value: serde_json::Value) -> InfoPlistSource {
+        InfoPlistSource::from_info_plist(name, &crate::types::info_plist_from_json(value).unwrap())
+            .unwrap()
+    }
+
+    #[test]
+    fn merges_sources_by_priority_and_reports_conflicts() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Info.plist");
+        Value::Dictionary(Dictionary::from_iter([
+            (
+                "CFBundleIdentifier".to_owned(),
+                Value::String("com.example.game".into()),
+            ),
+            ("CFBundleName".to_owned(), Value::String("From file".into())),
+            (
+                "UIBackgroundModes".to_owned(),
+                Value::Array(vec![Value::String("audio".into())]),
+            ),
+        ]))
+        .to_file_xml(&path)
+        .unwrap();
+        let mut metadata = crate::types::parse_project_config(serde_json::json!({
+            "apple": { "info_plist": { "CFBundleName": "Inline" } },
+            "permissions": ["camera"]
+        }))
+        .unwrap()
+        .resolve_with(|_| Ok(None))
+        .unwrap();
+        assert_eq!(metadata.permissions, [Permission::Camera]);
+        metadata
+            .apple
+            .discovered_plugins
+            .push(crate::types::ApplePlugin {
+                package: "camera".into(),
+                version: "0.1.0".into(),
+                metadata: crate::types::ApplePluginMetadata {
+                    info_plist: Some(
+                        crate::types::info_plist_from_json(serde_json::json!({
+                            "NSCameraUsageDescription": "Scan codes",
+                            "UIBackgroundModes": ["fetch"]
+                        }))
+                        .unwrap(),
+                    ),
+                },
+            });
+
+        let merged = merge_project_info_plist(&metadata, "game", Some(&path)).unwrap();
+
+        let info_plist = merged.info_plist;
+        assert_eq!(
+            info_plist.identification.bundle_identifier,
+            "com.example.game"
+        );
+        assert_eq!(info_plist.naming.bundle_name.as_deref(), Some("Inline"));
+        assert_eq!(
+            info_plist
+                .camera_and_microphone
+                .camera_usage_description
+                .as_deref(),
+            Some("Scan codes")
+        );
+        assert_eq!(info_plist.launch.bundle_executable.as_deref(), Some("game"));
+        let path = path.display().to_string();
+        assert_eq!(
+            merged.conflicts,
+            [InfoPlistConflict {
+                key: "CFBundleName".into(),
+                kept: "apple.info_plist".into(),
+                ignored: path,
+            }]
+        );
+    }
+
+    #[test]
+    fn reports_conflicting_nested_keys_of_plugins() {
+        let (merged, conflicts) = merge_info_plists(&[
+            source(
+                "plugin ads",
+                serde_json::json!({ "NSAppTransportSecurity": { "NSAllowsArbitraryLoads": true } }),
+            ),
+            source(
+                "plugin web",
+                serde_json::json!({ "NSAppTransportSecurity": { "NSAllowsArbitraryLoads": false } }),
+            ),
+        ]);
+        assert_eq!(
+            merged
+                .get("NSAppTransportSecurity")
+                .and_then(Value::as_dictionary)
+                .and_then(|security| security.get("NSAllowsArbitraryLoads")),
+            Some(&Value::Boolean(true))
+        );
+        assert_eq!(
+            conflicts[0].to_string(),
+            "Info.plist key `NSAppTransportSecurity/NSAllowsArbitraryLoads` of plugin web conflicts with plugin ads; the value of plugin ads is used"
+        );
+    }
+}
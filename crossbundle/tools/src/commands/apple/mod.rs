@@ -1,9 +1,12 @@
 mod codesign;
 mod copy_profile;
 mod gen_app_folder;
+mod gen_app_icon;
 mod gen_ipa;
+mod gen_launch_screen;
 mod gen_xcent;
 mod launch_app;
+mod merge_plist;
 mod read_plist;
 mod run_on_device;
 mod rust_compile;
@@ -12,9 +15,12 @@ mod save_plist;
 pub use codesign::*;
 pub use copy_profile::*;
 pub use gen_app_folder::*;
+pub use gen_app_icon::*;
 pub use gen_ipa::*;
+pub use gen_launch_screen::*;
 pub use gen_xcent::*;
 pub use launch_app::*;
+pub use merge_plist::*;
 pub use read_plist::*;
 pub use run_on_device::*;
 pub use rust_compile::*;
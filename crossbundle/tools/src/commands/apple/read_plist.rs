@@ -1,9 +1,7 @@
+use super::merge_project_info_plist;
 use crate::{
     error::{AppleError, Result},
-    types::{
-        BuildVariables, ProjectConfig, exact_variable, interpolate_string,
-        update_info_plist_with_default,
-    },
+    types::{BuildVariables, ProjectConfig, exact_variable, interpolate_string},
 };
 use apple_bundle::{plist, prelude::InfoPlist};
 use std::path::Path;
@@ -17,12 +15,20 @@ pub fn read_info_plist(path: &Path) -> Result<InfoPlist> {
 /// Reads an XML or binary Info.plist after recursively expanding declared Crossbow build
 /// variables.
 fn read_info_plist_with_variables(path: &Path, variables: &BuildVariables) -> Result<InfoPlist> {
+    let value = read_plist_value_with_variables(path, variables)?;
+    Ok(plist::from_value(&value)?)
+}
+
+pub(crate) fn read_plist_value_with_variables(
+    path: &Path,
+    variables: &BuildVariables,
+) -> Result<plist::Value> {
     if !path.exists() {
         return Err(AppleError::FailedToFindInfoPlist(path.to_string_lossy().to_string()).into());
     }
     let mut value = plist::Value::from_file(path)?;
     interpolate_plist(&mut value, variables)?;
-    Ok(plist::from_value(&value)?)
+    Ok(value)
 }
 
 fn interpolate_plist(value: &mut plist::Value, variables: &BuildVariables) -> Result<()> {
@@ -49,21 +55,14 @@ fn interpolate_plist(value: &mut plist::Value, variables: &BuildVariables) -> Re
     Ok(())
 }
 
-/// Resolves the same typed Info.plist used by Apple builds without writing it.
+/// Resolves the same typed Info.plist used by Apple builds without writing it. See
+/// [`merge_project_info_plist`] for the sources and their conflicts.
 pub fn resolve_info_plist(
     metadata: &ProjectConfig,
     package_name: &str,
     configured_path: Option<&Path>,
 ) -> Result<InfoPlist> {
-    if let Some(path) = configured_path {
-        return read_info_plist_with_variables(path, metadata.build_variables());
-    }
-    let mut plist = metadata.apple.info_plist.clone().unwrap_or_default();
-    update_info_plist_with_default(&mut plist, package_name, metadata.app_name.clone());
-    for permission in &metadata.permissions {
-        permission.update_info_plist(&mut plist);
-    }
-    Ok(plist)
+    Ok(merge_project_info_plist(metadata, package_name, configured_path)?.info_plist)
 }
 
 #[cfg(test)]
@@ -14,7 +14,7 @@ pub struct CargoBuild<'a> {
     pub target: &'a CargoTargetSelection,
     pub target_triple: &'a str,
     pub target_dir: &'a std::path::Path,
-    pub profile: Profile,
+    pub profile: &'a Profile,
     pub features: &'a [String],
     pub all_features: bool,
     pub no_default_features: bool,
@@ -52,9 +52,24 @@ struct CargoDiagnostic {
 
 impl CargoBuild<'_> {
     pub fn command(&self) -> Command {
+        self.cargo_command("build")
+    }
+
+    /// `cargo rustc` invocation that passes `rustc_args` to the compiler of the selected
+    /// target only. The arguments are added to the flags from `RUSTFLAGS` and Cargo
+    /// configuration instead of replacing them.
+    pub fn rustc_command(&self, rustc_args: &[String]) -> Command {
+        let mut command = self.cargo_command("rustc");
+        if !rustc_args.is_empty() {
+            command.arg("--").args(rustc_args);
+        }
+        command
+    }
+
+    fn cargo_command(&self, subcommand: &str) -> Command {
         let mut command = Command::new("cargo");
         command
-            .arg("build")
+            .arg(subcommand)
             .arg("--manifest-path")
             .arg(&self.package.manifest_path)
             .arg("--package")
@@ -83,7 +98,26 @@ impl CargoBuild<'_> {
     }
 
     pub fn run(self, configure: impl FnOnce(&mut Command)) -> Result<CargoArtifact> {
-        let mut command = self.command();
+        let command = self.command();
+        self.run_command(command, configure)
+    }
+
+    /// Runs [`CargoBuild::rustc_command`]. `configure` must not add arguments, they would
+    /// be passed to `rustc`.
+    pub fn run_rustc(
+        self,
+        rustc_args: &[String],
+        configure: impl FnOnce(&mut Command),
+    ) -> Result<CargoArtifact> {
+        let command = self.rustc_command(rustc_args);
+        self.run_command(command, configure)
+    }
+
+    fn run_command(
+        self,
+        mut command: Command,
+        configure: impl FnOnce(&mut Command),
+    ) -> Result<CargoArtifact> {
         configure(&mut command);
         command.stdout(Stdio::piped()).stderr(Stdio::inherit());
 
@@ -195,7 +229,7 @@ mod tests {
             target: &target,
             target_triple: "aarch64-apple-ios",
             target_dir: std::path::Path::new("/tmp/output"),
-            profile: Profile::Release,
+            profile: &Profile::Release,
             features: &["mobile".into(), "bevy/png".into()],
             all_features: false,
             no_default_features: true,
@@ -285,20 +285,61 @@ impl CargoProject {
         .into())
     }
 
-    /// Find one named package in the selected package's resolved dependency closure.
-    pub fn dependency(&self, name: &str) -> Result<&CargoPackage> {
+    /// Crossbow plugins declared with `package.metadata.crossbow-plugin.android` in the
+    /// resolved dependency closure of the selected package, ordered by package name.
+    #[cfg(feature = "android")]
+    pub fn android_plugins(&self) -> Result<Vec<crate::types::AndroidPlugin>> {
+        let mut plugins = Vec::new();
+        for package in self.dependency_closure() {
+            if let Some(plugin) = crate::types::AndroidPlugin::from_package_metadata(
+                &package.name,
+                &package.version,
+                &package.manifest_path,
+                &package.metadata,
+            )? {
+                plugins.push(plugin);
+            }
+        }
+        plugins.sort_by(|left, right| {
+            (&left.package, &left.version).cmp(&(&right.package, &right.version))
+        });
+        Ok(plugins)
+    }
+
+    /// Crossbow plugins declared with `package.metadata.crossbow-plugin.apple` in the
+    /// resolved dependency closure of the selected package, ordered by package name.
+    #[cfg(feature = "apple")]
+    pub fn apple_plugins(&self) -> Result<Vec<crate::types::ApplePlugin>> {
+        let mut plugins = Vec::new();
+        for package in self.dependency_closure() {
+            if let Some(plugin) = crate::types::ApplePlugin::from_package_metadata(
+                &package.name,
+                &package.version,
+                &package.metadata,
+            )? {
+                plugins.push(plugin);
+            }
+        }
+        plugins.sort_by(|left, right| {
+            (&left.package, &left.version).cmp(&(&right.package, &right.version))
+        });
+        Ok(plugins)
+    }
+
+    /// Packages in the selected package's resolved dependency closure, without the
+    /// selected package itself.
+    fn dependency_closure(&self) -> Vec<&CargoPackage> {
         let mut pending = vec![self.package.id.as_str()];
         let mut visited = HashSet::new();
-        let mut matches = Vec::new();
+        let mut packages = Vec::new();
         while let Some(id) = pending.pop() {
             if !visited.insert(id) {
                 continue;
             }
             if id != self.package.id
                 && let Some(package) = self.packages.get(id)
-                && package.name == name
             {
-                matches.push(package);
+                packages.push(package);
             }
             pending.extend(
                 self.dependencies
@@ -308,6 +349,16 @@ impl CargoProject {
                     .map(String::as_str),
             );
         }
+        packages
+    }
+
+    /// Find one named package in the selected package's resolved dependency closure.
+    pub fn dependency(&self, name: &str) -> Result<&CargoPackage> {
+        let mut matches = self
+            .dependency_closure()
+            .into_iter()
+            .filter(|package| package.name == name)
+            .collect::<Vec<_>>();
         matches.sort_by(|left, right| left.version.cmp(&right.version));
         match matches.as_slice() {
             [package] => Ok(package),
@@ -458,6 +509,57 @@ mod tests {
         assert_eq!(project.dependency("miniquad").unwrap().version, "1.2.3");
     }
 
+    #[cfg(feature = "android")]
+    #[test]
+    fn discovers_android_plugins_in_dependency_closure() {
+        let root = tempfile::tempdir().unwrap();
+        for package in ["app", "glue", "billing"] {
+            std::fs::create_dir_all(root.path().join(package).join("src")).unwrap();
+            std::fs::write(root.path().join(package).join("src/lib.rs"), "").unwrap();
+        }
+        std::fs::create_dir_all(root.path().join("billing/android")).unwrap();
+        std::fs::write(
+            root.path().join("Cargo.toml"),
+            "[workspace]\nresolver = \"3\"\nmembers = [\"app\", \"glue\", \"billing\"]\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.path().join("app/Cargo.toml"),
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2024\"\n\
+             [target.'cfg(target_os = \"android\")'.dependencies]\nglue = { path = \"../glue\" }\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.path().join("glue/Cargo.toml"),
+            "[package]\nname = \"glue\"\nversion = \"0.1.0\"\nedition = \"2024\"\n\
+             [dependencies]\nbilling = { path = \"../billing\" }\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.path().join("billing/Cargo.toml"),
+            "[package]\nname = \"billing\"\nversion = \"0.2.3\"\nedition = \"2024\"\n\
+             [package.metadata.crossbow-plugin.android]\n\
+             permissions = [\"com.android.vending.BILLING\"]\n\
+             [[package.metadata.crossbow-plugin.android.local_projects]]\n\
+             include = \":billing\"\nproject_dir = \"android\"\n",
+        )
+        .unwrap();
+
+        let plugins = CargoProject::load(&root.path().join("app/Cargo.toml"))
+            .unwrap()
+            .android_plugins()
+            .unwrap();
+        assert_eq!(plugins.len(), 1);
+        assert_eq!(plugins[0].package, "billing");
+        assert!(plugins[0].has_local_projects());
+        assert!(plugins[0].has_binaries());
+        assert_eq!(
+            dunce::canonicalize(plugins[0].metadata.local_projects[0].project_dir().unwrap())
+                .unwrap(),
+            dunce::canonicalize(root.path().join("billing/android")).unwrap()
+        );
+    }
+
     #[test]
     fn rejects_ambiguous_dependency_versions() {
         let root = tempfile::tempdir().unwrap();
@@ -0,0 +1,81 @@
+use crate::error::{Error, Result};
+use der::Encode;
+use serde::Serialize;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use x509_cert::Certificate;
+
+/// Summary of a signer certificate, as printed by `keytool -printcert`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CertificateInfo {
+    pub subject: String,
+    pub issuer: String,
+    pub serial_number: String,
+    /// Start of the validity period in RFC 3339 format.
+    pub not_before: String,
+    /// End of the validity period in RFC 3339 format.
+    pub not_after: String,
+    pub sha1: String,
+    pub sha256: String,
+}
+
+impl CertificateInfo {
+    pub fn new(certificate: &Certificate) -> Result<Self> {
+        let der = certificate
+            .to_der()
+            .map_err(|error| Error::InvalidCertificate(error.to_string()))?;
+        let tbs = &certificate.tbs_certificate;
+        Ok(Self {
+            subject: tbs.subject.to_string(),
+            issuer: tbs.issuer.to_string(),
+            serial_number: hex(tbs.serial_number.as_bytes(), ""),
+            not_before: tbs.validity.not_before.to_date_time().to_string(),
+            not_after: tbs.validity.not_after.to_date_time().to_string(),
+            sha1: fingerprint::<Sha1>(&der),
+            sha256: fingerprint::<Sha256>(&der),
+        })
+    }
+}
+
+/// Returns a colon-separated, uppercase hex digest of `data`, e.g. `AB:CD:...`.
+pub fn fingerprint<D: Digest>(data: &[u8]) -> String {
+    hex(&D::digest(data), ":")
+}
+
+fn hex(bytes: &[u8], separator: &str) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "android")]
+    #[test]
+    fn describes_certificate() {
+        let key = crate::commands::android::pkcs12_key();
+        let info = CertificateInfo::new(key.certificate()).unwrap();
+        assert_eq!(info.subject, "CN=Crossbow Test,O=Crossbow,C=US");
+        assert_eq!(info.issuer, info.subject);
+        assert_eq!(info.sha1.len(), 20 * 3 - 1);
+        assert_eq!(info.sha256.len(), 32 * 3 - 1);
+        assert_eq!(
+            info.sha256,
+            fingerprint::<Sha256>(&key.certificate_der().unwrap())
+        );
+        assert!(info.not_before < info.not_after);
+        assert!(info.not_after.ends_with('Z'));
+    }
+
+    #[test]
+    fn formats_fingerprints() {
+        assert_eq!(
+            fingerprint::<Sha1>(b"abc"),
+            "A9:99:3E:36:47:06:81:6A:BA:3E:25:71:78:50:C2:6C:9C:D0:D8:9D"
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use crate::{
+    error::{CommandExt, Error, Result},
+    types::{ExternalSigner, SignedArtifactKind, SignerMode},
+};
+use std::{
+    io::Write,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+/// Runs an [`ExternalSigner`] in `artifact` mode. The program reads the unsigned artifact
+/// at `input` and must write the signed one to `output`.
+///
+/// Besides the `{input}`, `{output}` and `{kind}` arguments, the program gets the
+/// `CROSSBUNDLE_SIGNER_INPUT`, `CROSSBUNDLE_SIGNER_OUTPUT` and `CROSSBUNDLE_SIGNER_KIND`
+/// environment variables.
+pub fn sign_artifact_externally(
+    signer: &ExternalSigner,
+    kind: SignedArtifactKind,
+    input: &Path,
+    output: &Path,
+) -> Result<()> {
+    if signer.mode != SignerMode::Artifact {
+        return Err(Error::InvalidSignerConfig(format!(
+            "{kind} signing requires the `artifact` mode"
+        )));
+    }
+    if output.is_dir() {
+        std::fs::remove_dir_all(output)?;
+    } else if output.exists() {
+        std::fs::remove_file(output)?;
+    }
+    let mut command = signer_command(signer, kind, Some((input, output)))?;
+    command
+        .env("CROSSBUNDLE_SIGNER_INPUT", input)
+        .env("CROSSBUNDLE_SIGNER_OUTPUT", output);
+    command.output_err(true)?;
+    if !output.exists() {
+        return Err(Error::ExternalSignerFailed(format!(
+            "`{}` didn't write the signed {kind} to {output:?}",
+            signer.command[0]
+        )));
+    }
+    Ok(())
+}
+
+/// Runs an [`ExternalSigner`] in `digest` mode. The SHA-256 `digest` is written to the
+/// program's stdin and the signature is read from its stdout.
+pub fn external_signature(
+    signer: &ExternalSigner,
+    kind: SignedArtifactKind,
+    digest: &[u8],
+) -> Result<Vec<u8>> {
+    let program = signer.command.first().cloned().unwrap_or_default();
+    let mut command = signer_command(signer, kind, None)?;
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(|error| {
+            Error::ExternalSignerFailed(format!("failed to run `{program}`: {error}"))
+        })?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(digest)?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(Error::ExternalSignerFailed(format!(
+            "`{program}` exited with {}",
+            output.status
+        )));
+    }
+    if output.stdout.is_empty() {
+        return Err(Error::ExternalSignerFailed(format!(
+            "`{program}` returned an empty signature"
+        )));
+    }
+    Ok(output.stdout)
+}
+
+fn signer_command(
+    signer: &ExternalSigner,
+    kind: SignedArtifactKind,
+    paths: Option<(&Path, &Path)>,
+) -> Result<Command> {
+    let (program, args) = signer
+        .command
+        .split_first()
+        .ok_or_else(|| Error::InvalidSignerConfig("`command` can't be empty".to_owned()))?;
+    let mut command = Command::new(program);
+    for arg in args {
+        let mut arg = arg.replace("{kind}", kind.as_str());
+        if let Some((input, output)) = paths {
+            arg = arg
+                .replace("{input}", &input.to_string_lossy())
+                .replace("{output}", &output.to_string_lossy());
+        }
+        command.arg(arg);
+    }
+    command.env("CROSSBUNDLE_SIGNER_KIND", kind.as_str());
+    Ok(command)
+}
+
+#[cfg(all(test, unix))]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Writes an executable `sign.sh` with given body to stand in for a signing tool.
+    /// Returns the path of the script.
+    pub(crate) fn signer_script(dir: &Path, body: &str) -> String {
+        use std::os::unix::fs::PermissionsExt;
+        let path = dir.join("sign.sh");
+        std::fs::write(&path, format!("#!/bin/sh\n{body}\n")).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    fn signer(command: Vec<String>, mode: SignerMode) -> ExternalSigner {
+        ExternalSigner {
+            command,
+            mode,
+            certificate: None,
+        }
+    }
+
+    #[test]
+    fn runs_artifact_signers() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let input = temp_dir.path().join("app.aab");
+        let output = temp_dir.path().join("app.signed.aab");
+        std::fs::write(&input, "unsigned").unwrap();
+        let program = signer_script(
+            temp_dir.path(),
+            r#"[ "$1" = "$CROSSBUNDLE_SIGNER_KIND" ] && [ "$2" = "$CROSSBUNDLE_SIGNER_INPUT" ] || exit 3
+cat "$2" > "$3" && printf ' signed' >> "$3""#,
+        );
+        let command = vec![
+            program.clone(),
+            "{kind}".into(),
+            "{input}".into(),
+            "{output}".into(),
+        ];
+        sign_artifact_externally(
+            &signer(command.clone(), SignerMode::Artifact),
+            SignedArtifactKind::Aab,
+            &input,
+            &output,
+        )
+        .unwrap();
+        assert_eq!(std::fs::read_to_string(&output).unwrap(), "unsigned signed");
+
+        assert!(matches!(
+            sign_artifact_externally(
+                &signer(command, SignerMode::Digest),
+                SignedArtifactKind::Aab,
+                &input,
+                &output,
+            ),
+            Err(Error::InvalidSignerConfig(_))
+        ));
+        assert!(matches!(
+            sign_artifact_externally(
+                &signer(vec![program, "apk".into()], SignerMode::Artifact),
+                SignedArtifactKind::Aab,
+                &input,
+                &output,
+            ),
+            Err(Error::CmdFailed(..))
+        ));
+        assert!(matches!(
+            sign_artifact_externally(
+                &signer(vec!["true".into()], SignerMode::Artifact),
+                SignedArtifactKind::Aab,
+                &input,
+                &output,
+            ),
+            Err(Error::ExternalSignerFailed(_))
+        ));
+    }
+
+    #[test]
+    fn exchanges_digests_and_signatures() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let received = temp_dir.path().join("digest");
+        let program = signer_script(
+            temp_dir.path(),
+            &format!("cat > {:?}\nprintf \"signature-$1\"", received),
+        );
+        let signature = external_signature(
+            &signer(vec![program, "{kind}".into()], SignerMode::Digest),
+            SignedArtifactKind::Apk,
+            &[1, 2, 3],
+        )
+        .unwrap();
+        assert_eq!(signature, b"signature-apk");
+        assert_eq!(std::fs::read(&received).unwrap(), [1, 2, 3]);
+
+        for command in [vec!["false".to_owned()], vec!["true".to_owned()], vec![]] {
+            assert!(
+                external_signature(
+                    &signer(command, SignerMode::Digest),
+                    SignedArtifactKind::Apk,
+                    &[1, 2, 3],
+                )
+                .is_err()
+            );
+        }
+    }
+}
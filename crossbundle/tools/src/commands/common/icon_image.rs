@@ -0,0 +1,183 @@
+use crate::{
+    error::*,
+    types::{IconBackground, IconConfig},
+};
+use image::{DynamicImage, GenericImageView, Rgba, RgbaImage, imageops};
+use resvg::{tiny_skia, usvg};
+use std::path::Path;
+
+/// Opens an icon image. Width and height of the image must be equal.
+pub fn open_square_image(path: &Path) -> Result<DynamicImage> {
+    let image = image::open(path)?;
+    let (width, height) = image.dimensions();
+    if width != height {
+        return Err(Error::WidthAndHeightDifSizes);
+    }
+    Ok(image)
+}
+
+/// Source image of an icon or icon layer.
+///
+/// Files with the `.svg` extension are vector images, rasterised separately for every
+/// output size. Any other file is a raster image that must be square and is scaled.
+pub enum IconImage {
+    Raster(DynamicImage),
+    Svg(Box<usvg::Tree>),
+}
+
+impl IconImage {
+    pub fn open(path: &Path) -> Result<Self> {
+        if !is_svg(path) {
+            return Ok(Self::Raster(open_square_image(path)?));
+        }
+        let options = usvg::Options {
+            resources_dir: path.parent().map(Path::to_owned),
+            ..Default::default()
+        };
+        let tree = usvg::Tree::from_data(&std::fs::read(path)?, &options)
+            .map_err(|error| Error::InvalidSvgIcon(path.to_owned(), error.to_string()))?;
+        Ok(Self::Svg(Box::new(tree)))
+    }
+
+    /// Renders the image as a square of `size` pixels. Vector images that aren't square
+    /// are scaled to fit and centered.
+    pub fn render(&self, size: u32) -> RgbaImage {
+        match self {
+            Self::Raster(image) => image
+                .resize_exact(size, size, imageops::FilterType::Lanczos3)
+                .to_rgba8(),
+            Self::Svg(tree) => {
+                let mut pixmap = tiny_skia::Pixmap::new(size, size)
+                    .expect("icon size must be greater than zero");
+                let tree_size = tree.size();
+                let scale = size as f32 / tree_size.width().max(tree_size.height());
+                let transform = tiny_skia::Transform::from_scale(scale, scale).post_translate(
+                    (size as f32 - tree_size.width() * scale) / 2.0,
+                    (size as f32 - tree_size.height() * scale) / 2.0,
+                );
+                resvg::render(tree, transform, &mut pixmap.as_mut());
+                RgbaImage::from_fn(size, size, |x, y| {
+                    let color = pixmap.pixel(x, y).unwrap().demultiply();
+                    Rgba([color.red(), color.green(), color.blue(), color.alpha()])
+                })
+            }
+        }
+    }
+}
+
+/// Renders an image of any aspect ratio scaled to fit and centered in a transparent square
+/// of `size` pixels, like the image of a splash screen.
+pub fn render_centered_image(path: &Path, size: u32) -> Result<RgbaImage> {
+    if is_svg(path) {
+        return Ok(IconImage::open(path)?.render(size));
+    }
+    let image = image::open(path)?
+        .resize(size, size, imageops::FilterType::Lanczos3)
+        .to_rgba8();
+    let mut canvas = RgbaImage::new(size, size);
+    let x = (size - image.width()) / 2;
+    let y = (size - image.height()) / 2;
+    imageops::overlay(&mut canvas, &image, x.into(), y.into());
+    Ok(canvas)
+}
+
+fn is_svg(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("svg"))
+}
+
+/// Renders the icon as an opaque square image of `size` pixels, for platforms that mask
+/// icons themselves.
+///
+/// Layered icons are composed from the centered 72x72 dp of their 108x108 dp layers,
+/// the area Android launchers always show. Transparent pixels of single images are drawn
+/// over white.
+pub fn render_flat_icon(icon: &IconConfig, size: u32) -> Result<RgbaImage> {
+    let mut canvas = RgbaImage::from_pixel(size, size, Rgba([255, 255, 255, 255]));
+    match icon {
+        IconConfig::Path(path) => {
+            imageops::overlay(&mut canvas, &IconImage::open(path)?.render(size), 0, 0)
+        }
+        IconConfig::Layers(layers) => {
+            let layer_size = size * 108 / 72;
+            let background = match &layers.background {
+                IconBackground::Color(color) => {
+                    let rgba = IconBackground::rgba(color).ok_or_else(|| {
+                        anyhow::anyhow!("invalid icon background color `{color}`")
+                    })?;
+                    RgbaImage::from_pixel(size, size, Rgba(rgba))
+                }
+                IconBackground::Image(path) => viewport(&IconImage::open(path)?.render(layer_size)),
+            };
+            imageops::overlay(&mut canvas, &background, 0, 0);
+            let foreground = IconImage::open(&layers.foreground)?.render(layer_size);
+            imageops::overlay(&mut canvas, &viewport(&foreground), 0, 0);
+        }
+    }
+    Ok(canvas)
+}
+
+/// Centered 72x72 dp of a 108x108 dp layer.
+fn viewport(layer: &RgbaImage) -> RgbaImage {
+    let size = layer.width() * 72 / 108;
+    let offset = (layer.width() - size) / 2;
+    imageops::crop_imm(layer, offset, offset, size, size).to_image()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::IconLayers;
+
+    #[test]
+    fn renders_opaque_icons_from_layers() {
+        let dir = tempfile::tempdir().unwrap();
+        let foreground = dir.path().join("foreground.png");
+        // Transparent layer with a centered opaque square of 36 dp.
+        let mut layer = RgbaImage::new(108, 108);
+        for x in 36..72 {
+            for y in 36..72 {
+                layer.put_pixel(x, y, Rgba([0, 0, 255, 255]));
+            }
+        }
+        layer.save(&foreground).unwrap();
+        let icon = IconConfig::Layers(IconLayers {
+            foreground,
+            background: IconBackground::Color("#00FF00".into()),
+            monochrome: None,
+        });
+
+        let image = render_flat_icon(&icon, 72).unwrap();
+
+        assert_eq!(image.dimensions(), (72, 72));
+        assert_eq!(*image.get_pixel(0, 0), Rgba([0, 255, 0, 255]));
+        assert_eq!(*image.get_pixel(36, 36), Rgba([0, 0, 255, 255]));
+    }
+
+    #[test]
+    fn rasterises_svg_icons_at_each_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("icon.SVG");
+        // Wide image: the red square is centered vertically in the output.
+        std::fs::write(
+            &path,
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="20" height="10"><rect width="20" height="10" fill="#FF0000"/></svg>"##,
+        )
+        .unwrap();
+        let image = IconImage::open(&path).unwrap();
+        assert!(matches!(image, IconImage::Svg(_)));
+
+        for size in [48, 1024] {
+            let rendered = image.render(size);
+            assert_eq!(rendered.dimensions(), (size, size));
+            assert_eq!(
+                *rendered.get_pixel(size / 2, size / 2),
+                Rgba([255, 0, 0, 255])
+            );
+            assert_eq!(rendered.get_pixel(size / 2, 0)[3], 0);
+        }
+
+        let flat = render_flat_icon(&IconConfig::Path(path), 64).unwrap();
+        assert_eq!(*flat.get_pixel(32, 0), Rgba([255, 255, 255, 255]));
+    }
+}
@@ -2,16 +2,29 @@
 
 mod cargo_build;
 mod cargo_project;
+#[cfg(any(feature = "android", feature = "apple"))]
+mod certificate;
 mod combine_folders;
 mod copy_directory;
 mod create_project;
+mod external_signer;
 mod find_cargo_manifest_path;
 mod gen_minimal_project;
+mod icon_image;
+mod write_zip;
 
 pub use cargo_build::*;
 pub use cargo_project::*;
+#[cfg(any(feature = "android", feature = "apple"))]
+pub use certificate::*;
 pub use combine_folders::*;
 pub(crate) use copy_directory::*;
 pub use create_project::*;
+pub use external_signer::*;
 pub use find_cargo_manifest_path::*;
 pub use gen_minimal_project::*;
+pub use icon_image::*;
+pub use write_zip::*;
+
+#[cfg(all(test, unix))]
+pub(crate) use external_signer::tests::signer_script;
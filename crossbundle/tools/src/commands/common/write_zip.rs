@@ -0,0 +1,264 @@
+use std::{fs::File, io, path::Path};
+use zip::{DateTime, ZipWriter, write::SimpleFileOptions};
+
+/// Returns the modification time written into generated archives: `SOURCE_DATE_EPOCH` if
+/// it's set to a valid number of seconds, otherwise the earliest time ZIP can store.
+pub fn reproducible_timestamp() -> DateTime {
+    std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.trim().parse().ok())
+        .map(timestamp_from_unix)
+        .unwrap_or_default()
+}
+
+/// Converts seconds since the Unix epoch into a ZIP timestamp, clamped to the range ZIP
+/// can store (1980-2107).
+pub fn timestamp_from_unix(seconds: i64) -> DateTime {
+    let days = seconds.div_euclid(86_400);
+    let time = seconds.rem_euclid(86_400);
+    // Civil date from days since 1970-01-01, http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    match year {
+        ..1980 => DateTime::default(),
+        2108.. => DateTime::from_date_and_time(2107, 12, 31, 23, 59, 58).unwrap_or_default(),
+        _ => DateTime::from_date_and_time(
+            year as u16,
+            month as u8,
+            day as u8,
+            (time / 3600) as u8,
+            (time / 60 % 60) as u8,
+            (time % 60) as u8,
+        )
+        .unwrap_or_default(),
+    }
+}
+
+/// Writing files into archive.
+///
+/// Archives are reproducible: entries are sorted by path, have the same modification time
+/// from [`reproducible_timestamp`], and their permissions are normalised to `0o755` for
+/// directories and executables and `0o644` for other files. Symlinks are followed like
+/// `zip -r` does, and their targets are stored under the path of the link.
+pub fn zip_write(source_path: &Path, archive_file: &Path) -> zip::result::ZipResult<()> {
+    let mut entries = Vec::new();
+    // Directories to visit with the canonical paths of their ancestors, to detect cycles.
+    let mut directories = vec![(source_path.to_path_buf(), Vec::new())];
+    while let Some((directory, mut ancestors)) = directories.pop() {
+        let canonical = std::fs::canonicalize(&directory)?;
+        if ancestors.contains(&canonical) {
+            return Err(unsupported_entry(&directory, "symlink cycle").into());
+        }
+        ancestors.push(canonical);
+        for entry in std::fs::read_dir(directory)? {
+            let entry = entry?;
+            let path = entry.path();
+            let mut file_type = entry.file_type()?;
+            if file_type.is_symlink() {
+                file_type = std::fs::metadata(&path)
+                    .map_err(|_| unsupported_entry(&path, "dangling symlink"))?
+                    .file_type();
+            }
+            if file_type.is_dir() {
+                directories.push((path.clone(), ancestors.clone()));
+            } else if !file_type.is_file() {
+                return Err(unsupported_entry(&path, "unsupported archive source entry").into());
+            }
+            let relative_path = path
+                .strip_prefix(source_path)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?
+                .to_owned();
+            entries.push((relative_path, path, file_type.is_dir()));
+        }
+    }
+    entries.sort();
+
+    let options = SimpleFileOptions::default().last_modified_time(reproducible_timestamp());
+    let mut zip = ZipWriter::new(File::create(archive_file)?);
+    for (relative_path, path, is_dir) in entries {
+        if is_dir {
+            zip.add_directory_from_path(relative_path, options.unix_permissions(0o755))?;
+        } else {
+            let permissions = if is_executable(&path)? { 0o755 } else { 0o644 };
+            zip.start_file_from_path(relative_path, options.unix_permissions(permissions))?;
+            let mut source = File::open(path)?;
+            io::copy(&mut source, &mut zip)?;
+        }
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+fn unsupported_entry(path: &Path, reason: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("{reason}: {}", path.display()),
+    )
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> io::Result<bool> {
+    use std::os::unix::fs::PermissionsExt;
+    Ok(std::fs::metadata(path)?.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(_path: &Path) -> io::Result<bool> {
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn zip_write_archives_directory_contents() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let source_dir = temp_dir.path().join("module");
+        let manifest_dir = source_dir.join("manifest");
+        let library_dir = source_dir.join("lib").join("arm64-v8a");
+        let empty_dir = source_dir.join("assets").join("empty");
+        std::fs::create_dir_all(&manifest_dir).unwrap();
+        std::fs::create_dir_all(&library_dir).unwrap();
+        std::fs::create_dir_all(&empty_dir).unwrap();
+        std::fs::write(
+            manifest_dir.join("AndroidManifest.xml"),
+            b"<manifest package=\"com.crossbow.test\" />",
+        )
+        .unwrap();
+        std::fs::write(library_dir.join("libcrossbow.so"), b"native-library").unwrap();
+
+        let archive_path = temp_dir.path().join("module.zip");
+        zip_write(&source_dir, &archive_path).unwrap();
+
+        let archive_file = std::fs::File::open(archive_path).unwrap();
+        let mut archive = zip::ZipArchive::new(archive_file).unwrap();
+
+        let mut manifest = String::new();
+        archive
+            .by_name("manifest/AndroidManifest.xml")
+            .unwrap()
+            .read_to_string(&mut manifest)
+            .unwrap();
+        assert_eq!(manifest, "<manifest package=\"com.crossbow.test\" />");
+
+        let mut library = Vec::new();
+        archive
+            .by_name("lib/arm64-v8a/libcrossbow.so")
+            .unwrap()
+            .read_to_end(&mut library)
+            .unwrap();
+        assert_eq!(library, b"native-library");
+
+        assert!(archive.by_name("assets/empty/").unwrap().is_dir());
+    }
+
+    #[test]
+    fn zip_write_is_reproducible() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let build = |name: &str, files: &[&str]| {
+            let source_dir = temp_dir.path().join(name);
+            for file in files {
+                let path = source_dir.join(file);
+                std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+                std::fs::write(&path, file.as_bytes()).unwrap();
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+            let archive_path = temp_dir.path().join(format!("{name}.zip"));
+            zip_write(&source_dir, &archive_path).unwrap();
+            std::fs::read(archive_path).unwrap()
+        };
+        let files = [
+            "res/b.xml",
+            "AndroidManifest.xml",
+            "res/a.xml",
+            "lib/x86/liba.so",
+        ];
+        let first = build("first", &files);
+        let reversed: Vec<_> = files.iter().rev().copied().collect();
+        let second = build("second", &reversed);
+        assert_eq!(first, second);
+
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(first)).unwrap();
+        let names: Vec<_> = archive.file_names().map(ToOwned::to_owned).collect();
+        let mut sorted = names.clone();
+        sorted.sort();
+        assert_eq!(names, sorted);
+        assert_eq!(names.len(), 7);
+        let file = archive.by_name("res/a.xml").unwrap();
+        assert_eq!(file.unix_mode().map(|mode| mode & 0o777), Some(0o644));
+        assert_eq!(file.last_modified(), Some(reproducible_timestamp()));
+    }
+
+    #[test]
+    fn converts_source_date_epoch() {
+        let timestamp = timestamp_from_unix(1_700_000_000);
+        assert_eq!(
+            (
+                timestamp.year(),
+                timestamp.month(),
+                timestamp.day(),
+                timestamp.hour(),
+                timestamp.minute(),
+                timestamp.second()
+            ),
+            (2023, 11, 14, 22, 13, 20)
+        );
+        assert_eq!(timestamp_from_unix(0), DateTime::default());
+        assert_eq!(timestamp_from_unix(i64::MAX / 2).year(), 2107);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn zip_write_follows_symlinks_and_rejects_dangling_ones() {
+        use std::os::unix::fs::symlink;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let source_dir = temp_dir.path().join("source");
+        let framework = temp_dir.path().join("Game.framework");
+        std::fs::create_dir_all(&source_dir).unwrap();
+        std::fs::create_dir_all(&framework).unwrap();
+        std::fs::write(temp_dir.path().join("target"), "target").unwrap();
+        std::fs::write(framework.join("Game"), "binary").unwrap();
+        symlink(temp_dir.path().join("target"), source_dir.join("symlink")).unwrap();
+        symlink(&framework, source_dir.join("Game.framework")).unwrap();
+        symlink(&framework, source_dir.join("Current")).unwrap();
+
+        let archive_path = temp_dir.path().join("archive.zip");
+        zip_write(&source_dir, &archive_path).unwrap();
+        let mut archive = zip::ZipArchive::new(File::open(&archive_path).unwrap()).unwrap();
+        let mut contents = String::new();
+        archive
+            .by_name("symlink")
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "target");
+        assert!(archive.by_name("Game.framework/").unwrap().is_dir());
+        assert!(archive.by_name("Game.framework/Game").is_ok());
+
+        assert!(archive.by_name("Current/Game").is_ok());
+
+        symlink(&source_dir, framework.join("Loop")).unwrap();
+        let error = zip_write(&source_dir, &archive_path).unwrap_err();
+        assert!(error.to_string().contains("symlink cycle"));
+        std::fs::remove_file(framework.join("Loop")).unwrap();
+
+        symlink(temp_dir.path().join("missing"), source_dir.join("dangling")).unwrap();
+        let error = zip_write(&source_dir, &archive_path).unwrap_err();
+        assert!(error.to_string().contains("dangling symlink"));
+    }
+}
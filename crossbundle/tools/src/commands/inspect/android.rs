@@ -0,0 +1,306 @@
+use super::{ArchiveFile, InspectReport, archive_files, read_archive_file};
+use crate::{
+    commands::{
+        CertificateInfo,
+        android::{
+            XmlElement, decode_binary_xml, decode_proto_xml, verify_apk_bytes, verify_jar_signature,
+        },
+    },
+    error::{AndroidError, Error, Result},
+};
+
+const APK_MANIFEST: &str = "AndroidManifest.xml";
+const AAB_BASE_MANIFEST: &str = "base/manifest/AndroidManifest.xml";
+
+pub(super) fn inspect_apk(data: &[u8], report: &mut InspectReport) -> Result<()> {
+    let manifest = read_archive_file(data, APK_MANIFEST)?
+        .ok_or_else(|| AndroidError::FailedToFindAndroidManifest(APK_MANIFEST.to_owned()))?;
+    read_manifest(report, &decode_binary_xml(&manifest)?);
+    add_apk_files(report, data, "")?;
+    match verify_apk_bytes(data) {
+        Ok(signatures) => {
+            report.signing.schemes = signatures.schemes.iter().map(ToString::to_string).collect();
+            report.signing.certificates = vec![CertificateInfo::new(&signatures.certificate)?];
+        }
+        Err(error) => report.signing.error = Some(verification_error(error)),
+    }
+    Ok(())
+}
+
+pub(super) fn inspect_aab(data: &[u8], report: &mut InspectReport) -> Result<()> {
+    let manifest = read_archive_file(data, AAB_BASE_MANIFEST)?
+        .ok_or_else(|| AndroidError::FailedToFindAndroidManifest(AAB_BASE_MANIFEST.to_owned()))?;
+    read_manifest(report, &decode_proto_xml(&manifest)?);
+    for file in archive_files(data)? {
+        let Some((module, path)) = file.path.split_once('/') else {
+            continue;
+        };
+        if path == "manifest/AndroidManifest.xml" && module != "base" {
+            report.modules.push(module.to_owned());
+        }
+        add_module_file(report, path, file.clone());
+    }
+    match verify_jar_signature(data) {
+        Ok(Some(signature)) => {
+            report.signing.schemes = vec!["jar".to_owned()];
+            report.signing.certificates = vec![CertificateInfo::new(&signature.certificate)?];
+        }
+        Ok(None) => report.signing.error = Some("AAB is not signed".to_owned()),
+        Err(error) => report.signing.error = Some(verification_error(error)),
+    }
+    Ok(())
+}
+
+/// Inspects an APK set produced by `bundletool build-apks`. The manifest and signature
+/// are read from the universal, base master or first standalone APK, while native
+/// libraries and assets are collected from every APK.
+pub(super) fn inspect_apks(data: &[u8], report: &mut InspectReport) -> Result<()> {
+    report.apks = archive_files(data)?
+        .into_iter()
+        .filter(|file| file.path.ends_with(".apk"))
+        .collect();
+    let main_apk = ["universal.apk", "splits/base-master.apk"]
+        .into_iter()
+        .find(|name| report.apks.iter().any(|apk| apk.path == *name))
+        .map(ToOwned::to_owned)
+        .or_else(|| {
+            let standalone = report
+                .apks
+                .iter()
+                .find(|apk| apk.path.starts_with("standalones/"));
+            standalone
+                .or(report.apks.first())
+                .map(|apk| apk.path.clone())
+        })
+        .ok_or_else(|| AndroidError::InvalidApkArchive("APK set contains no APKs".to_owned()))?;
+
+    let mut main_report = report.clone();
+    for apk in report.apks.clone() {
+        let apk_data = read_archive_file(data, &apk.path)?.unwrap_or_default();
+        if apk.path == main_apk {
+            inspect_apk(&apk_data, &mut main_report)?;
+        }
+        add_apk_files(report, &apk_data, &format!("{}!", apk.path))?;
+    }
+    report.identifier = main_report.identifier;
+    report.version_name = main_report.version_name;
+    report.version_code = main_report.version_code;
+    report.min_sdk_version = main_report.min_sdk_version;
+    report.target_sdk_version = main_report.target_sdk_version;
+    report.manifest = main_report.manifest;
+    report.signing = main_report.signing;
+    Ok(())
+}
+
+fn read_manifest(report: &mut InspectReport, manifest: &XmlElement) {
+    report.identifier = manifest.attribute(None, "package").map(ToOwned::to_owned);
+    report.version_code = manifest
+        .android_attribute("versionCode")
+        .map(ToOwned::to_owned);
+    report.version_name = manifest
+        .android_attribute("versionName")
+        .map(ToOwned::to_owned);
+    if let Some(uses_sdk) = manifest.child("uses-sdk") {
+        report.min_sdk_version = uses_sdk
+            .android_attribute("minSdkVersion")
+            .map(ToOwned::to_owned);
+        report.target_sdk_version = uses_sdk
+            .android_attribute("targetSdkVersion")
+            .map(ToOwned::to_owned);
+    }
+    report.manifest = Some(manifest.to_xml_string());
+}
+
+fn add_apk_files(report: &mut InspectReport, data: &[u8], prefix: &str) -> Result<()> {
+    for mut file in archive_files(data)? {
+        let path = file.path.clone();
+        file.path = format!("{prefix}{path}");
+        add_module_file(report, &path, file);
+    }
+    Ok(())
+}
+
+/// Sorts a file by its path relative to the APK root or the AAB module directory.
+fn add_module_file(report: &mut InspectReport, path: &str, file: ArchiveFile) {
+    if let Some(library) = path.strip_prefix("lib/") {
+        if let Some((abi, name)) = library.split_once('/')
+            && name.ends_with(".so")
+        {
+            report.abis.push(abi.to_owned());
+            report.native_libraries.push(file);
+        }
+    } else if path.starts_with("assets/") {
+        report.assets.push(file);
+    }
+}
+
+fn verification_error(error: Error) -> String {
+    match error {
+        Error::Android(AndroidError::ApkVerificationFailed(reason)) => reason,
+        error => error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::*, *};
+    use crate::commands::android::{
+        ApkSignatureScheme, DEFAULT_APK_SIGNATURE_SCHEMES, encode_binary_xml, encode_proto_xml,
+        jar_sign, pkcs12_key, sign_apk_with_key, test_manifest,
+    };
+    use std::io::Write;
+    use zip::{ZipWriter, write::SimpleFileOptions};
+
+    fn write_archive(path: &Path, files: &[(&str, &[u8])]) {
+        let mut zip = ZipWriter::new(std::fs::File::create(path).unwrap());
+        for (name, contents) in files {
+            zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(contents).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    fn apk(path: &Path) {
+        let manifest = encode_binary_xml(&test_manifest());
+        write_archive(
+            path,
+            &[
+                ("AndroidManifest.xml", &manifest),
+                ("classes.dex", b"dex"),
+                ("lib/arm64-v8a/libgame.so", &[0; 64]),
+                ("lib/x86_64/libgame.so", &[0; 32]),
+                ("assets/levels/1.json", b"{}"),
+            ],
+        );
+    }
+
+    #[test]
+    fn inspects_signed_apk() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let apk_path = temp_dir.path().join("game.apk");
+        apk(&apk_path);
+        let key = pkcs12_key();
+        sign_apk_with_key(&apk_path, &key, &DEFAULT_APK_SIGNATURE_SCHEMES).unwrap();
+
+        let report = inspect_artifact(&apk_path).unwrap();
+        assert_eq!(report.kind, ArtifactKind::Apk);
+        assert_eq!(report.identifier.as_deref(), Some("com.crossbow.test"));
+        assert_eq!(report.version_code.as_deref(), Some("7"));
+        assert_eq!(report.version_name.as_deref(), Some("1.2.0"));
+        assert_eq!(report.min_sdk_version.as_deref(), Some("23"));
+        assert_eq!(report.target_sdk_version.as_deref(), Some("36"));
+        assert!(
+            report
+                .manifest
+                .unwrap()
+                .contains("android:minSdkVersion=\"23\"")
+        );
+        assert_eq!(report.abis, vec!["arm64-v8a", "x86_64"]);
+        assert_eq!(report.native_libraries.len(), 2);
+        assert_eq!(report.native_libraries[0].size, 64);
+        assert_eq!(report.assets[0].path, "assets/levels/1.json");
+        assert_eq!(report.signing.schemes, vec!["v1", "v2", "v3"]);
+        assert_eq!(
+            report.signing.certificates,
+            vec![CertificateInfo::new(key.certificate()).unwrap()]
+        );
+        assert_eq!(report.signing.error, None);
+    }
+
+    #[test]
+    fn reports_unsigned_apk() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let apk_path = temp_dir.path().join("game.apk");
+        apk(&apk_path);
+
+        let report = inspect_artifact(&apk_path).unwrap();
+        assert!(report.signing.schemes.is_empty());
+        assert_eq!(report.signing.error.as_deref(), Some("APK is not signed"));
+
+        sign_apk_with_key(&apk_path, &pkcs12_key(), &[ApkSignatureScheme::V2]).unwrap();
+        let report = inspect_artifact(&apk_path).unwrap();
+        assert_eq!(report.signing.schemes, vec!["v2"]);
+    }
+
+    #[test]
+    fn inspects_aab_modules() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let aab_path = temp_dir.path().join("game.aab");
+        let manifest = encode_proto_xml(&test_manifest());
+        write_archive(
+            &aab_path,
+            &[
+                ("BundleConfig.pb", b""),
+                ("base/manifest/AndroidManifest.xml", &manifest),
+                ("base/lib/arm64-v8a/libgame.so", &[0; 16]),
+                ("base/assets/intro.txt", b"intro"),
+                ("levels/manifest/AndroidManifest.xml", &manifest),
+                ("levels/assets/level.bin", b"level"),
+            ],
+        );
+        let report = inspect_artifact(&aab_path).unwrap();
+        assert_eq!(report.identifier.as_deref(), Some("com.crossbow.test"));
+        assert_eq!(report.target_sdk_version.as_deref(), Some("36"));
+        assert_eq!(report.modules, vec!["levels"]);
+        assert_eq!(report.abis, vec!["arm64-v8a"]);
+        assert_eq!(
+            report.native_libraries[0].path,
+            "base/lib/arm64-v8a/libgame.so"
+        );
+        assert_eq!(report.assets.len(), 2);
+        assert_eq!(report.signing.error.as_deref(), Some("AAB is not signed"));
+
+        jar_sign(&aab_path, &pkcs12_key(), &[]).unwrap();
+        let report = inspect_artifact(&aab_path).unwrap();
+        assert_eq!(report.signing.schemes, vec!["jar"]);
+        assert_eq!(report.signing.certificates.len(), 1);
+    }
+
+    #[test]
+    fn inspects_apk_set() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let master = temp_dir.path().join("base-master.apk");
+        apk(&master);
+        sign_apk_with_key(&master, &pkcs12_key(), &DEFAULT_APK_SIGNATURE_SCHEMES).unwrap();
+        let split = temp_dir.path().join("base-x86.apk");
+        write_archive(
+            &split,
+            &[
+                ("AndroidManifest.xml", b""),
+                ("lib/x86/libgame.so", &[0; 8]),
+            ],
+        );
+        let apks_path = temp_dir.path().join("game.apks");
+        write_archive(
+            &apks_path,
+            &[
+                ("toc.pb", b""),
+                ("splits/base-master.apk", &std::fs::read(master).unwrap()),
+                ("splits/base-x86.apk", &std::fs::read(split).unwrap()),
+            ],
+        );
+
+        let report = inspect_artifact(&apks_path).unwrap();
+        assert_eq!(report.apks.len(), 2);
+        assert_eq!(report.identifier.as_deref(), Some("com.crossbow.test"));
+        assert_eq!(report.abis, vec!["arm64-v8a", "x86", "x86_64"]);
+        assert!(
+            report
+                .native_libraries
+                .iter()
+                .any(|library| library.path == "splits/base-x86.apk!lib/x86/libgame.so")
+        );
+        assert_eq!(report.signing.schemes, vec!["v1", "v2", "v3"]);
+    }
+
+    #[test]
+    fn rejects_unknown_artifacts() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("game.zip");
+        std::fs::write(&path, b"").unwrap();
+        assert!(matches!(
+            inspect_artifact(&path),
+            Err(Error::UnsupportedArtifact(_))
+        ));
+    }
+}
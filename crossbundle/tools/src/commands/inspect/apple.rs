@@ -0,0 +1,253 @@
+use super::{InspectReport, archive_files, read_archive_file};
+use crate::{
+    commands::CertificateInfo,
+    error::{AppleError, Result},
+};
+use apple_bundle::plist::{Dictionary, Value};
+use der::Decode;
+use std::io::Cursor;
+use x509_cert::Certificate;
+
+const MH_MAGIC_64: u32 = 0xfeed_facf;
+const FAT_MAGIC: u32 = 0xcafe_babe;
+
+pub(super) fn inspect_ipa(data: &[u8], report: &mut InspectReport) -> Result<()> {
+    let files = archive_files(data)?;
+    let app_dir = files
+        .iter()
+        .find_map(|file| {
+            let app_dir = file.path.strip_suffix("Info.plist")?;
+            let name = app_dir.strip_prefix("Payload/")?.strip_suffix(".app/")?;
+            (!name.contains('/')).then(|| app_dir.to_owned())
+        })
+        .ok_or_else(|| {
+            AppleError::InvalidIpaArchive("Payload/*.app/Info.plist not found".into())
+        })?;
+
+    let info_plist = read_archive_file(data, &format!("{app_dir}Info.plist"))?.unwrap_or_default();
+    let info_plist = Value::from_reader(Cursor::new(info_plist))?;
+    let dictionary = info_plist
+        .as_dictionary()
+        .ok_or_else(|| AppleError::InvalidIpaArchive("Info.plist is not a dictionary".into()))?;
+    let string = |key: &str| {
+        dictionary
+            .get(key)
+            .and_then(Value::as_string)
+            .map(ToOwned::to_owned)
+    };
+    report.identifier = string("CFBundleIdentifier");
+    report.version_name = string("CFBundleShortVersionString");
+    report.version_code = string("CFBundleVersion");
+    report.minimum_os_version = string("MinimumOSVersion");
+    let mut manifest = Vec::new();
+    info_plist.to_writer_xml(&mut manifest)?;
+    report.manifest = Some(String::from_utf8_lossy(&manifest).into_owned());
+
+    if let Some(executable) = string("CFBundleExecutable") {
+        let executable = read_archive_file(data, &format!("{app_dir}{executable}"))?;
+        report.abis = executable
+            .map(|executable| mach_o_architectures(&executable))
+            .unwrap_or_default();
+    }
+    for file in files {
+        let Some(path) = file.path.strip_prefix(app_dir.as_str()) else {
+            continue;
+        };
+        if path.starts_with("Frameworks/") {
+            report.native_libraries.push(file);
+        } else if path.starts_with("assets/") {
+            report.assets.push(file);
+        }
+    }
+
+    let code_resources = format!("{app_dir}_CodeSignature/CodeResources");
+    let signed = read_archive_file(data, &code_resources)?.is_some();
+    if signed {
+        report.signing.schemes = vec!["codesign".to_owned()];
+    } else {
+        report.signing.error = Some("IPA is not signed".to_owned());
+    }
+    if let Some(profile) = read_archive_file(data, &format!("{app_dir}embedded.mobileprovision"))? {
+        for certificate in provisioning_profile_certificates(&profile) {
+            report
+                .signing
+                .certificates
+                .push(CertificateInfo::new(&certificate)?);
+        }
+    }
+    Ok(())
+}
+
+/// Reads developer certificates from a provisioning profile. The profile is a CMS
+/// message with an XML plist inside, so the plist is located without verifying the CMS
+/// signature.
+fn provisioning_profile_certificates(profile: &[u8]) -> Vec<Certificate> {
+    let find = |needle: &[u8]| {
+        profile
+            .windows(needle.len())
+            .position(|window| window == needle)
+    };
+    let (Some(start), Some(end)) = (find(b"<?xml"), find(b"</plist>")) else {
+        return Vec::new();
+    };
+    let Some(plist) = profile.get(start..end + b"</plist>".len()) else {
+        return Vec::new();
+    };
+    let Ok(Value::Dictionary(profile)) = Value::from_reader_xml(plist) else {
+        return Vec::new();
+    };
+    developer_certificates(&profile)
+}
+
+fn developer_certificates(profile: &Dictionary) -> Vec<Certificate> {
+    profile
+        .get("DeveloperCertificates")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_data)
+        .filter_map(|certificate| Certificate::from_der(certificate).ok())
+        .collect()
+}
+
+/// Returns architectures of a thin or universal Mach-O binary.
+fn mach_o_architectures(binary: &[u8]) -> Vec<String> {
+    let read = |offset: usize, big_endian: bool| {
+        let bytes: [u8; 4] = binary.get(offset..offset + 4)?.try_into().ok()?;
+        Some(if big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    };
+    match read(0, true) {
+        Some(FAT_MAGIC) => {
+            let count = read(4, true).unwrap_or_default() as usize;
+            (0..count.min(32))
+                .filter_map(|index| read(8 + index * 20, true))
+                .map(cpu_type_name)
+                .collect()
+        }
+        _ if read(0, false) == Some(MH_MAGIC_64) => {
+            read(4, false).map(cpu_type_name).into_iter().collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn cpu_type_name(cpu_type: u32) -> String {
+    match cpu_type {
+        0x0100_000c => "arm64".to_owned(),
+        0x0100_0007 => "x86_64".to_owned(),
+        0x0000_000c => "armv7".to_owned(),
+        cpu_type => format!("{cpu_type:#x}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::*, *};
+    use std::io::Write;
+    use zip::{ZipWriter, write::SimpleFileOptions};
+
+    fn info_plist() -> Vec<u8> {
+        let mut dictionary = Dictionary::new();
+        for (key, value) in [
+            ("CFBundleIdentifier", "com.crossbow.game"),
+            ("CFBundleShortVersionString", "1.2.0"),
+            ("CFBundleVersion", "7"),
+            ("CFBundleExecutable", "Game"),
+            ("MinimumOSVersion", "15.0"),
+        ] {
+            dictionary.insert(key.to_owned(), Value::String(value.to_owned()));
+        }
+        let mut output = Vec::new();
+        Value::Dictionary(dictionary)
+            .to_writer_binary(&mut output)
+            .unwrap();
+        output
+    }
+
+    fn executable() -> Vec<u8> {
+        let mut binary = Vec::new();
+        binary.extend(FAT_MAGIC.to_be_bytes());
+        binary.extend(2u32.to_be_bytes());
+        for cpu_type in [0x0100_000cu32, 0x0100_0007] {
+            binary.extend(cpu_type.to_be_bytes());
+            binary.extend([0; 16]);
+        }
+        binary
+    }
+
+    #[test]
+    fn inspects_ipa() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let ipa_path = temp_dir.path().join("Game.ipa");
+        let mut zip = ZipWriter::new(std::fs::File::create(&ipa_path).unwrap());
+        for (name, contents) in [
+            ("Payload/Game.app/Info.plist", info_plist()),
+            ("Payload/Game.app/Game", executable()),
+            ("Payload/Game.app/assets/intro.txt", b"intro".to_vec()),
+            ("Payload/Game.app/Frameworks/libgame.dylib", vec![0; 12]),
+            ("Payload/Game.app/_CodeSignature/CodeResources", Vec::new()),
+        ] {
+            zip.start_file(name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(&contents).unwrap();
+        }
+        zip.finish().unwrap();
+
+        let report = inspect_artifact(&ipa_path).unwrap();
+        assert_eq!(report.kind, ArtifactKind::Ipa);
+        assert_eq!(report.identifier.as_deref(), Some("com.crossbow.game"));
+        assert_eq!(report.version_name.as_deref(), Some("1.2.0"));
+        assert_eq!(report.version_code.as_deref(), Some("7"));
+        assert_eq!(report.minimum_os_version.as_deref(), Some("15.0"));
+        assert!(
+            report
+                .manifest
+                .unwrap()
+                .contains("<string>com.crossbow.game</string>")
+        );
+        assert_eq!(report.abis, vec!["arm64", "x86_64"]);
+        assert_eq!(
+            report.native_libraries[0].path,
+            "Payload/Game.app/Frameworks/libgame.dylib"
+        );
+        assert_eq!(report.assets.len(), 1);
+        assert_eq!(report.signing.schemes, vec!["codesign"]);
+    }
+
+    #[cfg(feature = "android")]
+    #[test]
+    fn reads_provisioning_profile_certificates() {
+        use der::Encode;
+        let certificate = crate::commands::android::pkcs12_key()
+            .certificate_der()
+            .unwrap();
+        let mut dictionary = Dictionary::new();
+        dictionary.insert(
+            "DeveloperCertificates".to_owned(),
+            Value::Array(vec![Value::Data(certificate.clone())]),
+        );
+        let mut profile = b"\x30\x80cms".to_vec();
+        Value::Dictionary(dictionary)
+            .to_writer_xml(&mut profile)
+            .unwrap();
+        profile.extend(b"\x00\x00signature");
+
+        let certificates = provisioning_profile_certificates(&profile);
+        assert_eq!(certificates.len(), 1);
+        assert_eq!(certificates[0].to_der().unwrap(), certificate);
+    }
+
+    #[test]
+    fn rejects_ipa_without_app() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let ipa_path = temp_dir.path().join("Game.ipa");
+        let mut zip = ZipWriter::new(std::fs::File::create(&ipa_path).unwrap());
+        zip.start_file("Info.plist", SimpleFileOptions::default())
+            .unwrap();
+        zip.finish().unwrap();
+        assert!(inspect_artifact(&ipa_path).is_err());
+    }
+}
@@ -0,0 +1,181 @@
+//! Inspection of built artifacts: APKs, AABs, APK sets and IPAs.
+
+#[cfg(feature = "android")]
+mod android;
+#[cfg(feature = "apple")]
+mod apple;
+
+use crate::{
+    commands::CertificateInfo,
+    error::{Error, Result},
+};
+use serde::Serialize;
+use std::{
+    collections::BTreeSet,
+    io::Cursor,
+    path::{Path, PathBuf},
+};
+
+/// Version of the [`InspectReport`] JSON layout. Bumped on incompatible changes.
+pub const INSPECT_REPORT_SCHEMA_VERSION: u32 = 1;
+
+/// Kind of an inspected artifact, detected from its file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArtifactKind {
+    Apk,
+    Aab,
+    Apks,
+    Ipa,
+}
+
+impl ArtifactKind {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "apk" => Some(Self::Apk),
+            "aab" => Some(Self::Aab),
+            "apks" => Some(Self::Apks),
+            "ipa" => Some(Self::Ipa),
+            _ => None,
+        }
+    }
+}
+
+/// File stored inside an artifact.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ArchiveFile {
+    /// Path inside the artifact. Files of APKs nested in an APK set are written as
+    /// `splits/base-master.apk!lib/arm64-v8a/libgame.so`.
+    pub path: String,
+    pub size: u64,
+    pub compressed_size: u64,
+}
+
+/// Signatures found in an artifact.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SigningInfo {
+    /// Valid signature schemes: `v1`, `v2` and `v3` for APKs, `jar` for AABs and
+    /// `codesign` for IPAs.
+    pub schemes: Vec<String>,
+    /// Signer certificates. For IPAs these are the developer certificates of the embedded
+    /// provisioning profile.
+    pub certificates: Vec<CertificateInfo>,
+    /// Why the artifact is considered unsigned or its signature invalid.
+    pub error: Option<String>,
+}
+
+/// Contents of an artifact as reported by `crossbundle inspect`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InspectReport {
+    pub schema_version: u32,
+    pub path: PathBuf,
+    pub kind: ArtifactKind,
+    pub size: u64,
+    /// Android package name or Apple bundle identifier.
+    pub identifier: Option<String>,
+    pub version_name: Option<String>,
+    /// Android `versionCode` or Apple `CFBundleVersion`.
+    pub version_code: Option<String>,
+    pub min_sdk_version: Option<String>,
+    pub target_sdk_version: Option<String>,
+    pub minimum_os_version: Option<String>,
+    /// Decoded `AndroidManifest.xml` or `Info.plist` of the base module.
+    pub manifest: Option<String>,
+    /// Feature and asset pack modules of an AAB.
+    pub modules: Vec<String>,
+    /// APKs inside an APK set.
+    pub apks: Vec<ArchiveFile>,
+    pub abis: Vec<String>,
+    pub native_libraries: Vec<ArchiveFile>,
+    pub assets: Vec<ArchiveFile>,
+    pub signing: SigningInfo,
+}
+
+impl InspectReport {
+    fn new(path: &Path, kind: ArtifactKind, size: u64) -> Self {
+        Self {
+            schema_version: INSPECT_REPORT_SCHEMA_VERSION,
+            path: path.to_owned(),
+            kind,
+            size,
+            identifier: None,
+            version_name: None,
+            version_code: None,
+            min_sdk_version: None,
+            target_sdk_version: None,
+            minimum_os_version: None,
+            manifest: None,
+            modules: Vec::new(),
+            apks: Vec::new(),
+            abis: Vec::new(),
+            native_libraries: Vec::new(),
+            assets: Vec::new(),
+            signing: SigningInfo::default(),
+        }
+    }
+}
+
+/// Opens an artifact and decodes its manifest, native libraries, assets and signatures.
+///
+/// Signature problems don't fail the inspection, they are reported in
+/// [`SigningInfo::error`] instead.
+pub fn inspect_artifact(path: &Path) -> Result<InspectReport> {
+    if !path.exists() {
+        return Err(Error::PathNotFound(path.to_owned()));
+    }
+    let kind =
+        ArtifactKind::from_path(path).ok_or_else(|| Error::UnsupportedArtifact(path.to_owned()))?;
+    let data = std::fs::read(path)?;
+    let mut report = InspectReport::new(path, kind, data.len() as u64);
+    match kind {
+        #[cfg(feature = "android")]
+        ArtifactKind::Apk => android::inspect_apk(&data, &mut report)?,
+        #[cfg(feature = "android")]
+        ArtifactKind::Aab => android::inspect_aab(&data, &mut report)?,
+        #[cfg(feature = "android")]
+        ArtifactKind::Apks => android::inspect_apks(&data, &mut report)?,
+        #[cfg(feature = "apple")]
+        ArtifactKind::Ipa => apple::inspect_ipa(&data, &mut report)?,
+        #[allow(unreachable_patterns)]
+        _ => return Err(Error::UnsupportedArtifact(path.to_owned())),
+    }
+    report.abis = report
+        .abis
+        .drain(..)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    Ok(report)
+}
+
+fn open_archive(data: &[u8]) -> Result<zip::ZipArchive<Cursor<&[u8]>>> {
+    Ok(zip::ZipArchive::new(Cursor::new(data))?)
+}
+
+/// Lists files of an archive, skipping directories.
+fn archive_files(data: &[u8]) -> Result<Vec<ArchiveFile>> {
+    let mut archive = open_archive(data)?;
+    let mut files = Vec::with_capacity(archive.len());
+    for index in 0..archive.len() {
+        let file = archive.by_index_raw(index)?;
+        if !file.is_dir() {
+            files.push(ArchiveFile {
+                path: file.name().to_owned(),
+                size: file.size(),
+                compressed_size: file.compressed_size(),
+            });
+        }
+    }
+    Ok(files)
+}
+
+fn read_archive_file(data: &[u8], name: &str) -> Result<Option<Vec<u8>>> {
+    let mut archive = open_archive(data)?;
+    let Ok(mut file) = archive.by_name(name) else {
+        return Ok(None);
+    };
+    let mut contents = Vec::with_capacity(file.size() as usize);
+    std::io::copy(&mut file, &mut contents)?;
+    Ok(Some(contents))
+}
@@ -3,5 +3,7 @@ pub mod android;
 #[cfg(feature = "apple")]
 pub mod apple;
 mod common;
+#[cfg(any(feature = "android", feature = "apple"))]
+pub mod inspect;
 
 pub use common::*;
@@ -26,6 +26,14 @@ pub enum AndroidError {
     GradleDependencyProjectNoBuildFile(PathBuf),
     /// Gradle is not found
     GradleNotFound,
+    /// Gradle didn't produce an app bundle in {0:?}
+    GradleBundleNotFound(PathBuf),
+    /// This crossbundle build doesn't embed the Crossbow bridge DEX. Use a Gradle strategy or
+    /// rebuild crossbundle after `cargo run -p xtask -- bridge-dex build`
+    CrossbowBridgeDexMissing,
+    /// The Crossbow bridge DEX embedded into this crossbundle build doesn't match its stamp.
+    /// Regenerate it with `cargo run -p xtask -- bridge-dex build`
+    CrossbowBridgeDexStale,
     /// Android SDK has no build tools
     BuildToolsNotFound,
     /// Android SDK has no platforms installed
@@ -44,8 +52,45 @@ pub enum AndroidError {
     InvalidBuildStrategy(String),
     /// Failed to find AndroidManifest.xml in path: {0}
     FailedToFindAndroidManifest(String),
+    /// Failed to merge Android manifests: {0}
+    ManifestMergeConflict(String),
     /// Unable to find NDK file
     UnableToFindNDKFile,
+    /// Failed to load keystore {0:?}: {1}
+    InvalidKeystore(PathBuf, String),
+    /// Keystore doesn't contain a private key with alias `{0}`
+    KeyAliasNotFound(String),
+    /// Keystore {0:?} already exists
+    KeystoreAlreadyExists(PathBuf),
+    /// Invalid keystore options: {0}
+    InvalidKeystoreOptions(String),
+    /// Invalid signing configuration: {0}
+    InvalidSigningConfig(String),
+    /// Signing secret is unavailable: {0}
+    SigningSecretUnavailable(String),
+    /// Version code {0} must be below 1000 to derive per-ABI version codes
+    SplitVersionCodeOutOfRange(u32),
+    /// Invalid asset pack: {0}
+    InvalidAssetPack(String),
+    /// Invalid feature module: {0}
+    InvalidFeatureModule(String),
+    /// Invalid APK archive: {0}
+    InvalidApkArchive(String),
+    /// Failed to sign APK: {0}
+    ApkSigningFailed(String),
+    /// APK signature verification failed: {0}
+    ApkVerificationFailed(String),
+    /// Failed to decode compiled XML: {0}
+    InvalidCompiledXml(String),
+    /// Failed to read ELF file {0:?}: {1}
+    InvalidElf(PathBuf, String),
+    /// Native library {0:?} isn't built for {1}
+    NativeLibraryTargetMismatch(PathBuf, String),
+    /// Failed to find shared libraries: {0}
+    UnresolvedNativeLibraries(String),
+    /// Native libraries must align LOAD segments to {page_size} bytes, relink them with
+    /// `-Wl,-z,max-page-size={page_size}`: {libraries}
+    UnalignedNativeLibraries { page_size: u64, libraries: String },
     /// AndroidTools error: {0:?}
     AndroidTools(#[from] android_tools::error::Error),
     /// AndroidManifest error: {0:?}
@@ -74,6 +119,8 @@ pub enum AppleError {
     AssetsNotFound,
     /// Failed to find Info.plist in path: {0}
     FailedToFindInfoPlist(String),
+    /// Invalid IPA archive: {0}
+    InvalidIpaArchive(String),
     /// Plist data error: {0:?}
     Plist(#[from] plist::Error),
 }
@@ -93,12 +140,16 @@ pub enum Error {
     },
     /// Width and height of the icon have different sizes. Choose another image
     WidthAndHeightDifSizes,
+    /// Invalid SVG icon {0:?}: {1}
+    InvalidSvgIcon(PathBuf, String),
     /// Icons already exist. Use overwrite flag
     IconsAlreadyExist,
     /// Failed to find the manifest in path: {0}
     FailedToFindManifest(PathBuf),
     /// Invalid profile: {0}
     InvalidProfile(String),
+    /// Profile `{0}` is not defined in {1:?}
+    UndefinedProfile(String, PathBuf),
     /// Invalid semantic version
     #[cfg(not(feature = "android"))]
     InvalidSemver,
@@ -111,6 +162,14 @@ pub enum Error {
     },
     /// Path {0:?} doesn't exist
     PathNotFound(PathBuf),
+    /// Invalid certificate: {0}
+    InvalidCertificate(String),
+    /// Invalid external signer configuration: {0}
+    InvalidSignerConfig(String),
+    /// External signer failed: {0}
+    ExternalSignerFailed(String),
+    /// Unsupported artifact {0:?}, expected an .apk, .aab, .apks or .ipa file
+    UnsupportedArtifact(PathBuf),
     /// Failed to find cargo manifest: {0}
     FailedToFindCargoManifest(String),
     /// Failed to choose shell string color.
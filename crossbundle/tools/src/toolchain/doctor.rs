@@ -9,13 +9,13 @@ use std::{
 };
 
 #[cfg(feature = "android")]
-use crate::types::{AndroidConfig, IntoRustTriple};
+use crate::types::{AndroidConfig, AndroidPageSize, IntoRustTriple};
 
 #[cfg(feature = "android")]
 mod android;
 #[cfg(all(test, feature = "android"))]
 use android::{
-    bundletool_check, compatible_child, executable_version_from_metadata,
+    bundletool_check, compatible_child, executable_version_from_metadata, project_page_size_check,
     project_rust_targets_check, versioned_executable_check,
 };
 #[cfg(feature = "apple")]
@@ -1025,4 +1025,40 @@ release_build_targets = ["not-an-apple-target"]
         );
         assert_eq!(check.status, CheckStatus::Fail);
     }
+
+    #[cfg(feature = "android")]
+    #[test]
+    fn page_size_check_inspects_previously_packaged_libraries() {
+        use crate::commands::android::{ElfClass, elf_library};
+
+        let temp = tempfile::tempdir().unwrap();
+        let libs = temp
+            .path()
+            .join("target/android/game/libs/release/arm64-v8a");
+        fs::create_dir_all(&libs).unwrap();
+        fs::write(
+            libs.join("libgame.so"),
+            elf_library(ElfClass::Elf64, &[16384]),
+        )
+        .unwrap();
+        let environment = Environment::default();
+        let mut android = AndroidConfig::default();
+        let check = project_page_size_check(&android, &environment, temp.path(), "game");
+        assert_eq!(check.id, "android.elf.page-size");
+        assert_eq!(check.status, CheckStatus::Pass);
+
+        fs::write(
+            libs.join("libthirdparty.so"),
+            elf_library(ElfClass::Elf64, &[4096]),
+        )
+        .unwrap();
+        let check = project_page_size_check(&android, &environment, temp.path(), "game");
+        assert_eq!(check.status, CheckStatus::Fail);
+        assert!(check.summary.contains("libthirdparty.so"));
+        assert!(!check.summary.contains("libgame.so"));
+
+        android.page_size = AndroidPageSize::Page4K;
+        let check = project_page_size_check(&android, &environment, temp.path(), "game");
+        assert_eq!(check.status, CheckStatus::Warn);
+    }
 }
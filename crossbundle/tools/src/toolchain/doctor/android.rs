@@ -553,7 +553,7 @@ fn project_checks(
             "project.android.icon",
             "Project",
             "icon",
-            metadata.icon.as_ref().map(|path| base.join(path)),
+            metadata.icon.as_ref().map(|icon| base.join(icon.source())),
             false,
             PathRequirement::Exists,
         ),
@@ -568,6 +568,7 @@ fn project_checks(
         project_targets_check(android),
         project_rust_targets_check(android, environment, base, strict, requested_targets),
         project_plugins_check(android, base),
+        project_page_size_check(android, environment, base, &project.package_name),
     ];
     if let Some(uses_sdk) = android
         .manifest
@@ -755,6 +756,109 @@ fn project_plugins_check(android: &AndroidConfig, base: &Path) -> DoctorCheck {
     }
 }
 
+/// Inspects `LOAD` segments of native libraries packaged by a previous build, as the
+/// APK/AAB build does.
+#[cfg(feature = "android")]
+pub(super) fn project_page_size_check(
+    android: &AndroidConfig,
+    environment: &Environment,
+    project_dir: &Path,
+    package_name: &str,
+) -> DoctorCheck {
+    let id = "android.elf.page-size";
+    let page_size = android.page_size;
+    let mut check = if page_size == AndroidPageSize::Page4K {
+        check(
+            id,
+            CheckStatus::Warn,
+            "Android",
+            "Native libraries are aligned for 4 KB pages and won't load on 16 KB page size devices"
+                .into(),
+            true,
+            None,
+            None,
+            Some("Set page_size = \"16k\"; Google Play requires 16 KB page size support".into()),
+        )
+    } else {
+        let build_dir = environment
+            .variables
+            .get("CARGO_TARGET_DIR")
+            .map(|dir| project_dir.join(dir))
+            .unwrap_or_else(|| project_dir.join("target"))
+            .join("android")
+            .join(package_name);
+        let mut libraries = Vec::new();
+        collect_native_libraries(&build_dir, &mut libraries);
+        libraries.sort();
+        let found = Some(ObservedValue {
+            version: Some(page_size.to_string()),
+            path: build_dir.is_dir().then_some(build_dir),
+        });
+        match crate::commands::android::unaligned_libraries(&libraries, page_size) {
+            Ok(unaligned) if unaligned.is_empty() => check(
+                id,
+                CheckStatus::Pass,
+                "Android",
+                format!(
+                    "Native libraries are linked for {} byte pages",
+                    page_size.bytes()
+                ),
+                true,
+                found,
+                None,
+                None,
+            ),
+            Ok(unaligned) => check(
+                id,
+                CheckStatus::Fail,
+                "Android",
+                format!(
+                    "Native libraries have LOAD segments aligned to less than {} bytes: {}",
+                    page_size.bytes(),
+                    unaligned
+                        .iter()
+                        .map(|library| library.path.display().to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                true,
+                found,
+                None,
+                Some(format!(
+                    "Relink the libraries with -Wl,-z,max-page-size={} or update them to 16 KB compatible releases",
+                    page_size.bytes()
+                )),
+            ),
+            Err(error) => check(
+                id,
+                CheckStatus::Warn,
+                "Android",
+                format!("Could not inspect packaged native libraries: {error}"),
+                true,
+                found,
+                None,
+                None,
+            ),
+        }
+    };
+    check.source = Some("package.metadata.android.page_size".into());
+    check
+}
+
+#[cfg(feature = "android")]
+fn collect_native_libraries(dir: &Path, libraries: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for path in entries.filter_map(Result::ok).map(|entry| entry.path()) {
+        if path.is_dir() {
+            collect_native_libraries(&path, libraries);
+        } else if path.extension().is_some_and(|extension| extension == "so") {
+            libraries.push(path);
+        }
+    }
+}
+
 #[cfg(feature = "android")]
 fn project_sdk_check(
     id: &str,
@@ -673,7 +673,7 @@ fn project_checks(context: &project::ProjectContext) -> Vec<DoctorCheck> {
             "project.apple.icon",
             "Project Apple",
             "Apple icon",
-            metadata.icon.as_ref().map(|path| base.join(path)),
+            metadata.icon.as_ref().map(|icon| base.join(icon.source())),
             true,
             PathRequirement::ReadableFile,
         ),
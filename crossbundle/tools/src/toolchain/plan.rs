@@ -1,7 +1,7 @@
 use super::{
     CheckStatus, DoctorCheck, DoctorPlatform, DoctorReport, DoctorRequest, Environment, diagnose,
 };
-use crate::types::AndroidRuntime;
+use crate::types::{AndroidRuntime, BuildVariant};
 use serde::{Deserialize, Serialize};
 use std::{fmt, path::PathBuf};
 
@@ -18,6 +18,7 @@ pub enum PlanOperation {
 #[serde(rename_all = "kebab-case")]
 pub enum PlanStrategy {
     GradleApk,
+    GradleAab,
     NativeApk,
     NativeAab,
 }
@@ -31,6 +32,16 @@ pub struct PlanRequest {
     pub attach_logger: bool,
     pub library_only: bool,
     pub runtime: AndroidRuntime,
+    /// Build one APK per target instead of a single APK with every target.
+    #[serde(default)]
+    pub split_per_abi: bool,
+    /// The project depends on Crossbow plugin binaries or projects that only Gradle can
+    /// compile and package.
+    #[serde(default)]
+    pub plugin_binaries: bool,
+    /// Build variant selected with `--variant`.
+    #[serde(default)]
+    pub variant: Option<BuildVariant>,
 }
 
 #[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -41,6 +52,7 @@ pub enum PlanStepKind {
     BuildNativeAab,
     PrepareGradleProject,
     BuildGradleProject,
+    BuildGradleBundle,
     GenerateApksArchive,
     InstallArtifact,
     LaunchApplication,
@@ -52,6 +64,30 @@ pub struct PlanStep {
     pub id: String,
     pub kind: PlanStepKind,
     pub action: String,
+    /// Rust target triple the step is limited to. Steps without a target cover every
+    /// requested target.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PlanArtifactKind {
+    Apk,
+    Aab,
+    ApkSet,
+    GradleProject,
+}
+
+/// Output produced by a plan step.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PlanArtifact {
+    pub id: String,
+    pub kind: PlanArtifactKind,
+    pub step_id: String,
+    /// Rust target triple of a per-ABI artifact.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -60,9 +96,14 @@ pub struct BuildPlan {
     pub platform: DoctorPlatform,
     pub operation: PlanOperation,
     pub strategy: PlanStrategy,
+    /// Build variant whose overrides the plan builds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variant: Option<BuildVariant>,
     pub toolchain: ResolvedAndroidToolchain,
     pub diagnostics: DoctorReport,
     pub steps: Vec<PlanStep>,
+    #[serde(default)]
+    pub artifacts: Vec<PlanArtifact>,
 }
 
 #[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
@@ -116,24 +157,54 @@ pub fn plan(request: &PlanRequest, environment: &Environment) -> BuildPlan {
         environment,
     );
     let mut required = Vec::new();
-    if request.runtime == AndroidRuntime::Miniquad
-        && request.strategy != PlanStrategy::GradleApk
-        && !request.library_only
-    {
+    let gradle = matches!(
+        request.strategy,
+        PlanStrategy::GradleApk | PlanStrategy::GradleAab
+    );
+    if request.runtime == AndroidRuntime::Miniquad && !gradle && !request.library_only {
         diagnostics.checks.push(DoctorCheck {
             id: "project.android.runtime".into(),
             status: CheckStatus::Fail,
             category: "Project".into(),
-            summary: "The Miniquad runtime requires the Gradle APK strategy".into(),
+            summary: "The Miniquad runtime requires a Gradle strategy".into(),
             required: true,
             found: None,
             expected: None,
             source: Some("package.metadata.android.runtime".into()),
-            remediation: Some("Use `--strategy gradle-apk`; native APK/AAB packaging does not compile Miniquad's Java runtime".into()),
+            remediation: Some("Use `--strategy gradle-apk` or `--strategy gradle-aab`; native APK/AAB packaging does not compile Miniquad's Java runtime".into()),
+        });
+    }
+    if request.plugin_binaries && !gradle && !request.library_only {
+        diagnostics.checks.push(DoctorCheck {
+            id: "project.android.plugins".into(),
+            status: CheckStatus::Fail,
+            category: "Project".into(),
+            summary: "Crossbow plugin binaries require a Gradle strategy".into(),
+            required: true,
+            found: None,
+            expected: None,
+            source: Some("package.metadata.android.plugins".into()),
+            remediation: Some("Use `--strategy gradle-apk` or `--strategy gradle-aab`; native APK/AAB packaging only includes the prebuilt Crossbow bridge".into()),
+        });
+    }
+    if request.split_per_abi && request.strategy != PlanStrategy::NativeApk {
+        diagnostics.checks.push(DoctorCheck {
+            id: "project.android.split-per-abi".into(),
+            status: CheckStatus::Warn,
+            category: "Project".into(),
+            summary: "Per-ABI APKs are only built by the native APK strategy".into(),
+            required: false,
+            found: None,
+            expected: None,
+            source: Some("package.metadata.android.split_per_abi".into()),
+            remediation: Some("Use `--strategy native-apk` to build one APK per target".into()),
         });
     }
     if request.operation == PlanOperation::Run && !request.library_only {
         required.push("android.adb");
+        if request.strategy == PlanStrategy::GradleAab {
+            required.push("android.bundletool");
+        }
     }
     let mut steps = if request.library_only {
         vec![step(
@@ -150,12 +221,12 @@ pub fn plan(request: &PlanRequest, environment: &Environment) -> BuildPlan {
                 "Compile, package, align, and sign the APK",
             ),
             PlanStrategy::NativeAab => (
-                &["android.bundletool", "host.java.jarsigner"][..],
+                &["android.bundletool"][..],
                 PlanStepKind::BuildNativeAab,
                 "android.aab.build",
                 "Compile, package, and sign the Android App Bundle",
             ),
-            PlanStrategy::GradleApk => (
+            PlanStrategy::GradleApk | PlanStrategy::GradleAab => (
                 &["host.gradle"][..],
                 PlanStepKind::PrepareGradleProject,
                 "android.gradle.prepare",
@@ -163,7 +234,20 @@ pub fn plan(request: &PlanRequest, environment: &Environment) -> BuildPlan {
             ),
         };
         required.extend(tools);
-        vec![step(kind, id, action)]
+        if request.strategy == PlanStrategy::NativeApk && request.split_per_abi {
+            request
+                .targets
+                .iter()
+                .map(|target| PlanStep {
+                    id: format!("{id}.{target}"),
+                    kind,
+                    action: format!("Compile, package, align, and sign the {target} APK"),
+                    target: Some(target.clone()),
+                })
+                .collect()
+        } else {
+            vec![step(kind, id, action)]
+        }
     };
     for check in diagnostics
         .checks
@@ -203,8 +287,18 @@ pub fn plan(request: &PlanRequest, environment: &Environment) -> BuildPlan {
             "Build the generated Gradle project",
         ));
     }
+    if request.strategy == PlanStrategy::GradleAab && !request.library_only {
+        steps.push(step(
+            PlanStepKind::BuildGradleBundle,
+            "android.gradle.bundle",
+            "Build and sign the Android App Bundle with Gradle",
+        ));
+    }
     if request.operation == PlanOperation::Run && !request.library_only {
-        if request.strategy == PlanStrategy::NativeAab {
+        if matches!(
+            request.strategy,
+            PlanStrategy::NativeAab | PlanStrategy::GradleAab
+        ) {
             steps.push(step(
                 PlanStepKind::GenerateApksArchive,
                 "android.apks.generate",
@@ -229,17 +323,43 @@ pub fn plan(request: &PlanRequest, environment: &Environment) -> BuildPlan {
             ));
         }
     }
+    let artifacts = steps.iter().filter_map(artifact).collect();
     BuildPlan {
         schema_version: BUILD_PLAN_SCHEMA_VERSION,
         platform: DoctorPlatform::Android,
         operation: request.operation,
         strategy: request.strategy,
+        variant: request.variant.clone(),
         toolchain,
         diagnostics,
         steps,
+        artifacts,
     }
 }
 
+fn artifact(step: &PlanStep) -> Option<PlanArtifact> {
+    let (kind, id) = match step.kind {
+        PlanStepKind::BuildNativeApk => (PlanArtifactKind::Apk, "android.apk"),
+        PlanStepKind::BuildNativeAab | PlanStepKind::BuildGradleBundle => {
+            (PlanArtifactKind::Aab, "android.aab")
+        }
+        PlanStepKind::GenerateApksArchive => (PlanArtifactKind::ApkSet, "android.apks"),
+        PlanStepKind::PrepareGradleProject => {
+            (PlanArtifactKind::GradleProject, "android.gradle.project")
+        }
+        _ => return None,
+    };
+    Some(PlanArtifact {
+        id: match &step.target {
+            Some(target) => format!("{id}.{target}"),
+            None => id.to_owned(),
+        },
+        kind,
+        step_id: step.id.clone(),
+        target: step.target.clone(),
+    })
+}
+
 fn observed_path(report: &DoctorReport, id: &str) -> Option<PathBuf> {
     report
         .checks
@@ -269,6 +389,7 @@ fn step(kind: PlanStepKind, id: &str, action: &str) -> PlanStep {
         id: id.into(),
         kind,
         action: action.into(),
+        target: None,
     }
 }
 
@@ -285,6 +406,9 @@ mod tests {
             attach_logger: false,
             library_only: false,
             runtime: AndroidRuntime::NativeActivity,
+            split_per_abi: false,
+            plugin_binaries: false,
+            variant: None,
         }
     }
 
@@ -316,6 +440,26 @@ mod tests {
             )),
             ["android.gradle.prepare", "android.gradle.build"]
         );
+        assert_eq!(
+            ids(&plan(
+                &request(PlanOperation::Build, PlanStrategy::GradleAab),
+                &environment
+            )),
+            ["android.gradle.prepare", "android.gradle.bundle"]
+        );
+        assert_eq!(
+            ids(&plan(
+                &request(PlanOperation::Run, PlanStrategy::GradleAab),
+                &environment
+            )),
+            [
+                "android.gradle.prepare",
+                "android.gradle.bundle",
+                "android.apks.generate",
+                "android.device.install",
+                "android.device.launch",
+            ]
+        );
         assert_eq!(
             ids(&plan(
                 &request(PlanOperation::Run, PlanStrategy::NativeAab),
@@ -330,6 +474,85 @@ mod tests {
         );
     }
 
+    #[test]
+    fn split_per_abi_plans_one_apk_step_and_artifact_per_target() {
+        let mut request = request(PlanOperation::Build, PlanStrategy::NativeApk);
+        request.targets = vec![
+            "aarch64-linux-android".into(),
+            "x86_64-linux-android".into(),
+        ];
+        request.split_per_abi = true;
+        let plan = plan(&request, &Environment::default());
+        assert_eq!(
+            ids(&plan),
+            [
+                "android.apk.build.aarch64-linux-android",
+                "android.apk.build.x86_64-linux-android",
+            ]
+        );
+        assert_eq!(
+            plan.steps[1].target.as_deref(),
+            Some("x86_64-linux-android")
+        );
+        assert_eq!(
+            plan.artifacts,
+            [
+                PlanArtifact {
+                    id: "android.apk.aarch64-linux-android".into(),
+                    kind: PlanArtifactKind::Apk,
+                    step_id: "android.apk.build.aarch64-linux-android".into(),
+                    target: Some("aarch64-linux-android".into()),
+                },
+                PlanArtifact {
+                    id: "android.apk.x86_64-linux-android".into(),
+                    kind: PlanArtifactKind::Apk,
+                    step_id: "android.apk.build.x86_64-linux-android".into(),
+                    target: Some("x86_64-linux-android".into()),
+                },
+            ]
+        );
+        let json = serde_json::to_value(&plan).unwrap();
+        assert_eq!(
+            json["steps"][0]["target"],
+            serde_json::json!("aarch64-linux-android")
+        );
+    }
+
+    #[test]
+    fn split_per_abi_warns_for_other_strategies() {
+        let mut request = request(PlanOperation::Build, PlanStrategy::NativeAab);
+        request.split_per_abi = true;
+        let plan = plan(&request, &Environment::default());
+        assert_eq!(ids(&plan), ["android.aab.build"]);
+        let check = plan
+            .diagnostics
+            .checks
+            .iter()
+            .find(|check| check.id == "project.android.split-per-abi")
+            .unwrap();
+        assert_eq!(check.status, CheckStatus::Warn);
+        let json = serde_json::to_value(&plan).unwrap();
+        assert!(json["steps"][0].get("target").is_none());
+        assert_eq!(json["artifacts"][0]["kind"], serde_json::json!("aab"));
+    }
+
+    #[test]
+    fn plan_records_the_resolved_variant() {
+        let mut request = request(PlanOperation::Build, PlanStrategy::NativeApk);
+        let json = serde_json::to_value(plan(&request, &Environment::default())).unwrap();
+        assert!(json.get("variant").is_none());
+
+        request.variant = Some(BuildVariant {
+            name: "staging".into(),
+            features: vec!["staging".into()],
+        });
+        let json = serde_json::to_value(plan(&request, &Environment::default())).unwrap();
+        assert_eq!(
+            json["variant"],
+            serde_json::json!({ "name": "staging", "features": ["staging"] })
+        );
+    }
+
     #[test]
     fn each_strategy_requires_the_tools_it_executes() {
         let environment = Environment::default();
@@ -344,6 +567,52 @@ mod tests {
         }
     }
 
+    #[test]
+    fn gradle_bundles_need_bundletool_only_to_run() {
+        let environment = Environment::default();
+        let bundletool = |operation| {
+            let plan = plan(&request(operation, PlanStrategy::GradleAab), &environment);
+            let artifacts = plan
+                .artifacts
+                .iter()
+                .map(|artifact| (artifact.id.clone(), artifact.kind))
+                .collect::<Vec<_>>();
+            assert!(artifacts.contains(&("android.aab".to_owned(), PlanArtifactKind::Aab)));
+            plan.diagnostics
+                .checks
+                .iter()
+                .find(|check| check.id == "android.bundletool")
+                .unwrap()
+                .required
+        };
+        assert!(!bundletool(PlanOperation::Build));
+        assert!(bundletool(PlanOperation::Run));
+    }
+
+    #[test]
+    fn plugin_binaries_require_gradle_packaging() {
+        let mut request = request(PlanOperation::Build, PlanStrategy::NativeAab);
+        request.plugin_binaries = true;
+        let plan = plan(&request, &Environment::default());
+        let check = plan
+            .diagnostics
+            .checks
+            .iter()
+            .find(|check| check.id == "project.android.plugins")
+            .unwrap();
+        assert_eq!(check.status, CheckStatus::Fail);
+        assert!(check.required);
+
+        request.strategy = PlanStrategy::GradleApk;
+        let plan = super::plan(&request, &Environment::default());
+        assert!(
+            plan.diagnostics
+                .checks
+                .iter()
+                .all(|check| check.id != "project.android.plugins")
+        );
+    }
+
     #[test]
     fn miniquad_requires_gradle_packaging() {
         let mut request = request(PlanOperation::Build, PlanStrategy::NativeApk);
@@ -357,6 +626,15 @@ mod tests {
             .unwrap();
         assert_eq!(check.status, CheckStatus::Fail);
         assert!(check.remediation.as_deref().unwrap().contains("gradle-apk"));
+
+        request.strategy = PlanStrategy::GradleAab;
+        let plan = super::plan(&request, &Environment::default());
+        assert!(
+            plan.diagnostics
+                .checks
+                .iter()
+                .all(|check| check.id != "project.android.runtime")
+        );
     }
 
     #[test]
@@ -404,6 +682,9 @@ mod tests {
             attach_logger: true,
             library_only: false,
             runtime: AndroidRuntime::NativeActivity,
+            split_per_abi: false,
+            plugin_binaries: false,
+            variant: None,
         };
         let result = plan(&request, &Environment::default());
         assert!(result.steps.iter().any(|s| s.id == "android.device.log"));
@@ -428,6 +709,9 @@ mod tests {
             attach_logger: false,
             library_only: false,
             runtime: AndroidRuntime::NativeActivity,
+            split_per_abi: false,
+            plugin_binaries: false,
+            variant: None,
         };
         let plan = plan(&request, &Environment::default());
         let mut runner = RecordingRunner(Vec::new());
@@ -460,6 +744,9 @@ mod tests {
             attach_logger: false,
             library_only: false,
             runtime: AndroidRuntime::NativeActivity,
+            split_per_abi: false,
+            plugin_binaries: false,
+            variant: None,
         };
         let plan = plan(&request, &Environment::default());
         let error = execute(&plan, &mut FailingRunner).unwrap_err();
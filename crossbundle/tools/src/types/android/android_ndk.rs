@@ -1,7 +1,6 @@
 use crate::error::*;
 use crate::types::AndroidTarget;
 use std::path::{Path, PathBuf};
-use std::process::Command;
 
 /// Helper structure that contains information about the Android NDK Path
 /// and returns paths to the tools.
@@ -179,12 +178,6 @@ impl AndroidNdk {
         }
     }
 
-    /// Displaying various information
-    pub fn readelf(&self, build_target: AndroidTarget) -> Result<Command> {
-        let readelf_path = self.toolchain_bin("readelf", build_target)?;
-        Ok(Command::new(readelf_path))
-    }
-
     /// Sysroot and lib platform
     pub fn sysroot_platform_lib_dir(
         &self,
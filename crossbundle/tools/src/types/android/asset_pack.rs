@@ -0,0 +1,135 @@
+use crate::error::{AndroidError, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// When Google Play delivers an asset pack.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AssetPackDelivery {
+    /// Delivered together with the app.
+    #[default]
+    InstallTime,
+    /// Downloaded automatically right after the app is installed.
+    FastFollow,
+    /// Downloaded when the app requests it.
+    OnDemand,
+}
+
+impl AssetPackDelivery {
+    /// Name of the `dist:delivery` child element.
+    pub fn element_name(self) -> &'static str {
+        match self {
+            Self::InstallTime => "install-time",
+            Self::FastFollow => "fast-follow",
+            Self::OnDemand => "on-demand",
+        }
+    }
+}
+
+/// Play Asset Delivery pack packaged as a separate module of an Android App Bundle.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AssetPack {
+    /// Module name of the pack. Used by the app to request the pack at runtime.
+    pub name: String,
+    #[serde(default)]
+    pub delivery: AssetPackDelivery,
+    /// Directory whose contents are packaged into the `assets/` directory of the pack.
+    pub directory: PathBuf,
+}
+
+impl AssetPack {
+    /// Checks names and directories of asset packs before anything is built.
+    pub fn validate(asset_packs: &[AssetPack]) -> Result<()> {
+        for (index, pack) in asset_packs.iter().enumerate() {
+            if !is_valid_module_name(&pack.name) {
+                return Err(AndroidError::InvalidAssetPack(format!(
+                    "`{}` is not a valid module name, use letters, digits and underscores",
+                    pack.name
+                ))
+                .into());
+            }
+            if asset_packs[..index]
+                .iter()
+                .any(|other| other.name == pack.name)
+            {
+                return Err(AndroidError::InvalidAssetPack(format!(
+                    "`{}` is defined more than once",
+                    pack.name
+                ))
+                .into());
+            }
+            if !pack.directory.is_dir() {
+                return Err(AndroidError::InvalidAssetPack(format!(
+                    "directory {:?} of `{}` doesn't exist",
+                    pack.directory, pack.name
+                ))
+                .into());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Module names of App Bundles start with a letter and contain letters, digits and
+/// underscores. `base` is reserved for the base module.
+pub(crate) fn is_valid_module_name(name: &str) -> bool {
+    let mut characters = name.chars();
+    characters
+        .next()
+        .is_some_and(|character| character.is_ascii_alphabetic())
+        && characters.all(|character| character.is_ascii_alphanumeric() || character == '_')
+        && name != "base"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+
+    fn pack(name: &str, directory: PathBuf) -> AssetPack {
+        AssetPack {
+            name: name.to_owned(),
+            delivery: AssetPackDelivery::default(),
+            directory,
+        }
+    }
+
+    #[test]
+    fn deserializes_delivery_modes() {
+        let pack: AssetPack = serde_json::from_value(serde_json::json!({
+            "name": "levels",
+            "delivery": "fast-follow",
+            "directory": "packs/levels"
+        }))
+        .unwrap();
+        assert_eq!(pack.delivery, AssetPackDelivery::FastFollow);
+        let pack: AssetPack = serde_json::from_value(serde_json::json!({
+            "name": "levels",
+            "directory": "packs/levels"
+        }))
+        .unwrap();
+        assert_eq!(pack.delivery, AssetPackDelivery::InstallTime);
+    }
+
+    #[test]
+    fn rejects_invalid_asset_packs() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let directory = temp_dir.path().to_owned();
+        assert!(AssetPack::validate(&[pack("levels", directory.clone())]).is_ok());
+        for packs in [
+            vec![pack("base", directory.clone())],
+            vec![pack("1levels", directory.clone())],
+            vec![pack("level-pack", directory.clone())],
+            vec![
+                pack("levels", directory.clone()),
+                pack("levels", directory.clone()),
+            ],
+            vec![pack("levels", directory.join("missing"))],
+        ] {
+            assert!(matches!(
+                AssetPack::validate(&packs),
+                Err(Error::Android(AndroidError::InvalidAssetPack(_)))
+            ));
+        }
+    }
+}
@@ -20,6 +20,9 @@ pub enum AndroidTarget {
     X8664,
 }
 
+/// Multiplier of the ABI code in per-ABI version codes.
+const SPLIT_VERSION_CODE_MULTIPLIER: u32 = 1000;
+
 impl AndroidTarget {
     /// Identifier used in the NDK to refer to the ABI.
     pub fn android_abi(self) -> &'static str {
@@ -62,6 +65,37 @@ impl AndroidTarget {
         }
     }
 
+    /// Returns the `e_machine` value of ELF files built for the target.
+    pub fn elf_machine(self) -> u16 {
+        match self {
+            Self::Armv7 => 40,
+            Self::Aarch64 => 183,
+            Self::I686 => 3,
+            Self::X8664 => 62,
+        }
+    }
+
+    /// Code of the ABI in the conventional per-ABI version code scheme. 64-bit ABIs have
+    /// higher codes than their 32-bit counterparts, so devices supporting both get the
+    /// 64-bit APK.
+    pub fn abi_version_code(self) -> u32 {
+        match self {
+            Self::Armv7 => 1,
+            Self::Aarch64 => 2,
+            Self::I686 => 3,
+            Self::X8664 => 4,
+        }
+    }
+
+    /// Returns the version code of an APK that only contains this target's libraries:
+    /// `abi_version_code * 1000 + version_code`.
+    pub fn split_version_code(self, version_code: u32) -> Result<u32> {
+        if version_code >= SPLIT_VERSION_CODE_MULTIPLIER {
+            return Err(AndroidError::SplitVersionCodeOutOfRange(version_code).into());
+        }
+        Ok(self.abi_version_code() * SPLIT_VERSION_CODE_MULTIPLIER + version_code)
+    }
+
     // Returns just the architecture component for clang
     pub fn clang_arch(self) -> &'static str {
         match self {
@@ -97,3 +131,29 @@ impl IntoRustTriple for AndroidTarget {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+
+    #[test]
+    fn derives_split_version_codes() {
+        let codes: Vec<_> = [
+            AndroidTarget::Armv7,
+            AndroidTarget::Aarch64,
+            AndroidTarget::I686,
+            AndroidTarget::X8664,
+        ]
+        .into_iter()
+        .map(|target| target.split_version_code(7).unwrap())
+        .collect();
+        assert_eq!(codes, [1007, 2007, 3007, 4007]);
+        assert!(matches!(
+            AndroidTarget::Aarch64.split_version_code(1000),
+            Err(Error::Android(AndroidError::SplitVersionCodeOutOfRange(
+                1000
+            )))
+        ));
+    }
+}
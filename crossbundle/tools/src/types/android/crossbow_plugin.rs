@@ -0,0 +1,172 @@
+use crate::types::GradleDependencyProject;
+use android_manifest::{AndroidManifest, UsesPermission};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Android part of `package.metadata.crossbow-plugin` in a plugin crate.
+///
+/// Paths are relative to the plugin crate. Local Gradle projects are used when every
+/// `project_dir` exists, e.g. for path and git dependencies. Published crates usually
+/// don't ship their Gradle projects, so `remote` coordinates are used instead.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct AndroidPluginMetadata {
+    /// Gradle projects of the plugin, included into the generated Gradle project.
+    #[serde(default)]
+    pub local_projects: Vec<GradleDependencyProject>,
+    /// Maven coordinates of the plugin, used when its Gradle projects aren't available.
+    #[serde(default)]
+    pub remote: Vec<String>,
+    /// Maven repositories the remote coordinates are resolved from.
+    #[serde(default)]
+    pub maven_repos: Vec<String>,
+    /// Android permissions the plugin needs, added as `<uses-permission>`.
+    #[serde(default)]
+    pub permissions: Vec<String>,
+    /// Manifest fragment merged into the application manifest.
+    pub manifest: Option<AndroidManifest>,
+}
+
+/// Crossbow plugin found in the resolved Cargo dependencies of the project.
+#[derive(Debug, Clone, Serialize)]
+pub struct AndroidPlugin {
+    /// Cargo package name of the plugin.
+    pub package: String,
+    pub version: String,
+    pub metadata: AndroidPluginMetadata,
+}
+
+impl AndroidPlugin {
+    /// Parses `package.metadata.crossbow-plugin.android` of a Cargo package. Returns
+    /// `None` for packages that aren't Android plugins.
+    pub fn from_package_metadata(
+        package: &str,
+        version: &str,
+        manifest_path: &Path,
+        metadata: &serde_json::Value,
+    ) -> anyhow::Result<Option<Self>> {
+        let Some(android) = metadata
+            .get("crossbow-plugin")
+            .and_then(|plugin| plugin.get("android"))
+        else {
+            return Ok(None);
+        };
+        let mut metadata: AndroidPluginMetadata =
+            serde_json::from_value(android.clone()).map_err(|error| {
+                anyhow::anyhow!(
+                    "invalid `package.metadata.crossbow-plugin.android` in `{package}`: {error}"
+                )
+            })?;
+        let root = manifest_path.parent().unwrap_or(Path::new(""));
+        for project in &mut metadata.local_projects {
+            if let Some(dir) = &mut project.project_dir
+                && dir.is_relative()
+            {
+                *dir = root.join(&*dir);
+            }
+        }
+        Ok(Some(Self {
+            package: package.to_owned(),
+            version: version.to_owned(),
+            metadata,
+        }))
+    }
+
+    /// Whether the local Gradle projects of the plugin exist on disk.
+    pub fn has_local_projects(&self) -> bool {
+        !self.metadata.local_projects.is_empty()
+            && self
+                .metadata
+                .local_projects
+                .iter()
+                .filter_map(GradleDependencyProject::project_dir)
+                .all(Path::is_dir)
+    }
+
+    /// Whether the plugin contributes code that the application links against. Plugins
+    /// that only provide support projects (`dont_implement`) don't.
+    pub fn has_binaries(&self) -> bool {
+        if self.has_local_projects() {
+            self.metadata
+                .local_projects
+                .iter()
+                .any(|project| !project.dont_implement)
+        } else {
+            !self.metadata.remote.is_empty()
+        }
+    }
+
+    /// Manifest fragment of the plugin with its permissions added as `<uses-permission>`.
+    /// Returns `None` when the plugin doesn't contribute to the manifest.
+    pub fn manifest_fragment(&self) -> Option<AndroidManifest> {
+        if self.metadata.permissions.is_empty() && self.metadata.manifest.is_none() {
+            return None;
+        }
+        let mut fragment = self.metadata.manifest.clone().unwrap_or_default();
+        for permission in &self.metadata.permissions {
+            if !fragment
+                .uses_permission
+                .iter()
+                .any(|entry| entry.name.as_deref() == Some(permission))
+            {
+                fragment.uses_permission.push(UsesPermission {
+                    name: Some(permission.clone()),
+                    ..Default::default()
+                });
+            }
+        }
+        Some(fragment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adds_permissions_to_manifest_fragment() {
+        let plugin = AndroidPlugin::from_package_metadata(
+            "play-billing",
+            "0.2.3",
+            Path::new("/plugins/play-billing/Cargo.toml"),
+            &serde_json::json!({ "crossbow-plugin": { "android": {
+                "permissions": ["com.android.vending.BILLING", "android.permission.INTERNET"],
+                "manifest": {
+                    "uses_permission": [
+                        { "name": "android.permission.INTERNET", "max_sdk_version": 30 }
+                    ],
+                    "application": { "meta_data": [
+                        { "name": "com.crossbow.plugin.v1.CrossbowPlayBilling", "value": "plugin" }
+                    ] }
+                }
+            } } }),
+        )
+        .unwrap()
+        .unwrap();
+
+        let fragment = plugin.manifest_fragment().unwrap();
+
+        let permissions = fragment
+            .uses_permission
+            .iter()
+            .map(|permission| permission.name.as_deref().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            permissions,
+            ["android.permission.INTERNET", "com.android.vending.BILLING"]
+        );
+        assert_eq!(fragment.uses_permission[0].max_sdk_version, Some(30));
+        assert_eq!(fragment.application.meta_data.len(), 1);
+    }
+
+    #[test]
+    fn ignores_packages_without_plugin_metadata() {
+        let plugin = AndroidPlugin::from_package_metadata(
+            "log",
+            "0.4.0",
+            Path::new("/log/Cargo.toml"),
+            &serde_json::json!({ "docs": { "rs": {} } }),
+        )
+        .unwrap();
+        assert!(plugin.is_none());
+    }
+}
@@ -0,0 +1,185 @@
+use super::{AssetPack, asset_pack::is_valid_module_name};
+use crate::error::{AndroidError, Result};
+use android_manifest::AndroidManifest;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// When Google Play delivers a dynamic feature module.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FeatureModuleDelivery {
+    /// Installed together with the app.
+    #[default]
+    InstallTime,
+    /// Downloaded when the app requests it.
+    OnDemand,
+}
+
+impl FeatureModuleDelivery {
+    /// Name of the `dist:delivery` child element.
+    pub fn element_name(self) -> &'static str {
+        match self {
+            Self::InstallTime => "install-time",
+            Self::OnDemand => "on-demand",
+        }
+    }
+}
+
+/// Highest resource package ID of feature modules, right below the `0x7f` of the base module.
+const FIRST_PACKAGE_ID: usize = 0x7e;
+/// Lowest resource package ID of feature modules. `0x00` and `0x01` are reserved for shared
+/// libraries and the framework.
+const LAST_PACKAGE_ID: usize = 0x02;
+/// Number of feature modules an app can have, one per free resource package ID.
+pub const MAX_FEATURE_MODULES: usize = FIRST_PACKAGE_ID - LAST_PACKAGE_ID + 1;
+
+/// Dynamic feature module of an Android App Bundle with its own native library.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct FeatureModule {
+    /// Module name of the feature. Used by the app to request the module at runtime.
+    pub name: String,
+    /// Title shown to users while the module is downloaded. Defaults to the name.
+    pub title: Option<String>,
+    #[serde(default)]
+    pub delivery: FeatureModuleDelivery,
+    /// Directory of the Cargo package whose `cdylib` target is packaged into the module.
+    pub path: PathBuf,
+    /// Features of the module package enabled on top of its default features. Features
+    /// passed to crossbundle only apply to the app package.
+    #[serde(default)]
+    pub features: Vec<String>,
+    /// Directories packaged into the `assets/` directory of the module.
+    #[serde(default)]
+    pub assets: Vec<PathBuf>,
+    /// Manifest fragment merged into the module manifest, e.g. activities of the module.
+    pub manifest: Option<AndroidManifest>,
+}
+
+impl FeatureModule {
+    /// Name of the base module string resource with the title of the module.
+    pub fn title_resource(&self) -> String {
+        format!("crossbow_feature_{}", self.name)
+    }
+
+    /// Resource package ID of the module at `index` of the feature modules. IDs count down
+    /// from `0x7e`.
+    pub fn package_id(index: usize) -> Result<u8> {
+        FIRST_PACKAGE_ID
+            .checked_sub(index)
+            .filter(|id| *id >= LAST_PACKAGE_ID)
+            .map(|id| id as u8)
+            .ok_or_else(|| {
+                AndroidError::InvalidFeatureModule(format!(
+                    "an app can have at most {MAX_FEATURE_MODULES} feature modules"
+                ))
+                .into()
+            })
+    }
+
+    /// Checks names and paths of feature modules before anything is built. Names must
+    /// not clash with each other or with asset packs.
+    pub fn validate(feature_modules: &[FeatureModule], asset_packs: &[AssetPack]) -> Result<()> {
+        if feature_modules.len() > MAX_FEATURE_MODULES {
+            return Err(AndroidError::InvalidFeatureModule(format!(
+                "{} feature modules are defined, but an app can have at most {MAX_FEATURE_MODULES}",
+                feature_modules.len()
+            ))
+            .into());
+        }
+        for (index, module) in feature_modules.iter().enumerate() {
+            if !is_valid_module_name(&module.name) {
+                return Err(AndroidError::InvalidFeatureModule(format!(
+                    "`{}` is not a valid module name, use letters, digits and underscores",
+                    module.name
+                ))
+                .into());
+            }
+            if feature_modules[..index]
+                .iter()
+                .any(|other| other.name == module.name)
+                || asset_packs.iter().any(|pack| pack.name == module.name)
+            {
+                return Err(AndroidError::InvalidFeatureModule(format!(
+                    "`{}` is defined more than once",
+                    module.name
+                ))
+                .into());
+            }
+            if !module.path.join("Cargo.toml").is_file() {
+                return Err(AndroidError::InvalidFeatureModule(format!(
+                    "{:?} of `{}` doesn't contain Cargo.toml",
+                    module.path, module.name
+                ))
+                .into());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::Error, types::AssetPackDelivery};
+
+    fn module(name: &str, path: PathBuf) -> FeatureModule {
+        FeatureModule {
+            name: name.to_owned(),
+            title: None,
+            delivery: FeatureModuleDelivery::OnDemand,
+            path,
+            features: Vec::new(),
+            assets: Vec::new(),
+            manifest: None,
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_feature_modules() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().to_owned();
+        std::fs::write(path.join("Cargo.toml"), "").unwrap();
+        let pack = AssetPack {
+            name: "levels".to_owned(),
+            delivery: AssetPackDelivery::OnDemand,
+            directory: path.clone(),
+        };
+        assert!(
+            FeatureModule::validate(
+                &[module("arena", path.clone())],
+                std::slice::from_ref(&pack)
+            )
+            .is_ok()
+        );
+        for modules in [
+            vec![module("base", path.clone())],
+            vec![module("arena mode", path.clone())],
+            vec![module("levels", path.clone())],
+            vec![module("arena", path.clone()), module("arena", path.clone())],
+            vec![module("arena", path.join("missing"))],
+            (0..=MAX_FEATURE_MODULES)
+                .map(|index| module(&format!("feature_{index}"), path.clone()))
+                .collect(),
+        ] {
+            assert!(matches!(
+                FeatureModule::validate(&modules, std::slice::from_ref(&pack)),
+                Err(Error::Android(AndroidError::InvalidFeatureModule(_)))
+            ));
+        }
+    }
+
+    #[test]
+    fn assigns_unreserved_package_ids() {
+        assert_eq!(FeatureModule::package_id(0).unwrap(), 0x7e);
+        assert_eq!(
+            FeatureModule::package_id(MAX_FEATURE_MODULES - 1).unwrap(),
+            0x02
+        );
+        for index in [MAX_FEATURE_MODULES, 0x7e, 0x7f, usize::MAX] {
+            assert!(matches!(
+                FeatureModule::package_id(index),
+                Err(Error::Android(AndroidError::InvalidFeatureModule(_)))
+            ));
+        }
+    }
+}
@@ -37,13 +37,17 @@ fn handles_launcher(filters: &[IntentFilter]) -> bool {
 }
 
 /// Updates [`AndroidManifest`](android_manifest::AndroidManifest) with default values.
+///
+/// With `splash`, the application uses the theme generated by
+/// [`SplashScreenGeneration`](crate::commands::android::SplashScreenGeneration).
 pub fn update_android_manifest_with_default(
     manifest: &mut AndroidManifest,
     app_name: Option<String>,
     library_name: &str,
-    strategy: super::AndroidStrategy,
+    strategy: AndroidStrategy,
     runtime: super::AndroidRuntime,
     crossbow_bridge: bool,
+    splash: bool,
 ) {
     if manifest.package.as_ref().is_none_or(String::is_empty) {
         manifest.package = Some(format!("com.crossbow.{}", library_name.replace('-', "_")));
@@ -62,8 +66,9 @@ pub fn update_android_manifest_with_default(
         });
     }
     if manifest.application.has_code.is_none() {
+        // Native builds only carry code when the prebuilt Crossbow bridge DEX is packaged.
         manifest.application.has_code =
-            VarOrBool::Bool(strategy == AndroidStrategy::GradleApk).into();
+            VarOrBool::Bool(strategy.is_gradle() || crossbow_bridge).into();
     }
     if manifest.application.label.is_none() {
         manifest.application.label = Some(StringResourceOrString::string(
@@ -71,10 +76,14 @@ pub fn update_android_manifest_with_default(
         ));
     }
     if manifest.application.theme.is_none() {
-        manifest.application.theme = Some(Resource::new_with_package(
-            "Theme.DeviceDefault.NoActionBar.Fullscreen",
-            Some("android".to_string()),
-        ));
+        manifest.application.theme = Some(if splash {
+            Resource::new(crate::commands::android::SPLASH_THEME)
+        } else {
+            Resource::new_with_package(
+                "Theme.DeviceDefault.NoActionBar.Fullscreen",
+                Some("android".to_string()),
+            )
+        });
     }
     if manifest.application.activity.is_empty() {
         manifest.application.activity = vec![Activity::default()];
@@ -82,20 +91,19 @@ pub fn update_android_manifest_with_default(
     if let [activity] = manifest.application.activity.as_mut_slice() {
         if activity.name.is_empty() {
             let package = manifest.package.as_deref().unwrap_or_default();
-            activity.name = match (strategy, runtime) {
-                (AndroidStrategy::GradleApk, super::AndroidRuntime::Miniquad)
-                    if crossbow_bridge =>
-                {
+            activity.name = match (strategy.is_gradle(), runtime) {
+                (true, super::AndroidRuntime::Miniquad) if crossbow_bridge => {
                     format!("{package}.CrossbowApp")
                 }
-                (AndroidStrategy::GradleApk, super::AndroidRuntime::Miniquad) => {
+                (true, super::AndroidRuntime::Miniquad) => {
                     format!("{package}.MainActivity")
                 }
-                (AndroidStrategy::GradleApk, super::AndroidRuntime::NativeActivity)
-                    if crossbow_bridge =>
-                {
+                (true, super::AndroidRuntime::NativeActivity) if crossbow_bridge => {
                     "com.crossbow.game.CrossbowApp".to_string()
                 }
+                (false, super::AndroidRuntime::NativeActivity) if crossbow_bridge => {
+                    "com.crossbow.library.CrossbowNativeActivity".to_string()
+                }
                 _ => "android.app.NativeActivity".to_string(),
             };
         }
@@ -153,6 +161,7 @@ mod tests {
             AndroidStrategy::GradleApk,
             super::super::AndroidRuntime::Miniquad,
             false,
+            false,
         );
 
         assert_eq!(
@@ -195,6 +204,7 @@ mod tests {
             AndroidStrategy::GradleApk,
             super::super::AndroidRuntime::NativeActivity,
             false,
+            false,
         );
 
         assert_eq!(
@@ -203,4 +213,44 @@ mod tests {
         );
         assert_eq!(manifest.application.activity[0].meta_data.len(), 1);
     }
+
+    #[test]
+    fn native_strategies_launch_the_prebuilt_bridge_activity() {
+        let mut manifest = AndroidManifest::default();
+        update_android_manifest_with_default(
+            &mut manifest,
+            None,
+            "my_game",
+            AndroidStrategy::NativeApk,
+            super::super::AndroidRuntime::NativeActivity,
+            true,
+            false,
+        );
+
+        assert_eq!(
+            launcher_activity(&manifest),
+            Some("com.crossbow.library.CrossbowNativeActivity")
+        );
+        assert_eq!(manifest.application.has_code, VarOrBool::Bool(true).into());
+        assert_eq!(manifest.application.activity[0].meta_data.len(), 1);
+    }
+
+    #[test]
+    fn splash_screen_replaces_default_theme() {
+        let mut manifest = AndroidManifest::default();
+        update_android_manifest_with_default(
+            &mut manifest,
+            None,
+            "my_game",
+            AndroidStrategy::NativeApk,
+            super::super::AndroidRuntime::NativeActivity,
+            false,
+            true,
+        );
+
+        assert_eq!(
+            manifest.application.theme,
+            Some(Resource::new("Theme.Crossbow.Splash"))
+        );
+    }
 }
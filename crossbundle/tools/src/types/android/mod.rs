@@ -1,15 +1,25 @@
 mod android_ndk;
 mod android_runtime;
 mod android_sdk;
+mod asset_pack;
 mod build_target;
+mod crossbow_plugin;
+mod feature_module;
 mod manifest;
+mod page_size;
+mod signing;
 mod strategies;
 
 pub use android_ndk::*;
 pub use android_runtime::*;
 pub use android_sdk::*;
+pub use asset_pack::*;
 pub use build_target::*;
+pub use crossbow_plugin::*;
+pub use feature_module::*;
 pub use manifest::*;
+pub use page_size::*;
+pub use signing::*;
 pub use strategies::*;
 
 pub use android_tools::aapt2::*;
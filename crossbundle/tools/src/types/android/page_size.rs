@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+
+/// Memory page size that packaged native libraries are aligned for.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum AndroidPageSize {
+    /// 4 KB pages used by devices before Android 15.
+    #[serde(rename = "4k")]
+    Page4K,
+    /// 16 KB pages. Libraries aligned for 16 KB also load on 4 KB devices, and Google
+    /// Play requires them for apps targeting Android 15 and later.
+    #[default]
+    #[serde(rename = "16k")]
+    Page16K,
+}
+
+impl AndroidPageSize {
+    /// Page size in bytes.
+    pub fn bytes(self) -> u64 {
+        match self {
+            Self::Page4K => 4096,
+            Self::Page16K => 16384,
+        }
+    }
+}
+
+impl std::fmt::Display for AndroidPageSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Page4K => write!(f, "4k"),
+            Self::Page16K => write!(f, "16k"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sixteen_kilobyte_pages_are_the_default() {
+        assert_eq!(AndroidPageSize::default().bytes(), 16384);
+        assert_eq!(
+            serde_json::from_str::<AndroidPageSize>("\"4k\"").unwrap(),
+            AndroidPageSize::Page4K
+        );
+    }
+}
@@ -0,0 +1,273 @@
+use crate::error::{AndroidError, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    io::BufRead,
+    path::{Path, PathBuf},
+};
+
+/// Password or other signing secret. Never printed, [`Secret::expose`] has to be called
+/// to read the value.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+
+    /// Returns the secret value. Only pass it to code that doesn't log it.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
+impl std::str::FromStr for Secret {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(Self::new(s))
+    }
+}
+
+/// Where a signing secret is read from. Secrets can't be written into `Cargo.toml`
+/// directly.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretSource {
+    /// Environment variable with the secret, e.g. `{ env = "UPLOAD_STORE_PASSWORD" }`.
+    Env(String),
+    /// File with the secret on its first line, e.g. `{ file = "secrets/store-password" }`.
+    File(PathBuf),
+    /// A line of standard input, e.g. `"stdin"`.
+    Stdin,
+}
+
+impl SecretSource {
+    /// Reads the secret from the process environment, the file system or stdin.
+    pub fn read(&self) -> Result<Secret> {
+        self.read_with(
+            |name| std::env::var(name).ok(),
+            &mut std::io::stdin().lock(),
+        )
+    }
+
+    /// Reads the secret with an injected environment and stdin.
+    pub fn read_with(
+        &self,
+        environment: impl FnOnce(&str) -> Option<String>,
+        stdin: &mut dyn BufRead,
+    ) -> Result<Secret> {
+        let unavailable = |reason: String| AndroidError::SigningSecretUnavailable(reason);
+        let value = match self {
+            Self::Env(name) => environment(name)
+                .ok_or_else(|| unavailable(format!("environment variable `{name}` is not set")))?,
+            Self::File(path) => first_line(
+                &std::fs::read_to_string(path)
+                    .map_err(|error| unavailable(format!("failed to read {path:?}: {error}")))?,
+            )
+            .to_owned(),
+            Self::Stdin => {
+                let mut line = String::new();
+                stdin
+                    .read_line(&mut line)
+                    .map_err(|error| unavailable(format!("failed to read stdin: {error}")))?;
+                first_line(&line).to_owned()
+            }
+        };
+        if value.is_empty() {
+            return Err(unavailable(format!("{} is empty", self.description())).into());
+        }
+        Ok(Secret::new(value))
+    }
+
+    /// Resolves a relative file path against the project directory.
+    pub fn resolve_path(&mut self, root: &Path) {
+        if let Self::File(path) = self
+            && path.is_relative()
+        {
+            *path = root.join(&*path);
+        }
+    }
+
+    fn description(&self) -> String {
+        match self {
+            Self::Env(name) => format!("environment variable `{name}`"),
+            Self::File(path) => format!("file {path:?}"),
+            Self::Stdin => "stdin".to_owned(),
+        }
+    }
+}
+
+impl std::str::FromStr for SecretSource {
+    type Err = AndroidError;
+
+    /// Parses `env:NAME`, `file:PATH` or `stdin`.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some(("env", name)) if !name.is_empty() => Ok(Self::Env(name.to_owned())),
+            Some(("file", path)) if !path.is_empty() => Ok(Self::File(path.into())),
+            None if s == "stdin" => Ok(Self::Stdin),
+            _ => Err(AndroidError::InvalidSigningConfig(format!(
+                "`{s}` is not a secret source, use `env:NAME`, `file:PATH` or `stdin`"
+            ))),
+        }
+    }
+}
+
+fn first_line(value: &str) -> &str {
+    value.lines().next().unwrap_or_default()
+}
+
+/// File format of a keystore.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeystoreType {
+    /// PKCS#12 keystore. Store and key passwords must be the same.
+    #[default]
+    Pkcs12,
+    /// Legacy Java keystore, which supports a key password separate from the store password.
+    Jks,
+}
+
+impl std::str::FromStr for KeystoreType {
+    type Err = AndroidError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "pkcs12" => Ok(Self::Pkcs12),
+            "jks" => Ok(Self::Jks),
+            _ => Err(AndroidError::InvalidKeystoreOptions(format!(
+                "unsupported store type `{s}`, use `pkcs12` or `jks`"
+            ))),
+        }
+    }
+}
+
+/// Release signing key configured in `package.metadata.android.signing`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SigningConfig {
+    /// Path to the JKS or PKCS#12 keystore.
+    pub keystore: PathBuf,
+    pub key_alias: String,
+    pub store_password: SecretSource,
+    /// Password of the key. Defaults to the store password.
+    pub key_password: Option<SecretSource>,
+    /// Expected keystore format. Detected from the keystore contents if not set.
+    pub store_type: Option<KeystoreType>,
+}
+
+impl SigningConfig {
+    pub fn resolve_paths(&mut self, root: &Path) {
+        if self.keystore.is_relative() {
+            self.keystore = root.join(&self.keystore);
+        }
+        self.store_password.resolve_path(root);
+        if let Some(key_password) = &mut self.key_password {
+            key_password.resolve_path(root);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+
+    #[test]
+    fn deserializes_secret_sources() {
+        let config: SigningConfig = toml::from_str(
+            r#"
+            keystore = "upload.p12"
+            key_alias = "upload"
+            store_password = { env = "UPLOAD_STORE_PASSWORD" }
+            key_password = "stdin"
+            store_type = "pkcs12"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.store_password,
+            SecretSource::Env("UPLOAD_STORE_PASSWORD".to_owned())
+        );
+        assert_eq!(config.key_password, Some(SecretSource::Stdin));
+        assert_eq!(config.store_type, Some(KeystoreType::Pkcs12));
+        assert!(
+            toml::from_str::<SigningConfig>(
+                r#"
+                keystore = "upload.p12"
+                key_alias = "upload"
+                store_password = "android"
+                "#,
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn parses_secret_sources_from_arguments() {
+        assert_eq!(
+            "env:STORE_PASS".parse::<SecretSource>().unwrap(),
+            SecretSource::Env("STORE_PASS".to_owned())
+        );
+        assert_eq!(
+            "file:secrets/pass".parse::<SecretSource>().unwrap(),
+            SecretSource::File("secrets/pass".into())
+        );
+        assert_eq!(
+            "stdin".parse::<SecretSource>().unwrap(),
+            SecretSource::Stdin
+        );
+        for source in ["android", "env:", "pass:android"] {
+            assert!(source.parse::<SecretSource>().is_err());
+        }
+    }
+
+    #[test]
+    fn reads_secrets() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("password");
+        std::fs::write(&path, "from-file\n").unwrap();
+        let mut stdin = std::io::Cursor::new("from-stdin\nsecond\n");
+        let environment = |name: &str| (name == "STORE_PASS").then(|| "from-env".to_owned());
+
+        let read = |source: SecretSource, stdin: &mut dyn BufRead| {
+            source
+                .read_with(environment, stdin)
+                .map(|secret| secret.expose().to_owned())
+        };
+        assert_eq!(
+            read(SecretSource::Env("STORE_PASS".into()), &mut stdin).unwrap(),
+            "from-env"
+        );
+        assert_eq!(
+            read(SecretSource::File(path), &mut stdin).unwrap(),
+            "from-file"
+        );
+        assert_eq!(read(SecretSource::Stdin, &mut stdin).unwrap(), "from-stdin");
+        assert_eq!(read(SecretSource::Stdin, &mut stdin).unwrap(), "second");
+        for source in [
+            SecretSource::Env("MISSING".into()),
+            SecretSource::File(temp_dir.path().join("missing")),
+            SecretSource::Stdin,
+        ] {
+            assert!(matches!(
+                read(source, &mut stdin),
+                Err(Error::Android(AndroidError::SigningSecretUnavailable(_)))
+            ));
+        }
+    }
+
+    #[test]
+    fn debug_output_redacts_secrets() {
+        let secret = Secret::new("upload-password");
+        assert_eq!(format!("{secret:?}"), "<redacted>");
+        assert_eq!(secret.expose(), "upload-password");
+    }
+}
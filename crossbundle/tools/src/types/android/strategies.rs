@@ -8,22 +8,34 @@ pub enum AndroidStrategy {
     #[default]
     #[serde(rename = "gradle-apk")]
     GradleApk,
-    /// Generate native .aab without Gradle. This strategy currently doesn't support
-    /// Crossbow plugins.
+    /// Generate .aab with Gradle. Unlike `native-aab`, supports Crossbow plugin binaries and
+    /// the Miniquad runtime.
+    #[serde(rename = "gradle-aab")]
+    GradleAab,
+    /// Generate native .apk without Gradle. Packages the prebuilt Crossbow bridge, but not
+    /// plugin binaries.
     #[serde(rename = "native-apk")]
     NativeApk,
-    /// Generate native .apk without Gradle. This strategy currently doesn't support
-    /// Crossbow plugins.
+    /// Generate native .aab without Gradle. Packages the prebuilt Crossbow bridge, but not
+    /// plugin binaries.
     #[serde(rename = "native-aab")]
     NativeAab,
 }
 
+impl AndroidStrategy {
+    /// Whether the application is packaged by a generated Gradle project.
+    pub fn is_gradle(self) -> bool {
+        matches!(self, Self::GradleApk | Self::GradleAab)
+    }
+}
+
 impl std::str::FromStr for AndroidStrategy {
     type Err = AndroidError;
 
     fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
         match s {
             "gradle-apk" => Ok(Self::GradleApk),
+            "gradle-aab" => Ok(Self::GradleAab),
             "native-apk" => Ok(Self::NativeApk),
             "native-aab" => Ok(Self::NativeAab),
             _ => Err(AndroidError::InvalidBuildStrategy(s.to_owned())),
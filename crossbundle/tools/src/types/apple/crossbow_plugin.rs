@@ -0,0 +1,79 @@
+use super::info_plist_from_json;
+use apple_bundle::prelude::InfoPlist;
+use serde::Serialize;
+
+/// Apple part of `package.metadata.crossbow-plugin` in a plugin crate.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ApplePluginMetadata {
+    /// Info.plist fragment merged into the application Info.plist.
+    pub info_plist: Option<InfoPlist>,
+}
+
+/// Crossbow plugin with Apple metadata found in the resolved Cargo dependencies of the
+/// project.
+#[derive(Debug, Clone, Serialize)]
+pub struct ApplePlugin {
+    /// Cargo package name of the plugin.
+    pub package: String,
+    pub version: String,
+    pub metadata: ApplePluginMetadata,
+}
+
+impl ApplePlugin {
+    /// Parses `package.metadata.crossbow-plugin.apple` of a Cargo package. Returns `None`
+    /// for packages that aren't Apple plugins.
+    pub fn from_package_metadata(
+        package: &str,
+        version: &str,
+        metadata: &serde_json::Value,
+    ) -> anyhow::Result<Option<Self>> {
+        let Some(apple) = metadata
+            .get("crossbow-plugin")
+            .and_then(|plugin| plugin.get("apple"))
+        else {
+            return Ok(None);
+        };
+        let info_plist = apple
+            .get("info_plist")
+            .cloned()
+            .map(info_plist_from_json)
+            .transpose()
+            .map_err(|error| {
+                anyhow::anyhow!(
+                    "invalid `package.metadata.crossbow-plugin.apple.info_plist` in `{package}`: {error}"
+                )
+            })?;
+        Ok(Some(Self {
+            package: package.to_owned(),
+            version: version.to_owned(),
+            metadata: ApplePluginMetadata { info_plist },
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_partial_info_plist_fragment() {
+        let plugin = ApplePlugin::from_package_metadata(
+            "camera",
+            "0.1.0",
+            &serde_json::json!({ "crossbow-plugin": { "apple": { "info_plist": {
+                "NSCameraUsageDescription": "Scan codes"
+            } } } }),
+        )
+        .unwrap()
+        .unwrap();
+        let info_plist = plugin.metadata.info_plist.unwrap();
+        assert!(info_plist.identification.bundle_identifier.is_empty());
+        assert_eq!(
+            info_plist
+                .camera_and_microphone
+                .camera_usage_description
+                .as_deref(),
+            Some("Scan codes")
+        );
+    }
+}
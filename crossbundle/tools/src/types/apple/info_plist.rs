@@ -2,6 +2,22 @@ pub use apple_bundle;
 
 use apple_bundle::prelude::*;
 
+/// Deserializes an Info.plist that may omit `CFBundleIdentifier`, like inline metadata and
+/// plugin fragments. A missing identifier is left empty for
+/// [`update_info_plist_with_default`].
+pub(crate) fn info_plist_from_json(mut value: serde_json::Value) -> serde_json::Result<InfoPlist> {
+    fill_bundle_identifier(&mut value);
+    serde_json::from_value(value)
+}
+
+pub(crate) fn fill_bundle_identifier(value: &mut serde_json::Value) {
+    if let Some(dictionary) = value.as_object_mut() {
+        dictionary
+            .entry("CFBundleIdentifier")
+            .or_insert_with(|| serde_json::Value::String(String::new()));
+    }
+}
+
 /// Updates [`InfoPlist`](InfoPlist) with default values.
 pub fn update_info_plist_with_default(
     props: &mut InfoPlist,
@@ -37,7 +53,10 @@ pub fn update_info_plist_with_default(
     if props.categorization.bundle_package_type.is_none() {
         props.categorization.bundle_package_type = Some("APPL".to_owned());
     }
-    if props.launch_interface.launch_storyboard_name.is_none() {
+    // A launch storyboard takes precedence over `UILaunchScreen`.
+    if props.launch_interface.launch_storyboard_name.is_none()
+        && props.launch_interface.launch_screen.is_none()
+    {
         props.launch_interface.launch_storyboard_name = Some("LaunchScreen".to_owned());
     }
 }
@@ -1,5 +1,7 @@
 mod build_target;
+mod crossbow_plugin;
 mod info_plist;
 
 pub use build_target::*;
+pub use crossbow_plugin::*;
 pub use info_plist::*;
@@ -98,14 +98,35 @@ pub(crate) fn interpolate_metadata(
 ) -> anyhow::Result<()> {
     // Limit expansion to the public platform documents named by the feature. In particular,
     // values must never flow into paths, plugin configuration, or other generated files.
-    for pointer in ["/android/manifest", "/apple/info_plist"] {
-        if let Some(value) = metadata.pointer_mut(pointer) {
+    for pointer in platform_document_pointers(metadata) {
+        if let Some(value) = metadata.pointer_mut(&pointer) {
             interpolate_json(value, variables)?;
         }
     }
     Ok(())
 }
 
+/// JSON pointers of the platform documents in project metadata, including the manifest
+/// overlays of `android.profiles`.
+pub(crate) fn platform_document_pointers(metadata: &Value) -> Vec<String> {
+    let mut pointers = vec![
+        "/android/manifest".to_owned(),
+        "/apple/info_plist".to_owned(),
+    ];
+    if let Some(profiles) = metadata
+        .pointer("/android/profiles")
+        .and_then(Value::as_object)
+    {
+        pointers.extend(profiles.keys().map(|name| {
+            format!(
+                "/android/profiles/{}/manifest",
+                name.replace('~', "~0").replace('/', "~1")
+            )
+        }));
+    }
+    pointers
+}
+
 pub(crate) fn take_definitions(metadata: &mut Value) -> anyhow::Result<BuildVariableDefinitions> {
     let Some(raw) = metadata
         .as_object_mut()
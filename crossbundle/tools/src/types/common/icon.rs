@@ -0,0 +1,156 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::path::{Path, PathBuf};
+
+/// Application icon configured with `icon` in the project metadata.
+///
+/// Either the path to a square image, or the layers of an adaptive icon:
+///
+/// ```toml
+/// icon = { foreground = "icon/foreground.png", background = "#1B1B1F", monochrome = "icon/monochrome.png" }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum IconConfig {
+    Path(PathBuf),
+    Layers(IconLayers),
+}
+
+impl IconConfig {
+    /// Image that represents the whole icon on platforms without layered icons.
+    pub fn source(&self) -> &Path {
+        match self {
+            Self::Path(path) => path,
+            Self::Layers(layers) => &layers.foreground,
+        }
+    }
+
+    pub fn resolve_paths(&mut self, root: &Path) {
+        match self {
+            Self::Path(path) => resolve(path, root),
+            Self::Layers(layers) => {
+                resolve(&mut layers.foreground, root);
+                if let IconBackground::Image(path) = &mut layers.background {
+                    resolve(path, root);
+                }
+                if let Some(monochrome) = &mut layers.monochrome {
+                    resolve(monochrome, root);
+                }
+            }
+        }
+    }
+}
+
+fn resolve(path: &mut PathBuf, root: &Path) {
+    if path.is_relative() {
+        *path = root.join(&*path);
+    }
+}
+
+/// Layers of an adaptive icon. Every layer covers 108x108 dp, of which launchers show at
+/// least the centered 72x72 dp.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct IconLayers {
+    /// Square image drawn over the background.
+    pub foreground: PathBuf,
+    /// Color like `#1B1B1F` or path to a square image. Defaults to white.
+    #[serde(default)]
+    pub background: IconBackground,
+    /// Square single-color image used by themed icons on Android 13 and newer.
+    pub monochrome: Option<PathBuf>,
+}
+
+/// Background layer of an adaptive icon.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IconBackground {
+    /// Color in the `#RGB`, `#RRGGBB` or `#AARRGGBB` format.
+    Color(String),
+    Image(PathBuf),
+}
+
+impl Default for IconBackground {
+    fn default() -> Self {
+        Self::Color("#FFFFFF".to_owned())
+    }
+}
+
+impl IconBackground {
+    /// Parses the color into RGBA components.
+    pub fn rgba(color: &str) -> Option<[u8; 4]> {
+        let hex = color.strip_prefix('#')?;
+        if !hex.chars().all(|char| char.is_ascii_hexdigit()) {
+            return None;
+        }
+        let channel = |index: usize, width: usize| {
+            let value = u8::from_str_radix(&hex[index * width..(index + 1) * width], 16).ok()?;
+            Some(if width == 1 { value * 17 } else { value })
+        };
+        match hex.len() {
+            3 => Some([channel(0, 1)?, channel(1, 1)?, channel(2, 1)?, 255]),
+            6 => Some([channel(0, 2)?, channel(1, 2)?, channel(2, 2)?, 255]),
+            8 => Some([
+                channel(1, 2)?,
+                channel(2, 2)?,
+                channel(3, 2)?,
+                channel(0, 2)?,
+            ]),
+            _ => None,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for IconBackground {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        if !value.starts_with('#') {
+            return Ok(Self::Image(value.into()));
+        }
+        if Self::rgba(&value).is_none() {
+            return Err(serde::de::Error::custom(format!(
+                "invalid icon background color `{value}`, expected #RGB, #RRGGBB or #AARRGGBB"
+            )));
+        }
+        Ok(Self::Color(value))
+    }
+}
+
+impl Serialize for IconBackground {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Color(color) => serializer.serialize_str(color),
+            Self::Image(path) => path.serialize(serializer),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_path_and_layered_icons() {
+        let icon: IconConfig = serde_json::from_value(serde_json::json!("icon.png")).unwrap();
+        assert_eq!(icon, IconConfig::Path("icon.png".into()));
+
+        let icon: IconConfig = serde_json::from_value(serde_json::json!({
+            "foreground": "foreground.png",
+            "background": "#80FF0000",
+            "monochrome": "monochrome.png"
+        }))
+        .unwrap();
+        let IconConfig::Layers(layers) = &icon else {
+            panic!("expected icon layers");
+        };
+        assert_eq!(layers.background, IconBackground::Color("#80FF0000".into()));
+        assert_eq!(IconBackground::rgba("#80FF0000"), Some([255, 0, 0, 128]));
+        assert_eq!(IconBackground::rgba("#fff"), Some([255, 255, 255, 255]));
+        assert_eq!(icon.source(), Path::new("foreground.png"));
+
+        let error = serde_json::from_value::<IconLayers>(serde_json::json!({
+            "foreground": "foreground.png",
+            "background": "#12345"
+        }))
+        .unwrap_err();
+        assert!(error.to_string().contains("#12345"));
+    }
+}
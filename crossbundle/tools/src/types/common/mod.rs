@@ -1,13 +1,21 @@
 mod build_variables;
 mod cli_context;
+mod icon;
 mod profile;
 mod shell;
+mod signer;
+mod splash;
 mod target;
+mod variant;
 mod version;
 
 pub use build_variables::*;
 pub use cli_context::*;
+pub use icon::*;
 pub use profile::*;
 pub use shell::*;
+pub use signer::*;
+pub use splash::*;
 pub use target::*;
+pub use variant::*;
 pub use version::*;
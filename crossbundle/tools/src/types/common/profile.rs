@@ -1,31 +1,108 @@
-use crate::error::Error;
+use crate::error::{Error, Result};
 use std::{path::Path, str::FromStr};
 
-#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
 pub enum Profile {
     #[default]
     Debug,
     Release,
+    /// Profile declared in `[profile.<name>]` of the workspace manifest, like
+    /// `release-lto`. `release` tells whether it inherits from the `release` profile.
+    Custom {
+        name: String,
+        release: bool,
+    },
 }
 
 impl Profile {
+    /// Resolves a Cargo profile name. Custom profiles are looked up in the workspace
+    /// manifest and follow their `inherits` chain up to `dev` or `release`.
+    pub fn resolve(name: &str, workspace_manifest: &Path) -> Result<Self> {
+        if let Ok(profile) = name.parse() {
+            return Ok(profile);
+        }
+        let manifest: toml::Value = toml::from_str(&std::fs::read_to_string(workspace_manifest)?)
+            .map_err(|error| {
+            anyhow::anyhow!("failed to parse {workspace_manifest:?}: {error}")
+        })?;
+        let profiles = manifest.get("profile").and_then(toml::Value::as_table);
+        let mut visited = vec![name];
+        let mut current = name;
+        let release = loop {
+            match current {
+                "dev" | "test" => break false,
+                "release" | "bench" => break true,
+                _ => {}
+            }
+            let inherits = profiles
+                .and_then(|profiles| profiles.get(current))
+                .ok_or_else(|| {
+                    Error::UndefinedProfile(current.to_owned(), workspace_manifest.into())
+                })?
+                .get("inherits")
+                .and_then(toml::Value::as_str)
+                .ok_or_else(|| Error::InvalidProfile(format!("`{current}` must set `inherits`")))?;
+            if visited.contains(&inherits) {
+                return Err(Error::InvalidProfile(format!(
+                    "`{name}` inherits from itself through `{inherits}`"
+                )));
+            }
+            visited.push(inherits);
+            current = inherits;
+        };
+        Ok(Self::Custom {
+            name: name.to_owned(),
+            release,
+        })
+    }
+
     pub fn is_debug(&self) -> bool {
-        Self::Debug == *self
+        !self.is_release()
     }
 
-    pub(crate) fn cargo_name(self) -> &'static str {
+    /// Whether the profile is `release` or inherits from it. Release profiles are signed
+    /// with the release configuration.
+    pub fn is_release(&self) -> bool {
         match self {
-            Self::Debug => "dev",
+            Self::Debug => false,
+            Self::Release => true,
+            Self::Custom { release, .. } => *release,
+        }
+    }
+
+    /// The built-in profile the profile inherits from.
+    pub fn base(&self) -> Self {
+        match self.is_release() {
+            true => Self::Release,
+            false => Self::Debug,
+        }
+    }
+
+    /// Name of the profile in `package.metadata`, like `debug` or `release-lto`.
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Debug => "debug",
             Self::Release => "release",
+            Self::Custom { name, .. } => name,
+        }
+    }
+
+    pub(crate) fn cargo_name(&self) -> &str {
+        match self {
+            Self::Debug => "dev",
+            profile => profile.name(),
         }
     }
 }
 
+/// Directory of the profile in the Cargo target directory. Cargo writes the artifacts of
+/// the built-in `test` and `bench` profiles into `debug` and `release`.
 impl AsRef<Path> for Profile {
     fn as_ref(&self) -> &Path {
-        Path::new(match self {
-            Self::Debug => "debug",
-            Self::Release => "release",
+        Path::new(match self.name() {
+            "test" => "debug",
+            "bench" => "release",
+            name => name,
         })
     }
 }
@@ -33,9 +110,9 @@ impl AsRef<Path> for Profile {
 impl FromStr for Profile {
     type Err = Error;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
         match s {
-            "debug" => Ok(Profile::Debug),
+            "debug" | "dev" => Ok(Profile::Debug),
             "release" => Ok(Profile::Release),
             _ => Err(Error::InvalidProfile(s.to_owned())),
         }
@@ -44,9 +121,67 @@ impl FromStr for Profile {
 
 impl std::fmt::Display for Profile {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(match self {
-            Self::Debug => "debug",
-            Self::Release => "release",
-        })
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_custom_profiles_through_inherits() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = dir.path().join("Cargo.toml");
+        std::fs::write(
+            &manifest,
+            r#"
+[profile.release-lto]
+inherits = "release"
+lto = true
+
+[profile.profiling]
+inherits = "release-lto"
+debug = true
+
+[profile.fast-dev]
+inherits = "dev"
+
+[profile.looping]
+inherits = "looping"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(Profile::resolve("dev", &manifest).unwrap(), Profile::Debug);
+        let profiling = Profile::resolve("profiling", &manifest).unwrap();
+        assert!(profiling.is_release());
+        assert_eq!(profiling.cargo_name(), "profiling");
+        assert_eq!(profiling.as_ref(), Path::new("profiling"));
+        assert_eq!(profiling.base(), Profile::Release);
+        assert!(Profile::resolve("fast-dev", &manifest).unwrap().is_debug());
+        assert!(matches!(
+            Profile::resolve("missing", &manifest),
+            Err(Error::UndefinedProfile(..))
+        ));
+        assert!(matches!(
+            Profile::resolve("looping", &manifest),
+            Err(Error::InvalidProfile(_))
+        ));
+    }
+
+    #[test]
+    fn test_and_bench_profiles_use_cargo_artifact_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = dir.path().join("Cargo.toml");
+        std::fs::write(&manifest, "").unwrap();
+        let test = Profile::resolve("test", &manifest).unwrap();
+        assert!(test.is_debug());
+        assert_eq!(test.cargo_name(), "test");
+        assert_eq!(test.as_ref(), Path::new("debug"));
+        let bench = Profile::resolve("bench", &manifest).unwrap();
+        assert!(bench.is_release());
+        assert_eq!(bench.cargo_name(), "bench");
+        assert_eq!(bench.as_ref(), Path::new("release"));
     }
 }
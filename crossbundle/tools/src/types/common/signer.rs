@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Program that signs release artifacts instead of crossbundle, e.g. a client of a signing
+/// service or a script that copies the artifact to a separate machine.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExternalSigner {
+    /// Program and its arguments. `{input}`, `{output}` and `{kind}` arguments are
+    /// replaced with the unsigned artifact, the signed artifact path and the
+    /// [`SignedArtifactKind`].
+    pub command: Vec<String>,
+    #[serde(default)]
+    pub mode: SignerMode,
+    /// PEM or DER certificate of the signing key. Required in `digest` mode.
+    pub certificate: Option<PathBuf>,
+}
+
+impl ExternalSigner {
+    /// Resolves the certificate and a relative program path, like `./sign.sh`, against
+    /// the project directory.
+    pub fn resolve_paths(&mut self, root: &Path) {
+        if let Some(program) = self.command.first_mut() {
+            let path = Path::new(program.as_str());
+            if path.is_relative() && path.components().count() > 1 {
+                *program = root.join(path).to_string_lossy().into_owned();
+            }
+        }
+        if let Some(certificate) = &mut self.certificate
+            && certificate.is_relative()
+        {
+            *certificate = root.join(&*certificate);
+        }
+    }
+}
+
+/// What an [`ExternalSigner`] signs.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SignerMode {
+    /// The program gets the unsigned artifact and writes the signed one.
+    #[default]
+    Artifact,
+    /// The program gets the SHA-256 digest of the data to sign on stdin and writes the
+    /// RSASSA-PKCS1-v1_5 signature to stdout. Crossbundle embeds the signature itself.
+    /// Only supported for APKs and AABs.
+    Digest,
+}
+
+/// Kind of artifact passed to an [`ExternalSigner`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignedArtifactKind {
+    Apk,
+    Aab,
+    /// Apple `.app` bundle directory.
+    App,
+}
+
+impl SignedArtifactKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Apk => "apk",
+            Self::Aab => "aab",
+            Self::App => "app",
+        }
+    }
+}
+
+impl std::fmt::Display for SignedArtifactKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -0,0 +1,109 @@
+use super::IconBackground;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Screen shown while the application starts, configured with `splash` in the project
+/// metadata.
+///
+/// ```toml
+/// [package.metadata.splash]
+/// background = "#1B1B1F"
+/// image = "splash/logo.svg"
+/// dark = { background = "#000000", image = "splash/logo-dark.svg" }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SplashConfig {
+    /// Color like `#1B1B1F`. Defaults to white.
+    #[serde(default = "default_background", deserialize_with = "deserialize_color")]
+    pub background: String,
+    /// Square or SVG image centered on the background.
+    pub image: Option<PathBuf>,
+    /// Background and image used in dark mode. Unset values are taken from the light
+    /// splash screen.
+    pub dark: Option<SplashVariant>,
+}
+
+/// Values of a [`SplashConfig`] that differ in dark mode.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SplashVariant {
+    #[serde(default, deserialize_with = "deserialize_optional_color")]
+    pub background: Option<String>,
+    pub image: Option<PathBuf>,
+}
+
+impl SplashConfig {
+    /// Background color in dark mode.
+    pub fn dark_background(&self) -> &str {
+        self.dark
+            .as_ref()
+            .and_then(|dark| dark.background.as_deref())
+            .unwrap_or(&self.background)
+    }
+
+    /// Image shown in dark mode.
+    pub fn dark_image(&self) -> Option<&Path> {
+        self.dark
+            .as_ref()
+            .and_then(|dark| dark.image.as_deref())
+            .or(self.image.as_deref())
+    }
+
+    pub fn resolve_paths(&mut self, root: &Path) {
+        let dark = self.dark.as_mut().and_then(|dark| dark.image.as_mut());
+        for path in self.image.iter_mut().chain(dark) {
+            if path.is_relative() {
+                *path = root.join(&*path);
+            }
+        }
+    }
+}
+
+fn default_background() -> String {
+    "#FFFFFF".to_owned()
+}
+
+fn deserialize_color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let color = String::deserialize(deserializer)?;
+    if IconBackground::rgba(&color).is_none() {
+        return Err(serde::de::Error::custom(format!(
+            "invalid splash background color `{color}`, expected #RGB, #RRGGBB or #AARRGGBB"
+        )));
+    }
+    Ok(color)
+}
+
+fn deserialize_optional_color<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error> {
+    deserialize_color(deserializer).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dark_splash_falls_back_to_light_values() {
+        let mut splash: SplashConfig = serde_json::from_value(serde_json::json!({
+            "background": "#1B1B1F",
+            "image": "logo.svg",
+            "dark": { "background": "#000" }
+        }))
+        .unwrap();
+        splash.resolve_paths(Path::new("/project"));
+        assert_eq!(splash.dark_background(), "#000");
+        assert_eq!(splash.dark_image(), Some(Path::new("/project/logo.svg")));
+
+        let splash: SplashConfig = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert_eq!(splash.background, "#FFFFFF");
+        assert_eq!(splash.dark_image(), None);
+
+        let error = serde_json::from_value::<SplashConfig>(serde_json::json!({
+            "dark": { "background": "black" }
+        }))
+        .unwrap_err();
+        assert!(error.to_string().contains("`black`"));
+    }
+}
@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Build variant selected with `--variant`, declared in
+/// `package.metadata.crossbow.variants.<name>`.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BuildVariant {
+    pub name: String,
+    /// Cargo features the variant activates in addition to the requested ones.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub features: Vec<String>,
+}
+
+/// Values a variant overrides in the project metadata.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct VariantOverrides {
+    app_name: Option<Value>,
+    icon: Option<Value>,
+    assets: Option<Value>,
+    #[serde(default)]
+    features: Vec<String>,
+    android: Option<AndroidOverrides>,
+    apple: Option<AppleOverrides>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AndroidOverrides {
+    manifest: Option<Value>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AppleOverrides {
+    info_plist: Option<Value>,
+}
+
+pub(crate) type VariantDefinitions = BTreeMap<String, VariantOverrides>;
+
+/// Removes `crossbow.variants` from the metadata.
+pub(crate) fn take_variants(metadata: &mut Value) -> anyhow::Result<VariantDefinitions> {
+    let Some(crossbow) = metadata
+        .as_object_mut()
+        .and_then(|metadata| metadata.get_mut("crossbow"))
+        .and_then(Value::as_object_mut)
+    else {
+        return Ok(VariantDefinitions::new());
+    };
+    let Some(raw) = crossbow.remove("variants") else {
+        return Ok(VariantDefinitions::new());
+    };
+    if crossbow.is_empty() {
+        metadata.as_object_mut().unwrap().remove("crossbow");
+    }
+    serde_json::from_value(raw)
+        .map_err(|error| anyhow::anyhow!("invalid `package.metadata.crossbow.variants`: {error}"))
+}
+
+/// Applies the overrides of the variant `name` to the metadata.
+pub(crate) fn apply_variant(
+    metadata: &mut Value,
+    variants: &VariantDefinitions,
+    name: &str,
+) -> anyhow::Result<BuildVariant> {
+    let Some(overrides) = variants.get(name) else {
+        if variants.is_empty() {
+            anyhow::bail!(
+                "unknown variant `{name}`; declare it in `package.metadata.crossbow.variants`"
+            );
+        }
+        let names = variants.keys().map(String::as_str).collect::<Vec<_>>();
+        anyhow::bail!(
+            "unknown variant `{name}`; declared variants are {}",
+            names.join(", ")
+        );
+    };
+    let metadata = metadata
+        .as_object_mut()
+        .ok_or_else(|| anyhow::anyhow!("`package.metadata` must be a table"))?;
+    for (key, value) in [
+        ("app_name", &overrides.app_name),
+        ("icon", &overrides.icon),
+        ("assets", &overrides.assets),
+    ] {
+        if let Some(value) = value {
+            metadata.insert(key.to_owned(), value.clone());
+        }
+    }
+    let documents = [
+        (
+            "android",
+            "manifest",
+            overrides.android.as_ref().and_then(|a| a.manifest.as_ref()),
+        ),
+        (
+            "apple",
+            "info_plist",
+            overrides.apple.as_ref().and_then(|a| a.info_plist.as_ref()),
+        ),
+    ];
+    for (platform, document, value) in documents {
+        if let Some(value) = value {
+            let base = metadata
+                .entry(platform)
+                .or_insert_with(|| Value::Object(Default::default()))
+                .as_object_mut()
+                .ok_or_else(|| anyhow::anyhow!("`package.metadata.{platform}` must be a table"))?
+                .entry(document)
+                .or_insert(Value::Null);
+            merge_values(base, value);
+        }
+    }
+    Ok(BuildVariant {
+        name: name.to_owned(),
+        features: overrides.features.clone(),
+    })
+}
+
+/// Merges tables key by key. Any other value replaces the base value.
+fn merge_values(base: &mut Value, overlay: &Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                merge_values(base.entry(key).or_insert(Value::Null), value);
+            }
+        }
+        (base, overlay) => *base = overlay.clone(),
+    }
+}
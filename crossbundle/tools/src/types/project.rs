@@ -1,19 +1,26 @@
 use crossbow::Permission;
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::BTreeMap,
     fmt,
     path::{Path, PathBuf},
 };
 
 use super::{
-    BuildVariableDefinitions, BuildVariables, interpolate_metadata, resolve_definitions,
-    resolve_process_environment, take_definitions,
+    BuildVariableDefinitions, BuildVariables, BuildVariant, IconConfig, SplashConfig,
+    VariantDefinitions, apply_variant, interpolate_metadata, platform_document_pointers,
+    resolve_definitions, resolve_process_environment, take_definitions, take_variants,
 };
 
 #[cfg(feature = "android")]
-use crate::types::{AndroidRuntime, AndroidTarget, android_manifest::AndroidManifest};
+use crate::types::{
+    AndroidPageSize, AndroidPlugin, AndroidRuntime, AndroidTarget, AssetPack, FeatureModule,
+    SigningConfig, android_manifest::AndroidManifest,
+};
 #[cfg(feature = "apple")]
-use crate::types::{IosTarget, apple_bundle::prelude::InfoPlist};
+use crate::types::{ApplePlugin, IosTarget, apple_bundle::prelude::InfoPlist};
+#[cfg(any(feature = "android", feature = "apple"))]
+use crate::types::{ExternalSigner, Profile};
 
 /// Typed Android plugin configuration shared by builds and cross-platform diagnostics.
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
@@ -39,6 +46,11 @@ impl AndroidGradlePlugins {
             && self.maven_repos.is_empty()
             && self.local_projects.is_empty()
     }
+
+    /// Whether any plugin code has to be compiled and packaged by Gradle.
+    pub fn has_binaries(&self) -> bool {
+        !self.local.is_empty() || !self.remote.is_empty() || !self.local_projects.is_empty()
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -65,12 +77,16 @@ pub struct ProjectConfig {
     /// Resolved allow-listed values used by platform configuration templates.
     #[serde(skip)]
     build_variables: BuildVariables,
+    /// Variant whose overrides are applied.
+    #[serde(skip)]
+    variant: Option<BuildVariant>,
     pub app_name: Option<String>,
     #[serde(default)]
     pub assets: Vec<PathBuf>,
     #[serde(default)]
     pub permissions: Vec<Permission>,
-    pub icon: Option<PathBuf>,
+    pub icon: Option<IconConfig>,
+    pub splash: Option<SplashConfig>,
     #[cfg(feature = "android")]
     #[serde(default)]
     pub android: AndroidConfig,
@@ -83,8 +99,8 @@ impl fmt::Debug for ProjectConfig {
     fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut metadata = serde_json::to_value(self).map_err(|_| fmt::Error)?;
         if !self.build_variables.is_empty() {
-            for pointer in ["/android/manifest", "/apple/info_plist"] {
-                if let Some(value) = metadata.pointer_mut(pointer) {
+            for pointer in platform_document_pointers(&metadata) {
+                if let Some(value) = metadata.pointer_mut(&pointer) {
                     *value = serde_json::Value::String("<redacted>".into());
                 }
             }
@@ -99,6 +115,11 @@ impl ProjectConfig {
         &self.build_variables
     }
 
+    /// Returns the selected build variant.
+    pub fn variant(&self) -> Option<&BuildVariant> {
+        self.variant.as_ref()
+    }
+
     #[cfg(feature = "android")]
     pub fn android_assets(&self) -> &[PathBuf] {
         if self.android.assets.is_empty() {
@@ -118,6 +139,37 @@ impl ProjectConfig {
         !self.permissions.is_empty() || !self.android.plugins.is_empty()
     }
 
+    /// Wires Crossbow plugins discovered in the Cargo dependencies into the Gradle
+    /// configuration. Projects and coordinates declared by the application take
+    /// precedence. Plugins that only provide support projects are wired only when the
+    /// Crossbow bridge is used anyway.
+    #[cfg(feature = "android")]
+    pub fn add_android_plugins(&mut self, plugins: Vec<AndroidPlugin>) {
+        let crossbow_bridge =
+            self.android_uses_crossbow_bridge() || plugins.iter().any(AndroidPlugin::has_binaries);
+        let gradle = &mut self.android.plugins;
+        for plugin in plugins
+            .iter()
+            .filter(|plugin| crossbow_bridge || plugin.has_binaries())
+        {
+            if plugin.has_local_projects() {
+                for project in &plugin.metadata.local_projects {
+                    if !gradle
+                        .local_projects
+                        .iter()
+                        .any(|existing| existing.include == project.include)
+                    {
+                        gradle.local_projects.push(project.clone());
+                    }
+                }
+            } else {
+                extend_unique(&mut gradle.remote, &plugin.metadata.remote);
+            }
+            extend_unique(&mut gradle.maven_repos, &plugin.metadata.maven_repos);
+        }
+        self.android.discovered_plugins = plugins;
+    }
+
     #[cfg(feature = "apple")]
     pub fn apple_assets(&self) -> &[PathBuf] {
         if self.apple.assets.is_empty() {
@@ -135,13 +187,38 @@ impl ProjectConfig {
     /// Resolves every configured filesystem path relative to the selected Cargo package.
     pub fn resolve_paths(&mut self, root: &Path) {
         resolve_paths(&mut self.assets, root);
-        resolve_path(&mut self.icon, root);
+        if let Some(icon) = &mut self.icon {
+            icon.resolve_paths(root);
+        }
+        if let Some(splash) = &mut self.splash {
+            splash.resolve_paths(root);
+        }
         #[cfg(feature = "android")]
         {
             resolve_path(&mut self.android.manifest_path, root);
+            for profile in self.android.profiles.values_mut() {
+                resolve_path(&mut profile.manifest_path, root);
+            }
             resolve_paths(&mut self.android.resources, root);
             resolve_paths(&mut self.android.assets, root);
             resolve_paths(&mut self.android.plugins.local, root);
+            for pack in &mut self.android.asset_packs {
+                if pack.directory.is_relative() {
+                    pack.directory = root.join(&pack.directory);
+                }
+            }
+            for module in &mut self.android.feature_modules {
+                if module.path.is_relative() {
+                    module.path = root.join(&module.path);
+                }
+                resolve_paths(&mut module.assets, root);
+            }
+            if let Some(signing) = &mut self.android.signing {
+                signing.resolve_paths(root);
+            }
+            if let Some(signer) = &mut self.android.signer {
+                signer.resolve_paths(root);
+            }
             for project in &mut self.android.plugins.local_projects {
                 resolve_path(&mut project.project_dir, root);
             }
@@ -151,6 +228,18 @@ impl ProjectConfig {
             resolve_path(&mut self.apple.info_plist_path, root);
             resolve_paths(&mut self.apple.resources, root);
             resolve_paths(&mut self.apple.assets, root);
+            if let Some(signer) = &mut self.apple.signer {
+                signer.resolve_paths(root);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "android")]
+fn extend_unique(values: &mut Vec<String>, new_values: &[String]) {
+    for value in new_values {
+        if !values.contains(value) {
+            values.push(value.clone());
         }
     }
 }
@@ -163,6 +252,26 @@ fn resolve_path(path: &mut Option<PathBuf>, root: &Path) {
     }
 }
 
+/// Build targets of the profile: `build_targets.<profile>`, then `debug_build_targets`
+/// or `release_build_targets` depending on the profile the profile inherits from.
+#[cfg(any(feature = "android", feature = "apple"))]
+fn profile_build_targets<'a, T>(
+    build_targets: &'a BTreeMap<String, Vec<T>>,
+    debug: &'a [T],
+    release: &'a [T],
+    profile: &Profile,
+) -> &'a [T] {
+    if let Some(targets) = build_targets.get(profile.name())
+        && !targets.is_empty()
+    {
+        return targets;
+    }
+    match profile.is_release() {
+        true => release,
+        false => debug,
+    }
+}
+
 fn resolve_paths(paths: &mut [PathBuf], root: &Path) {
     for path in paths {
         if path.is_relative() {
@@ -178,25 +287,91 @@ pub struct AndroidConfig {
     pub runtime: AndroidRuntime,
     pub manifest: Option<AndroidManifest>,
     pub manifest_path: Option<PathBuf>,
+    /// Manifest overlays of Cargo profiles, keyed by profile name like `release`. They
+    /// take precedence over `manifest` and `manifest_path`.
+    #[serde(default)]
+    pub profiles: BTreeMap<String, AndroidProfileConfig>,
     #[serde(default)]
     pub resources: Vec<PathBuf>,
     #[serde(default)]
     pub assets: Vec<PathBuf>,
+    /// Build targets of Cargo profiles, keyed by profile name like `release-lto`.
+    #[serde(default)]
+    pub build_targets: BTreeMap<String, Vec<AndroidTarget>>,
     #[serde(default)]
     pub debug_build_targets: Vec<AndroidTarget>,
     #[serde(default)]
     pub release_build_targets: Vec<AndroidTarget>,
+    /// Page size that uncompressed native libraries are aligned for inside APKs.
+    #[serde(default)]
+    pub page_size: AndroidPageSize,
+    /// Build one APK per build target with an ABI-derived version code instead of a
+    /// single APK with every target. Only used by the `native-apk` strategy.
+    #[serde(default)]
+    pub split_per_abi: bool,
+    /// Play Asset Delivery packs added as separate modules to AABs.
+    #[serde(default)]
+    pub asset_packs: Vec<AssetPack>,
+    /// Dynamic feature modules added to AABs.
+    #[serde(default)]
+    pub feature_modules: Vec<FeatureModule>,
+    /// Keystore that signs release builds of native strategies.
+    pub signing: Option<SigningConfig>,
+    /// Program that signs release builds of native strategies instead of a keystore.
+    pub signer: Option<ExternalSigner>,
     #[serde(flatten)]
     pub plugins: AndroidGradlePlugins,
+    /// Crossbow plugins found in the Cargo dependencies, see
+    /// [`ProjectConfig::add_android_plugins`].
+    #[serde(skip)]
+    pub discovered_plugins: Vec<AndroidPlugin>,
+}
+
+#[cfg(feature = "android")]
+impl AndroidConfig {
+    /// Build targets of the profile, empty when the metadata doesn't set any.
+    pub fn profile_build_targets(&self, profile: &Profile) -> &[AndroidTarget] {
+        profile_build_targets(
+            &self.build_targets,
+            &self.debug_build_targets,
+            &self.release_build_targets,
+            profile,
+        )
+    }
+}
+
+/// Android configuration of one Cargo profile in `android.profiles.<name>`.
+#[cfg(feature = "android")]
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+pub struct AndroidProfileConfig {
+    /// Inline manifest overlay. Its values replace the ones of the app manifest.
+    pub manifest: Option<AndroidManifest>,
+    /// `AndroidManifest.xml` overlay that may use `tools:` merge directives.
+    pub manifest_path: Option<PathBuf>,
 }
 
 /// Cargo metadata parsed independently of the process environment.
 pub struct ParsedProjectConfig {
     metadata: serde_json::Value,
     build_variables: BuildVariableDefinitions,
+    variants: VariantDefinitions,
+    variant: Option<BuildVariant>,
 }
 
 impl ParsedProjectConfig {
+    /// Applies the overrides of a variant declared in `crossbow.variants`.
+    pub fn with_variant(mut self, name: Option<&str>) -> anyhow::Result<Self> {
+        if let Some(name) = name {
+            self.variant = Some(apply_variant(&mut self.metadata, &self.variants, name)?);
+        }
+        Ok(self)
+    }
+
+    /// Returns the variant selected with [`with_variant`](Self::with_variant).
+    pub fn variant(&self) -> Option<&BuildVariant> {
+        self.variant.as_ref()
+    }
+
     /// Resolves declared build variables and deserializes the typed project configuration.
     pub fn resolve(self) -> anyhow::Result<ProjectConfig> {
         let build_variables = resolve_process_environment(&self.build_variables)?;
@@ -214,8 +389,14 @@ impl ParsedProjectConfig {
 
     fn finish(mut self, build_variables: BuildVariables) -> anyhow::Result<ProjectConfig> {
         interpolate_metadata(&mut self.metadata, &build_variables)?;
+        #[cfg(feature = "apple")]
+        if let Some(info_plist) = self.metadata.pointer_mut("/apple/info_plist") {
+            // Inline values override the Info.plist file, so they may omit the identifier.
+            crate::types::fill_bundle_identifier(info_plist);
+        }
         let mut config: ProjectConfig = serde_json::from_value(self.metadata)?;
         config.build_variables = build_variables;
+        config.variant = self.variant;
         Ok(config)
     }
 }
@@ -228,6 +409,7 @@ pub fn parse_project_config(
         metadata = serde_json::Value::Object(Default::default());
     }
     let build_variables = take_definitions(&mut metadata)?;
+    let variants = take_variants(&mut metadata)?;
     #[cfg(feature = "android")]
     if let Some(android) = metadata.get("android") {
         if android.get("rust_compiler").is_some() {
@@ -244,6 +426,8 @@ pub fn parse_project_config(
     Ok(ParsedProjectConfig {
         metadata,
         build_variables,
+        variants,
+        variant: None,
     })
 }
 
@@ -251,6 +435,101 @@ pub fn parse_project_config(
 mod project_config_tests {
     use super::*;
 
+    #[test]
+    fn variant_overrides_metadata_and_platform_documents() {
+        let metadata = serde_json::json!({
+            "app_name": "Game",
+            "assets": ["assets"],
+            "android": { "manifest": { "package": "com.example.game", "version_code": 3 } },
+            "crossbow": {
+                "variants": {
+                    "staging": {
+                        "app_name": "Game Staging",
+                        "features": ["staging"],
+                        "android": { "manifest": { "package": "com.example.game.staging" } },
+                        "apple": { "info_plist": { "CFBundleIdentifier": "com.example.game.staging" } }
+                    },
+                    "paid": { "assets": ["assets", "paid"] }
+                }
+            }
+        });
+        let config = parse_project_config(metadata.clone())
+            .unwrap()
+            .with_variant(Some("staging"))
+            .unwrap()
+            .resolve_with(|_| Ok(None))
+            .unwrap();
+
+        let variant = config.variant().unwrap();
+        assert_eq!(variant.name, "staging");
+        assert_eq!(variant.features, ["staging"]);
+        assert_eq!(config.app_name.as_deref(), Some("Game Staging"));
+        assert_eq!(config.assets, [PathBuf::from("assets")]);
+        #[cfg(feature = "android")]
+        {
+            let manifest = config.android.manifest.as_ref().unwrap();
+            assert_eq!(
+                manifest.package.as_deref(),
+                Some("com.example.game.staging")
+            );
+            assert_eq!(manifest.version_code, Some(3));
+        }
+        #[cfg(feature = "apple")]
+        assert_eq!(
+            config
+                .apple
+                .info_plist
+                .as_ref()
+                .unwrap()
+                .identification
+                .bundle_identifier,
+            "com.example.game.staging"
+        );
+
+        let error = parse_project_config(metadata)
+            .unwrap()
+            .with_variant(Some("free"))
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "unknown variant `free`; declared variants are paid, staging"
+        );
+    }
+
+    #[cfg(feature = "android")]
+    #[test]
+    fn build_targets_are_keyed_by_profile_name() {
+        let config = parse_project_config(serde_json::json!({
+            "android": {
+                "release_build_targets": ["aarch64-linux-android"],
+                "build_targets": { "profiling": ["x86_64-linux-android"] }
+            }
+        }))
+        .unwrap()
+        .resolve_with(|_| Ok(None))
+        .unwrap();
+        let custom = |name: &str| Profile::Custom {
+            name: name.to_owned(),
+            release: true,
+        };
+
+        assert_eq!(
+            config.android.profile_build_targets(&custom("profiling")),
+            [AndroidTarget::X8664]
+        );
+        assert_eq!(
+            config.android.profile_build_targets(&custom("release-lto")),
+            [AndroidTarget::Aarch64]
+        );
+        assert!(
+            config
+                .android
+                .profile_build_targets(&Profile::Debug)
+                .is_empty()
+        );
+    }
+
     #[test]
     fn parsing_does_not_read_the_environment() {
         let parsed = parse_project_config(serde_json::json!({
@@ -272,8 +551,24 @@ mod project_config_tests {
         let mut config = parse_project_config(serde_json::json!({
             "assets": ["assets"],
             "icon": "icon.png",
-            "android": { "manifest_path": "AndroidManifest.xml" },
-            "apple": { "info_plist_path": "Info.plist" }
+            "android": {
+                "manifest_path": "AndroidManifest.xml",
+                "asset_packs": [{ "name": "levels", "directory": "packs/levels" }],
+                "signing": {
+                    "keystore": "upload.p12",
+                    "key_alias": "upload",
+                    "store_password": { "file": "secrets/store-password" }
+                },
+                "signer": {
+                    "command": ["scripts/sign.sh", "{input}"],
+                    "mode": "digest",
+                    "certificate": "upload.pem"
+                }
+            },
+            "apple": {
+                "info_plist_path": "Info.plist",
+                "signer": { "command": ["codesign-service", "{input}", "{output}"] }
+            }
         }))
         .unwrap()
         .resolve_with(|_| Ok(None))
@@ -284,7 +579,7 @@ mod project_config_tests {
 
         assert_eq!(config.assets, [root.join("assets")]);
         assert_eq!(
-            config.icon.as_deref(),
+            config.icon.as_ref().map(IconConfig::source),
             Some(root.join("icon.png").as_path())
         );
         #[cfg(feature = "android")]
@@ -292,11 +587,39 @@ mod project_config_tests {
             config.android.manifest_path.as_deref(),
             Some(root.join("AndroidManifest.xml").as_path())
         );
+        #[cfg(feature = "android")]
+        assert_eq!(
+            config.android.asset_packs[0].directory,
+            root.join("packs/levels")
+        );
+        #[cfg(feature = "android")]
+        {
+            let signing = config.android.signing.as_ref().unwrap();
+            assert_eq!(signing.keystore, root.join("upload.p12"));
+            assert_eq!(
+                signing.store_password,
+                crate::types::SecretSource::File(root.join("secrets/store-password"))
+            );
+            let signer = config.android.signer.as_ref().unwrap();
+            assert_eq!(
+                signer.command,
+                [
+                    root.join("scripts/sign.sh").to_string_lossy(),
+                    "{input}".into()
+                ]
+            );
+            assert_eq!(signer.certificate, Some(root.join("upload.pem")));
+        }
         #[cfg(feature = "apple")]
         assert_eq!(
             config.apple.info_plist_path.as_deref(),
             Some(root.join("Info.plist").as_path())
         );
+        #[cfg(feature = "apple")]
+        assert_eq!(
+            config.apple.signer.as_ref().unwrap().command[0],
+            "codesign-service"
+        );
     }
 }
 
@@ -323,6 +646,70 @@ mod android_config_tests {
         assert!(error.contains("app_wrapper` was removed"));
     }
 
+    #[test]
+    fn wires_discovered_plugins_into_gradle_configuration() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(root.path().join("crossbow-android")).unwrap();
+        std::fs::create_dir_all(root.path().join("play-billing/android")).unwrap();
+        let plugin = |package: &str, metadata: serde_json::Value| {
+            AndroidPlugin::from_package_metadata(
+                package,
+                "0.2.3",
+                &root.path().join(package).join("Cargo.toml"),
+                &serde_json::json!({ "crossbow-plugin": { "android": metadata } }),
+            )
+            .unwrap()
+            .unwrap()
+        };
+        let support = plugin(
+            "crossbow-android",
+            serde_json::json!({
+                "local_projects": [{ "include": ":crossbow", "dont_implement": true, "project_dir": "." }]
+            }),
+        );
+        let billing = plugin(
+            "play-billing",
+            serde_json::json!({
+                "remote": ["com.crossbow.play_billing:play_billing:0.2.3"],
+                "local_projects": [{ "include": ":play_billing", "project_dir": "android" }]
+            }),
+        );
+        let games = plugin(
+            "play-games-services",
+            serde_json::json!({
+                "remote": ["com.crossbow.play_games_services:play_games_services:0.2.3"],
+                "maven_repos": ["https://maven.example.com"],
+                "local_projects": [{ "include": ":play_games_services", "project_dir": "android" }]
+            }),
+        );
+
+        let mut config = ProjectConfig::default();
+        config.add_android_plugins(vec![support.clone()]);
+        assert!(config.android.plugins.is_empty());
+        assert!(!config.android_uses_crossbow_bridge());
+
+        let mut config = ProjectConfig::default();
+        config.add_android_plugins(vec![support, billing, games]);
+        let includes = config
+            .android
+            .plugins
+            .local_projects
+            .iter()
+            .map(GradleDependencyProject::include)
+            .collect::<Vec<_>>();
+        assert_eq!(includes, [":crossbow", ":play_billing"]);
+        assert_eq!(
+            config.android.plugins.remote,
+            ["com.crossbow.play_games_services:play_games_services:0.2.3"]
+        );
+        assert_eq!(
+            config.android.plugins.maven_repos,
+            ["https://maven.example.com"]
+        );
+        assert_eq!(config.android.discovered_plugins.len(), 3);
+        assert!(config.android_uses_crossbow_bridge());
+    }
+
     #[test]
     fn accepts_cargo_metadata_null_for_unconfigured_packages() {
         let metadata = parse_project_config(serde_json::Value::Null)
@@ -382,6 +769,35 @@ mod android_config_tests {
             Some(true)
         );
     }
+
+    #[test]
+    fn resolves_android_profile_overlays() {
+        let mut metadata = parse_project_config(serde_json::json!({
+            "build_variables": {
+                "LABEL": { "env": "CROSSBOW_TEST_UNSET_PROFILE_LABEL", "default": "Preview" }
+            },
+            "android": { "profiles": { "release": {
+                "manifest": { "application": { "label": "{{crossbow.LABEL}}" } },
+                "manifest_path": "release/AndroidManifest.xml"
+            } } }
+        }))
+        .unwrap()
+        .resolve_with(|_| Ok(None))
+        .unwrap();
+        metadata.resolve_paths(Path::new("/project"));
+
+        assert!(!format!("{metadata:?}").contains("Preview"));
+        let release = &metadata.android.profiles["release"];
+        let manifest = release.manifest.as_ref().unwrap();
+        assert_eq!(
+            manifest.application.label.as_ref().unwrap().to_string(),
+            "Preview"
+        );
+        assert_eq!(
+            release.manifest_path.as_deref(),
+            Some(Path::new("/project/release/AndroidManifest.xml"))
+        );
+    }
 }
 
 #[cfg(all(test, feature = "apple"))]
@@ -428,8 +844,29 @@ pub struct AppleConfig {
     pub resources: Vec<PathBuf>,
     #[serde(default)]
     pub assets: Vec<PathBuf>,
+    /// Build targets of Cargo profiles, keyed by profile name like `release-lto`.
+    #[serde(default)]
+    pub build_targets: BTreeMap<String, Vec<IosTarget>>,
     #[serde(default)]
     pub debug_build_targets: Vec<IosTarget>,
     #[serde(default)]
     pub release_build_targets: Vec<IosTarget>,
+    /// Program that signs release device builds instead of `codesign`.
+    pub signer: Option<ExternalSigner>,
+    /// Crossbow plugins with Apple metadata found in the Cargo dependencies.
+    #[serde(skip)]
+    pub discovered_plugins: Vec<ApplePlugin>,
+}
+
+#[cfg(feature = "apple")]
+impl AppleConfig {
+    /// Build targets of the profile, empty when the metadata doesn't set any.
+    pub fn profile_build_targets(&self, profile: &Profile) -> &[IosTarget] {
+        profile_build_targets(
+            &self.build_targets,
+            &self.debug_build_targets,
+            &self.release_build_targets,
+            profile,
+        )
+    }
 }
@@ -70,6 +70,7 @@ fn test_aapt2_link() {
         AndroidStrategy::NativeApk,
         AndroidRuntime::NativeActivity,
         false,
+        false,
     );
 
     // Saves android manifest into temporary directory
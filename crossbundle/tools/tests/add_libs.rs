@@ -19,11 +19,12 @@ fn compile_and_package(macroquad: bool) {
         target,
         &project.package,
         &package,
-        profile,
+        &profile,
         &[],
         false,
         false,
         23,
+        AndroidPageSize::Page16K,
         &target_dir,
     )
     .unwrap();
@@ -32,11 +33,13 @@ fn compile_and_package(macroquad: bool) {
         &ndk,
         &library,
         target,
-        profile,
+        &profile,
         23,
+        AndroidPageSize::Page16K,
         dir.path(),
         &target_dir,
         &package,
+        None,
     )
     .unwrap();
     assert!(output.join(library.file_name().unwrap()).is_file());
@@ -80,7 +80,7 @@ fn test_apple_full() {
             target: &target,
             target_triple: build_target.rust_triple(),
             target_dir: &target_dir,
-            profile,
+            profile: &profile,
             features: &[],
             all_features: false,
             no_default_features: false,
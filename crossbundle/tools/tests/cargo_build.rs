@@ -1,8 +1,21 @@
 use crossbundle_tools::{
     commands::{CargoBuild, CargoProject},
-    types::Profile,
+    types::{CargoTargetSelection, Profile},
 };
 
+fn host_triple() -> String {
+    let version = std::process::Command::new("rustc")
+        .arg("-vV")
+        .output()
+        .unwrap();
+    let version = String::from_utf8(version.stdout).unwrap();
+    version
+        .lines()
+        .find_map(|line| line.strip_prefix("host: "))
+        .unwrap()
+        .to_owned()
+}
+
 #[test]
 fn cargo_reports_a_renamed_executable_in_a_custom_target_directory() {
     let root = tempfile::tempdir().unwrap();
@@ -18,22 +31,14 @@ fn cargo_reports_a_renamed_executable_in_a_custom_target_directory() {
     let project = CargoProject::load(&root.path().join("Cargo.toml")).unwrap();
     let target = project.executable_target(None, None).unwrap();
     let target_dir = root.path().join("custom-target");
-    let version = std::process::Command::new("rustc")
-        .arg("-vV")
-        .output()
-        .unwrap();
-    let version = String::from_utf8(version.stdout).unwrap();
-    let host = version
-        .lines()
-        .find_map(|line| line.strip_prefix("host: "))
-        .unwrap();
+    let host = host_triple();
 
     let artifact = CargoBuild {
         package: &project.package,
         target: &target,
-        target_triple: host,
+        target_triple: &host,
         target_dir: &target_dir,
-        profile: Profile::Debug,
+        profile: &Profile::Debug,
         features: &[],
         all_features: false,
         no_default_features: false,
@@ -45,3 +50,42 @@ fn cargo_reports_a_renamed_executable_in_a_custom_target_directory() {
     assert!(executable.starts_with(target_dir));
     assert_eq!(executable.file_stem().unwrap(), "mobile-app");
 }
+
+#[test]
+fn rustc_args_are_added_to_rustflags() {
+    let root = tempfile::tempdir().unwrap();
+    std::fs::create_dir(root.path().join("src")).unwrap();
+    std::fs::write(
+        root.path().join("Cargo.toml"),
+        "[package]\nname = \"fixture-lib\"\nversion = \"0.1.0\"\nedition = \"2024\"\n\
+         [lints.rust]\nunexpected_cfgs = \"allow\"\n",
+    )
+    .unwrap();
+    std::fs::write(
+        root.path().join("src/lib.rs"),
+        "#[cfg(not(all(from_rustflags, from_crossbundle)))]\n\
+         compile_error!(\"both RUSTFLAGS and rustc arguments must be applied\");\n",
+    )
+    .unwrap();
+
+    let project = CargoProject::load(&root.path().join("Cargo.toml")).unwrap();
+    let artifact = CargoBuild {
+        package: &project.package,
+        target: &CargoTargetSelection::Lib("fixture_lib".to_owned()),
+        target_triple: &host_triple(),
+        target_dir: &root.path().join("target"),
+        profile: &Profile::Debug,
+        features: &[],
+        all_features: false,
+        no_default_features: false,
+    }
+    .run_rustc(
+        &["--cfg".to_owned(), "from_crossbundle".to_owned()],
+        |cargo| {
+            cargo.env("RUSTFLAGS", "--cfg from_rustflags");
+        },
+    )
+    .unwrap();
+
+    assert!(artifact.crate_types.iter().any(|kind| kind == "lib"));
+}
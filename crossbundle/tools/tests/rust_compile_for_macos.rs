@@ -21,7 +21,7 @@ fn test_compile_apple() {
             target: &target,
             target_triple: IosTarget::Aarch64Device.rust_triple(),
             target_dir: &target_dir,
-            profile: Profile::Release,
+            profile: &Profile::Release,
             features: &[],
             all_features: false,
             no_default_features: false,
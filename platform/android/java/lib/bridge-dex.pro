@@ -0,0 +1,6 @@
+# Rules used by the `crossbowBridgeDex` task. Everything reachable from JNI or from plugin
+# reflection must survive shrinking, so the bridge itself is kept as is.
+-keep class com.crossbow.library.** { *; }
+-keepattributes *Annotation*,Signature,InnerClasses,EnclosingMethod
+-dontobfuscate
+-dontwarn org.jetbrains.annotations.**
@@ -11,3 +11,11 @@ use rust_embed::RustEmbed;
 #[exclude = "build/"]
 #[exclude = "libs/"]
 pub struct CrossbowAndroidAppTemplate;
+
+/// Prebuilt `classes.dex` of the `com.crossbow.library` Kotlin bridge, packaged by the
+/// native build strategies. Regenerate it with `cargo run -p xtask -- bridge-dex build`.
+pub const CROSSBOW_BRIDGE_DEX: &[u8] = include_bytes!("../dex/classes.dex");
+
+/// Stamp written next to [`CROSSBOW_BRIDGE_DEX`] with the SHA-256 of its sources and of
+/// the DEX itself.
+pub const CROSSBOW_BRIDGE_DEX_STAMP: &str = include_str!("../dex/classes.dex.sha256");
@@ -0,0 +1,168 @@
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+const GRADLE_PROJECT: &str = "platform/android/java";
+const BRIDGE_DEX: &str = "platform/android/dex/classes.dex";
+const BRIDGE_DEX_STAMP: &str = "platform/android/dex/classes.dex.sha256";
+const BRIDGE_DEX_OUTPUT: &str = "platform/android/java/lib/build/outputs/bridge-dex";
+const BRIDGE_DEX_INPUTS: &[&str] = &[
+    "platform/android/java/lib/src",
+    "platform/android/java/lib/build.gradle",
+    "platform/android/java/lib/bridge-dex.pro",
+    "platform/android/java/app/config.gradle",
+];
+
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    if path.is_dir() {
+        let entries = fs::read_dir(path).map_err(|error| format!("{path:?}: {error}"))?;
+        for entry in entries {
+            let entry = entry.map_err(|error| format!("{path:?}: {error}"))?;
+            collect_files(&entry.path(), files)?;
+        }
+    } else if path.is_file() {
+        files.push(path.to_owned());
+    } else {
+        return Err(format!("{}: bridge DEX input not found", path.display()));
+    }
+    Ok(())
+}
+
+fn hex(digest: impl AsRef<[u8]>) -> String {
+    digest
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Hashes every source the bridge DEX is compiled from. Paths are hashed relative to the
+/// repository root so the stamp doesn't depend on where the repository is checked out.
+fn inputs_digest(root: &Path, inputs: &[&str]) -> Result<String, String> {
+    let mut files = Vec::new();
+    for input in inputs {
+        collect_files(&root.join(input), &mut files)?;
+    }
+    let mut files = files
+        .into_iter()
+        .map(|path| {
+            let relative = path
+                .strip_prefix(root)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            (relative, path)
+        })
+        .collect::<Vec<_>>();
+    files.sort();
+    let mut hasher = Sha256::new();
+    for (relative, path) in files {
+        let contents = fs::read(&path).map_err(|error| format!("{path:?}: {error}"))?;
+        hasher.update(relative.as_bytes());
+        hasher.update([0]);
+        hasher.update(&contents);
+        hasher.update([0]);
+    }
+    Ok(hex(hasher.finalize()))
+}
+
+fn render_stamp(inputs: &str, dex: &[u8]) -> String {
+    format!("inputs {inputs}\ndex {}\n", hex(Sha256::digest(dex)))
+}
+
+fn check_dex(root: &Path, inputs: &[&str]) -> Result<(), String> {
+    let dex_path = root.join(BRIDGE_DEX);
+    let dex = fs::read(&dex_path).map_err(|error| format!("{dex_path:?}: {error}"))?;
+    if !dex.starts_with(b"dex\n") {
+        return Err(format!(
+            "{}: not a generated DEX file; run `cargo run -p xtask -- bridge-dex build`",
+            dex_path.display()
+        ));
+    }
+    let stamp_path = root.join(BRIDGE_DEX_STAMP);
+    let stamp = fs::read_to_string(&stamp_path).map_err(|error| {
+        format!("{stamp_path:?}: {error}; run `cargo run -p xtask -- bridge-dex build`")
+    })?;
+    let expected = render_stamp(&inputs_digest(root, inputs)?, &dex);
+    if stamp != expected {
+        return Err(format!(
+            "{} is stale; run `cargo run -p xtask -- bridge-dex build` and commit the result",
+            dex_path.display()
+        ));
+    }
+    Ok(())
+}
+
+pub fn check(root: &Path) -> Result<(), String> {
+    check_dex(root, BRIDGE_DEX_INPUTS)
+}
+
+pub fn build(root: &Path) -> Result<(), String> {
+    let project = root.join(GRADLE_PROJECT);
+    let gradlew = if cfg!(windows) {
+        "gradlew.bat"
+    } else {
+        "gradlew"
+    };
+    let status = Command::new(project.join(gradlew))
+        .current_dir(&project)
+        .args([":lib:crossbowBridgeDex", "--no-daemon"])
+        .status()
+        .map_err(|error| format!("failed to run {gradlew}: {error}"))?;
+    if !status.success() {
+        return Err(format!(":lib:crossbowBridgeDex failed with {status}"));
+    }
+
+    let output = root.join(BRIDGE_DEX_OUTPUT);
+    let mut outputs = Vec::new();
+    collect_files(&output, &mut outputs)?;
+    if outputs.len() != 1 || !outputs[0].ends_with("classes.dex") {
+        return Err(format!(
+            "{}: expected a single classes.dex, found {outputs:?}",
+            output.display()
+        ));
+    }
+    let dex = fs::read(&outputs[0]).map_err(|error| format!("{:?}: {error}", outputs[0]))?;
+    let dex_path = root.join(BRIDGE_DEX);
+    fs::write(&dex_path, &dex).map_err(|error| format!("{dex_path:?}: {error}"))?;
+    let stamp_path = root.join(BRIDGE_DEX_STAMP);
+    let stamp = render_stamp(&inputs_digest(root, BRIDGE_DEX_INPUTS)?, &dex);
+    fs::write(&stamp_path, stamp).map_err(|error| format!("{stamp_path:?}: {error}"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stale_sources_fail_the_check() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        fs::create_dir_all(root.join("platform/android/dex")).unwrap();
+        fs::create_dir_all(root.join("lib/src/com")).unwrap();
+        fs::write(root.join("lib/src/com/Bridge.kt"), "class Bridge").unwrap();
+        let inputs = &["lib/src"];
+
+        fs::write(root.join(BRIDGE_DEX), b"").unwrap();
+        assert!(
+            check_dex(root, inputs)
+                .unwrap_err()
+                .contains("not a generated DEX")
+        );
+
+        let dex = b"dex\n035\0";
+        fs::write(root.join(BRIDGE_DEX), dex).unwrap();
+        assert!(check_dex(root, inputs).is_err());
+
+        let stamp = render_stamp(&inputs_digest(root, inputs).unwrap(), dex);
+        fs::write(root.join(BRIDGE_DEX_STAMP), stamp).unwrap();
+        check_dex(root, inputs).unwrap();
+
+        fs::write(root.join("lib/src/com/Bridge.kt"), "class Bridge {}").unwrap();
+        assert!(check_dex(root, inputs).unwrap_err().contains("stale"));
+    }
+}
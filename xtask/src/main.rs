@@ -1,3 +1,5 @@
+mod bridge_dex;
+
 use std::{
     collections::{BTreeMap, BTreeSet},
     env, fs,
@@ -573,9 +575,11 @@ fn check(root: &Path, versions: &BTreeMap<String, String>) -> Result<(), Vec<Str
     }
 }
 
+const USAGE: &str =
+    "usage: cargo run -p xtask -- <android-stack <check|sync>|bridge-dex <check|build>>";
+
 fn run() -> Result<(), String> {
     let root = repository_root();
-    let versions = read_versions(&root)?;
     let args: Vec<_> = env::args().skip(1).collect();
     match args
         .iter()
@@ -583,13 +587,17 @@ fn run() -> Result<(), String> {
         .collect::<Vec<_>>()
         .as_slice()
     {
-        ["android-stack", "check"] => {
-            check(&root, &versions).map_err(|failures| failures.join("\n- "))?
-        }
-        ["android-stack", "sync"] => sync(&root, &versions)?,
-        _ => {
-            return Err("usage: cargo run -p xtask -- android-stack <check|sync>".to_owned());
-        }
+        ["android-stack", "check"] => check(&root, &read_versions(&root)?).map_err(|failures| {
+            format!(
+                "Android stack consistency failed:\n- {}",
+                failures.join("\n- ")
+            )
+        })?,
+        ["android-stack", "sync"] => sync(&root, &read_versions(&root)?)?,
+        ["bridge-dex", "check"] => bridge_dex::check(&root)
+            .map_err(|error| format!("Crossbow bridge DEX check failed:\n- {error}"))?,
+        ["bridge-dex", "build"] => bridge_dex::build(&root)?,
+        _ => return Err(USAGE.to_owned()),
     }
     Ok(())
 }
@@ -598,7 +606,7 @@ fn main() -> ExitCode {
     match run() {
         Ok(()) => ExitCode::SUCCESS,
         Err(error) => {
-            eprintln!("Android stack consistency failed:\n- {error}");
+            eprintln!("{error}");
             ExitCode::FAILURE
         }
     }